@@ -1,3 +1,4 @@
+use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
@@ -6,6 +7,19 @@ use crate::error::{AppError, Result};
 const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
 const API_VERSION: &str = "2023-06-01";
 
+/// Something that answers Messages API requests: the API itself through
+/// [`ClaudeClient`], or a recorded run (see [`crate::agent::recording`]).
+#[async_trait]
+pub trait MessagesApi: Send + Sync {
+    async fn send_message(&self, request: &MessagesRequest) -> Result<MessagesResponse>;
+
+    /// Model requests are made for.
+    fn model(&self) -> &str;
+
+    /// Output token limit per response.
+    fn max_tokens(&self) -> u32;
+}
+
 pub struct ClaudeClient {
     client: Client,
     api_key: String,
@@ -14,12 +28,9 @@ pub struct ClaudeClient {
 }
 
 impl ClaudeClient {
-    pub fn new(api_key: &str, model: &str, max_tokens: u32) -> Self {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(300)) // 5 min timeout for long Sonnet responses
-            .build()
-            .expect("Failed to build HTTP client");
-
+    /// `client` should come from [`ClaudeClient::http_client`] and be shared,
+    /// so runs reuse its connection pool.
+    pub fn new(client: Client, api_key: &str, model: &str, max_tokens: u32) -> Self {
         Self {
             client,
             api_key: api_key.to_string(),
@@ -28,7 +39,18 @@ impl ClaudeClient {
         }
     }
 
-    pub async fn send_message(&self, request: &MessagesRequest) -> Result<MessagesResponse> {
+    /// HTTP client suited to the Messages API, meant to be built once and shared.
+    pub fn http_client() -> Client {
+        Client::builder()
+            .timeout(std::time::Duration::from_secs(300)) // 5 min timeout for long Sonnet responses
+            .build()
+            .expect("Failed to build HTTP client")
+    }
+}
+
+#[async_trait]
+impl MessagesApi for ClaudeClient {
+    async fn send_message(&self, request: &MessagesRequest) -> Result<MessagesResponse> {
         let response = self
             .client
             .post(CLAUDE_API_URL)
@@ -51,11 +73,17 @@ impl ClaudeClient {
 
         let status = response.status();
         if !status.is_success() {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after);
             let body = response.text().await.unwrap_or_default();
             if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
-                return Err(AppError::ClaudeRateLimited(format!(
-                    "Rate limited (429): {body}"
-                )));
+                return Err(AppError::ClaudeRateLimited {
+                    message: format!("Rate limited (429): {body}"),
+                    retry_after,
+                });
             }
             // 5xx and 529 (overloaded) are transient
             if status.is_server_error() || status.as_u16() == 529 {
@@ -72,15 +100,24 @@ impl ClaudeClient {
         Ok(body)
     }
 
-    pub fn model(&self) -> &str {
+    fn model(&self) -> &str {
         &self.model
     }
 
-    pub fn max_tokens(&self) -> u32 {
+    fn max_tokens(&self) -> u32 {
         self.max_tokens
     }
 }
 
+/// Parse a `retry-after` header given in seconds, as the API sends it.
+fn parse_retry_after(value: &str) -> Option<std::time::Duration> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| std::time::Duration::try_from_secs_f64(secs).ok())
+}
+
 // --- Cache control ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,6 +144,20 @@ pub struct MessagesRequest {
     pub messages: Vec<Message>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub tools: Vec<ToolDefinition>,
+    #[serde(flatten)]
+    pub sampling: SamplingParams,
+}
+
+/// Optional sampling settings. Unset fields are left out of the request so
+/// the API defaults apply.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SamplingParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
 }
 
 /// A block in the system prompt array (supports cache_control).
@@ -174,7 +225,7 @@ pub struct ToolDefinition {
 
 // --- Response types ---
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MessagesResponse {
     pub id: String,
     pub content: Vec<ContentBlock>,
@@ -182,7 +233,7 @@ pub struct MessagesResponse {
     pub usage: Usage,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Usage {
     pub input_tokens: u32,
     pub output_tokens: u32,
@@ -191,3 +242,51 @@ pub struct Usage {
     #[serde(default)]
     pub cache_read_input_tokens: Option<u32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(sampling: SamplingParams) -> serde_json::Value {
+        serde_json::to_value(MessagesRequest {
+            model: "claude-test".to_string(),
+            max_tokens: 1024,
+            system: vec![SystemContent::cached_text("system")],
+            messages: Vec::new(),
+            tools: Vec::new(),
+            sampling,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sampling_params_serialized_only_when_set() {
+        let json = request(SamplingParams::default());
+        for field in ["temperature", "top_p", "stop_sequences"] {
+            assert!(json.get(field).is_none(), "{field} should be omitted");
+        }
+
+        let json = request(SamplingParams {
+            temperature: Some(0.0),
+            top_p: Some(0.5),
+            stop_sequences: vec!["STOP".to_string()],
+        });
+        assert_eq!(json["temperature"], 0.0);
+        assert_eq!(json["top_p"], 0.5);
+        assert_eq!(json["stop_sequences"], serde_json::json!(["STOP"]));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        use std::time::Duration;
+
+        assert_eq!(parse_retry_after("30"), Some(Duration::from_secs(30)));
+        assert_eq!(
+            parse_retry_after(" 1.5 "),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(parse_retry_after("-1"), None);
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+        assert_eq!(parse_retry_after("1e30"), None);
+    }
+}
@@ -1,13 +1,15 @@
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::agent::claude::{
-    CacheControl, ClaudeClient, ContentBlock, Message, MessageContent, MessagesRequest,
-    SystemContent,
+    CacheControl, ClaudeClient, ContentBlock, Message, MessageContent, MessagesApi,
+    MessagesRequest, SamplingParams, SystemContent,
 };
-use crate::agent::tools::{ToolOutput, ToolRegistry};
+use crate::agent::recording::RecordingClient;
+use crate::agent::tools::{DeniedPaths, ToolOutput, ToolRegistry};
 use crate::config::AppConfig;
 use crate::error::{AppError, Result};
+use crate::metrics::METRICS;
 
 /// Outcome of an agent run.
 #[derive(Debug)]
@@ -16,8 +18,12 @@ pub enum AgentOutcome {
     Completed { summary: String },
     /// Agent needs clarification from a human.
     ClarificationNeeded { question: String },
-    /// Agent hit the turn limit without finishing.
-    TurnLimitReached { partial_summary: String },
+    /// Agent hit the turn limit without finishing. `messages` is the
+    /// conversation so far, for resuming the run with [`AgentEngine::resume`].
+    TurnLimitReached {
+        partial_summary: String,
+        messages: Vec<Message>,
+    },
     /// Agent hit Claude API rate limits.
     RateLimited { message: String },
     /// Agent was cancelled (e.g., issue closed).
@@ -32,7 +38,9 @@ pub struct RateLimitConfig {
     pub enabled: bool,
     /// Maximum number of retries before giving up.
     pub max_retries: u32,
-    /// Initial backoff duration (doubles each retry).
+    /// Initial backoff duration (doubles each retry). Used when the API
+    /// doesn't say how long to wait, with up to a quarter added at random so
+    /// concurrent runs don't retry in lockstep.
     pub initial_backoff: Duration,
 }
 
@@ -46,44 +54,111 @@ impl Default for RateLimitConfig {
     }
 }
 
+/// How often the engine reports progress during a run. Progress is
+/// reported when either threshold is reached; unset thresholds never trigger.
+#[derive(Default)]
+pub struct ProgressConfig {
+    /// Report every this many turns.
+    pub every_turns: Option<u32>,
+    /// Report when this long has passed since the last report.
+    pub interval: Option<Duration>,
+}
+
+/// Progress of a run, reported before a turn starts.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    /// The turn about to start, counting from 1.
+    pub turn: u32,
+    pub max_turns: u32,
+}
+
+/// Something worth telling the issue about while a run is under way.
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    /// Reported as configured by `ProgressConfig`.
+    Progress(Progress),
+    /// The agent shared its plan with `submit_plan` (see [`AgentEngine::enable_plan`]).
+    Plan(String),
+}
+
 pub struct AgentEngine {
-    client: ClaudeClient,
+    client: Box<dyn MessagesApi>,
     tools: ToolRegistry,
     max_turns: u32,
     rate_limit: RateLimitConfig,
+    progress: ProgressConfig,
+    sampling: SamplingParams,
 }
 
 impl AgentEngine {
     pub fn new(
-        client: ClaudeClient,
+        client: Box<dyn MessagesApi>,
         tools: ToolRegistry,
         max_turns: u32,
         rate_limit: RateLimitConfig,
+        progress: ProgressConfig,
+        sampling: SamplingParams,
     ) -> Self {
         Self {
             client,
             tools,
             max_turns,
             rate_limit,
+            progress,
+            sampling,
         }
     }
 
-    pub fn from_config(config: &AppConfig) -> Self {
+    /// Build an engine for one task. `config` carries the task's repo and
+    /// label overrides, so only the HTTP client is shared between tasks.
+    pub fn from_config(config: &AppConfig, http: &reqwest::Client) -> Self {
         let claude = ClaudeClient::new(
+            http.clone(),
             config.claude_api_key(),
             &config.claude.model,
             config.claude.max_tokens,
         );
+        let client: Box<dyn MessagesApi> = match &config.claude.record_dir {
+            Some(dir) => Box::new(RecordingClient::in_dir(Box::new(claude), dir)),
+            None => Box::new(claude),
+        };
         let tools = ToolRegistry::new(
             config.agent.max_file_size_bytes,
             config.agent.max_search_results,
+            DeniedPaths::new(&config.agent.denied_paths),
+            config.agent.search_exclude_dirs.clone(),
         );
         let rate_limit = RateLimitConfig {
             enabled: config.claude.rate_limit_retry,
             max_retries: config.claude.rate_limit_max_retries,
             initial_backoff: Duration::from_secs(config.claude.rate_limit_backoff_secs),
         };
-        Self::new(claude, tools, config.claude.max_turns, rate_limit)
+        let progress = ProgressConfig {
+            every_turns: Some(config.agent.progress_update_turns).filter(|&n| n > 0),
+            interval: Some(config.agent.progress_update_secs)
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+        };
+        let sampling = SamplingParams {
+            temperature: config.claude.temperature,
+            top_p: config.claude.top_p,
+            stop_sequences: config.claude.stop_sequences.clone(),
+        };
+        Self::new(
+            client,
+            tools,
+            config.claude.max_turns,
+            rate_limit,
+            progress,
+            sampling,
+        )
+    }
+
+    /// Offer the `submit_plan` tool, whose plans are reported as
+    /// [`AgentEvent::Plan`] while the run carries on.
+    pub fn enable_plan(&mut self) {
+        self.tools
+            .register(Box::new(crate::agent::tools::submit_plan::SubmitPlanTool));
     }
 
     /// Run the agentic loop.
@@ -92,16 +167,52 @@ impl AgentEngine {
     /// - `workspace_root`: The root directory of the cloned repo.
     /// - `initial_message`: The initial user message to start the conversation.
     /// - `is_cancelled`: Async callback checked each turn; returns true if work should stop.
-    pub async fn run<F, Fut>(
+    /// - `on_event`: Async callback told about progress (as configured by
+    ///   `ProgressConfig`) and plans.
+    pub async fn run<F, Fut, E, EFut>(
         &self,
         system_prompt: &str,
         workspace_root: &Path,
         initial_message: &str,
         is_cancelled: F,
+        on_event: E,
+    ) -> AgentOutcome
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = bool>,
+        E: Fn(AgentEvent) -> EFut,
+        EFut: std::future::Future<Output = ()>,
+    {
+        let messages = vec![Message {
+            role: "user".to_string(),
+            content: MessageContent::Text(initial_message.to_string()),
+        }];
+        self.resume(
+            system_prompt,
+            workspace_root,
+            messages,
+            is_cancelled,
+            on_event,
+        )
+        .await
+    }
+
+    /// Continue the agentic loop from an earlier conversation, such as the
+    /// one returned with `TurnLimitReached`, with a fresh turn budget.
+    /// `messages` must end with a user message.
+    pub async fn resume<F, Fut, E, EFut>(
+        &self,
+        system_prompt: &str,
+        workspace_root: &Path,
+        mut messages: Vec<Message>,
+        is_cancelled: F,
+        on_event: E,
     ) -> AgentOutcome
     where
         F: Fn() -> Fut,
         Fut: std::future::Future<Output = bool>,
+        E: Fn(AgentEvent) -> EFut,
+        EFut: std::future::Future<Output = ()>,
     {
         // Build cached tool definitions — mark the last tool for caching
         // so the entire system prompt + tools prefix is cached across turns
@@ -113,15 +224,11 @@ impl AgentEngine {
         // System prompt with cache_control so it's cached across turns
         let system = vec![SystemContent::cached_text(system_prompt)];
 
-        let mut messages = vec![Message {
-            role: "user".to_string(),
-            content: MessageContent::Text(initial_message.to_string()),
-        }];
-
         let mut total_input_tokens = 0u32;
         let mut total_output_tokens = 0u32;
         let mut total_cache_read_tokens = 0u32;
         let mut total_cache_creation_tokens = 0u32;
+        let mut last_progress = Instant::now();
 
         for turn in 0..self.max_turns {
             // Check for cancellation before each turn
@@ -130,6 +237,15 @@ impl AgentEngine {
                 return AgentOutcome::Cancelled;
             }
 
+            if turn > 0 && self.progress_due(turn, last_progress.elapsed()) {
+                on_event(AgentEvent::Progress(Progress {
+                    turn: turn + 1,
+                    max_turns: self.max_turns,
+                }))
+                .await;
+                last_progress = Instant::now();
+            }
+
             tracing::info!(turn = turn, "Agent turn");
 
             let request = MessagesRequest {
@@ -138,6 +254,7 @@ impl AgentEngine {
                 system: system.clone(),
                 messages: messages.clone(),
                 tools: tool_definitions.clone(),
+                sampling: self.sampling.clone(),
             };
 
             // Send with retry on transient errors and rate limits
@@ -149,11 +266,15 @@ impl AgentEngine {
                         Err(ref e)
                             if matches!(
                                 e,
-                                AppError::ClaudeRateLimited(_) | AppError::ClaudeTransient(_)
+                                AppError::ClaudeRateLimited { .. } | AppError::ClaudeTransient(_)
                             ) =>
                         {
-                            let is_rate_limit =
-                                matches!(e, AppError::ClaudeRateLimited(_));
+                            let (is_rate_limit, retry_after) = match e {
+                                AppError::ClaudeRateLimited { retry_after, .. } => {
+                                    (true, *retry_after)
+                                }
+                                _ => (false, None),
+                            };
                             let msg = e.to_string();
 
                             if !self.rate_limit.enabled
@@ -176,11 +297,16 @@ impl AgentEngine {
                                 };
                             }
                             retries += 1;
-                            let backoff = self.rate_limit.initial_backoff
-                                * 2u32.saturating_pow(retries - 1);
+                            let backoff = retry_after.unwrap_or_else(|| {
+                                with_jitter(
+                                    self.rate_limit.initial_backoff
+                                        * 2u32.saturating_pow(retries - 1),
+                                )
+                            });
                             tracing::warn!(
                                 retry = retries,
                                 backoff_secs = backoff.as_secs(),
+                                from_retry_after = retry_after.is_some(),
                                 error = %msg,
                                 "Transient error, waiting before retry"
                             );
@@ -206,6 +332,22 @@ impl AgentEngine {
             total_cache_read_tokens += response.usage.cache_read_input_tokens.unwrap_or(0);
             total_cache_creation_tokens += response.usage.cache_creation_input_tokens.unwrap_or(0);
 
+            let usage = &response.usage;
+            for (kind, count) in [
+                ("input", usage.input_tokens),
+                ("output", usage.output_tokens),
+                ("cache_read", usage.cache_read_input_tokens.unwrap_or(0)),
+                (
+                    "cache_creation",
+                    usage.cache_creation_input_tokens.unwrap_or(0),
+                ),
+            ] {
+                METRICS
+                    .claude_tokens
+                    .with_label_values(&[kind])
+                    .inc_by(u64::from(count));
+            }
+
             tracing::info!(
                 input_tokens = response.usage.input_tokens,
                 output_tokens = response.usage.output_tokens,
@@ -230,6 +372,10 @@ impl AgentEngine {
                         turns = turn + 1,
                         "Agent completed"
                     );
+                    METRICS
+                        .agent_turns
+                        .with_label_values(&["completed"])
+                        .observe(f64::from(turn + 1));
                     return AgentOutcome::Completed { summary };
                 }
                 "tool_use" => {
@@ -271,8 +417,22 @@ impl AgentEngine {
                                         "Agent requesting clarification: {}",
                                         question
                                     );
+                                    METRICS
+                                        .agent_turns
+                                        .with_label_values(&["clarification_needed"])
+                                        .observe(f64::from(turn + 1));
                                     return AgentOutcome::ClarificationNeeded { question };
                                 }
+                                Ok(ToolOutput::Plan(plan)) => {
+                                    tracing::info!("Agent submitted a plan");
+                                    on_event(AgentEvent::Plan(plan)).await;
+                                    tool_results.push(ContentBlock::ToolResult {
+                                        tool_use_id: id.clone(),
+                                        content: "Plan posted. Go ahead with the implementation."
+                                            .to_string(),
+                                        is_error: None,
+                                    });
+                                }
                                 Err(e) => {
                                     tracing::error!(tool = %name, error = %e, "Tool execution error");
                                     tool_results.push(ContentBlock::ToolResult {
@@ -314,12 +474,25 @@ impl AgentEngine {
         }
 
         tracing::warn!(max_turns = self.max_turns, "Agent hit turn limit");
+        METRICS
+            .agent_turns
+            .with_label_values(&["turn_limit_reached"])
+            .observe(f64::from(self.max_turns));
         AgentOutcome::TurnLimitReached {
             partial_summary: "Agent reached maximum number of turns without completing the task."
                 .to_string(),
+            messages,
         }
     }
 
+    /// Whether progress should be reported before `turn` (counting from 0),
+    /// `since_last` after the previous report.
+    fn progress_due(&self, turn: u32, since_last: Duration) -> bool {
+        let by_turns = self.progress.every_turns.is_some_and(|n| turn.is_multiple_of(n));
+        let by_time = self.progress.interval.is_some_and(|i| since_last >= i);
+        by_turns || by_time
+    }
+
     async fn execute_tool(
         &self,
         workspace_root: &Path,
@@ -348,3 +521,165 @@ fn extract_text(content: &[ContentBlock]) -> String {
         .collect::<Vec<_>>()
         .join("\n")
 }
+
+/// `backoff` plus a random extra of up to a quarter of it.
+fn with_jitter(backoff: Duration) -> Duration {
+    use std::hash::{BuildHasher, Hasher};
+
+    // Each `RandomState` is seeded differently; enough randomness for jitter
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    backoff + backoff.mul_f64((random % 1000) as f64 / 4000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::agent::recording::{Exchange, ReplayClient};
+
+    fn exchange(stop_reason: &str, content: serde_json::Value) -> Exchange {
+        Exchange {
+            request: json!({ "model": "claude-test", "max_tokens": 1024 }),
+            response: json!({
+                "id": "msg",
+                "content": content,
+                "stop_reason": stop_reason,
+                "usage": { "input_tokens": 1, "output_tokens": 1 }
+            }),
+        }
+    }
+
+    fn engine(exchanges: Vec<Exchange>) -> AgentEngine {
+        AgentEngine::new(
+            Box::new(ReplayClient::new(exchanges)),
+            ToolRegistry::new(1024 * 1024, 50, DeniedPaths::new(&[]), Vec::new()),
+            5,
+            RateLimitConfig::default(),
+            ProgressConfig::default(),
+            SamplingParams::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_replayed_run_dispatches_tools_and_completes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let engine = engine(vec![
+            exchange(
+                "tool_use",
+                json!([
+                    { "type": "text", "text": "Creating the file." },
+                    {
+                        "type": "tool_use",
+                        "id": "toolu_1",
+                        "name": "create_file",
+                        "input": { "path": "src/new.rs", "content": "fn main() {}\n" }
+                    }
+                ]),
+            ),
+            exchange("end_turn", json!([{ "type": "text", "text": "Added src/new.rs" }])),
+        ]);
+
+        let outcome = engine
+            .run("system", tmp.path(), "Add a file", || async { false }, |_| async {})
+            .await;
+
+        match outcome {
+            AgentOutcome::Completed { summary } => assert_eq!(summary, "Added src/new.rs"),
+            other => panic!("expected Completed, got {other:?}"),
+        }
+        let created = std::fs::read_to_string(tmp.path().join("src/new.rs")).unwrap();
+        assert_eq!(created, "fn main() {}\n");
+    }
+
+    #[tokio::test]
+    async fn test_replayed_clarification_and_turn_limit() {
+        let tmp = tempfile::tempdir().unwrap();
+        let question = engine(vec![exchange(
+            "tool_use",
+            json!([{
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": "ask_clarification",
+                "input": { "question": "Which parser?" }
+            }]),
+        )]);
+        let outcome = question
+            .run("system", tmp.path(), "Fix it", || async { false }, |_| async {})
+            .await;
+        match outcome {
+            AgentOutcome::ClarificationNeeded { question } => assert_eq!(question, "Which parser?"),
+            other => panic!("expected ClarificationNeeded, got {other:?}"),
+        }
+
+        // Five turns of listing the directory exhaust the turn budget
+        let listing = exchange(
+            "tool_use",
+            json!([{
+                "type": "tool_use",
+                "id": "toolu_1",
+                "name": "list_directory",
+                "input": { "path": "." }
+            }]),
+        );
+        let looping = engine((0..5).map(|_| listing.clone()).collect());
+        let outcome = looping
+            .run("system", tmp.path(), "Fix it", || async { false }, |_| async {})
+            .await;
+        match outcome {
+            // Initial message plus an assistant turn and tool results per turn
+            AgentOutcome::TurnLimitReached { messages, .. } => assert_eq!(messages.len(), 11),
+            other => panic!("expected TurnLimitReached, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submitted_plan_is_reported_and_run_continues() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut engine = engine(vec![
+            exchange(
+                "tool_use",
+                json!([{
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "submit_plan",
+                    "input": { "plan": "Add src/new.rs" }
+                }]),
+            ),
+            exchange("end_turn", json!([{ "type": "text", "text": "Done" }])),
+        ]);
+        engine.enable_plan();
+
+        let plans = std::sync::Mutex::new(Vec::new());
+        let outcome = engine
+            .run(
+                "system",
+                tmp.path(),
+                "Add a file",
+                || async { false },
+                |event| {
+                    if let AgentEvent::Plan(plan) = event {
+                        plans.lock().unwrap().push(plan);
+                    }
+                    async {}
+                },
+            )
+            .await;
+
+        assert!(matches!(outcome, AgentOutcome::Completed { .. }));
+        assert_eq!(*plans.lock().unwrap(), ["Add src/new.rs"]);
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_within_a_quarter() {
+        let base = Duration::from_secs(40);
+        let backoffs: Vec<Duration> = (0..20).map(|_| with_jitter(base)).collect();
+        assert!(backoffs
+            .iter()
+            .all(|b| *b >= base && *b <= base + Duration::from_secs(10)));
+        // Concurrent runs must not all pick the same delay
+        assert!(backoffs.iter().any(|b| *b != backoffs[0]));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod claude;
 pub mod engine;
 pub mod prompt;
+pub mod recording;
 pub mod tools;
@@ -1,3 +1,6 @@
+use crate::workspace::languages::Language;
+
+#[allow(clippy::too_many_arguments)]
 pub fn system_prompt_for_issue(
     repo_full_name: &str,
     issue_number: u64,
@@ -5,6 +8,9 @@ pub fn system_prompt_for_issue(
     issue_body: &str,
     comments: &str,
     research_only: bool,
+    guidelines: Option<&str>,
+    file_tree: &str,
+    languages: &[Language],
 ) -> String {
     let mode_instructions = if research_only {
         r#"## Mode: Research Only
@@ -41,21 +47,28 @@ Your task is to address GitHub issue #{issue_number}.
 {issue_body}
 
 {comments_section}
-
+{file_tree_section}{languages_section}
 {mode_instructions}
-
+{guidelines_section}
 ## Guidelines
 - Follow the existing code style and patterns in the repository.
 - Be autonomous. Make reasonable decisions on your own based on the codebase context.
 - Be thorough in your exploration before drawing conclusions or making changes.
 
 ## Available Tools
-You have tools to read files, list directories, search code, write files, create new files, delete files, and ask for clarification. Use them as needed."#,
+You have tools to read files, list directories, search code, write files, create new files, delete files, list the files you've changed, and ask for clarification. Use them as needed."#,
         comments_section = if comments.is_empty() {
             String::new()
         } else {
             format!("**Comments:**\n{comments}")
-        }
+        },
+        file_tree_section = if file_tree.is_empty() {
+            String::new()
+        } else {
+            format!("\n## Repository Layout\nThe top two levels of the repository, without ignored files. Use list_directory and search_code to look deeper.\n```\n{file_tree}\n```\n")
+        },
+        languages_section = languages_section(languages),
+        guidelines_section = guidelines_section(guidelines),
     )
 }
 
@@ -64,6 +77,7 @@ pub fn system_prompt_for_review(
     pr_number: u64,
     review_body: &str,
     review_comments: &str,
+    guidelines: Option<&str>,
 ) -> String {
     format!(
         r#"You are Mycelium, an expert software engineer AI agent. You are working on repository `{repo_full_name}`.
@@ -80,7 +94,7 @@ Your task is to address the code review feedback on PR #{pr_number}.
 2. Explore the relevant files to understand the current state.
 3. Make the requested changes.
 4. Verify your changes address each review comment.
-
+{guidelines_section}
 ## Guidelines
 - Address each review comment specifically.
 - Follow the existing code style.
@@ -91,6 +105,67 @@ Your task is to address the code review feedback on PR #{pr_number}.
             String::new()
         } else {
             format!("**Review Comments:**\n{review_comments}")
-        }
+        },
+        guidelines_section = guidelines_section(guidelines),
     )
 }
+
+pub fn system_prompt_for_check_failure(
+    repo_full_name: &str,
+    pr_number: u64,
+    failure_report: &str,
+    guidelines: Option<&str>,
+) -> String {
+    format!(
+        r#"You are Mycelium, an expert software engineer AI agent. You are working on repository `{repo_full_name}`.
+
+Your task is to fix the failing CI checks on PR #{pr_number}.
+
+## Failing Checks
+{failure_report}
+
+## Instructions
+1. Read the failure output carefully and identify the root cause of each failure.
+2. Explore the relevant files to understand the current state.
+3. Fix the code so the checks pass.
+4. Verify your changes address every failure listed above.
+{guidelines_section}
+## Guidelines
+- Fix the underlying problem; do not disable, skip or weaken tests or checks.
+- Follow the existing code style.
+- Make minimal changes — only what is needed to make the checks pass.
+- If a failure is clearly unrelated to this PR (e.g. a flaky network error), say so in your summary instead of changing code.
+- Only use ask_clarification if the failure is genuinely impossible to interpret."#,
+        guidelines_section = guidelines_section(guidelines),
+    )
+}
+
+/// Names the repo's main languages so the agent narrows searches to them.
+fn languages_section(languages: &[Language]) -> String {
+    let Some(first) = languages.first() else {
+        return String::new();
+    };
+    let described: Vec<String> = languages
+        .iter()
+        .map(|l| format!("{} ({})", l.name, l.globs()))
+        .collect();
+    let list = match described.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {last}", rest.join(", ")),
+        _ => described.concat(),
+    };
+    format!(
+        "\n## Languages\nMost of the code is {list}. When you search for code, pass search_code a matching `include` glob (e.g. `*.{}`) to keep out matches from other files.\n",
+        first.extensions[0]
+    )
+}
+
+/// Repo-provided guidelines (e.g. `AGENTS.md`), placed ahead of the generic ones.
+fn guidelines_section(guidelines: Option<&str>) -> String {
+    match guidelines {
+        Some(text) => format!(
+            "\n## Project Guidelines\nThe maintainers of this repository wrote these instructions for contributors. Follow them where they apply (they take precedence over the general guidelines below):\n\n{}\n",
+            text.trim()
+        ),
+        None => String::new(),
+    }
+}
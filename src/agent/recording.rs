@@ -0,0 +1,236 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use crate::agent::claude::{MessagesApi, MessagesRequest, MessagesResponse};
+use crate::error::{AppError, Result};
+
+/// One request of a recorded run and the response it got.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub request: serde_json::Value,
+    pub response: serde_json::Value,
+}
+
+/// Read a transcript written by [`RecordingClient`].
+pub fn load_transcript(path: &Path) -> Result<Vec<Exchange>> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        AppError::Agent(format!("Failed to read transcript {}: {e}", path.display()))
+    })?;
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|e| AppError::Agent(format!("Invalid transcript {}: {e}", path.display())))
+        })
+        .collect()
+}
+
+/// Passes requests through to another client and appends every successful
+/// exchange to a transcript as one JSON line, so a run that dies midway still
+/// leaves what it got. Failing to write is logged and never affects the run.
+pub struct RecordingClient {
+    inner: Box<dyn MessagesApi>,
+    path: PathBuf,
+}
+
+impl RecordingClient {
+    pub fn new(inner: Box<dyn MessagesApi>, path: PathBuf) -> Self {
+        Self { inner, path }
+    }
+
+    /// Record to a new file in `dir`, named after the current time.
+    pub fn in_dir(inner: Box<dyn MessagesApi>, dir: &Path) -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let name = format!(
+            "{}-{}.jsonl",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        Self::new(inner, dir.join(name))
+    }
+
+    async fn append(&self, line: String) {
+        if let Some(dir) = self.path.parent() {
+            let _ = tokio::fs::create_dir_all(dir).await;
+        }
+        let written = async {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await?;
+            file.write_all(line.as_bytes()).await?;
+            file.flush().await
+        };
+        if let Err(e) = written.await {
+            tracing::warn!(path = %self.path.display(), error = %e, "Failed to save transcript");
+        }
+    }
+}
+
+#[async_trait]
+impl MessagesApi for RecordingClient {
+    async fn send_message(&self, request: &MessagesRequest) -> Result<MessagesResponse> {
+        let response = self.inner.send_message(request).await?;
+
+        let exchange = Exchange {
+            request: serde_json::to_value(request)?,
+            response: serde_json::to_value(&response)?,
+        };
+        self.append(serde_json::to_string(&exchange)? + "\n").await;
+
+        Ok(response)
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn max_tokens(&self) -> u32 {
+        self.inner.max_tokens()
+    }
+}
+
+/// Answers requests with the responses of a recorded run, in order, so an
+/// agent run can be repeated without the API. The requests it was sent are
+/// kept for comparing against the recorded ones.
+pub struct ReplayClient {
+    model: String,
+    max_tokens: u32,
+    responses: Mutex<VecDeque<serde_json::Value>>,
+    requests: Mutex<Vec<serde_json::Value>>,
+}
+
+impl ReplayClient {
+    /// Replay `exchanges`, building requests with the model and token limit
+    /// of the first recorded one.
+    pub fn new(exchanges: Vec<Exchange>) -> Self {
+        let first = exchanges.first().map(|e| &e.request);
+        Self {
+            model: first
+                .and_then(|r| r["model"].as_str())
+                .unwrap_or("replay")
+                .to_string(),
+            max_tokens: first
+                .and_then(|r| r["max_tokens"].as_u64())
+                .map_or(0, |n| n as u32),
+            responses: Mutex::new(exchanges.into_iter().map(|e| e.response).collect()),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Ok(Self::new(load_transcript(path)?))
+    }
+
+    /// Requests sent so far.
+    pub fn requests(&self) -> Vec<serde_json::Value> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl MessagesApi for ReplayClient {
+    async fn send_message(&self, request: &MessagesRequest) -> Result<MessagesResponse> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(serde_json::to_value(request)?);
+
+        let response = self
+            .responses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop_front()
+            .ok_or_else(|| {
+                AppError::ClaudeApi("Replayed transcript has no more responses".to_string())
+            })?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn max_tokens(&self) -> u32 {
+        self.max_tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::claude::{Message, MessageContent, SamplingParams};
+
+    fn request(text: &str) -> MessagesRequest {
+        MessagesRequest {
+            model: "claude-test".to_string(),
+            max_tokens: 1024,
+            system: Vec::new(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: MessageContent::Text(text.to_string()),
+            }],
+            tools: Vec::new(),
+            sampling: SamplingParams::default(),
+        }
+    }
+
+    fn response(text: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": "msg_1",
+            "content": [{ "type": "text", "text": text }],
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": 10, "output_tokens": 2 }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_recorded_run_replays() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("runs/run.jsonl");
+        let source = ReplayClient::new(vec![
+            Exchange {
+                request: serde_json::to_value(request("hi")).unwrap(),
+                response: response("hello"),
+            },
+            Exchange {
+                request: serde_json::to_value(request("bye")).unwrap(),
+                response: response("goodbye"),
+            },
+        ]);
+        let recorder = RecordingClient::new(Box::new(source), path.clone());
+        recorder.send_message(&request("hi")).await.unwrap();
+        recorder.send_message(&request("bye")).await.unwrap();
+
+        let exchanges = load_transcript(&path).unwrap();
+        assert_eq!(exchanges.len(), 2);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+        assert_eq!(exchanges[1].request["messages"][0]["content"], "bye");
+
+        let replay = ReplayClient::from_file(&path).unwrap();
+        assert_eq!(replay.model(), "claude-test");
+        assert_eq!(replay.max_tokens(), 1024);
+        let first = replay.send_message(&request("hi")).await.unwrap();
+        assert_eq!(first.usage.input_tokens, 10);
+        replay.send_message(&request("bye")).await.unwrap();
+        assert_eq!(
+            replay.requests(),
+            vec![exchanges[0].request.clone(), exchanges[1].request.clone()]
+        );
+        assert!(matches!(
+            replay.send_message(&request("again")).await,
+            Err(AppError::ClaudeApi(_))
+        ));
+    }
+}
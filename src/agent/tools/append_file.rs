@@ -0,0 +1,145 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+
+use crate::agent::claude::ToolDefinition;
+use crate::agent::tools::{require_param, verified_path, DeniedPaths, Tool, ToolOutput};
+use crate::error::Result;
+
+/// Appends to an existing file, so adding to a changelog or log doesn't mean
+/// reading and rewriting all of it.
+pub struct AppendFileTool {
+    max_file_size: usize,
+    denied: DeniedPaths,
+}
+
+impl AppendFileTool {
+    pub fn new(max_file_size: usize, denied: DeniedPaths) -> Self {
+        Self {
+            max_file_size,
+            denied,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for AppendFileTool {
+    fn name(&self) -> &str {
+        "append_file"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "append_file".to_string(),
+            description: "Append content to the end of an existing file, leaving the rest of it untouched. Include a leading newline if the file doesn't end with one. Use create_file for new files.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Relative path to the file from the repository root"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The content to add at the end of the file"
+                    }
+                },
+                "required": ["path", "content"]
+            }),
+            cache_control: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        workspace_root: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolOutput> {
+        let path_str = require_param!(input, "path");
+        let content = require_param!(input, "content");
+
+        let full_path = match verified_path(workspace_root, path_str, &self.denied) {
+            Ok(p) => p,
+            Err(e) => return Ok(e),
+        };
+
+        if !full_path.is_file() {
+            return Ok(ToolOutput::Error(format!(
+                "File does not exist: {path_str}. Use create_file (or create_or_update_file) for new files."
+            )));
+        }
+
+        if content.len() > self.max_file_size {
+            return Ok(ToolOutput::Error(format!(
+                "Content is too large ({} bytes, max {} bytes)",
+                content.len(),
+                self.max_file_size
+            )));
+        }
+
+        let mut file = match tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&full_path)
+            .await
+        {
+            Ok(file) => file,
+            Err(e) => return Ok(ToolOutput::Error(format!("Failed to open file: {e}"))),
+        };
+        // tokio hands the write to a background task; flushing waits for it
+        // so the content is on disk before the agent reads the file again
+        let written = match file.write_all(content.as_bytes()).await {
+            Ok(()) => file.flush().await,
+            Err(e) => Err(e),
+        };
+        match written {
+            Ok(()) => Ok(ToolOutput::Success(format!(
+                "Successfully appended {} bytes to {path_str}",
+                content.len()
+            ))),
+            Err(e) => Ok(ToolOutput::Error(format!("Failed to append to file: {e}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_append_file_appends_to_existing_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("CHANGELOG.md"), "# Changelog\n").unwrap();
+        let tool = AppendFileTool::new(16, DeniedPaths::default());
+
+        let output = tool
+            .execute(
+                tmp.path(),
+                json!({ "path": "CHANGELOG.md", "content": "- Fix crash\n" }),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Success(_)));
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("CHANGELOG.md")).unwrap(),
+            "# Changelog\n- Fix crash\n"
+        );
+
+        let output = tool
+            .execute(
+                tmp.path(),
+                json!({ "path": "CHANGELOG.md", "content": "- A much longer entry\n" }),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Error(e) if e.contains("too large")));
+
+        let output = tool
+            .execute(tmp.path(), json!({ "path": "NEWS.md", "content": "x" }))
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Error(_)));
+        assert!(!tmp.path().join("NEWS.md").exists());
+    }
+}
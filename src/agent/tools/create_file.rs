@@ -4,10 +4,22 @@ use async_trait::async_trait;
 use serde_json::json;
 
 use crate::agent::claude::ToolDefinition;
-use crate::agent::tools::{require_param, verified_path, Tool, ToolOutput};
+use crate::agent::tools::{require_param, verified_new_path, DeniedPaths, Tool, ToolOutput};
 use crate::error::Result;
 
-pub struct CreateFileTool;
+pub struct CreateFileTool {
+    max_file_size: usize,
+    denied: DeniedPaths,
+}
+
+impl CreateFileTool {
+    pub fn new(max_file_size: usize, denied: DeniedPaths) -> Self {
+        Self {
+            max_file_size,
+            denied,
+        }
+    }
+}
 
 #[async_trait]
 impl Tool for CreateFileTool {
@@ -45,14 +57,23 @@ impl Tool for CreateFileTool {
         let path_str = require_param!(input, "path");
         let content = require_param!(input, "content");
 
-        let full_path = match verified_path(workspace_root, path_str) {
+        // Checked first so a rejected file leaves no new directories behind
+        if content.len() > self.max_file_size {
+            return Ok(ToolOutput::Error(format!(
+                "Content is too large ({} bytes, max {} bytes)",
+                content.len(),
+                self.max_file_size
+            )));
+        }
+
+        let full_path = match verified_new_path(workspace_root, path_str, &self.denied) {
             Ok(p) => p,
             Err(e) => return Ok(e),
         };
 
         if full_path.exists() {
             return Ok(ToolOutput::Error(format!(
-                "File already exists: {path_str}. Use write_file to modify existing files."
+                "File already exists: {path_str}. Use write_file (or create_or_update_file) to modify existing files."
             )));
         }
 
@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::agent::claude::ToolDefinition;
+use crate::agent::tools::{require_param, verified_new_path, DeniedPaths, Tool, ToolOutput};
+use crate::error::Result;
+
+/// Writes a file whether or not it exists, for when the agent doesn't know or
+/// care which of `create_file` and `write_file` applies.
+pub struct CreateOrUpdateFileTool {
+    max_file_size: usize,
+    denied: DeniedPaths,
+}
+
+impl CreateOrUpdateFileTool {
+    pub fn new(max_file_size: usize, denied: DeniedPaths) -> Self {
+        Self {
+            max_file_size,
+            denied,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for CreateOrUpdateFileTool {
+    fn name(&self) -> &str {
+        "create_or_update_file"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "create_or_update_file".to_string(),
+            description: "Write a file with the given content, creating it (and its parent directories) if it doesn't exist and overwriting it if it does. Prefer create_file or write_file when it matters whether the file already exists.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Relative path to the file from the repository root"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The complete content for the file"
+                    }
+                },
+                "required": ["path", "content"]
+            }),
+            cache_control: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        workspace_root: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolOutput> {
+        let path_str = require_param!(input, "path");
+        let content = require_param!(input, "content");
+
+        // Checked first so a rejected file leaves no new directories behind
+        if content.len() > self.max_file_size {
+            return Ok(ToolOutput::Error(format!(
+                "Content is too large ({} bytes, max {} bytes)",
+                content.len(),
+                self.max_file_size
+            )));
+        }
+
+        let full_path = match verified_new_path(workspace_root, path_str, &self.denied) {
+            Ok(p) => p,
+            Err(e) => return Ok(e),
+        };
+
+        if full_path.is_dir() {
+            return Ok(ToolOutput::Error(format!("{path_str} is a directory")));
+        }
+        let existed = full_path.exists();
+
+        match tokio::fs::write(&full_path, content).await {
+            Ok(()) if existed => Ok(ToolOutput::Success(format!(
+                "Successfully wrote to {path_str}"
+            ))),
+            Ok(()) => Ok(ToolOutput::Success(format!(
+                "Successfully created {path_str}"
+            ))),
+            Err(e) => Ok(ToolOutput::Error(format!("Failed to write file: {e}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_create_or_update_file_creates_then_overwrites() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tool = CreateOrUpdateFileTool::new(16, DeniedPaths::new(&[".env*".to_string()]));
+        let write = |path: &str, content: &str| {
+            tool.execute(tmp.path(), json!({ "path": path, "content": content }))
+        };
+
+        let output = write("docs/notes.md", "first").await.unwrap();
+        assert!(
+            matches!(output, ToolOutput::Success(s) if s == "Successfully created docs/notes.md")
+        );
+        let output = write("docs/notes.md", "second").await.unwrap();
+        assert!(
+            matches!(output, ToolOutput::Success(s) if s == "Successfully wrote to docs/notes.md")
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("docs/notes.md")).unwrap(),
+            "second"
+        );
+
+        let output = write("docs/notes.md", "far more than sixteen bytes")
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Error(e) if e.contains("too large")));
+        let output = write(".env", "SECRET=1").await.unwrap();
+        assert!(matches!(output, ToolOutput::Error(e) if e.contains("denied")));
+        let output = write("docs", "x").await.unwrap();
+        assert!(matches!(output, ToolOutput::Error(e) if e == "docs is a directory"));
+    }
+}
@@ -4,10 +4,18 @@ use async_trait::async_trait;
 use serde_json::json;
 
 use crate::agent::claude::ToolDefinition;
-use crate::agent::tools::{require_param, verified_path, Tool, ToolOutput};
+use crate::agent::tools::{require_param, verified_path, DeniedPaths, Tool, ToolOutput};
 use crate::error::Result;
 
-pub struct DeleteFileTool;
+pub struct DeleteFileTool {
+    denied: DeniedPaths,
+}
+
+impl DeleteFileTool {
+    pub fn new(denied: DeniedPaths) -> Self {
+        Self { denied }
+    }
+}
 
 #[async_trait]
 impl Tool for DeleteFileTool {
@@ -40,7 +48,7 @@ impl Tool for DeleteFileTool {
     ) -> Result<ToolOutput> {
         let path_str = require_param!(input, "path");
 
-        let full_path = match verified_path(workspace_root, path_str) {
+        let full_path = match verified_path(workspace_root, path_str, &self.denied) {
             Ok(p) => p,
             Err(e) => return Ok(e),
         };
@@ -0,0 +1,138 @@
+use std::path::Path;
+use std::sync::Arc;
+
+/// Glob patterns for workspace files the agent's tools must not touch.
+///
+/// A pattern without `/` matches a file or directory name at any depth, so
+/// `.env*` covers `config/.env.local`. Patterns with `/` match the whole path
+/// relative to the workspace root: `*` and `?` stay within one component and
+/// `**` matches any number of components. Matching is case-insensitive.
+#[derive(Clone, Default)]
+pub struct DeniedPaths {
+    patterns: Arc<Vec<String>>,
+}
+
+impl DeniedPaths {
+    pub fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: Arc::new(
+                patterns
+                    .iter()
+                    .map(|p| p.trim_matches('/').to_ascii_lowercase())
+                    .filter(|p| !p.is_empty())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The first pattern matching `relative_path`, if any.
+    pub fn matching(&self, relative_path: &Path) -> Option<&str> {
+        let path = relative_path.to_string_lossy().to_ascii_lowercase();
+        let components: Vec<&str> = path
+            .split(['/', '\\'])
+            .filter(|c| !c.is_empty() && *c != ".")
+            .collect();
+        if components.is_empty() {
+            return None;
+        }
+
+        self.patterns
+            .iter()
+            .find(|pattern| {
+                if pattern.contains('/') {
+                    let segments: Vec<&str> = pattern.split('/').collect();
+                    match_segments(&segments, &components)
+                } else {
+                    components.iter().any(|c| match_component(pattern, c))
+                }
+            })
+            .map(String::as_str)
+    }
+}
+
+/// Match path components against pattern segments, where a `**` segment
+/// matches zero or more components.
+fn match_segments(segments: &[&str], components: &[&str]) -> bool {
+    match segments.split_first() {
+        None => components.is_empty(),
+        Some((&"**", rest)) => {
+            (0..=components.len()).any(|skip| match_segments(rest, &components[skip..]))
+        }
+        Some((segment, rest)) => match components.split_first() {
+            Some((component, remaining)) => {
+                match_component(segment, component) && match_segments(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Match a single path component, where `*` matches any run of characters
+/// and `?` any single character.
+fn match_component(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it is currently matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn denied(patterns: &[&str]) -> DeniedPaths {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        DeniedPaths::new(&patterns)
+    }
+
+    #[test]
+    fn test_name_patterns_match_at_any_depth() {
+        let denied = denied(&[".env*", "*.pem", "id_rsa*"]);
+        assert_eq!(denied.matching(Path::new(".env")), Some(".env*"));
+        assert_eq!(denied.matching(Path::new("app/.env.local")), Some(".env*"));
+        assert_eq!(
+            denied.matching(Path::new("certs/Server.PEM")),
+            Some("*.pem")
+        );
+        assert_eq!(denied.matching(Path::new("id_rsa.pub")), Some("id_rsa*"));
+        assert_eq!(denied.matching(Path::new("src/environment.rs")), None);
+        assert_eq!(denied.matching(Path::new(".")), None);
+    }
+
+    #[test]
+    fn test_path_patterns_match_whole_path() {
+        let denied = denied(&["**/secrets/**", "config/*.json"]);
+        assert!(denied.matching(Path::new("secrets")).is_some());
+        assert!(denied
+            .matching(Path::new("deploy/secrets/prod.yaml"))
+            .is_some());
+        assert!(denied.matching(Path::new("config/app.json")).is_some());
+        assert!(denied
+            .matching(Path::new("config/nested/app.json"))
+            .is_none());
+        assert!(denied.matching(Path::new("src/secrets.rs")).is_none());
+    }
+}
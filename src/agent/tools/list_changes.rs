@@ -0,0 +1,91 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::agent::claude::ToolDefinition;
+use crate::agent::tools::{Tool, ToolOutput};
+use crate::error::Result;
+use crate::workspace::git;
+
+pub struct ListChangesTool;
+
+#[async_trait]
+impl Tool for ListChangesTool {
+    fn name(&self) -> &str {
+        "list_changes"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_changes".to_string(),
+            description: "List the files you have added (A), modified (M) or deleted (D) so far, like `git status --short`. Use this to keep track of your work and to write an accurate summary.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {}
+            }),
+            cache_control: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        workspace_root: &Path,
+        _input: serde_json::Value,
+    ) -> Result<ToolOutput> {
+        let changes = match git::changed_files(workspace_root).await {
+            Ok(changes) => changes,
+            Err(e) => return Ok(ToolOutput::Error(format!("Failed to list changes: {e}"))),
+        };
+
+        if changes.is_empty() {
+            return Ok(ToolOutput::Success("No files changed yet.".to_string()));
+        }
+
+        let listing = changes
+            .iter()
+            .map(|(path, change)| format!("{} {path}", change.code()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(ToolOutput::Success(listing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_changes_reports_added_modified_deleted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(tmp.path()).unwrap();
+        std::fs::write(tmp.path().join("keep.rs"), "fn main() {}").unwrap();
+        std::fs::write(tmp.path().join("gone.rs"), "").unwrap();
+        std::fs::write(tmp.path().join(".gitignore"), "target/\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+            .unwrap();
+
+        let tool = ListChangesTool;
+        let output = tool.execute(tmp.path(), json!({})).await.unwrap();
+        assert!(matches!(output, ToolOutput::Success(s) if s == "No files changed yet."));
+
+        std::fs::write(tmp.path().join("keep.rs"), "fn main() { run() }").unwrap();
+        std::fs::remove_file(tmp.path().join("gone.rs")).unwrap();
+        std::fs::create_dir_all(tmp.path().join("src")).unwrap();
+        std::fs::write(tmp.path().join("src/new.rs"), "").unwrap();
+        std::fs::create_dir_all(tmp.path().join("target")).unwrap();
+        std::fs::write(tmp.path().join("target/out"), "").unwrap();
+
+        let output = tool.execute(tmp.path(), json!({})).await.unwrap();
+        assert!(
+            matches!(output, ToolOutput::Success(s) if s == "D gone.rs\nM keep.rs\nA src/new.rs")
+        );
+    }
+}
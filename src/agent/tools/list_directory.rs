@@ -4,10 +4,25 @@ use async_trait::async_trait;
 use serde_json::json;
 
 use crate::agent::claude::ToolDefinition;
-use crate::agent::tools::{require_param, verified_path, Tool, ToolOutput};
+use crate::agent::tools::{
+    require_param, verified_path, workspace_walker, DeniedPaths, Tool, ToolOutput,
+};
 use crate::error::Result;
 
-pub struct ListDirectoryTool;
+pub struct ListDirectoryTool {
+    denied: DeniedPaths,
+    /// Directory names left out besides the built-in excludes.
+    exclude_dirs: Vec<String>,
+}
+
+impl ListDirectoryTool {
+    pub fn new(denied: DeniedPaths, exclude_dirs: Vec<String>) -> Self {
+        Self {
+            denied,
+            exclude_dirs,
+        }
+    }
+}
 
 #[async_trait]
 impl Tool for ListDirectoryTool {
@@ -40,7 +55,7 @@ impl Tool for ListDirectoryTool {
     ) -> Result<ToolOutput> {
         let path_str = require_param!(input, "path");
 
-        let full_path = match verified_path(workspace_root, path_str) {
+        let full_path = match verified_path(workspace_root, path_str, &self.denied) {
             Ok(p) => p,
             Err(e) => return Ok(e),
         };
@@ -53,23 +68,25 @@ impl Tool for ListDirectoryTool {
             return Ok(ToolOutput::Error(format!("{path_str} is not a directory")));
         }
 
+        let walker = workspace_walker(&full_path, &self.exclude_dirs)
+            .max_depth(Some(1))
+            .build();
         let mut entries = Vec::new();
-        let mut read_dir = tokio::fs::read_dir(&full_path).await.map_err(|e| {
-            crate::error::AppError::Workspace(format!("Failed to read directory: {e}"))
-        })?;
-
-        while let Some(entry) = read_dir.next_entry().await.map_err(|e| {
-            crate::error::AppError::Workspace(format!("Failed to read directory entry: {e}"))
-        })? {
+        for entry in walker {
+            let entry = entry.map_err(|e| {
+                crate::error::AppError::Workspace(format!("Failed to read directory entry: {e}"))
+            })?;
+            // The walk starts with the directory itself
+            if entry.depth() == 0 {
+                continue;
+            }
             let name = entry.file_name().to_string_lossy().to_string();
             // Skip hidden files like .git
             if name.starts_with('.') {
                 continue;
             }
-            let file_type = entry.file_type().await.map_err(|e| {
-                crate::error::AppError::Workspace(format!("Failed to get file type: {e}"))
-            })?;
-            let kind = if file_type.is_dir() { "dir" } else { "file" };
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            let kind = if is_dir { "dir" } else { "file" };
             entries.push(format!("{name} ({kind})"));
         }
 
@@ -82,3 +99,23 @@ impl Tool for ListDirectoryTool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_directory_skips_ignored_and_excluded_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for dir in ["src", "dist", "target"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join(".gitignore"), "dist/\n").unwrap();
+        std::fs::write(root.join("Cargo.toml"), "").unwrap();
+
+        let tool = ListDirectoryTool::new(DeniedPaths::default(), Vec::new());
+        let output = tool.execute(root, json!({ "path": "." })).await.unwrap();
+        assert!(matches!(output, ToolOutput::Success(s) if s == "Cargo.toml (file)\nsrc (dir)"));
+    }
+}
@@ -1,9 +1,14 @@
+pub mod append_file;
 pub mod ask_clarification;
 pub mod create_file;
+pub mod create_or_update_file;
 pub mod delete_file;
+pub mod denied_paths;
+pub mod list_changes;
 pub mod list_directory;
 pub mod read_file;
 pub mod search_code;
+pub mod submit_plan;
 pub mod write_file;
 
 use std::path::Path;
@@ -12,6 +17,9 @@ use async_trait::async_trait;
 
 use crate::agent::claude::ToolDefinition;
 use crate::error::Result;
+use crate::workspace::manager::WorkspaceManager;
+
+pub use denied_paths::DeniedPaths;
 
 /// Extract a required string param from tool input, or early-return a `ToolOutput::Error`.
 macro_rules! require_param {
@@ -24,15 +32,75 @@ macro_rules! require_param {
 }
 pub(crate) use require_param;
 
-/// Verify a path is inside the workspace, or early-return a `ToolOutput::Error`.
+/// Verify a path is inside the workspace and not denied, or early-return a
+/// `ToolOutput::Error`. Nothing is created on disk.
 pub fn verified_path(
     workspace_root: &Path,
     path_str: &str,
+    denied: &DeniedPaths,
+) -> std::result::Result<std::path::PathBuf, ToolOutput> {
+    let full_path = WorkspaceManager::verify_path(workspace_root, Path::new(path_str))
+        .map_err(|e| ToolOutput::Error(format!("Invalid path: {e}")))?;
+    check_denied(workspace_root, path_str, &full_path, denied)?;
+    Ok(full_path)
+}
+
+/// Like [`verified_path`], for a file about to be created: missing parent
+/// directories are created once the path is known to be allowed.
+pub fn verified_new_path(
+    workspace_root: &Path,
+    path_str: &str,
+    denied: &DeniedPaths,
 ) -> std::result::Result<std::path::PathBuf, ToolOutput> {
-    crate::workspace::manager::WorkspaceManager::verify_path(workspace_root, Path::new(path_str))
+    // Reject denied paths before any directory is created for them
+    verified_path(workspace_root, path_str, denied)?;
+    WorkspaceManager::verify_path_for_create(workspace_root, Path::new(path_str))
         .map_err(|e| ToolOutput::Error(format!("Invalid path: {e}")))
 }
 
+fn check_denied(
+    workspace_root: &Path,
+    path_str: &str,
+    full_path: &Path,
+    denied: &DeniedPaths,
+) -> std::result::Result<(), ToolOutput> {
+    // Match on the resolved path so `..` and symlinks can't dodge the patterns
+    let relative = workspace_root
+        .canonicalize()
+        .ok()
+        .and_then(|root| full_path.strip_prefix(root).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| Path::new(path_str).to_path_buf());
+    if let Some(pattern) = denied.matching(&relative) {
+        return Err(ToolOutput::Error(format!(
+            "Access to {path_str} is denied (matches protected pattern '{pattern}')"
+        )));
+    }
+    Ok(())
+}
+
+/// Directories never searched or listed, whatever the repo's ignore files say.
+const EXCLUDED_DIRS: &[&str] = &[".git", "node_modules", "target", ".venv", "vendor"];
+
+/// Walker over `dir` that honours the repo's `.gitignore` files (also in a
+/// workspace without `.git`, such as a research tarball) and always skips
+/// `EXCLUDED_DIRS` and any directory named in `extra_excluded`. Hidden files
+/// are included.
+pub fn workspace_walker(dir: &Path, extra_excluded: &[String]) -> ignore::WalkBuilder {
+    let extra_excluded = extra_excluded.to_vec();
+    let mut builder = ignore::WalkBuilder::new(dir);
+    builder
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            let name = entry.file_name();
+            !(is_dir
+                && (EXCLUDED_DIRS.iter().any(|d| name == *d)
+                    || extra_excluded.iter().any(|d| name == d.as_str())))
+        });
+    builder
+}
+
 #[async_trait]
 pub trait Tool: Send + Sync {
     fn name(&self) -> &str;
@@ -51,6 +119,8 @@ pub enum ToolOutput {
     Error(String),
     /// Special signal: agent needs human input.
     ClarificationNeeded(String),
+    /// The agent's plan, to be shared while the run carries on.
+    Plan(String),
 }
 
 pub struct ToolRegistry {
@@ -58,20 +128,43 @@ pub struct ToolRegistry {
 }
 
 impl ToolRegistry {
-    pub fn new(max_file_size: usize, max_search_results: usize) -> Self {
+    pub fn new(
+        max_file_size: usize,
+        max_search_results: usize,
+        denied: DeniedPaths,
+        exclude_dirs: Vec<String>,
+    ) -> Self {
         let tools: Vec<Box<dyn Tool>> = vec![
-            Box::new(read_file::ReadFileTool::new(max_file_size)),
-            Box::new(list_directory::ListDirectoryTool),
-            Box::new(search_code::SearchCodeTool::new(max_search_results)),
-            Box::new(write_file::WriteFileTool),
-            Box::new(create_file::CreateFileTool),
-            Box::new(delete_file::DeleteFileTool),
+            Box::new(read_file::ReadFileTool::new(max_file_size, denied.clone())),
+            Box::new(list_directory::ListDirectoryTool::new(
+                denied.clone(),
+                exclude_dirs.clone(),
+            )),
+            Box::new(search_code::SearchCodeTool::new(
+                max_search_results,
+                denied.clone(),
+                exclude_dirs,
+            )),
+            Box::new(write_file::WriteFileTool::new(max_file_size, denied.clone())),
+            Box::new(append_file::AppendFileTool::new(max_file_size, denied.clone())),
+            Box::new(create_file::CreateFileTool::new(max_file_size, denied.clone())),
+            Box::new(create_or_update_file::CreateOrUpdateFileTool::new(
+                max_file_size,
+                denied.clone(),
+            )),
+            Box::new(delete_file::DeleteFileTool::new(denied)),
+            Box::new(list_changes::ListChangesTool),
             Box::new(ask_clarification::AskClarificationTool),
         ];
 
         Self { tools }
     }
 
+    /// Offer an extra tool, such as one only some runs get.
+    pub fn register(&mut self, tool: Box<dyn Tool>) {
+        self.tools.push(tool);
+    }
+
     pub fn definitions(&self) -> Vec<ToolDefinition> {
         self.tools.iter().map(|t| t.definition()).collect()
     }
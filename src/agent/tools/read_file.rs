@@ -1,19 +1,70 @@
+use std::io::SeekFrom;
 use std::path::Path;
 
 use async_trait::async_trait;
 use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
 use crate::agent::claude::ToolDefinition;
-use crate::agent::tools::{require_param, verified_path, Tool, ToolOutput};
+use crate::agent::tools::{require_param, verified_path, DeniedPaths, Tool, ToolOutput};
 use crate::error::Result;
+use crate::workspace::git;
+
+/// Bytes sniffed for a NUL when deciding whether a file is binary (as git does).
+const BINARY_SNIFF_BYTES: usize = 8000;
 
 pub struct ReadFileTool {
     max_file_size: usize,
+    denied: DeniedPaths,
 }
 
 impl ReadFileTool {
-    pub fn new(max_file_size: usize) -> Self {
-        Self { max_file_size }
+    pub fn new(max_file_size: usize, denied: DeniedPaths) -> Self {
+        Self {
+            max_file_size,
+            denied,
+        }
+    }
+
+    fn too_large(&self, len: usize) -> Option<ToolOutput> {
+        (len > self.max_file_size).then(|| {
+            ToolOutput::Error(format!(
+                "File is too large ({len} bytes, max {} bytes)",
+                self.max_file_size
+            ))
+        })
+    }
+
+    /// Read the blob at `rev`, looked up by the verified path relative to the
+    /// workspace root so `./` and `..` segments resolve as on disk. A blob
+    /// over the size limit is only returned when `excerpt` is set.
+    async fn read_at_ref(
+        &self,
+        workspace_root: &Path,
+        full_path: &Path,
+        rev: &str,
+        excerpt: bool,
+    ) -> std::result::Result<Vec<u8>, ToolOutput> {
+        let root = workspace_root
+            .canonicalize()
+            .map_err(|e| ToolOutput::Error(format!("Invalid path: {e}")))?;
+        let relative = full_path
+            .strip_prefix(&root)
+            .map_err(|_| ToolOutput::Error("Invalid path".to_string()))?;
+
+        match git::file_at_ref(workspace_root, rev, relative).await {
+            Ok(Some(bytes)) => match self.too_large(bytes.len()).filter(|_| !excerpt) {
+                Some(error) => Err(error),
+                None => Ok(bytes),
+            },
+            Ok(None) => Err(ToolOutput::Error(format!(
+                "File not found at {rev}: {}",
+                relative.display()
+            ))),
+            Err(e) => Err(ToolOutput::Error(format!(
+                "Failed to read file at {rev}: {e}"
+            ))),
+        }
     }
 }
 
@@ -26,13 +77,25 @@ impl Tool for ReadFileTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "read_file".to_string(),
-            description: "Read the contents of a file. Returns the file content as text. Use this to understand existing code before making changes.".to_string(),
+            description: "Read the contents of a file. Returns the file content as text. Use this to understand existing code before making changes. Pass `ref` to read the file as it was at a commit or branch instead, e.g. to compare your changes with the original. Pass `head` and/or `tail` to read only the first and/or last lines, which also works for files over the size limit, such as logs or generated files.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "path": {
                         "type": "string",
                         "description": "Relative path to the file from the repository root"
+                    },
+                    "ref": {
+                        "type": "string",
+                        "description": "Optional commit, branch or tag to read the file at (e.g. \"HEAD\", \"main\", a commit SHA). Omit to read the working tree."
+                    },
+                    "head": {
+                        "type": "integer",
+                        "description": "Optional number of lines to read from the start of the file"
+                    },
+                    "tail": {
+                        "type": "integer",
+                        "description": "Optional number of lines to read from the end of the file"
                     }
                 },
                 "required": ["path"]
@@ -48,35 +111,302 @@ impl Tool for ReadFileTool {
     ) -> Result<ToolOutput> {
         let path_str = require_param!(input, "path");
 
-        let full_path = match verified_path(workspace_root, path_str) {
+        let full_path = match verified_path(workspace_root, path_str, &self.denied) {
             Ok(p) => p,
             Err(e) => return Ok(e),
         };
 
-        if !full_path.exists() {
-            return Ok(ToolOutput::Error(format!("File not found: {path_str}")));
-        }
+        let lines = Lines {
+            head: input["head"].as_u64().map(|n| n as usize),
+            tail: input["tail"].as_u64().map(|n| n as usize),
+        };
+        let excerpt = lines.head.is_some() || lines.tail.is_some();
 
-        if !full_path.is_file() {
-            return Ok(ToolOutput::Error(format!("{path_str} is not a file")));
-        }
+        let bytes = match input["ref"].as_str() {
+            Some(rev) => match self
+                .read_at_ref(workspace_root, &full_path, rev, excerpt)
+                .await
+            {
+                Ok(bytes) if excerpt => {
+                    let len = bytes.len() as u64;
+                    let (prefix, suffix) = lines.windows(len, self.max_file_size);
+                    let prefix = &bytes[..prefix as usize];
+                    let suffix = &bytes[(len - suffix) as usize..];
+                    return Ok(render_excerpt(path_str, len, &lines, prefix, suffix));
+                }
+                Ok(bytes) => bytes,
+                Err(e) => return Ok(e),
+            },
+            None => {
+                if !full_path.exists() {
+                    return Ok(ToolOutput::Error(format!("File not found: {path_str}")));
+                }
 
-        // Check file size
-        let metadata = tokio::fs::metadata(&full_path).await.map_err(|e| {
-            crate::error::AppError::Workspace(format!("Failed to read file metadata: {e}"))
-        })?;
+                if !full_path.is_file() {
+                    return Ok(ToolOutput::Error(format!("{path_str} is not a file")));
+                }
 
-        if metadata.len() as usize > self.max_file_size {
+                // Check file size
+                let metadata = tokio::fs::metadata(&full_path).await.map_err(|e| {
+                    crate::error::AppError::Workspace(format!("Failed to read file metadata: {e}"))
+                })?;
+
+                if excerpt {
+                    let len = metadata.len();
+                    let (prefix, suffix) = lines.windows(len, self.max_file_size);
+                    let read = async {
+                        let mut file = tokio::fs::File::open(&full_path).await?;
+                        let mut head = Vec::new();
+                        (&mut file).take(prefix).read_to_end(&mut head).await?;
+                        file.seek(SeekFrom::Start(len - suffix)).await?;
+                        let mut tail = Vec::new();
+                        file.take(suffix).read_to_end(&mut tail).await?;
+                        std::io::Result::Ok((head, tail))
+                    };
+                    return Ok(match read.await {
+                        Ok((head, tail)) => render_excerpt(path_str, len, &lines, &head, &tail),
+                        Err(e) => ToolOutput::Error(format!("Failed to read file: {e}")),
+                    });
+                }
+
+                if let Some(error) = self.too_large(metadata.len() as usize) {
+                    return Ok(error);
+                }
+
+                match tokio::fs::read(&full_path).await {
+                    Ok(bytes) => bytes,
+                    Err(e) => return Ok(ToolOutput::Error(format!("Failed to read file: {e}"))),
+                }
+            }
+        };
+
+        if is_binary(&bytes) {
             return Ok(ToolOutput::Error(format!(
-                "File is too large ({} bytes, max {} bytes)",
-                metadata.len(),
-                self.max_file_size
+                "Cannot read binary file {path_str}"
             )));
         }
 
-        match tokio::fs::read_to_string(&full_path).await {
+        match String::from_utf8(bytes) {
             Ok(content) => Ok(ToolOutput::Success(content)),
-            Err(e) => Ok(ToolOutput::Error(format!("Failed to read file: {e}"))),
+            Err(_) => Ok(ToolOutput::Error(format!(
+                "Cannot read {path_str}: file is not valid UTF-8 text"
+            ))),
         }
     }
 }
+
+/// Lines asked for with the `head` and `tail` parameters.
+struct Lines {
+    head: Option<usize>,
+    tail: Option<usize>,
+}
+
+impl Lines {
+    /// Bytes to read from the start and the end of a file of `len` bytes to
+    /// find the lines, splitting `max_bytes` between the two ends.
+    fn windows(&self, len: u64, max_bytes: usize) -> (u64, u64) {
+        let ends = self.head.is_some() as usize + self.tail.is_some() as usize;
+        let budget = len.min((max_bytes / ends.max(1)) as u64);
+        (
+            if self.head.is_some() { budget } else { 0 },
+            if self.tail.is_some() { budget } else { 0 },
+        )
+    }
+}
+
+/// The requested lines of a file of `len` bytes, from `prefix` read at its
+/// start and `suffix` read at its end, marking where part of it was left out.
+fn render_excerpt(
+    path_str: &str,
+    len: u64,
+    lines: &Lines,
+    prefix: &[u8],
+    suffix: &[u8],
+) -> ToolOutput {
+    let head = lines.head.map_or(&prefix[..0], |n| first_lines(prefix, n));
+    let tail = lines
+        .tail
+        .map_or(&suffix[suffix.len()..], |n| last_lines(suffix, n));
+    if is_binary(head) || is_binary(tail) {
+        return ToolOutput::Error(format!("Cannot read binary file {path_str}"));
+    }
+
+    let head_end = head.len() as u64;
+    let tail_start = len - tail.len() as u64;
+    let mut content = String::from_utf8_lossy(head).into_owned();
+    if head_end >= tail_start {
+        // The two ends meet, so this is the whole file
+        content.push_str(&String::from_utf8_lossy(
+            &tail[(head_end - tail_start) as usize..],
+        ));
+        return ToolOutput::Success(content);
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&format!(
+        "[... {} of {len} bytes omitted ...]\n",
+        tail_start - head_end
+    ));
+    content.push_str(&String::from_utf8_lossy(tail));
+    ToolOutput::Success(content)
+}
+
+/// The first `n` lines of `bytes`, or all of it if it has fewer.
+fn first_lines(bytes: &[u8], n: usize) -> &[u8] {
+    if n == 0 {
+        return &bytes[..0];
+    }
+    match bytes
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| **b == b'\n')
+        .nth(n - 1)
+    {
+        Some((i, _)) => &bytes[..=i],
+        None => bytes,
+    }
+}
+
+/// The last `n` lines of `bytes`, or all of it if it has fewer. A final
+/// newline doesn't start another line.
+fn last_lines(bytes: &[u8], n: usize) -> &[u8] {
+    if n == 0 {
+        return &bytes[bytes.len()..];
+    }
+    let body = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    match body
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, b)| **b == b'\n')
+        .nth(n - 1)
+    {
+        Some((i, _)) => &bytes[i + 1..],
+        None => bytes,
+    }
+}
+
+/// Whether `bytes` look like binary content: a NUL byte near the start.
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_file_refuses_binary() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("logo.png"), b"\x89PNG\r\n\x1a\n\0\0\0\r").unwrap();
+        std::fs::write(tmp.path().join("latin1.txt"), b"caf\xe9").unwrap();
+        let tool = ReadFileTool::new(1024, DeniedPaths::default());
+
+        let output = tool
+            .execute(tmp.path(), json!({ "path": "logo.png" }))
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Error(e) if e == "Cannot read binary file logo.png"));
+
+        let output = tool
+            .execute(tmp.path(), json!({ "path": "latin1.txt" }))
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Error(e) if e.contains("not valid UTF-8")));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_at_ref() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(tmp.path()).unwrap();
+        std::fs::create_dir(tmp.path().join("src")).unwrap();
+        std::fs::write(tmp.path().join("src/lib.rs"), "fn old() {}").unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+            .unwrap();
+        std::fs::write(tmp.path().join("src/lib.rs"), "fn new() {}").unwrap();
+        let tool = ReadFileTool::new(1024, DeniedPaths::default());
+
+        let output = tool
+            .execute(tmp.path(), json!({ "path": "src/lib.rs" }))
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Success(s) if s == "fn new() {}"));
+
+        let output = tool
+            .execute(tmp.path(), json!({ "path": "./src/lib.rs", "ref": "HEAD" }))
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Success(s) if s == "fn old() {}"));
+
+        let output = tool
+            .execute(tmp.path(), json!({ "path": "src/main.rs", "ref": "HEAD" }))
+            .await
+            .unwrap();
+        assert!(
+            matches!(output, ToolOutput::Error(e) if e == "File not found at HEAD: src/main.rs")
+        );
+
+        let output = tool
+            .execute(
+                tmp.path(),
+                json!({ "path": "src/lib.rs", "ref": "no-such-branch" }),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Error(e) if e.contains("Unknown revision")));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_head_and_tail_of_large_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log: String = (1..=100).map(|i| format!("line {i}\n")).collect();
+        std::fs::write(tmp.path().join("build.log"), &log).unwrap();
+        let tool = ReadFileTool::new(64, DeniedPaths::default());
+
+        let output = tool
+            .execute(tmp.path(), json!({ "path": "build.log" }))
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Error(e) if e.contains("too large")));
+
+        let output = tool
+            .execute(
+                tmp.path(),
+                json!({ "path": "build.log", "head": 2, "tail": 2 }),
+            )
+            .await
+            .unwrap();
+        let omitted = log.len() - "line 1\nline 2\nline 99\nline 100\n".len();
+        let expected = format!(
+            "line 1\nline 2\n[... {omitted} of {} bytes omitted ...]\nline 99\nline 100\n",
+            log.len()
+        );
+        assert!(matches!(output, ToolOutput::Success(s) if s == expected));
+
+        let output = tool
+            .execute(tmp.path(), json!({ "path": "build.log", "tail": 1 }))
+            .await
+            .unwrap();
+        assert!(
+            matches!(output, ToolOutput::Success(s) if s.ends_with("bytes omitted ...]\nline 100\n"))
+        );
+
+        std::fs::write(tmp.path().join("short.txt"), "a\nb\nc\n").unwrap();
+        let output = tool
+            .execute(
+                tmp.path(),
+                json!({ "path": "short.txt", "head": 2, "tail": 2 }),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Success(s) if s == "a\nb\nc\n"));
+    }
+}
@@ -5,16 +5,26 @@ use async_trait::async_trait;
 use serde_json::json;
 
 use crate::agent::claude::ToolDefinition;
-use crate::agent::tools::{require_param, Tool, ToolOutput};
-use crate::error::Result;
+use crate::agent::tools::{require_param, workspace_walker, DeniedPaths, Tool, ToolOutput};
+use crate::error::{AppError, Result};
+
+/// Files passed to a single grep invocation.
+const SEARCH_BATCH_FILES: usize = 500;
 
 pub struct SearchCodeTool {
     max_results: usize,
+    denied: DeniedPaths,
+    /// Directory names skipped besides the built-in excludes.
+    exclude_dirs: Vec<String>,
 }
 
 impl SearchCodeTool {
-    pub fn new(max_results: usize) -> Self {
-        Self { max_results }
+    pub fn new(max_results: usize, denied: DeniedPaths, exclude_dirs: Vec<String>) -> Self {
+        Self {
+            max_results,
+            denied,
+            exclude_dirs,
+        }
     }
 }
 
@@ -70,8 +80,28 @@ impl Tool for SearchCodeTool {
             )));
         }
 
+        // Files left after the repo's ignore rules and the excluded directories
+        let walk_dir = search_dir.clone();
+        let exclude_dirs = self.exclude_dirs.clone();
+        let files = tokio::task::spawn_blocking(move || {
+            workspace_walker(&walk_dir, &exclude_dirs)
+                .build()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+                .filter_map(|entry| {
+                    entry
+                        .path()
+                        .strip_prefix(&walk_dir)
+                        .ok()
+                        .map(Path::to_path_buf)
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .map_err(|e| AppError::Workspace(format!("Search walk task panicked: {e}")))?;
+
         let mut args = vec![
-            "-rn".to_string(),
+            "-Hn".to_string(),
             "--max-count=5".to_string(), // Max matches per file
             format!("--max-count={}", self.max_results),
         ];
@@ -80,51 +110,111 @@ impl Tool for SearchCodeTool {
             args.push(format!("--include={include}"));
         }
 
-        // Exclude common non-code directories
-        args.extend_from_slice(&[
-            "--exclude-dir=.git".to_string(),
-            "--exclude-dir=node_modules".to_string(),
-            "--exclude-dir=target".to_string(),
-            "--exclude-dir=.venv".to_string(),
-            "--exclude-dir=vendor".to_string(),
-        ]);
-
+        args.push("-e".to_string());
         args.push(pattern.to_string());
-        args.push(".".to_string());
-
-        let output = tokio::process::Command::new("grep")
-            .args(&args)
-            .current_dir(&search_dir)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .await;
-
-        match output {
-            Ok(output) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-
-                if stdout.is_empty() {
-                    return Ok(ToolOutput::Success(
-                        "No matches found".to_string(),
-                    ));
-                }
-
-                // Truncate to max results
-                let lines: Vec<&str> = stdout.lines().take(self.max_results).collect();
-                let result = lines.join("\n");
-
-                let total_lines = stdout.lines().count();
-                if total_lines > self.max_results {
-                    Ok(ToolOutput::Success(format!(
-                        "{result}\n\n... ({} more matches truncated)",
-                        total_lines - self.max_results
-                    )))
-                } else {
-                    Ok(ToolOutput::Success(result))
-                }
+        args.push("--".to_string());
+
+        // Grep the files in batches to stay under the argument length limit,
+        // stopping once there are more matches than will be returned
+        let mut raw = String::new();
+        for batch in files.chunks(SEARCH_BATCH_FILES) {
+            let output = tokio::process::Command::new("grep")
+                .args(&args)
+                .args(batch)
+                .current_dir(&search_dir)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+                .await;
+            match output {
+                Ok(output) => raw.push_str(&String::from_utf8_lossy(&output.stdout)),
+                Err(e) => return Ok(ToolOutput::Error(format!("Search failed: {e}"))),
+            }
+            if raw.lines().count() > self.max_results {
+                break;
             }
-            Err(e) => Ok(ToolOutput::Error(format!("Search failed: {e}"))),
         }
+
+        // Drop matches in denied files so their contents never reach the agent
+        let search_root = Path::new(input["path"].as_str().unwrap_or("."));
+        let stdout = raw
+            .lines()
+            .filter(|line| {
+                let file = line.split(':').next().unwrap_or_default();
+                self.denied.matching(&search_root.join(file)).is_none()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if stdout.is_empty() {
+            return Ok(ToolOutput::Success("No matches found".to_string()));
+        }
+
+        // Truncate to max results
+        let lines: Vec<&str> = stdout.lines().take(self.max_results).collect();
+        let result = lines.join("\n");
+
+        let total_lines = stdout.lines().count();
+        if total_lines > self.max_results {
+            Ok(ToolOutput::Success(format!(
+                "{result}\n\n... ({} more matches truncated)",
+                total_lines - self.max_results
+            )))
+        } else {
+            Ok(ToolOutput::Success(result))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_skips_ignored_and_excluded_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for dir in ["src", "generated", "node_modules"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join(".gitignore"), "generated/\n*.log\n").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "let needle = 1;\n").unwrap();
+        std::fs::write(root.join("generated/out.rs"), "let needle = 2;\n").unwrap();
+        std::fs::write(root.join("build.log"), "needle\n").unwrap();
+        std::fs::write(root.join("node_modules/dep.js"), "needle\n").unwrap();
+
+        let tool = SearchCodeTool::new(50, DeniedPaths::default(), Vec::new());
+        let output = tool
+            .execute(root, json!({ "pattern": "needle" }))
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Success(s) if s == "src/lib.rs:1:let needle = 1;"));
+    }
+
+    #[tokio::test]
+    async fn test_search_skips_configured_exclude_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for dir in ["src", "web/dist", "web/src"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("src/lib.rs"), "let needle = 1;\n").unwrap();
+        std::fs::write(root.join("web/dist/app.js"), "needle\n").unwrap();
+        std::fs::write(root.join("web/src/app.ts"), "needle\n").unwrap();
+
+        let tool = SearchCodeTool::new(50, DeniedPaths::default(), vec!["dist".to_string()]);
+        let output = tool
+            .execute(root, json!({ "pattern": "needle" }))
+            .await
+            .unwrap();
+        let ToolOutput::Success(matches) = output else {
+            panic!("expected matches");
+        };
+        let mut files: Vec<&str> = matches
+            .lines()
+            .filter_map(|l| l.split(':').next())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["src/lib.rs", "web/src/app.ts"]);
     }
 }
@@ -0,0 +1,50 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::agent::claude::ToolDefinition;
+use crate::agent::tools::{require_param, Tool, ToolOutput};
+use crate::error::Result;
+
+/// Reports the agent's intended approach so it can be shared before any code
+/// changes. Unlike `ask_clarification` the run carries on.
+pub struct SubmitPlanTool;
+
+#[async_trait]
+impl Tool for SubmitPlanTool {
+    fn name(&self) -> &str {
+        "submit_plan"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "submit_plan".to_string(),
+            description: "Share your plan for resolving the issue. Call this once, after exploring enough to know your approach and before changing any files. The plan is posted as a comment on the issue; you then continue with the implementation without waiting for a reply.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "plan": {
+                        "type": "string",
+                        "description": "Your intended approach in Markdown: the files you expect to change and what you will do in each"
+                    }
+                },
+                "required": ["plan"]
+            }),
+            cache_control: None,
+        }
+    }
+
+    async fn execute(
+        &self,
+        _workspace_root: &Path,
+        input: serde_json::Value,
+    ) -> Result<ToolOutput> {
+        let plan = require_param!(input, "plan").trim();
+        if plan.is_empty() {
+            return Ok(ToolOutput::Error("The plan is empty".to_string()));
+        }
+
+        Ok(ToolOutput::Plan(plan.to_string()))
+    }
+}
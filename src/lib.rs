@@ -1,10 +1,14 @@
 pub mod agent;
 pub mod config;
 pub mod error;
+pub mod history;
+pub mod metrics;
+pub mod notify;
 pub mod platform;
 pub mod queue;
 pub mod server;
 pub mod shutdown;
+pub mod task_log;
 pub mod webhook;
 pub mod workflow;
 pub mod workspace;
@@ -1,31 +1,75 @@
 use std::sync::Arc;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
-use mycelium::config::AppConfig;
+use mycelium::config::{AppConfig, GitHubAuth, LogFormat};
+use mycelium::queue::task::IssueMode;
 use mycelium::server::{create_router, AppState};
 use mycelium::shutdown::{graceful_shutdown, wait_for_shutdown};
+use mycelium::task_log::TaskLogLayer;
+use mycelium::workflow::types::WorkflowOutcome;
 
 #[derive(Parser)]
 #[command(name = "mycelium", about = "AI-powered GitHub issue resolver")]
 struct Cli {
     /// Path to configuration file
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     config: Option<String>,
+
+    /// Resolve a single issue and exit instead of starting the webhook server
+    #[arg(long, requires_all = ["repo", "issue"])]
+    once: bool,
+
+    /// Repository of the issue to resolve with --once (owner/name)
+    #[arg(long, requires = "once")]
+    repo: Option<String>,
+
+    /// Issue number to resolve with --once
+    #[arg(long, requires = "once")]
+    issue: Option<u64>,
+
+    /// Post research findings instead of opening a PR (with --once)
+    #[arg(long, requires = "once")]
+    research: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Load and validate the configuration, print a summary and exit
+    ValidateConfig,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
     let cli = Cli::parse();
 
+    if let Some(Command::ValidateConfig) = cli.command {
+        return validate_config(cli.config.as_deref());
+    }
+
+    // Loaded before tracing starts, since it picks the log format
     let config = AppConfig::load(cli.config.as_deref())?;
+    init_tracing(
+        config.server.log_format,
+        config.agent.failure_log_lines > 0,
+    );
+    config.validate()?;
+
+    if config.dry_run {
+        tracing::warn!("Dry run: pushes, PRs, comments and label changes are only logged");
+    }
+
+    if cli.once {
+        // Both are enforced by clap when --once is set
+        let (Some(repo), Some(issue)) = (cli.repo.as_deref(), cli.issue) else {
+            anyhow::bail!("--once requires --repo and --issue");
+        };
+        return run_once(config, repo, issue, cli.research).await;
+    }
 
     tracing::info!(
         host = %config.server.host,
@@ -35,6 +79,13 @@ async fn main() -> anyhow::Result<()> {
 
     let state = Arc::new(AppState::new(config.clone()).await?);
 
+    // Resume work that was running or queued when the last process shut down
+    mycelium::queue::startup::restore_saved_tasks(&state).await;
+
+    // Reclaim disk from workspaces left by crashed or failed runs, after
+    // restoring so the workspaces of resumed tasks are kept
+    mycelium::queue::startup::sweep_stale_workspaces(&state).await;
+
     // Start the task queue processor
     let queue_state = Arc::clone(&state);
     tokio::spawn(async move {
@@ -47,6 +98,11 @@ async fn main() -> anyhow::Result<()> {
         mycelium::queue::startup::scan_pending_issues(&scan_state).await;
     });
 
+    // Periodically rescan for issues whose webhook was missed
+    tokio::spawn(mycelium::queue::startup::rescan_periodically(Arc::clone(
+        &state,
+    )));
+
     let app = create_router(Arc::clone(&state));
 
     let listener = tokio::net::TcpListener::bind(format!(
@@ -67,3 +123,93 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Resolve one issue without the webhook server or task queue, then exit.
+async fn run_once(config: AppConfig, repo: &str, issue: u64, research: bool) -> anyhow::Result<()> {
+    let state = AppState::new(config).await?;
+    let mode = if research {
+        IssueMode::Research
+    } else {
+        IssueMode::Implement
+    };
+
+    let outcome =
+        mycelium::workflow::oneshot::resolve_single_issue(&state, repo, issue, mode).await?;
+
+    tracing::info!(
+        repo,
+        issue,
+        outcome = outcome.label(),
+        "One-shot run finished"
+    );
+
+    if let WorkflowOutcome::Failed { error } = outcome {
+        anyhow::bail!("Failed to resolve {repo}#{issue}: {error}");
+    }
+
+    Ok(())
+}
+
+/// Log to stdout in `format`, filtered by `RUST_LOG` (default: info). With
+/// `capture_task_logs`, each task's lines are also kept for failure comments.
+fn init_tracing(format: LogFormat, capture_task_logs: bool) {
+    let registry = tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(capture_task_logs.then_some(TaskLogLayer));
+    match format {
+        LogFormat::Pretty => registry.with(tracing_subscriber::fmt::layer()).init(),
+        LogFormat::Json => registry
+            .with(tracing_subscriber::fmt::layer().json())
+            .init(),
+    }
+}
+
+/// Load and validate the config, printing a summary with secrets redacted.
+fn validate_config(config_path: Option<&str>) -> anyhow::Result<()> {
+    let config = AppConfig::load(config_path)?;
+
+    let presence = |secret: &str| {
+        if secret.is_empty() {
+            "absent"
+        } else {
+            "present"
+        }
+    };
+
+    match config.github.auth() {
+        Some(GitHubAuth::App {
+            app_id,
+            private_key_path,
+        }) => {
+            println!("app id:          {app_id}");
+            println!("private key:     {}", private_key_path.display());
+        }
+        Some(GitHubAuth::Token(_)) => println!("github auth:     personal access token"),
+        None => println!("github auth:     not configured"),
+    }
+    let webhook_secrets = match config.github.webhook_secret.len() {
+        0 => "absent".to_string(),
+        1 => "present".to_string(),
+        n => format!("{n} present (rotating)"),
+    };
+    println!("webhook secret:  {webhook_secrets}");
+    println!("trigger label:   {}", config.github.trigger_labels.join(", "));
+    println!("api key:         {}", presence(&config.claude.api_key));
+    println!("model:           {}", config.claude.model);
+    println!("workspace dir:   {}", config.workspace.base_dir.display());
+    println!("dry run:         {}", config.dry_run);
+    println!("log format:      {:?}", config.server.log_format);
+    match &config.server.history_path {
+        Some(path) => println!("history file:    {}", path.display()),
+        None => println!("history file:    not kept"),
+    }
+    println!(
+        "listen address:  {}:{}",
+        config.server.host, config.server.port
+    );
+
+    config.validate()?;
+
+    println!("Configuration is valid");
+    Ok(())
+}
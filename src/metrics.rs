@@ -0,0 +1,117 @@
+use std::sync::LazyLock;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+/// Process-wide Prometheus metrics, served by `GET /metrics` when enabled.
+pub struct Metrics {
+    registry: Registry,
+    /// Tasks added to the queue, by task kind.
+    pub tasks_enqueued: IntCounterVec,
+    /// Tasks that ran to a workflow outcome, by task kind and outcome.
+    pub tasks_completed: IntCounterVec,
+    /// Tasks that returned an error, by task kind.
+    pub tasks_failed: IntCounterVec,
+    /// Pending tasks across all repos (refreshed on scrape).
+    pub queue_depth: IntGauge,
+    /// Issues carrying the :working label (refreshed on scrape).
+    pub in_flight: IntGauge,
+    /// Claude tokens consumed, by type (input, output, cache_read, cache_creation).
+    pub claude_tokens: IntCounterVec,
+    /// Turns taken per agent run, by outcome.
+    pub agent_turns: HistogramVec,
+}
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("mycelium".to_string()), None).expect("valid metrics prefix");
+
+        let tasks_enqueued = IntCounterVec::new(
+            Opts::new("tasks_enqueued_total", "Tasks added to the queue"),
+            &["kind"],
+        )
+        .expect("valid metric");
+        let tasks_completed = IntCounterVec::new(
+            Opts::new(
+                "tasks_completed_total",
+                "Tasks that finished with a workflow outcome",
+            ),
+            &["kind", "outcome"],
+        )
+        .expect("valid metric");
+        let tasks_failed = IntCounterVec::new(
+            Opts::new("tasks_failed_total", "Tasks that returned an error"),
+            &["kind"],
+        )
+        .expect("valid metric");
+        let queue_depth = IntGauge::new("queue_depth", "Pending tasks across all repositories")
+            .expect("valid metric");
+        let in_flight = IntGauge::new("in_flight_issues", "Issues currently being worked on")
+            .expect("valid metric");
+        let claude_tokens = IntCounterVec::new(
+            Opts::new("claude_tokens_total", "Claude API tokens consumed"),
+            &["type"],
+        )
+        .expect("valid metric");
+        let agent_turns = HistogramVec::new(
+            HistogramOpts::new("agent_turns", "Turns taken per agent run")
+                .buckets(vec![1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 50.0, 75.0, 100.0]),
+            &["outcome"],
+        )
+        .expect("valid metric");
+
+        for collector in [
+            Box::new(tasks_enqueued.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(tasks_completed.clone()),
+            Box::new(tasks_failed.clone()),
+            Box::new(queue_depth.clone()),
+            Box::new(in_flight.clone()),
+            Box::new(claude_tokens.clone()),
+            Box::new(agent_turns.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            tasks_enqueued,
+            tasks_completed,
+            tasks_failed,
+            queue_depth,
+            in_flight,
+            claude_tokens,
+            agent_turns,
+        }
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut buf = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buf) {
+            tracing::warn!(error = %e, "Failed to encode metrics");
+        }
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_prefixed_metrics() {
+        METRICS
+            .tasks_enqueued
+            .with_label_values(&["resolve_issue"])
+            .inc();
+        let output = METRICS.render();
+        assert!(output.contains("mycelium_tasks_enqueued_total{kind=\"resolve_issue\"}"));
+        assert!(output.contains("mycelium_queue_depth"));
+    }
+}
@@ -0,0 +1,225 @@
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::Serialize;
+
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::queue::task::Task;
+use crate::workflow::types::WorkflowOutcome;
+
+/// Posts task outcomes to `notifications.webhook_url`.
+pub struct Notifier {
+    client: Client,
+    url: Option<String>,
+    slack: bool,
+    /// Web root PR links are built from, e.g. `https://github.com`.
+    web_url: String,
+}
+
+/// JSON body sent for a finished task.
+#[derive(Debug, Serialize)]
+pub struct Notification {
+    pub repo: String,
+    /// Task kind, e.g. `resolve_issue`.
+    pub task: &'static str,
+    /// Issue or PR number the task worked on.
+    pub number: u64,
+    /// Outcome label, or `error` when the task gave up with an error.
+    pub outcome: &'static str,
+    pub pr_number: Option<u64>,
+    pub pr_url: Option<String>,
+    pub error: Option<String>,
+    /// One-line human-readable summary.
+    pub text: String,
+}
+
+impl Notifier {
+    pub fn new(config: &AppConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.notifications.timeout_secs))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self {
+            client,
+            url: config.notifications.webhook_url.clone(),
+            slack: config.notifications.slack,
+            web_url: web_url(config.github.base_url.as_deref()),
+        }
+    }
+
+    /// Send a notification for a finished task in the background. Failures
+    /// are logged and never affect the task.
+    pub fn task_finished(&self, task: &Task, result: Result<&WorkflowOutcome, &AppError>) {
+        let Some(url) = self.url.clone() else {
+            return;
+        };
+        let notification = self.build(task, result);
+        let body = if self.slack {
+            serde_json::json!({ "text": notification.text })
+        } else {
+            serde_json::to_value(&notification).unwrap_or_default()
+        };
+
+        let client = self.client.clone();
+        tokio::spawn(async move {
+            let response = client.post(&url).json(&body).send().await;
+            match response.and_then(|r| r.error_for_status()) {
+                Ok(_) => tracing::debug!(
+                    repo = %notification.repo,
+                    number = notification.number,
+                    "Sent notification"
+                ),
+                Err(e) => tracing::warn!(
+                    repo = %notification.repo,
+                    number = notification.number,
+                    error = %e,
+                    "Failed to send notification"
+                ),
+            }
+        });
+    }
+
+    /// The notification describing a finished task, also what the history
+    /// store records.
+    pub(crate) fn build(
+        &self,
+        task: &Task,
+        result: Result<&WorkflowOutcome, &AppError>,
+    ) -> Notification {
+        let repo = task.repo_full_name().to_string();
+        let number = task.number();
+
+        let pr_number = match result {
+            Ok(
+                WorkflowOutcome::PullRequestCreated { pr_number }
+                | WorkflowOutcome::PullRequestUpdated { pr_number }
+                | WorkflowOutcome::PullRequestAlreadyOpen { pr_number },
+            ) => Some(*pr_number),
+            _ => None,
+        };
+        let pr_url = pr_number.map(|n| format!("{}/{repo}/pull/{n}", self.web_url));
+
+        let target = format!("{repo}#{number}");
+        let (outcome, error, text) = match result {
+            Ok(outcome) => {
+                let error = match outcome {
+                    WorkflowOutcome::Failed { error } => Some(error.clone()),
+                    _ => None,
+                };
+                let text = match (outcome, &pr_url) {
+                    (WorkflowOutcome::PullRequestCreated { .. }, Some(url)) => {
+                        format!("{target}: opened {url}")
+                    }
+                    (WorkflowOutcome::PullRequestUpdated { .. }, Some(url)) => {
+                        format!("{target}: updated {url}")
+                    }
+                    (WorkflowOutcome::PullRequestAlreadyOpen { .. }, Some(url)) => {
+                        format!("{target}: skipped, {url} is already open")
+                    }
+                    (WorkflowOutcome::ClarificationRequested, _) => {
+                        format!("{target}: needs clarification")
+                    }
+                    (WorkflowOutcome::AwaitingApproval, _) => {
+                        format!("{target}: changes awaiting approval")
+                    }
+                    (WorkflowOutcome::MergeConflict { files }, _) => {
+                        format!("{target}: conflicts with the base branch in {} file(s)", files.len())
+                    }
+                    (WorkflowOutcome::TooManyChangedFiles { files }, _) => {
+                        format!("{target}: not pushed, changed {} files", files.len())
+                    }
+                    (WorkflowOutcome::DiffTooLarge { lines }, _) => {
+                        format!("{target}: not pushed, changed {lines} lines")
+                    }
+                    (WorkflowOutcome::Failed { error }, _) => format!("{target}: failed: {error}"),
+                    (other, _) => format!("{target}: {}", other.label().replace('_', " ")),
+                };
+                (outcome.label(), error, text)
+            }
+            Err(e) => ("error", Some(e.to_string()), format!("{target}: error: {e}")),
+        };
+
+        Notification {
+            repo,
+            task: task.kind(),
+            number,
+            outcome,
+            pr_number,
+            pr_url,
+            error,
+            text,
+        }
+    }
+}
+
+/// Web root matching a REST API root: `https://api.github.com` maps to
+/// `https://github.com`, and a GitHub Enterprise `…/api/v3` to its host.
+fn web_url(api_base_url: Option<&str>) -> String {
+    match api_base_url {
+        None => "https://github.com".to_string(),
+        Some(base) => {
+            let base = base.trim_end_matches('/');
+            let base = base.strip_suffix("/api/v3").unwrap_or(base);
+            base.replace("://api.github.com", "://github.com")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notifier(web_url: &str) -> Notifier {
+        Notifier {
+            client: Client::new(),
+            url: None,
+            slack: false,
+            web_url: web_url.to_string(),
+        }
+    }
+
+    fn issue_task() -> Task {
+        Task::ApproveIssue {
+            installation_id: 1,
+            repo_full_name: "owner/repo".to_string(),
+            issue_number: 7,
+            attempt: 0,
+        }
+    }
+
+    #[test]
+    fn test_web_url() {
+        assert_eq!(web_url(None), "https://github.com");
+        assert_eq!(web_url(Some("https://api.github.com/")), "https://github.com");
+        assert_eq!(web_url(Some("https://ghe.example.com/api/v3")), "https://ghe.example.com");
+    }
+
+    #[test]
+    fn test_build_links_created_pr() {
+        let outcome = WorkflowOutcome::PullRequestCreated { pr_number: 12 };
+        let notification = notifier("https://github.com").build(&issue_task(), Ok(&outcome));
+
+        assert_eq!(notification.outcome, "pull_request_created");
+        assert_eq!(notification.number, 7);
+        assert_eq!(
+            notification.pr_url.as_deref(),
+            Some("https://github.com/owner/repo/pull/12")
+        );
+        assert_eq!(
+            notification.text,
+            "owner/repo#7: opened https://github.com/owner/repo/pull/12"
+        );
+    }
+
+    #[test]
+    fn test_build_reports_errors() {
+        let error = AppError::Git("push rejected".to_string());
+        let notification = notifier("https://github.com").build(&issue_task(), Err(&error));
+
+        assert_eq!(notification.outcome, "error");
+        assert!(notification.error.unwrap().contains("push rejected"));
+        assert!(notification.pr_url.is_none());
+    }
+}
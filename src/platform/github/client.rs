@@ -1,47 +1,232 @@
 use async_trait::async_trait;
-use octocrab::Octocrab;
+use octocrab::{Octocrab, Page};
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
 
-use crate::config::GitHubConfig;
-use crate::error::{AppError, Result};
+use crate::config::{GitHubAuth, GitHubConfig};
+use crate::error::{is_transient_status, AppError, Result};
 use crate::platform::types::*;
 use crate::platform::Platform;
 
 use super::auth::generate_app_jwt;
 use super::mapper;
 
-type TokenCache = std::collections::HashMap<u64, (String, chrono::DateTime<chrono::Utc>)>;
+type TokenCache = HashMap<u64, (String, chrono::DateTime<chrono::Utc>)>;
+
+/// Cached tokens closer than this to expiry are replaced before being handed
+/// out, so a run can keep using the token it was given for a while.
+const TOKEN_REFRESH_MARGIN: chrono::Duration = chrono::Duration::minutes(10);
+
+/// Times a rate-limited request is retried before the error is returned.
+const RATE_LIMIT_MAX_RETRIES: u32 = 3;
+
+/// Longest wait for a rate limit to lift; a request that would have to wait
+/// longer fails instead.
+const RATE_LIMIT_MAX_WAIT: Duration = Duration::from_secs(300);
+
+/// First wait after a secondary rate limit, which GitHub recommends when it
+/// doesn't say how long to wait. Doubles on each further retry.
+const SECONDARY_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
 
 pub struct GitHubPlatform {
     config: GitHubConfig,
+    /// Log write operations (comments, labels, PRs, ...) instead of performing them.
+    dry_run: bool,
     /// Cache of installation tokens: installation_id -> (token, expiry)
     token_cache: Arc<RwLock<TokenCache>>,
+    /// Held while minting a token for an installation, so concurrent tasks
+    /// wait for one refresh instead of each minting their own.
+    refresh_locks: Mutex<HashMap<u64, Arc<Mutex<()>>>>,
+    /// First wait after a secondary rate limit (`SECONDARY_RATE_LIMIT_WAIT`).
+    secondary_rate_limit_wait: Duration,
 }
 
 impl GitHubPlatform {
-    pub async fn new(config: &GitHubConfig) -> Result<Self> {
-        // Validate the private key exists
-        if !config.private_key_path.exists() {
-            return Err(AppError::Config(format!(
-                "GitHub App private key not found at: {}",
-                config.private_key_path.display()
-            )));
+    pub async fn new(config: &GitHubConfig, dry_run: bool) -> Result<Self> {
+        match config.auth() {
+            Some(GitHubAuth::App {
+                private_key_path, ..
+            }) => {
+                // Validate the private key exists
+                if !private_key_path.exists() {
+                    return Err(AppError::Config(format!(
+                        "GitHub App private key not found at: {}",
+                        private_key_path.display()
+                    )));
+                }
+            }
+            Some(GitHubAuth::Token(_)) => {}
+            None => {
+                return Err(AppError::Config(
+                    "Set github.app_id and github.private_key_path, or github.token".to_string(),
+                ))
+            }
         }
 
         Ok(Self {
             config: config.clone(),
+            dry_run,
             token_cache: Arc::new(RwLock::new(TokenCache::new())),
+            refresh_locks: Mutex::new(HashMap::new()),
+            secondary_rate_limit_wait: SECONDARY_RATE_LIMIT_WAIT,
         })
     }
 
+    /// A cached token for the installation that isn't about to expire.
+    async fn cached_token(&self, installation_id: u64) -> Option<String> {
+        let cache = self.token_cache.read().await;
+        cache
+            .get(&installation_id)
+            .filter(|(_, expiry)| *expiry > chrono::Utc::now() + TOKEN_REFRESH_MARGIN)
+            .map(|(token, _)| token.clone())
+    }
+
     /// Get an octocrab instance authenticated as an installation.
     async fn installation_client(&self, installation_id: u64) -> Result<Octocrab> {
         let token = self.get_access_token(installation_id).await?;
-        Octocrab::builder()
-            .personal_token(token)
+        Self::client_with_token(token, self.config.base_url.as_deref())
+    }
+
+    /// Get an installation client for the GraphQL API. GitHub Enterprise
+    /// serves it at `/api/graphql`, outside the REST root `/api/v3`.
+    async fn graphql_client(&self, installation_id: u64) -> Result<Octocrab> {
+        let token = self.get_access_token(installation_id).await?;
+        let base_url = self.config.base_url.as_deref().map(|url| {
+            let url = url.trim_end_matches('/');
+            url.strip_suffix("/v3").unwrap_or(url)
+        });
+        Self::client_with_token(token, base_url)
+    }
+
+    /// Get an octocrab instance authenticated as the app itself (JWT).
+    fn app_client(&self) -> Result<Octocrab> {
+        let Some(GitHubAuth::App {
+            app_id,
+            private_key_path,
+        }) = self.config.auth()
+        else {
+            return Err(AppError::Config(
+                "GitHub App credentials are not configured".to_string(),
+            ));
+        };
+        let jwt = generate_app_jwt(app_id, private_key_path)?;
+        Self::client_with_token(jwt, self.config.base_url.as_deref())
+    }
+
+    /// Build a client against an API root (github.com when `None`). Request
+    /// paths like `/repos/...` are resolved relative to it, keeping any path
+    /// prefix such as GitHub Enterprise's `/api/v3`.
+    fn client_with_token(token: String, base_url: Option<&str>) -> Result<Octocrab> {
+        let mut builder = Octocrab::builder().personal_token(token);
+        if let Some(base_url) = base_url {
+            builder = builder
+                .base_uri(base_url)
+                .map_err(|e| AppError::Config(format!("Invalid github.base_url: {e}")))?;
+        }
+        builder
             .build()
-            .map_err(|e| AppError::GitHubApi(format!("Failed to build octocrab client: {e}")))
+            .map_err(|e| api_error(format!("Failed to build octocrab client: {e}"), &e))
+    }
+
+    /// Send a request, waiting out GitHub rate limits and retrying up to
+    /// `RATE_LIMIT_MAX_RETRIES` times. Other errors are returned as is.
+    /// octocrab drops the response headers, so the wait comes from
+    /// `GET /rate_limit` for primary limits and GitHub's guidance for
+    /// secondary ones.
+    #[allow(clippy::result_large_err)]
+    async fn retry_rate_limited<T, F, Fut>(
+        &self,
+        client: &Octocrab,
+        request: F,
+    ) -> octocrab::Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = octocrab::Result<T>>,
+    {
+        let mut retries = 0;
+        loop {
+            let result = request().await;
+            let limit = match &result {
+                Err(octocrab::Error::GitHub { source, .. }) => {
+                    rate_limit_kind(source.status_code.as_u16(), &source.message)
+                }
+                _ => None,
+            };
+            let Some(limit) = limit.filter(|_| retries < RATE_LIMIT_MAX_RETRIES) else {
+                return result;
+            };
+
+            let wait = match limit {
+                RateLimit::Primary => Self::primary_rate_limit_reset(client).await,
+                RateLimit::Secondary => None,
+            }
+            .unwrap_or_else(|| self.secondary_rate_limit_wait * 2u32.pow(retries));
+            if wait > RATE_LIMIT_MAX_WAIT {
+                tracing::warn!(
+                    limit = ?limit,
+                    wait_secs = wait.as_secs(),
+                    "GitHub rate limit lifts too late to wait for"
+                );
+                return result;
+            }
+
+            retries += 1;
+            tracing::warn!(
+                limit = ?limit,
+                retry = retries,
+                wait_secs = wait.as_secs(),
+                "GitHub rate limit hit, backing off"
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Time until every exhausted primary rate limit resets, per `GET /rate_limit`
+    /// (which doesn't count against the limit itself).
+    async fn primary_rate_limit_reset(client: &Octocrab) -> Option<Duration> {
+        let response: serde_json::Value = client.get("/rate_limit", None::<&()>).await.ok()?;
+        let reset = response["resources"]
+            .as_object()?
+            .values()
+            .filter(|resource| resource["remaining"].as_u64() == Some(0))
+            .filter_map(|resource| resource["reset"].as_i64())
+            .max()?;
+        let secs = reset - chrono::Utc::now().timestamp();
+        // A second of slack for clock skew
+        Some(Duration::from_secs(secs.max(0) as u64 + 1))
+    }
+
+    /// Fetch every item of a list endpoint, following `Link` header pagination
+    /// and waiting out rate limits on each page. `what` names the items in
+    /// error messages.
+    async fn get_all_pages(
+        &self,
+        client: &Octocrab,
+        url: &str,
+        what: &str,
+    ) -> Result<Vec<serde_json::Value>> {
+        let fetch_failed =
+            |e: octocrab::Error| api_error(format!("Failed to fetch {what}: {e}"), &e);
+        let mut page: Page<serde_json::Value> = self
+            .retry_rate_limited(client, || client.get(url, Some(&[("per_page", "100")])))
+            .await
+            .map_err(fetch_failed)?;
+
+        let mut items = page.take_items();
+        loop {
+            let next = self
+                .retry_rate_limited(client, || client.get_page(&page.next))
+                .await
+                .map_err(fetch_failed)?;
+            let Some(next) = next else {
+                return Ok(items);
+            };
+            page = next;
+            items.extend(page.take_items());
+        }
     }
 
     fn parse_repo(repo_full_name: &str) -> Result<(&str, &str)> {
@@ -58,29 +243,33 @@ impl GitHubPlatform {
 #[async_trait]
 impl Platform for GitHubPlatform {
     async fn get_access_token(&self, installation_id: u64) -> Result<String> {
-        // Check cache
-        {
-            let cache = self.token_cache.read().await;
-            if let Some((token, expiry)) = cache.get(&installation_id) {
-                if *expiry > chrono::Utc::now() + chrono::Duration::minutes(5) {
-                    return Ok(token.clone());
-                }
-            }
+        if let Some(GitHubAuth::Token(token)) = self.config.auth() {
+            return Ok(token.to_string());
+        }
+        if let Some(token) = self.cached_token(installation_id).await {
+            return Ok(token);
         }
 
-        // Generate new token
-        let jwt = generate_app_jwt(self.config.app_id, &self.config.private_key_path)?;
+        // Only one refresh per installation at a time
+        let refresh_lock = {
+            let mut locks = self.refresh_locks.lock().await;
+            Arc::clone(locks.entry(installation_id).or_default())
+        };
+        let _refreshing = refresh_lock.lock().await;
 
-        let client = Octocrab::builder()
-            .personal_token(jwt)
-            .build()
-            .map_err(|e| AppError::GitHubApi(format!("Failed to build JWT client: {e}")))?;
+        // Another task may have refreshed it while we waited
+        if let Some(token) = self.cached_token(installation_id).await {
+            return Ok(token);
+        }
+
+        // Generate new token
+        let client = self.app_client()?;
 
         let url = format!("/app/installations/{installation_id}/access_tokens");
         let response: serde_json::Value = client
             .post(&url, None::<&()>)
             .await
-            .map_err(|e| AppError::GitHubApi(format!("Failed to create installation token: {e}")))?;
+            .map_err(|e| api_error(format!("Failed to create installation token: {e}"), &e))?;
 
         let token = response["token"]
             .as_str()
@@ -100,18 +289,32 @@ impl Platform for GitHubPlatform {
         Ok(token)
     }
 
-    async fn list_installations(&self) -> Result<Vec<Installation>> {
-        let jwt = generate_app_jwt(self.config.app_id, &self.config.private_key_path)?;
+    async fn check_connection(&self) -> Result<()> {
+        // `GET /app` also proves the private key loads and signs a JWT
+        let (client, url) = match self.config.auth() {
+            Some(GitHubAuth::Token(token)) => (
+                Self::client_with_token(token.to_string(), self.config.base_url.as_deref())?,
+                "/user",
+            ),
+            _ => (self.app_client()?, "/app"),
+        };
+        client
+            .get::<serde_json::Value, _, _>(url, None::<&()>)
+            .await
+            .map_err(|e| api_error(format!("Failed to reach GitHub: {e}"), &e))?;
+        Ok(())
+    }
 
-        let client = Octocrab::builder()
-            .personal_token(jwt)
-            .build()
-            .map_err(|e| AppError::GitHubApi(format!("Failed to build JWT client: {e}")))?;
+    async fn list_installations(&self) -> Result<Vec<Installation>> {
+        if let Some(GitHubAuth::Token(_)) = self.config.auth() {
+            return Ok(vec![Installation {
+                id: TOKEN_INSTALLATION_ID,
+            }]);
+        }
+        let client = self.app_client()?;
 
-        let installations: Vec<serde_json::Value> = client
-            .get("/app/installations", None::<&()>)
-            .await
-            .map_err(|e| AppError::GitHubApi(format!("Failed to list installations: {e}")))?;
+        let installations =
+            self.get_all_pages(&client, "/app/installations", "installations").await?;
 
         Ok(installations
             .into_iter()
@@ -122,15 +325,14 @@ impl Platform for GitHubPlatform {
     async fn list_installation_repos(&self, installation_id: u64) -> Result<Vec<InstallationRepo>> {
         let client = self.installation_client(installation_id).await?;
 
-        let response: serde_json::Value = client
-            .get("/installation/repositories", None::<&()>)
-            .await
-            .map_err(|e| AppError::GitHubApi(format!("Failed to list repos: {e}")))?;
-
-        let repos = response["repositories"]
-            .as_array()
-            .cloned()
-            .unwrap_or_default();
+        // A token has no installation; list the repos its user can access.
+        // Installation pages wrap the list in a `repositories` field, which
+        // `Page` unwraps.
+        let url = match self.config.auth() {
+            Some(GitHubAuth::Token(_)) => "/user/repos",
+            _ => "/installation/repositories",
+        };
+        let repos = self.get_all_pages(&client, url, "repositories").await?;
 
         Ok(repos
             .into_iter()
@@ -161,17 +363,9 @@ impl Platform for GitHubPlatform {
 
         // Use the search API to find open issues with the label (excluding PRs)
         let query = format!("repo:{owner}/{repo} is:issue is:open label:\"{label}\"");
-        let url = format!(
-            "/search/issues?q={}&per_page=100",
-            urlencoding::encode(&query)
-        );
+        let url = format!("/search/issues?q={}", urlencoding::encode(&query));
 
-        let response: serde_json::Value = client
-            .get(&url, None::<&()>)
-            .await
-            .map_err(|e| AppError::GitHubApi(format!("Failed to search issues: {e}")))?;
-
-        let items = response["items"].as_array().cloned().unwrap_or_default();
+        let items = self.get_all_pages(&client, &url, "labeled issues").await?;
 
         Ok(items
             .into_iter()
@@ -206,19 +400,25 @@ impl Platform for GitHubPlatform {
         let client = self.installation_client(installation_id).await?;
         let (owner, repo) = Self::parse_repo(repo_full_name)?;
 
-        let issue = client
-            .issues(owner, repo)
-            .get(issue_number)
+        let issue = self
+            .retry_rate_limited(&client, || async {
+                client.issues(owner, repo).get(issue_number).await
+            })
             .await?;
 
-        let comments_page = client
-            .issues(owner, repo)
-            .list_comments(issue_number)
-            .per_page(100)
-            .send()
+        let comments_page = self
+            .retry_rate_limited(&client, || async {
+                client
+                    .issues(owner, repo)
+                    .list_comments(issue_number)
+                    .per_page(100)
+                    .send()
+                    .await
+            })
             .await?;
+        let comments = client.all_pages(comments_page).await?;
 
-        Ok(mapper::map_issue(&issue, comments_page.items))
+        Ok(mapper::map_issue(&issue, comments))
     }
 
     async fn post_comment(
@@ -227,18 +427,145 @@ impl Platform for GitHubPlatform {
         repo_full_name: &str,
         issue_number: u64,
         body: &str,
-    ) -> Result<()> {
+    ) -> Result<u64> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                body,
+                "[dry run] Would post comment"
+            );
+            return Ok(0);
+        }
         let client = self.installation_client(installation_id).await?;
         let (owner, repo) = Self::parse_repo(repo_full_name)?;
 
-        client
-            .issues(owner, repo)
-            .create_comment(issue_number, body)
+        let comment = self
+            .retry_rate_limited(&client, || async {
+                client
+                    .issues(owner, repo)
+                    .create_comment(issue_number, body)
+                    .await
+            })
             .await?;
 
+        Ok(comment.id.0)
+    }
+
+    async fn update_comment(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        comment_id: u64,
+        body: &str,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                comment_id,
+                body,
+                "[dry run] Would update comment"
+            );
+            return Ok(());
+        }
+        let client = self.installation_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        // octocrab's `update_comment` sends POST; the REST API documents PATCH
+        let url = format!("/repos/{owner}/{repo}/issues/comments/{comment_id}");
+        let body = serde_json::json!({ "body": body });
+        let _: serde_json::Value = self
+            .retry_rate_limited(&client, || client.patch(&url, Some(&body)))
+            .await
+            .map_err(|e| api_error(format!("Failed to update comment: {e}"), &e))?;
+
+        Ok(())
+    }
+
+    async fn add_reaction(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+        reaction: Reaction,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                ?reaction,
+                "[dry run] Would add reaction"
+            );
+            return Ok(());
+        }
+        let client = self.installation_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        self.retry_rate_limited(&client, || async {
+            client
+                .issues(owner, repo)
+                .create_reaction(issue_number, mapper::map_reaction(reaction))
+                .await
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn add_comment_reaction(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        comment_id: u64,
+        reaction: Reaction,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                comment_id,
+                ?reaction,
+                "[dry run] Would add comment reaction"
+            );
+            return Ok(());
+        }
+        let client = self.installation_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        self.retry_rate_limited(&client, || async {
+            client
+                .issues(owner, repo)
+                .create_comment_reaction(comment_id, mapper::map_reaction(reaction))
+                .await
+        })
+        .await?;
+
         Ok(())
     }
 
+    async fn has_write_access(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        username: &str,
+    ) -> Result<bool> {
+        let client = self.installation_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        let url = format!(
+            "/repos/{owner}/{repo}/collaborators/{}/permission",
+            urlencoding::encode(username)
+        );
+        let response: serde_json::Value = client
+            .get(&url, None::<&()>)
+            .await
+            .map_err(|e| api_error(format!("Failed to fetch permission: {e}"), &e))?;
+
+        Ok(matches!(
+            response["permission"].as_str(),
+            Some("admin" | "maintain" | "write")
+        ))
+    }
+
     async fn get_pull_request(
         &self,
         installation_id: u64,
@@ -248,9 +575,10 @@ impl Platform for GitHubPlatform {
         let client = self.installation_client(installation_id).await?;
         let (owner, repo) = Self::parse_repo(repo_full_name)?;
 
-        let pr = client
-            .pulls(owner, repo)
-            .get(pr_number)
+        let pr = self
+            .retry_rate_limited(&client, || async {
+                client.pulls(owner, repo).get(pr_number).await
+            })
             .await?;
 
         Ok(mapper::map_pull_request(pr))
@@ -262,19 +590,128 @@ impl Platform for GitHubPlatform {
         repo_full_name: &str,
         pr: &CreatePullRequest,
     ) -> Result<PullRequest> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                title = %pr.title,
+                head = %pr.head_branch,
+                base = %pr.base_branch,
+                draft = pr.draft,
+                body = %pr.body,
+                "[dry run] Would create pull request"
+            );
+            return Ok(PullRequest {
+                number: 0,
+                title: pr.title.clone(),
+                body: pr.body.clone(),
+                head_branch: pr.head_branch.clone(),
+                base_branch: pr.base_branch.clone(),
+            });
+        }
         let client = self.installation_client(installation_id).await?;
         let (owner, repo) = Self::parse_repo(repo_full_name)?;
 
-        let created = client
-            .pulls(owner, repo)
-            .create(&pr.title, &pr.head_branch, &pr.base_branch)
-            .body(&pr.body)
-            .send()
-            .await?;
+        let created = match self
+            .retry_rate_limited(&client, || async {
+                client
+                    .pulls(owner, repo)
+                    .create(&pr.title, &pr.head_branch, &pr.base_branch)
+                    .body(&pr.body)
+                    .draft(pr.draft)
+                    .send()
+                    .await
+            })
+            .await
+        {
+            Ok(created) => created,
+            // A re-run can find its branch already has an open PR; reuse it
+            Err(octocrab::Error::GitHub { source, .. }) if is_pull_request_exists(&source) => {
+                tracing::info!(
+                    repo = repo_full_name,
+                    head = %pr.head_branch,
+                    "Pull request already exists, using the open one"
+                );
+                return self
+                    .find_open_pull_request(installation_id, repo_full_name, &pr.head_branch)
+                    .await?
+                    .ok_or_else(|| {
+                        AppError::GitHubApi(format!(
+                            "GitHub reported an existing pull request for {} but none is open",
+                            pr.head_branch
+                        ))
+                    });
+            }
+            Err(e) => return Err(e.into()),
+        };
 
         Ok(mapper::map_pull_request(created))
     }
 
+    async fn request_reviewers(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+        reviewers: &[String],
+    ) -> Result<()> {
+        if reviewers.is_empty() {
+            return Ok(());
+        }
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                pr = pr_number,
+                ?reviewers,
+                "[dry run] Would request reviewers"
+            );
+            return Ok(());
+        }
+
+        let client = self.installation_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        let url = format!("/repos/{owner}/{repo}/pulls/{pr_number}/requested_reviewers");
+        let (client, url) = (&client, url.as_str());
+        let request = |reviewers: &[String]| {
+            let body = serde_json::json!({ "reviewers": reviewers });
+            async move {
+                self.retry_rate_limited(client, || {
+                    client.post::<_, serde_json::Value>(url, Some(&body))
+                })
+                .await
+            }
+        };
+
+        match request(reviewers).await {
+            Ok(_) => Ok(()),
+            // A single reviewer GitHub can't request (no repo access, the PR
+            // author, ...) fails the whole call with 422, so retry one by one
+            Err(octocrab::Error::GitHub { source, .. }) if source.status_code.as_u16() == 422 => {
+                if reviewers.len() == 1 {
+                    tracing::warn!(
+                        pr = pr_number,
+                        reviewer = %reviewers[0],
+                        error = %source.message,
+                        "Could not request review"
+                    );
+                    return Ok(());
+                }
+                for reviewer in reviewers {
+                    if let Err(e) = request(std::slice::from_ref(reviewer)).await {
+                        tracing::warn!(
+                            pr = pr_number,
+                            reviewer = %reviewer,
+                            error = %e,
+                            "Could not request review"
+                        );
+                    }
+                }
+                Ok(())
+            }
+            Err(e) => Err(api_error(format!("Failed to request reviewers: {e}"), &e)),
+        }
+    }
+
     async fn add_label(
         &self,
         installation_id: u64,
@@ -282,13 +719,27 @@ impl Platform for GitHubPlatform {
         issue_number: u64,
         label: &str,
     ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                label,
+                "[dry run] Would add label"
+            );
+            return Ok(());
+        }
         let client = self.installation_client(installation_id).await?;
         let (owner, repo) = Self::parse_repo(repo_full_name)?;
 
-        client
-            .issues(owner, repo)
-            .add_labels(issue_number, &[label.to_string()])
-            .await?;
+        // GitHub ignores labels the issue already has, so this is idempotent
+        let labels = [label.to_string()];
+        self.retry_rate_limited(&client, || async {
+            client
+                .issues(owner, repo)
+                .add_labels(issue_number, &labels)
+                .await
+        })
+        .await?;
 
         Ok(())
     }
@@ -300,19 +751,192 @@ impl Platform for GitHubPlatform {
         issue_number: u64,
         label: &str,
     ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                label,
+                "[dry run] Would remove label"
+            );
+            return Ok(());
+        }
         let client = self.installation_client(installation_id).await?;
         let (owner, repo) = Self::parse_repo(repo_full_name)?;
 
         // octocrab doesn't have a direct remove_label, use the API directly
-        let url = format!("/repos/{owner}/{repo}/issues/{issue_number}/labels/{label}");
-        let _: serde_json::Value = client
-            .delete(&url, None::<&()>)
+        let url = format!(
+            "/repos/{owner}/{repo}/issues/{issue_number}/labels/{}",
+            urlencoding::encode(label)
+        );
+        match self
+            .retry_rate_limited(&client, || {
+                client.delete::<serde_json::Value, _, _>(&url, None::<&()>)
+            })
             .await
-            .map_err(|e| AppError::GitHubApi(format!("Failed to remove label: {e}")))?;
+        {
+            Ok(_) => Ok(()),
+            // The label is already gone, which is all the caller wanted
+            Err(octocrab::Error::GitHub { source, .. }) if source.status_code.as_u16() == 404 => {
+                tracing::debug!(
+                    repo = repo_full_name,
+                    issue = issue_number,
+                    label,
+                    "Label to remove was not on the issue"
+                );
+                Ok(())
+            }
+            Err(e) => Err(api_error(format!("Failed to remove label: {e}"), &e)),
+        }
+    }
+
+    async fn close_issue(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                "[dry run] Would close issue"
+            );
+            return Ok(());
+        }
+        let client = self.installation_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        self.retry_rate_limited(&client, || async {
+            client
+                .issues(owner, repo)
+                .update(issue_number)
+                .state(octocrab::models::IssueState::Closed)
+                .send()
+                .await
+        })
+        .await
+        .map_err(|e| api_error(format!("Failed to close issue: {e}"), &e))?;
 
         Ok(())
     }
 
+    async fn find_open_pull_request(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        head_branch: &str,
+    ) -> Result<Option<PullRequest>> {
+        let client = self.installation_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        let page = self
+            .retry_rate_limited(&client, || async {
+                client
+                    .pulls(owner, repo)
+                    .list()
+                    .state(octocrab::params::State::Open)
+                    .head(format!("{owner}:{head_branch}"))
+                    .per_page(1)
+                    .send()
+                    .await
+            })
+            .await?;
+
+        Ok(page.items.into_iter().next().map(mapper::map_pull_request))
+    }
+
+    async fn branch_exists(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        branch: &str,
+    ) -> Result<bool> {
+        let client = self.installation_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        let url = format!(
+            "/repos/{owner}/{repo}/branches/{}",
+            urlencoding::encode(branch)
+        );
+        match client
+            .get::<serde_json::Value, _, _>(&url, None::<&()>)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(octocrab::Error::GitHub { source, .. }) if source.status_code.as_u16() == 404 => {
+                Ok(false)
+            }
+            Err(e) => Err(api_error(format!("Failed to fetch branch: {e}"), &e)),
+        }
+    }
+
+    fn tarball_url(&self, repo_full_name: &str, git_ref: &str) -> String {
+        let api_root = self
+            .config
+            .base_url
+            .as_deref()
+            .unwrap_or("https://api.github.com")
+            .trim_end_matches('/');
+        format!(
+            "{api_root}/repos/{repo_full_name}/tarball/{}",
+            urlencoding::encode(git_ref)
+        )
+    }
+
+    async fn get_failed_job_logs(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        run_id: u64,
+    ) -> Result<Vec<JobLog>> {
+        let client = self.installation_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        let url = format!("/repos/{owner}/{repo}/actions/runs/{run_id}/jobs");
+        let response: serde_json::Value = client
+            .get(&url, Some(&[("per_page", "100")]))
+            .await
+            .map_err(|e| api_error(format!("Failed to list workflow jobs: {e}"), &e))?;
+
+        let failed_jobs = response["jobs"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|job| job["conclusion"].as_str() == Some("failure"));
+
+        let mut logs = Vec::new();
+        for job in failed_jobs {
+            let Some(job_id) = job["id"].as_u64() else {
+                continue;
+            };
+            let name = job["name"].as_str().unwrap_or("unnamed job").to_string();
+
+            // The logs endpoint redirects to a short-lived download URL
+            let url = format!("/repos/{owner}/{repo}/actions/jobs/{job_id}/logs");
+            let response = client._get(url).await?;
+            let response = client.follow_location_to_data(response).await?;
+            let status = response.status();
+            if !status.is_success() {
+                let message = format!("Failed to download logs for job {job_id}: HTTP {status}");
+                let status = status.as_u16();
+                return Err(if is_transient_status(status) {
+                    AppError::Unavailable {
+                        service: "GitHub API",
+                        status: Some(status),
+                        message,
+                    }
+                } else {
+                    AppError::GitHubApi(message)
+                });
+            }
+            let log = client.body_to_string(response).await?;
+
+            logs.push(JobLog { name, log });
+        }
+
+        Ok(logs)
+    }
+
     async fn get_reviews(
         &self,
         installation_id: u64,
@@ -323,10 +947,7 @@ impl Platform for GitHubPlatform {
         let (owner, repo) = Self::parse_repo(repo_full_name)?;
 
         let url = format!("/repos/{owner}/{repo}/pulls/{pr_number}/reviews");
-        let reviews: Vec<serde_json::Value> = client
-            .get(&url, None::<&()>)
-            .await
-            .map_err(|e| AppError::GitHubApi(format!("Failed to fetch reviews: {e}")))?;
+        let reviews = self.get_all_pages(&client, &url, "reviews").await?;
 
         let mut result = Vec::new();
         for review in reviews {
@@ -336,8 +957,7 @@ impl Platform for GitHubPlatform {
             let comments_url = format!(
                 "/repos/{owner}/{repo}/pulls/{pr_number}/reviews/{review_id}/comments"
             );
-            let comments: Vec<serde_json::Value> = client
-                .get(&comments_url, None::<&()>)
+            let comments = self.get_all_pages(&client, &comments_url, "review comments")
                 .await
                 .unwrap_or_default();
 
@@ -353,6 +973,7 @@ impl Platform for GitHubPlatform {
                     path: c["path"].as_str().map(|s| s.to_string()),
                     line: c["line"].as_u64(),
                     diff_hunk: c["diff_hunk"].as_str().map(|s| s.to_string()),
+                    in_reply_to: c["in_reply_to_id"].as_u64(),
                 })
                 .collect();
 
@@ -376,4 +997,511 @@ impl Platform for GitHubPlatform {
 
         Ok(result)
     }
+
+    async fn reply_to_review_comment(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+        comment_id: u64,
+        body: &str,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                pr = pr_number,
+                comment_id,
+                body,
+                "[dry run] Would reply to review comment"
+            );
+            return Ok(());
+        }
+        let client = self.installation_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        let url = format!("/repos/{owner}/{repo}/pulls/{pr_number}/comments/{comment_id}/replies");
+        let body = serde_json::json!({ "body": body });
+        let _: serde_json::Value = self
+            .retry_rate_limited(&client, || client.post(&url, Some(&body)))
+            .await
+            .map_err(|e| api_error(format!("Failed to reply to review comment: {e}"), &e))?;
+
+        Ok(())
+    }
+
+    async fn list_review_threads(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+    ) -> Result<Vec<ReviewThread>> {
+        let client = self.graphql_client(installation_id).await?;
+        let (owner, repo) = Self::parse_repo(repo_full_name)?;
+
+        let query = serde_json::json!({
+            "query": "query($owner: String!, $repo: String!, $number: Int!) {
+                repository(owner: $owner, name: $repo) {
+                    pullRequest(number: $number) {
+                        reviewThreads(first: 100) {
+                            nodes { id isResolved comments(first: 1) { nodes { databaseId } } }
+                        }
+                    }
+                }
+            }",
+            "variables": { "owner": owner, "repo": repo, "number": pr_number },
+        });
+        let response: serde_json::Value = client
+            .graphql(&query)
+            .await
+            .map_err(|e| api_error(format!("Failed to list review threads: {e}"), &e))?;
+        check_graphql_errors(&response)?;
+
+        let threads = response["data"]["repository"]["pullRequest"]["reviewThreads"]["nodes"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(threads
+            .into_iter()
+            .filter_map(|thread| {
+                Some(ReviewThread {
+                    id: thread["id"].as_str()?.to_string(),
+                    comment_id: thread["comments"]["nodes"][0]["databaseId"].as_u64()?,
+                    is_resolved: thread["isResolved"].as_bool().unwrap_or(false),
+                })
+            })
+            .collect())
+    }
+
+    async fn resolve_review_thread(&self, installation_id: u64, thread_id: &str) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(thread = thread_id, "[dry run] Would resolve review thread");
+            return Ok(());
+        }
+        let client = self.graphql_client(installation_id).await?;
+
+        let mutation = serde_json::json!({
+            "query": "mutation($id: ID!) {
+                resolveReviewThread(input: { threadId: $id }) { thread { id } }
+            }",
+            "variables": { "id": thread_id },
+        });
+        let response: serde_json::Value = client
+            .graphql(&mutation)
+            .await
+            .map_err(|e| api_error(format!("Failed to resolve review thread: {e}"), &e))?;
+        check_graphql_errors(&response)
+    }
+}
+
+/// Which GitHub rate limit a request ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RateLimit {
+    /// The hourly request quota is used up until its reset time.
+    Primary,
+    /// Too many requests in a burst; GitHub asks clients to slow down.
+    Secondary,
+}
+
+/// Classify an error response as a rate limit. GitHub reports both kinds as
+/// 403 or 429, and only the message tells them apart from other 403s.
+fn rate_limit_kind(status: u16, message: &str) -> Option<RateLimit> {
+    let message = message.to_lowercase();
+    match status {
+        403 | 429 if message.contains("secondary rate limit") => Some(RateLimit::Secondary),
+        403 | 429 if message.contains("rate limit") => Some(RateLimit::Primary),
+        429 => Some(RateLimit::Secondary),
+        _ => None,
+    }
+}
+
+/// A failed request described by `message`, as [`AppError::Unavailable`] when
+/// GitHub may answer a later try: a 5xx, a rate limit, or no response at all.
+pub(crate) fn api_error(message: String, error: &octocrab::Error) -> AppError {
+    let status = match error {
+        octocrab::Error::GitHub { source, .. } => {
+            let status = source.status_code.as_u16();
+            if !is_transient_status(status) && rate_limit_kind(status, &source.message).is_none() {
+                return AppError::GitHubApi(message);
+            }
+            Some(status)
+        }
+        octocrab::Error::Hyper { .. } | octocrab::Error::Service { .. } => None,
+        _ => return AppError::GitHubApi(message),
+    };
+    AppError::Unavailable {
+        service: "GitHub API",
+        status,
+        message,
+    }
+}
+
+/// Whether a 422 from creating a PR means one is already open for the branch.
+fn is_pull_request_exists(error: &octocrab::GitHubError) -> bool {
+    const MESSAGE: &str = "A pull request already exists";
+    error.status_code.as_u16() == 422
+        && (error.message.contains(MESSAGE)
+            || error.errors.iter().flatten().any(|e| {
+                e["message"]
+                    .as_str()
+                    .is_some_and(|m| m.contains(MESSAGE))
+            }))
+}
+
+/// GraphQL reports most failures with a 200 status and an `errors` array.
+fn check_graphql_errors(response: &serde_json::Value) -> Result<()> {
+    match response["errors"].as_array() {
+        Some(errors) if !errors.is_empty() => {
+            let messages: Vec<&str> = errors
+                .iter()
+                .filter_map(|e| e["message"].as_str())
+                .collect();
+            Err(AppError::GitHubApi(format!(
+                "GraphQL error: {}",
+                messages.join("; ")
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Request, State};
+    use axum::response::{IntoResponse, Response};
+
+    /// Serve `/reviews` and each review's `/comments` split over two pages.
+    async fn mock_github(State(base): State<String>, request: Request) -> Response {
+        let path = request.uri().path().to_string();
+        let second_page = request.uri().query().unwrap_or("").contains("page=2");
+        let next = format!("<{base}{path}?per_page=100&page=2>; rel=\"next\"");
+
+        let (items, link) = match (path.as_str(), second_page) {
+            ("/repos/o/r/pulls/1/reviews", false) => (serde_json::json!([{ "id": 1 }]), Some(next)),
+            ("/repos/o/r/pulls/1/reviews", true) => (serde_json::json!([{ "id": 2 }]), None),
+            (_, false) => (serde_json::json!([{ "id": 10, "body": "first" }]), Some(next)),
+            (_, true) => (serde_json::json!([{ "id": 11, "body": "second" }]), None),
+        };
+
+        match link {
+            Some(link) => ([("link", link)], axum::Json(items)).into_response(),
+            None => axum::Json(items).into_response(),
+        }
+    }
+
+    /// Two pages of installation repositories and of search results, each
+    /// wrapped in an object the way GitHub returns them.
+    async fn mock_listings(State(base): State<String>, request: Request) -> Response {
+        let path = request.uri().path().to_string();
+        let query = request.uri().query().unwrap_or("").to_string();
+        let second_page = query.contains("page=2");
+        let next = format!("<{base}{path}?{query}&page=2>; rel=\"next\"");
+
+        let (body, link) = match (path.as_str(), second_page) {
+            ("/installation/repositories", page2) => {
+                let name = if page2 { "o/second" } else { "o/first" };
+                let repo = serde_json::json!({
+                    "full_name": name,
+                    "clone_url": format!("https://github.com/{name}.git"),
+                    "default_branch": "main",
+                });
+                let body = serde_json::json!({ "total_count": 2, "repositories": [repo] });
+                (body, (!page2).then_some(next))
+            }
+            ("/search/issues", page2) => {
+                let number = if page2 { 2 } else { 1 };
+                let issue = serde_json::json!({
+                    "number": number,
+                    "title": format!("Issue {number}"),
+                    "labels": [{ "name": "mycelium" }],
+                });
+                let body = serde_json::json!({ "total_count": 2, "items": [issue] });
+                (body, (!page2).then_some(next))
+            }
+            _ => return axum::http::StatusCode::NOT_FOUND.into_response(),
+        };
+
+        match link {
+            Some(link) => ([("link", link)], axum::Json(body)).into_response(),
+            None => axum::Json(body).into_response(),
+        }
+    }
+
+    /// An issue comment as returned by the REST API.
+    fn comment_json(base: &str, id: u64, body: &str) -> serde_json::Value {
+        let user_url = format!("{base}/users/bot");
+        serde_json::json!({
+            "id": id,
+            "node_id": "IC_1",
+            "url": format!("{base}/repos/o/r/issues/comments/{id}"),
+            "html_url": format!("{base}/o/r/issues/5#issuecomment-{id}"),
+            "body": body,
+            "author_association": "NONE",
+            "created_at": "2024-01-01T00:00:00Z",
+            "user": {
+                "login": "bot",
+                "id": 1,
+                "node_id": "U_1",
+                "avatar_url": user_url,
+                "gravatar_id": "",
+                "url": user_url,
+                "html_url": user_url,
+                "followers_url": user_url,
+                "following_url": user_url,
+                "gists_url": user_url,
+                "starred_url": user_url,
+                "subscriptions_url": user_url,
+                "organizations_url": user_url,
+                "repos_url": user_url,
+                "events_url": user_url,
+                "received_events_url": user_url,
+                "type": "Bot",
+                "site_admin": false
+            }
+        })
+    }
+
+    /// Create comment 42 on issue 5 and edit it, echoing the request body back.
+    async fn mock_comments(State(base): State<String>, request: Request) -> Response {
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        let bytes = axum::body::to_bytes(request.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let sent: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let body = sent["body"].as_str().unwrap_or_default();
+
+        match (method.as_str(), path.as_str()) {
+            ("POST", "/repos/o/r/issues/5/comments")
+            | ("PATCH", "/repos/o/r/issues/comments/42") => {
+                axum::Json(comment_json(&base, 42, body)).into_response()
+            }
+            _ => axum::http::StatusCode::NOT_FOUND.into_response(),
+        }
+    }
+
+    /// Serve `handler` on a local port and return a platform pointed at it,
+    /// with a cached token for installation 7.
+    async fn mock_platform<H, T>(handler: H) -> GitHubPlatform
+    where
+        H: axum::handler::Handler<T, String>,
+        T: 'static,
+    {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let app = axum::Router::new()
+            .fallback(handler)
+            .with_state(base.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config: GitHubConfig = toml::from_str(&format!(
+            "app_id = 1\nprivate_key_path = \"/nonexistent\"\nbase_url = \"{base}\""
+        ))
+        .unwrap();
+        let expiry = chrono::Utc::now() + chrono::Duration::hours(1);
+        GitHubPlatform {
+            config,
+            dry_run: false,
+            token_cache: Arc::new(RwLock::new(TokenCache::from([(
+                7,
+                ("token".to_string(), expiry),
+            )]))),
+            refresh_locks: Mutex::new(HashMap::new()),
+            secondary_rate_limit_wait: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_reviews_follows_pagination() {
+        let platform = mock_platform(mock_github).await;
+
+        let reviews = platform.get_reviews(7, "o/r", 1).await.unwrap();
+
+        let review_ids: Vec<u64> = reviews.iter().map(|r| r.id).collect();
+        assert_eq!(review_ids, vec![1, 2]);
+        for review in &reviews {
+            let comment_ids: Vec<u64> = review.comments.iter().map(|c| c.id).collect();
+            assert_eq!(comment_ids, vec![10, 11]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_post_comment_returns_id_for_update() {
+        let platform = mock_platform(mock_comments).await;
+
+        let comment_id = platform.post_comment(7, "o/r", 5, "Working").await.unwrap();
+        assert_eq!(comment_id, 42);
+        platform
+            .update_comment(7, "o/r", comment_id, "Done")
+            .await
+            .unwrap();
+        assert!(platform.update_comment(7, "o/r", 43, "Done").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remove_label_tolerates_missing_label() {
+        let platform = mock_platform(|request: Request| async move {
+            assert_eq!(request.method(), "DELETE");
+            match request.uri().path() {
+                "/repos/o/r/issues/5/labels/mycelium%3Aworking" => (
+                    axum::http::StatusCode::NOT_FOUND,
+                    axum::Json(serde_json::json!({
+                        "message": "Label does not exist",
+                        "documentation_url": "https://docs.github.com/rest"
+                    })),
+                )
+                    .into_response(),
+                _ => (
+                    axum::http::StatusCode::FORBIDDEN,
+                    axum::Json(serde_json::json!({
+                        "message": "Resource not accessible by integration",
+                        "documentation_url": "https://docs.github.com/rest"
+                    })),
+                )
+                    .into_response(),
+            }
+        })
+        .await;
+
+        platform
+            .remove_label(7, "o/r", 5, "mycelium:working")
+            .await
+            .unwrap();
+        assert!(platform
+            .remove_label(7, "o/r", 6, "mycelium")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_token_auth_uses_token_without_installations() {
+        let mut platform = mock_platform(|request: Request| async move {
+            assert_eq!(request.uri().path(), "/user/repos");
+            let auth = request.headers()["authorization"].to_str().unwrap();
+            assert!(auth.ends_with("pat"), "{auth}");
+            axum::Json(serde_json::json!([{
+                "full_name": "me/repo",
+                "clone_url": "https://github.com/me/repo.git",
+                "default_branch": "trunk"
+            }]))
+        })
+        .await;
+        platform.config.token = "pat".to_string();
+
+        let token = platform.get_access_token(TOKEN_INSTALLATION_ID).await.unwrap();
+        assert_eq!(token, "pat");
+        let installations = platform.list_installations().await.unwrap();
+        let ids: Vec<u64> = installations.iter().map(|i| i.id).collect();
+        assert_eq!(ids, vec![TOKEN_INSTALLATION_ID]);
+
+        let repos = platform
+            .list_installation_repos(TOKEN_INSTALLATION_ID)
+            .await
+            .unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].full_name, "me/repo");
+        assert_eq!(repos[0].default_branch, "trunk");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_token_requests_mint_once() {
+        // Throwaway key generated for this test, not used by any app
+        let key = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/platform/github/testdata/app-key.pem");
+
+        let minted = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&minted);
+        let mut platform = mock_platform(move |request: Request| async move {
+            assert_eq!(request.uri().path(), "/app/installations/9/access_tokens");
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            let expires_at = chrono::Utc::now() + chrono::Duration::hours(1);
+            axum::Json(serde_json::json!({
+                "token": "fresh",
+                "expires_at": expires_at.to_rfc3339(),
+            }))
+        })
+        .await;
+        platform.config.private_key_path = Some(key);
+
+        let (a, b) = tokio::join!(platform.get_access_token(9), platform.get_access_token(9));
+        assert_eq!(a.unwrap(), "fresh");
+        assert_eq!(b.unwrap(), "fresh");
+        assert_eq!(minted.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_rate_limit_kind() {
+        let secondary = "You have exceeded a secondary rate limit. Please wait a few minutes.";
+        assert_eq!(rate_limit_kind(403, secondary), Some(RateLimit::Secondary));
+        let primary = "API rate limit exceeded for installation ID 7.";
+        assert_eq!(rate_limit_kind(403, primary), Some(RateLimit::Primary));
+        assert_eq!(rate_limit_kind(429, "Too many requests"), Some(RateLimit::Secondary));
+        assert_eq!(rate_limit_kind(403, "Resource not accessible by integration"), None);
+        assert_eq!(rate_limit_kind(404, "Not Found"), None);
+    }
+
+    #[tokio::test]
+    async fn test_label_removal_retries_after_secondary_rate_limit() {
+        let attempts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&attempts);
+        let platform = mock_platform(move |request: Request| async move {
+            assert_eq!(request.uri().path(), "/repos/o/r/issues/5/labels/mycelium");
+            let (status, body) = match counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+                0 => (
+                    axum::http::StatusCode::FORBIDDEN,
+                    serde_json::json!({ "message": "You have exceeded a secondary rate limit" }),
+                ),
+                _ => (axum::http::StatusCode::OK, serde_json::json!([])),
+            };
+            (status, axum::Json(body))
+        })
+        .await;
+
+        platform.remove_label(7, "o/r", 5, "mycelium").await.unwrap();
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_only_server_errors_are_transient() {
+        let platform = mock_platform(|request: Request| async move {
+            let status = match request.uri().path() {
+                "/repos/o/r/issues/4/comments" => axum::http::StatusCode::NOT_FOUND,
+                "/repos/o/r/issues/5/comments" => axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+                _ => axum::http::StatusCode::BAD_GATEWAY,
+            };
+            let body = serde_json::json!({
+                "message": status.canonical_reason(),
+                "documentation_url": "https://docs.github.com/rest"
+            });
+            (status, axum::Json(body))
+        })
+        .await;
+
+        for (issue, transient) in [(4, false), (5, false), (6, true)] {
+            let error = platform
+                .post_comment(7, "o/r", issue, "Done")
+                .await
+                .unwrap_err();
+            assert_eq!(error.is_transient(), transient, "{error:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_listings_follow_pagination() {
+        let platform = mock_platform(mock_listings).await;
+
+        let repos = platform.list_installation_repos(7).await.unwrap();
+        let names: Vec<&str> = repos.iter().map(|r| r.full_name.as_str()).collect();
+        assert_eq!(names, vec!["o/first", "o/second"]);
+
+        let issues = platform
+            .list_open_issues_with_label(7, "o/r", "mycelium")
+            .await
+            .unwrap();
+        let numbers: Vec<u64> = issues.iter().map(|i| i.number).collect();
+        assert_eq!(numbers, vec![1, 2]);
+        assert_eq!(issues[0].labels, vec!["mycelium"]);
+    }
 }
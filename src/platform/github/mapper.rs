@@ -10,6 +10,7 @@ pub fn map_issue(
         title: issue.title.clone(),
         body: issue.body.clone().unwrap_or_default(),
         labels: issue.labels.iter().map(|l| l.name.clone()).collect(),
+        assignees: issue.assignees.iter().map(|a| a.login.clone()).collect(),
         comments: comments.into_iter().map(map_comment).collect(),
     }
 }
@@ -31,3 +32,46 @@ pub fn map_pull_request(pr: octocrab::models::pulls::PullRequest) -> types::Pull
         base_branch: pr.base.ref_field.clone(),
     }
 }
+
+pub fn map_reaction(reaction: types::Reaction) -> octocrab::models::reactions::ReactionContent {
+    use octocrab::models::reactions::ReactionContent;
+
+    match reaction {
+        types::Reaction::Eyes => ReactionContent::Eyes,
+        types::Reaction::PlusOne => ReactionContent::PlusOne,
+        types::Reaction::Confused => ReactionContent::Confused,
+        types::Reaction::Rocket => ReactionContent::Rocket,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_fork_pull_request_uses_ref() {
+        let pr: octocrab::models::pulls::PullRequest = serde_json::from_value(serde_json::json!({
+            "url": "https://api.github.com/repos/owner/repo/pulls/12",
+            "id": 1200,
+            "number": 12,
+            "locked": false,
+            "title": "Fix #3: crash on startup",
+            "body": "Resolves #3",
+            "head": {
+                "label": "contributor:mycelium/issue-3",
+                "ref": "mycelium/issue-3",
+                "sha": "0123456789abcdef0123456789abcdef01234567"
+            },
+            "base": {
+                "label": "owner:main",
+                "ref": "main",
+                "sha": "89abcdef0123456789abcdef0123456789abcdef"
+            }
+        }))
+        .unwrap();
+
+        let mapped = map_pull_request(pr);
+        assert_eq!(mapped.head_branch, "mycelium/issue-3");
+        assert_eq!(mapped.base_branch, "main");
+    }
+}
@@ -0,0 +1,889 @@
+use async_trait::async_trait;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::config::GitLabConfig;
+use crate::error::{is_transient_status, AppError, Result};
+use crate::platform::types::*;
+use crate::platform::Platform;
+
+use super::mapper::{self, GitLabDiscussion, GitLabIssue, GitLabJob, GitLabMergeRequest};
+use super::mapper::{GitLabNote, GitLabProject, GitLabUser};
+
+/// Lowest access level allowed to push: Developer.
+const DEVELOPER_ACCESS_LEVEL: u64 = 30;
+
+/// Upper bound on remembered notes; the oldest are forgotten first.
+const MAX_REMEMBERED_NOTES: usize = 10_000;
+
+/// `Platform` for GitLab's REST API, authenticated with an access token.
+///
+/// Issue numbers are issue IIDs and pull requests are merge requests, whose
+/// IIDs are numbered separately from issues; comments, labels and reactions
+/// address issues. The single "installation" is `TOKEN_INSTALLATION_ID`.
+pub struct GitLabPlatform {
+    config: GitLabConfig,
+    /// Log write operations (comments, labels, MRs, ...) instead of performing them.
+    dry_run: bool,
+    http: reqwest::Client,
+    /// Project and issue of recently seen notes, as GitLab only addresses
+    /// notes through the issue they are on.
+    note_issues: Mutex<NoteIssues>,
+}
+
+/// Note ID -> (project, issue IID), bounded to `capacity` entries.
+struct NoteIssues {
+    issues: HashMap<u64, (String, u64)>,
+    /// Insertion order, for evicting the oldest entries.
+    order: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl NoteIssues {
+    fn new(capacity: usize) -> Self {
+        Self {
+            issues: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    fn insert(&mut self, note_id: u64, repo_full_name: &str, issue_number: u64) {
+        let issue = (repo_full_name.to_string(), issue_number);
+        if self.issues.insert(note_id, issue).is_some() {
+            return;
+        }
+        self.order.push_back(note_id);
+        while self.order.len() > self.capacity {
+            if let Some(id) = self.order.pop_front() {
+                self.issues.remove(&id);
+            }
+        }
+    }
+
+    fn get(&self, note_id: u64) -> Option<&(String, u64)> {
+        self.issues.get(&note_id)
+    }
+}
+
+impl GitLabPlatform {
+    pub fn new(config: &GitLabConfig, dry_run: bool) -> Result<Self> {
+        if config.token.is_empty() {
+            return Err(AppError::Config("Set gitlab.token".to_string()));
+        }
+        let http = reqwest::Client::builder()
+            .user_agent("mycelium")
+            .build()
+            .map_err(|e| AppError::Config(format!("Failed to build HTTP client: {e}")))?;
+
+        Ok(Self {
+            config: config.clone(),
+            dry_run,
+            http,
+            note_issues: Mutex::new(NoteIssues::new(MAX_REMEMBERED_NOTES)),
+        })
+    }
+
+    /// API path of a project, which GitLab accepts as its URL-encoded full path.
+    fn project(repo_full_name: &str) -> String {
+        format!("/projects/{}", urlencoding::encode(repo_full_name))
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}{path}", self.config.base_url.trim_end_matches('/'));
+        self.http
+            .request(method, url)
+            .bearer_auth(&self.config.token)
+    }
+
+    async fn send(request: RequestBuilder) -> Result<Response> {
+        Self::check_status(request.send().await?).await
+    }
+
+    /// Turn a non-2xx response into an error carrying GitLab's message, as
+    /// [`AppError::Unavailable`] for a 5xx or 429.
+    async fn check_status(response: Response) -> Result<Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await.unwrap_or_default();
+        let message = format!("{status}: {}", body.trim());
+        if is_transient_status(status.as_u16()) {
+            return Err(AppError::Unavailable {
+                service: "GitLab API",
+                status: Some(status.as_u16()),
+                message,
+            });
+        }
+        Err(AppError::GitLabApi(message))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T> {
+        let response = Self::send(self.request(Method::GET, path).query(query)).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Fetch every page of a listing, following `x-next-page`.
+    async fn get_all_pages<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut page = "1".to_string();
+        loop {
+            let request = self
+                .request(Method::GET, path)
+                .query(query)
+                .query(&[("per_page", "100"), ("page", page.as_str())]);
+            let response = Self::send(request).await?;
+            let next = response
+                .headers()
+                .get("x-next-page")
+                .and_then(|v| v.to_str().ok())
+                .filter(|next| !next.is_empty())
+                .map(str::to_string);
+            items.extend(response.json::<Vec<T>>().await?);
+            match next {
+                Some(next) => page = next,
+                None => return Ok(items),
+            }
+        }
+    }
+
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<T> {
+        let response = Self::send(self.request(method, path).json(body)).await?;
+        Ok(response.json().await?)
+    }
+
+    /// IDs of the users with these usernames, skipping unknown ones.
+    async fn user_ids(&self, usernames: &[String]) -> Result<Vec<u64>> {
+        let mut ids = Vec::new();
+        for username in usernames {
+            let users: Vec<GitLabUser> = self.get("/users", &[("username", username)]).await?;
+            match users.first() {
+                Some(user) => ids.push(user.id),
+                None => tracing::warn!(username = %username, "No GitLab user with this username"),
+            }
+        }
+        Ok(ids)
+    }
+
+    fn remember_notes<'a>(
+        &self,
+        repo_full_name: &str,
+        issue_number: u64,
+        notes: impl IntoIterator<Item = &'a GitLabNote>,
+    ) {
+        let mut note_issues = self.note_issues.lock().unwrap();
+        for note in notes {
+            note_issues.insert(note.id, repo_full_name, issue_number);
+        }
+    }
+
+    /// API path of an issue note, which must have been seen recently.
+    fn note_path(&self, repo_full_name: &str, note_id: u64) -> Result<String> {
+        match self.note_issues.lock().unwrap().get(note_id) {
+            Some((repo, issue_number)) if repo == repo_full_name => Ok(format!(
+                "{}/issues/{issue_number}/notes/{note_id}",
+                Self::project(repo_full_name)
+            )),
+            _ => Err(AppError::GitLabApi(format!(
+                "Note {note_id} in {repo_full_name} is not on a known issue"
+            ))),
+        }
+    }
+
+    async fn merge_request_discussions(
+        &self,
+        repo_full_name: &str,
+        pr_number: u64,
+    ) -> Result<Vec<GitLabDiscussion>> {
+        let path = format!(
+            "{}/merge_requests/{pr_number}/discussions",
+            Self::project(repo_full_name)
+        );
+        self.get_all_pages(&path, &[]).await
+    }
+
+    async fn update_issue(
+        &self,
+        repo_full_name: &str,
+        issue_number: u64,
+        update: serde_json::Value,
+    ) -> Result<()> {
+        let path = format!("{}/issues/{issue_number}", Self::project(repo_full_name));
+        let _: serde_json::Value = self.send_json(Method::PUT, &path, &update).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Platform for GitLabPlatform {
+    async fn get_access_token(&self, _installation_id: u64) -> Result<String> {
+        Ok(self.config.token.clone())
+    }
+
+    async fn check_connection(&self) -> Result<()> {
+        let _: GitLabUser = self.get("/user", &[]).await?;
+        Ok(())
+    }
+
+    async fn list_installations(&self) -> Result<Vec<Installation>> {
+        Ok(vec![Installation {
+            id: TOKEN_INSTALLATION_ID,
+        }])
+    }
+
+    async fn list_installation_repos(
+        &self,
+        _installation_id: u64,
+    ) -> Result<Vec<InstallationRepo>> {
+        let query = [
+            ("membership", "true"),
+            ("min_access_level", "30"),
+            ("archived", "false"),
+        ];
+        let projects: Vec<GitLabProject> = self.get_all_pages("/projects", &query).await?;
+        Ok(projects.into_iter().map(mapper::map_project).collect())
+    }
+
+    async fn list_open_issues_with_label(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        label: &str,
+    ) -> Result<Vec<OpenIssue>> {
+        let path = format!("{}/issues", Self::project(repo_full_name));
+        let issues: Vec<GitLabIssue> = self
+            .get_all_pages(&path, &[("state", "opened"), ("labels", label)])
+            .await?;
+        Ok(issues.into_iter().map(mapper::map_open_issue).collect())
+    }
+
+    async fn get_issue(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+    ) -> Result<Issue> {
+        let path = format!("{}/issues/{issue_number}", Self::project(repo_full_name));
+        let issue: GitLabIssue = self.get(&path, &[]).await?;
+        let notes: Vec<GitLabNote> = self
+            .get_all_pages(
+                &format!("{path}/notes"),
+                &[("sort", "asc"), ("order_by", "created_at")],
+            )
+            .await?;
+        self.remember_notes(repo_full_name, issue_number, &notes);
+
+        Ok(mapper::map_issue(issue, notes))
+    }
+
+    async fn post_comment(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+        body: &str,
+    ) -> Result<u64> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                body,
+                "[dry run] Would post comment"
+            );
+            return Ok(0);
+        }
+        let path = format!(
+            "{}/issues/{issue_number}/notes",
+            Self::project(repo_full_name)
+        );
+        let note: GitLabNote = self
+            .send_json(Method::POST, &path, &json!({ "body": body }))
+            .await?;
+        self.remember_notes(repo_full_name, issue_number, [&note]);
+
+        Ok(note.id)
+    }
+
+    async fn update_comment(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        comment_id: u64,
+        body: &str,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                comment_id,
+                body,
+                "[dry run] Would update comment"
+            );
+            return Ok(());
+        }
+        let path = self.note_path(repo_full_name, comment_id)?;
+        let _: GitLabNote = self
+            .send_json(Method::PUT, &path, &json!({ "body": body }))
+            .await?;
+        Ok(())
+    }
+
+    async fn add_reaction(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+        reaction: Reaction,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                ?reaction,
+                "[dry run] Would add reaction"
+            );
+            return Ok(());
+        }
+        let path = format!(
+            "{}/issues/{issue_number}/award_emoji",
+            Self::project(repo_full_name)
+        );
+        let name = mapper::map_reaction(reaction);
+        let _: serde_json::Value = self
+            .send_json(Method::POST, &path, &json!({ "name": name }))
+            .await?;
+        Ok(())
+    }
+
+    async fn add_comment_reaction(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        comment_id: u64,
+        reaction: Reaction,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                comment_id,
+                ?reaction,
+                "[dry run] Would add comment reaction"
+            );
+            return Ok(());
+        }
+        let path = format!(
+            "{}/award_emoji",
+            self.note_path(repo_full_name, comment_id)?
+        );
+        let name = mapper::map_reaction(reaction);
+        let _: serde_json::Value = self
+            .send_json(Method::POST, &path, &json!({ "name": name }))
+            .await?;
+        Ok(())
+    }
+
+    async fn has_write_access(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        username: &str,
+    ) -> Result<bool> {
+        let Some(&user_id) = self.user_ids(&[username.to_string()]).await?.first() else {
+            return Ok(false);
+        };
+        // Includes members inherited from parent groups
+        let path = format!("{}/members/all/{user_id}", Self::project(repo_full_name));
+        let response = self.request(Method::GET, &path).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        let member: serde_json::Value = Self::check_status(response).await?.json().await?;
+        Ok(member["access_level"]
+            .as_u64()
+            .is_some_and(|level| level >= DEVELOPER_ACCESS_LEVEL))
+    }
+
+    async fn create_pull_request(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        pr: &CreatePullRequest,
+    ) -> Result<PullRequest> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                title = %pr.title,
+                head = %pr.head_branch,
+                base = %pr.base_branch,
+                draft = pr.draft,
+                body = %pr.body,
+                "[dry run] Would create merge request"
+            );
+            return Ok(PullRequest {
+                number: 0,
+                title: pr.title.clone(),
+                body: pr.body.clone(),
+                head_branch: pr.head_branch.clone(),
+                base_branch: pr.base_branch.clone(),
+            });
+        }
+        // GitLab marks drafts by title
+        let title = if pr.draft {
+            format!("Draft: {}", pr.title)
+        } else {
+            pr.title.clone()
+        };
+        let path = format!("{}/merge_requests", Self::project(repo_full_name));
+        let request = self.request(Method::POST, &path).json(&json!({
+            "source_branch": pr.head_branch,
+            "target_branch": pr.base_branch,
+            "title": title,
+            "description": pr.body,
+        }));
+        let response = request.send().await?;
+
+        // A re-run can find its branch already has an open MR; reuse it
+        if response.status() == StatusCode::CONFLICT {
+            tracing::info!(
+                repo = repo_full_name,
+                head = %pr.head_branch,
+                "Merge request already exists, using the open one"
+            );
+            return self
+                .find_open_pull_request(installation_id, repo_full_name, &pr.head_branch)
+                .await?
+                .ok_or_else(|| {
+                    AppError::GitLabApi(format!(
+                        "GitLab reported an existing merge request for {} but none is open",
+                        pr.head_branch
+                    ))
+                });
+        }
+        let created: GitLabMergeRequest = Self::check_status(response).await?.json().await?;
+
+        Ok(mapper::map_merge_request(created))
+    }
+
+    async fn request_reviewers(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+        reviewers: &[String],
+    ) -> Result<()> {
+        if reviewers.is_empty() {
+            return Ok(());
+        }
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                pr = pr_number,
+                ?reviewers,
+                "[dry run] Would request reviewers"
+            );
+            return Ok(());
+        }
+        let reviewer_ids = self.user_ids(reviewers).await?;
+        if reviewer_ids.is_empty() {
+            return Ok(());
+        }
+        let path = format!(
+            "{}/merge_requests/{pr_number}",
+            Self::project(repo_full_name)
+        );
+        let _: serde_json::Value = self
+            .send_json(Method::PUT, &path, &json!({ "reviewer_ids": reviewer_ids }))
+            .await
+            .map_err(|e| AppError::GitLabApi(format!("Failed to request reviewers: {e}")))?;
+        Ok(())
+    }
+
+    async fn add_label(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+        label: &str,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                label,
+                "[dry run] Would add label"
+            );
+            return Ok(());
+        }
+        self.update_issue(repo_full_name, issue_number, json!({ "add_labels": label }))
+            .await
+    }
+
+    async fn remove_label(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+        label: &str,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                label,
+                "[dry run] Would remove label"
+            );
+            return Ok(());
+        }
+        // Removing a label the issue doesn't have is not an error
+        self.update_issue(
+            repo_full_name,
+            issue_number,
+            json!({ "remove_labels": label }),
+        )
+        .await
+    }
+
+    async fn close_issue(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                "[dry run] Would close issue"
+            );
+            return Ok(());
+        }
+        self.update_issue(
+            repo_full_name,
+            issue_number,
+            json!({ "state_event": "close" }),
+        )
+        .await
+    }
+
+    async fn get_pull_request(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+    ) -> Result<PullRequest> {
+        let path = format!(
+            "{}/merge_requests/{pr_number}",
+            Self::project(repo_full_name)
+        );
+        let mr: GitLabMergeRequest = self.get(&path, &[]).await?;
+        Ok(mapper::map_merge_request(mr))
+    }
+
+    async fn find_open_pull_request(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        head_branch: &str,
+    ) -> Result<Option<PullRequest>> {
+        let path = format!("{}/merge_requests", Self::project(repo_full_name));
+        let mrs: Vec<GitLabMergeRequest> = self
+            .get(
+                &path,
+                &[
+                    ("state", "opened"),
+                    ("source_branch", head_branch),
+                    ("per_page", "1"),
+                ],
+            )
+            .await?;
+        Ok(mrs.into_iter().next().map(mapper::map_merge_request))
+    }
+
+    async fn branch_exists(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        branch: &str,
+    ) -> Result<bool> {
+        let path = format!(
+            "{}/repository/branches/{}",
+            Self::project(repo_full_name),
+            urlencoding::encode(branch)
+        );
+        let response = self.request(Method::GET, &path).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        Self::check_status(response).await?;
+        Ok(true)
+    }
+
+    fn tarball_url(&self, repo_full_name: &str, git_ref: &str) -> String {
+        format!(
+            "{}{}/repository/archive.tar.gz?sha={}",
+            self.config.base_url.trim_end_matches('/'),
+            Self::project(repo_full_name),
+            urlencoding::encode(git_ref)
+        )
+    }
+
+    /// `run_id` is a pipeline ID.
+    async fn get_failed_job_logs(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        run_id: u64,
+    ) -> Result<Vec<JobLog>> {
+        let project = Self::project(repo_full_name);
+        let jobs: Vec<GitLabJob> = self
+            .get_all_pages(
+                &format!("{project}/pipelines/{run_id}/jobs"),
+                &[("scope[]", "failed")],
+            )
+            .await?;
+
+        let mut logs = Vec::new();
+        for job in jobs {
+            let path = format!("{project}/jobs/{}/trace", job.id);
+            let log = Self::send(self.request(Method::GET, &path))
+                .await?
+                .text()
+                .await?;
+            logs.push(JobLog {
+                name: job.name,
+                log,
+            });
+        }
+        Ok(logs)
+    }
+
+    async fn get_reviews(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+    ) -> Result<Vec<Review>> {
+        let discussions = self
+            .merge_request_discussions(repo_full_name, pr_number)
+            .await?;
+        Ok(mapper::map_reviews(discussions))
+    }
+
+    async fn reply_to_review_comment(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+        comment_id: u64,
+        body: &str,
+    ) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(
+                repo = repo_full_name,
+                pr = pr_number,
+                comment_id,
+                body,
+                "[dry run] Would reply to review comment"
+            );
+            return Ok(());
+        }
+        let discussions = self
+            .merge_request_discussions(repo_full_name, pr_number)
+            .await?;
+        let discussion = discussions
+            .iter()
+            .find(|d| d.notes.iter().any(|note| note.id == comment_id))
+            .ok_or_else(|| {
+                AppError::GitLabApi(format!(
+                    "No thread on !{pr_number} contains note {comment_id}"
+                ))
+            })?;
+        let path = format!(
+            "{}/merge_requests/{pr_number}/discussions/{}/notes",
+            Self::project(repo_full_name),
+            discussion.id
+        );
+        let _: GitLabNote = self
+            .send_json(Method::POST, &path, &json!({ "body": body }))
+            .await?;
+        Ok(())
+    }
+
+    async fn list_review_threads(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+    ) -> Result<Vec<ReviewThread>> {
+        let discussions = self
+            .merge_request_discussions(repo_full_name, pr_number)
+            .await?;
+
+        let threads: Vec<ReviewThread> = discussions
+            .into_iter()
+            .filter_map(|discussion| {
+                let first = discussion.notes.into_iter().find(|note| !note.system)?;
+                first.position.as_ref()?;
+                Some(ReviewThread {
+                    id: thread_id(repo_full_name, pr_number, &discussion.id),
+                    comment_id: first.id,
+                    is_resolved: first.resolved,
+                })
+            })
+            .collect();
+        Ok(threads)
+    }
+
+    async fn resolve_review_thread(&self, _installation_id: u64, thread_id: &str) -> Result<()> {
+        if self.dry_run {
+            tracing::info!(thread_id, "[dry run] Would resolve review thread");
+            return Ok(());
+        }
+        let Some((repo_full_name, pr_number, discussion_id)) = parse_thread_id(thread_id) else {
+            return Err(AppError::GitLabApi(format!(
+                "Thread {thread_id} is not on a known merge request"
+            )));
+        };
+        let path = format!(
+            "{}/merge_requests/{pr_number}/discussions/{discussion_id}",
+            Self::project(repo_full_name)
+        );
+        let request = self
+            .request(Method::PUT, &path)
+            .query(&[("resolved", "true")]);
+        Self::send(request).await?;
+        Ok(())
+    }
+}
+
+/// Review thread ID carrying the project and merge request a discussion is
+/// on, as GitLab only addresses discussions through their merge request.
+fn thread_id(repo_full_name: &str, pr_number: u64, discussion_id: &str) -> String {
+    format!("{repo_full_name}!{pr_number}!{discussion_id}")
+}
+
+/// Inverse of `thread_id`: (project, MR IID, discussion ID).
+fn parse_thread_id(thread_id: &str) -> Option<(&str, u64, &str)> {
+    let mut parts = thread_id.rsplitn(3, '!');
+    let discussion_id = parts.next()?;
+    let pr_number = parts.next()?.parse().ok()?;
+    let repo_full_name = parts.next()?;
+    Some((repo_full_name, pr_number, discussion_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Request;
+    use axum::response::{IntoResponse, Response};
+
+    /// Issue 5 of `group/project`, its notes over two pages, and note creation
+    /// and editing echoing the request body back.
+    async fn mock_gitlab(request: Request) -> Response {
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        let second_page = request.uri().query().unwrap_or("").contains("page=2");
+        let bytes = axum::body::to_bytes(request.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let sent: serde_json::Value = serde_json::from_slice(&bytes).unwrap_or_default();
+        let note = |id: u64, body: &str| json!({ "id": id, "body": body, "author": { "id": 1, "username": "alice" } });
+
+        match (method.as_str(), path.as_str(), second_page) {
+            ("GET", "/projects/group%2Fproject/issues/5", _) => axum::Json(json!({
+                "iid": 5,
+                "title": "Crash",
+                "description": null,
+                "labels": ["mycelium"],
+                "assignees": [{ "id": 1, "username": "alice" }]
+            }))
+            .into_response(),
+            ("GET", "/projects/group%2Fproject/issues/5/notes", false) => (
+                [("x-next-page", "2")],
+                axum::Json(json!([note(1, "first")])),
+            )
+                .into_response(),
+            ("GET", "/projects/group%2Fproject/issues/5/notes", true) => {
+                axum::Json(json!([note(2, "second")])).into_response()
+            }
+            ("POST", "/projects/group%2Fproject/issues/5/notes", _)
+            | ("PUT", "/projects/group%2Fproject/issues/5/notes/42", _) => {
+                axum::Json(note(42, sent["body"].as_str().unwrap())).into_response()
+            }
+            _ => StatusCode::NOT_FOUND.into_response(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_issue_notes_are_paginated_and_addressable() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = axum::Router::new().fallback(mock_gitlab);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let config = GitLabConfig {
+            base_url,
+            token: "glpat".to_string(),
+            ..GitLabConfig::default()
+        };
+        let platform = GitLabPlatform::new(&config, false).unwrap();
+
+        let issue = platform.get_issue(0, "group/project", 5).await.unwrap();
+        assert_eq!(issue.assignees, ["alice"]);
+        let bodies: Vec<&str> = issue.comments.iter().map(|c| c.body.as_str()).collect();
+        assert_eq!(bodies, ["first", "second"]);
+
+        // Notes are edited through their issue, so only known ones can be
+        assert!(platform
+            .update_comment(0, "group/project", 42, "edited")
+            .await
+            .is_err());
+        let id = platform
+            .post_comment(0, "group/project", 5, "hello")
+            .await
+            .unwrap();
+        assert_eq!(id, 42);
+        platform
+            .update_comment(0, "group/project", 42, "edited")
+            .await
+            .unwrap();
+        assert!(platform
+            .update_comment(0, "other/project", 42, "edited")
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_thread_id_round_trips() {
+        let id = thread_id("group/sub/project", 7, "6a9c1750b37d");
+        assert_eq!(
+            parse_thread_id(&id),
+            Some(("group/sub/project", 7, "6a9c1750b37d"))
+        );
+        assert_eq!(parse_thread_id("6a9c1750b37d"), None);
+        assert_eq!(parse_thread_id("group/project!x!6a9c1750b37d"), None);
+    }
+
+    #[test]
+    fn test_remembered_notes_are_bounded() {
+        let mut notes = NoteIssues::new(2);
+        notes.insert(1, "group/project", 5);
+        notes.insert(2, "group/project", 5);
+        notes.insert(1, "group/project", 5);
+        notes.insert(3, "group/project", 6);
+        assert!(notes.get(1).is_none());
+        assert_eq!(notes.get(3), Some(&("group/project".to_string(), 6)));
+        assert_eq!(notes.issues.len(), 2);
+    }
+}
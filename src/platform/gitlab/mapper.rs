@@ -0,0 +1,245 @@
+use serde::Deserialize;
+
+use crate::platform::types;
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabUser {
+    pub id: u64,
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabProject {
+    pub path_with_namespace: String,
+    pub http_url_to_repo: String,
+    /// Unset for an empty repository.
+    pub default_branch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabIssue {
+    pub iid: u64,
+    pub title: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub assignees: Vec<GitLabUser>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabMergeRequest {
+    pub iid: u64,
+    pub title: String,
+    pub description: Option<String>,
+    pub source_branch: String,
+    pub target_branch: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabNote {
+    pub id: u64,
+    pub body: String,
+    pub author: GitLabUser,
+    /// Notes GitLab writes itself, e.g. "added label ~bug".
+    #[serde(default)]
+    pub system: bool,
+    /// Set on notes anchored to a line of the diff.
+    pub position: Option<GitLabPosition>,
+    #[serde(default)]
+    pub resolved: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabPosition {
+    pub new_path: Option<String>,
+    pub old_path: Option<String>,
+    pub new_line: Option<u64>,
+    pub old_line: Option<u64>,
+}
+
+/// A thread of notes on an issue or merge request.
+#[derive(Debug, Deserialize)]
+pub struct GitLabDiscussion {
+    pub id: String,
+    pub notes: Vec<GitLabNote>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabJob {
+    pub id: u64,
+    pub name: String,
+}
+
+pub fn map_project(project: GitLabProject) -> types::InstallationRepo {
+    types::InstallationRepo {
+        full_name: project.path_with_namespace,
+        clone_url: project.http_url_to_repo,
+        default_branch: project.default_branch.unwrap_or_else(|| "main".to_string()),
+    }
+}
+
+pub fn map_open_issue(issue: GitLabIssue) -> types::OpenIssue {
+    types::OpenIssue {
+        number: issue.iid,
+        title: issue.title,
+        body: issue.description.unwrap_or_default(),
+        labels: issue.labels,
+    }
+}
+
+/// Map an issue and its notes, leaving out GitLab's own system notes.
+pub fn map_issue(issue: GitLabIssue, notes: Vec<GitLabNote>) -> types::Issue {
+    types::Issue {
+        number: issue.iid,
+        title: issue.title,
+        body: issue.description.unwrap_or_default(),
+        labels: issue.labels,
+        assignees: issue.assignees.into_iter().map(|a| a.username).collect(),
+        comments: notes
+            .into_iter()
+            .filter(|note| !note.system)
+            .map(|note| types::Comment {
+                id: note.id,
+                author: note.author.username,
+                body: note.body,
+            })
+            .collect(),
+    }
+}
+
+pub fn map_merge_request(mr: GitLabMergeRequest) -> types::PullRequest {
+    types::PullRequest {
+        number: mr.iid,
+        title: mr.title,
+        body: mr.description.unwrap_or_default(),
+        head_branch: mr.source_branch,
+        base_branch: mr.target_branch,
+    }
+}
+
+/// Map merge request discussions to reviews. GitLab has no review objects:
+/// each general comment becomes a review of its own, and every comment on
+/// the diff goes into one review, with replies pointing at the note that
+/// started their thread.
+pub fn map_reviews(discussions: Vec<GitLabDiscussion>) -> Vec<types::Review> {
+    let mut reviews = Vec::new();
+    let mut diff_comments = Vec::new();
+
+    for discussion in discussions {
+        let mut notes = discussion.notes.into_iter().filter(|note| !note.system);
+        let Some(first) = notes.next() else {
+            continue;
+        };
+        if first.position.is_none() {
+            for note in std::iter::once(first).chain(notes) {
+                reviews.push(types::Review {
+                    id: note.id,
+                    author: note.author.username,
+                    body: note.body,
+                    state: types::ReviewState::Commented,
+                    comments: Vec::new(),
+                });
+            }
+            continue;
+        }
+
+        let root_id = first.id;
+        diff_comments.push(map_diff_note(first, None));
+        diff_comments.extend(notes.map(|note| map_diff_note(note, Some(root_id))));
+    }
+
+    if let Some(first) = diff_comments.first() {
+        reviews.push(types::Review {
+            id: first.id,
+            author: first.author.clone(),
+            body: String::new(),
+            state: types::ReviewState::Commented,
+            comments: diff_comments,
+        });
+    }
+    reviews
+}
+
+fn map_diff_note(note: GitLabNote, in_reply_to: Option<u64>) -> types::ReviewComment {
+    let (path, line) = match note.position {
+        Some(position) => (
+            position.new_path.or(position.old_path),
+            position.new_line.or(position.old_line),
+        ),
+        None => (None, None),
+    };
+    types::ReviewComment {
+        id: note.id,
+        author: note.author.username,
+        body: note.body,
+        path,
+        line,
+        diff_hunk: None,
+        in_reply_to,
+    }
+}
+
+/// Award emoji name for a reaction.
+pub fn map_reaction(reaction: types::Reaction) -> &'static str {
+    match reaction {
+        types::Reaction::Eyes => "eyes",
+        types::Reaction::PlusOne => "thumbsup",
+        types::Reaction::Confused => "confused",
+        types::Reaction::Rocket => "rocket",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_reviews_threads_diff_notes() {
+        let discussions: Vec<GitLabDiscussion> = serde_json::from_value(serde_json::json!([
+            {
+                "id": "d1",
+                "notes": [
+                    { "id": 1, "body": "added 1 commit", "author": { "id": 9, "username": "mycelium" }, "system": true }
+                ]
+            },
+            {
+                "id": "d2",
+                "notes": [
+                    { "id": 2, "body": "Looks close", "author": { "id": 7, "username": "alice" } }
+                ]
+            },
+            {
+                "id": "d3",
+                "notes": [
+                    {
+                        "id": 3,
+                        "body": "Rename this",
+                        "author": { "id": 7, "username": "alice" },
+                        "position": { "new_path": "src/lib.rs", "old_path": "src/lib.rs", "new_line": 12, "old_line": null }
+                    },
+                    {
+                        "id": 4,
+                        "body": "To what?",
+                        "author": { "id": 9, "username": "mycelium" },
+                        "position": { "new_path": "src/lib.rs", "old_path": "src/lib.rs", "new_line": 12, "old_line": null }
+                    }
+                ]
+            }
+        ]))
+        .unwrap();
+
+        let reviews = map_reviews(discussions);
+        assert_eq!(reviews.len(), 2);
+        assert_eq!(reviews[0].body, "Looks close");
+        assert!(reviews[0].comments.is_empty());
+
+        let comments = &reviews[1].comments;
+        assert_eq!(reviews[1].author, "alice");
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(comments[0].line, Some(12));
+        assert_eq!(comments[0].in_reply_to, None);
+        assert_eq!(comments[1].in_reply_to, Some(3));
+    }
+}
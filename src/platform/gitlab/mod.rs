@@ -0,0 +1,4 @@
+pub mod client;
+pub mod mapper;
+
+pub use client::GitLabPlatform;
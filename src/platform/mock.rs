@@ -0,0 +1,364 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use super::types::*;
+use super::Platform;
+use crate::error::{AppError, Result};
+
+/// In-memory `Platform` for tests: serves canned issues and PRs and records
+/// every call so tests can assert on what the app did without a GitHub API.
+#[derive(Default)]
+pub struct MockPlatform {
+    calls: Mutex<Vec<String>>,
+    repos: Vec<InstallationRepo>,
+    issues: HashMap<(String, u64), Issue>,
+    pull_requests: HashMap<(String, u64), PullRequest>,
+    branches: HashSet<(String, String)>,
+    disconnected: bool,
+}
+
+impl MockPlatform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_repo(mut self, repo: InstallationRepo) -> Self {
+        self.repos.push(repo);
+        self
+    }
+
+    pub fn with_issue(mut self, repo_full_name: &str, issue: Issue) -> Self {
+        self.issues
+            .insert((repo_full_name.to_string(), issue.number), issue);
+        self
+    }
+
+    pub fn with_pull_request(mut self, repo_full_name: &str, pr: PullRequest) -> Self {
+        self.pull_requests
+            .insert((repo_full_name.to_string(), pr.number), pr);
+        self
+    }
+
+    /// A branch on the remote with no PR, e.g. left by an earlier run.
+    pub fn with_branch(mut self, repo_full_name: &str, branch: &str) -> Self {
+        self.branches
+            .insert((repo_full_name.to_string(), branch.to_string()));
+        self
+    }
+
+    /// Fail `check_connection` and permission checks, as if GitHub were
+    /// unreachable.
+    pub fn disconnected(mut self) -> Self {
+        self.disconnected = true;
+        self
+    }
+
+    /// Calls made so far, e.g. `"add_reaction owner/repo#1 Eyes"`.
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn record(&self, call: String) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+#[async_trait]
+impl Platform for MockPlatform {
+    async fn get_access_token(&self, installation_id: u64) -> Result<String> {
+        self.record(format!("get_access_token {installation_id}"));
+        Ok("mock-token".to_string())
+    }
+
+    async fn check_connection(&self) -> Result<()> {
+        self.record("check_connection".to_string());
+        if self.disconnected {
+            return Err(AppError::GitHubApi(
+                "mock platform is disconnected".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn list_installations(&self) -> Result<Vec<Installation>> {
+        self.record("list_installations".to_string());
+        Ok(vec![Installation { id: 1 }])
+    }
+
+    async fn list_installation_repos(&self, installation_id: u64) -> Result<Vec<InstallationRepo>> {
+        self.record(format!("list_installation_repos {installation_id}"));
+        Ok(self.repos.clone())
+    }
+
+    async fn list_open_issues_with_label(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        label: &str,
+    ) -> Result<Vec<OpenIssue>> {
+        self.record(format!(
+            "list_open_issues_with_label {repo_full_name} {label}"
+        ));
+        let mut issues: Vec<OpenIssue> = self
+            .issues
+            .iter()
+            .filter(|((repo, _), issue)| {
+                repo == repo_full_name && issue.labels.iter().any(|l| l == label)
+            })
+            .map(|(_, issue)| OpenIssue {
+                number: issue.number,
+                title: issue.title.clone(),
+                body: issue.body.clone(),
+                labels: issue.labels.clone(),
+            })
+            .collect();
+        issues.sort_by_key(|issue| issue.number);
+        Ok(issues)
+    }
+
+    async fn get_issue(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+    ) -> Result<Issue> {
+        self.record(format!("get_issue {repo_full_name}#{issue_number}"));
+        self.issues
+            .get(&(repo_full_name.to_string(), issue_number))
+            .cloned()
+            .ok_or_else(|| AppError::GitHubApi(format!("No issue {repo_full_name}#{issue_number}")))
+    }
+
+    async fn post_comment(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+        body: &str,
+    ) -> Result<u64> {
+        self.record(format!(
+            "post_comment {repo_full_name}#{issue_number} {body}"
+        ));
+        Ok(self.calls.lock().unwrap().len() as u64)
+    }
+
+    async fn update_comment(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        comment_id: u64,
+        body: &str,
+    ) -> Result<()> {
+        self.record(format!(
+            "update_comment {repo_full_name} {comment_id} {body}"
+        ));
+        Ok(())
+    }
+
+    async fn add_reaction(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+        reaction: Reaction,
+    ) -> Result<()> {
+        self.record(format!(
+            "add_reaction {repo_full_name}#{issue_number} {reaction:?}"
+        ));
+        Ok(())
+    }
+
+    async fn add_comment_reaction(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        comment_id: u64,
+        reaction: Reaction,
+    ) -> Result<()> {
+        self.record(format!(
+            "add_comment_reaction {repo_full_name} {comment_id} {reaction:?}"
+        ));
+        Ok(())
+    }
+
+    async fn has_write_access(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        username: &str,
+    ) -> Result<bool> {
+        self.record(format!("has_write_access {repo_full_name} {username}"));
+        if self.disconnected {
+            return Err(AppError::GitHubApi(
+                "mock platform is disconnected".to_string(),
+            ));
+        }
+        Ok(true)
+    }
+
+    async fn create_pull_request(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr: &CreatePullRequest,
+    ) -> Result<PullRequest> {
+        self.record(format!(
+            "create_pull_request {repo_full_name} {} -> {}",
+            pr.head_branch, pr.base_branch
+        ));
+        Ok(PullRequest {
+            number: 1000,
+            title: pr.title.clone(),
+            body: pr.body.clone(),
+            head_branch: pr.head_branch.clone(),
+            base_branch: pr.base_branch.clone(),
+        })
+    }
+
+    async fn request_reviewers(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+        reviewers: &[String],
+    ) -> Result<()> {
+        self.record(format!(
+            "request_reviewers {repo_full_name}#{pr_number} {}",
+            reviewers.join(",")
+        ));
+        Ok(())
+    }
+
+    async fn add_label(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+        label: &str,
+    ) -> Result<()> {
+        self.record(format!("add_label {repo_full_name}#{issue_number} {label}"));
+        Ok(())
+    }
+
+    async fn remove_label(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+        label: &str,
+    ) -> Result<()> {
+        self.record(format!(
+            "remove_label {repo_full_name}#{issue_number} {label}"
+        ));
+        Ok(())
+    }
+
+    async fn close_issue(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+    ) -> Result<()> {
+        self.record(format!("close_issue {repo_full_name}#{issue_number}"));
+        Ok(())
+    }
+
+    async fn get_pull_request(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+    ) -> Result<PullRequest> {
+        self.record(format!("get_pull_request {repo_full_name}#{pr_number}"));
+        self.pull_requests
+            .get(&(repo_full_name.to_string(), pr_number))
+            .cloned()
+            .ok_or_else(|| AppError::GitHubApi(format!("No PR {repo_full_name}#{pr_number}")))
+    }
+
+    async fn find_open_pull_request(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        head_branch: &str,
+    ) -> Result<Option<PullRequest>> {
+        self.record(format!(
+            "find_open_pull_request {repo_full_name} {head_branch}"
+        ));
+        Ok(self
+            .pull_requests
+            .iter()
+            .find(|((repo, _), pr)| repo == repo_full_name && pr.head_branch == head_branch)
+            .map(|(_, pr)| pr.clone()))
+    }
+
+    async fn branch_exists(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        branch: &str,
+    ) -> Result<bool> {
+        self.record(format!("branch_exists {repo_full_name} {branch}"));
+        let key = (repo_full_name.to_string(), branch.to_string());
+        Ok(self.branches.contains(&key)
+            || self
+                .pull_requests
+                .iter()
+                .any(|((repo, _), pr)| repo == repo_full_name && pr.head_branch == branch))
+    }
+
+    fn tarball_url(&self, repo_full_name: &str, git_ref: &str) -> String {
+        format!("http://mock.invalid/repos/{repo_full_name}/tarball/{git_ref}")
+    }
+
+    async fn get_failed_job_logs(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        run_id: u64,
+    ) -> Result<Vec<JobLog>> {
+        self.record(format!("get_failed_job_logs {repo_full_name} {run_id}"));
+        Ok(Vec::new())
+    }
+
+    async fn get_reviews(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+    ) -> Result<Vec<Review>> {
+        self.record(format!("get_reviews {repo_full_name}#{pr_number}"));
+        Ok(Vec::new())
+    }
+
+    async fn reply_to_review_comment(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+        comment_id: u64,
+        body: &str,
+    ) -> Result<()> {
+        self.record(format!(
+            "reply_to_review_comment {repo_full_name}#{pr_number} {comment_id} {body}"
+        ));
+        Ok(())
+    }
+
+    async fn list_review_threads(
+        &self,
+        _installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+    ) -> Result<Vec<ReviewThread>> {
+        self.record(format!("list_review_threads {repo_full_name}#{pr_number}"));
+        Ok(Vec::new())
+    }
+
+    async fn resolve_review_thread(&self, _installation_id: u64, thread_id: &str) -> Result<()> {
+        self.record(format!("resolve_review_thread {thread_id}"));
+        Ok(())
+    }
+}
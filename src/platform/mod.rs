@@ -1,4 +1,7 @@
 pub mod github;
+pub mod gitlab;
+#[cfg(test)]
+pub mod mock;
 pub mod types;
 
 use async_trait::async_trait;
@@ -11,6 +14,10 @@ pub trait Platform: Send + Sync {
     /// Get an installation-scoped access token.
     async fn get_access_token(&self, installation_id: u64) -> Result<String>;
 
+    /// Make a cheap authenticated call to confirm the credentials work and
+    /// the API is reachable.
+    async fn check_connection(&self) -> Result<()>;
+
     /// List all installations of this GitHub App.
     async fn list_installations(&self) -> Result<Vec<Installation>>;
 
@@ -33,15 +40,50 @@ pub trait Platform: Send + Sync {
         issue_number: u64,
     ) -> Result<Issue>;
 
-    /// Post a comment on an issue or PR.
+    /// Post a comment on an issue or PR, returning the new comment's ID.
     async fn post_comment(
         &self,
         installation_id: u64,
         repo_full_name: &str,
         issue_number: u64,
         body: &str,
+    ) -> Result<u64>;
+
+    /// Replace the body of an existing issue or PR comment.
+    async fn update_comment(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        comment_id: u64,
+        body: &str,
+    ) -> Result<()>;
+
+    /// Add a reaction to an issue or PR.
+    async fn add_reaction(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+        reaction: Reaction,
+    ) -> Result<()>;
+
+    /// Add a reaction to an issue or PR comment.
+    async fn add_comment_reaction(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        comment_id: u64,
+        reaction: Reaction,
     ) -> Result<()>;
 
+    /// Check whether a user has write (push) access to the repository.
+    async fn has_write_access(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        username: &str,
+    ) -> Result<bool>;
+
     /// Create a pull request.
     async fn create_pull_request(
         &self,
@@ -50,7 +92,17 @@ pub trait Platform: Send + Sync {
         pr: &CreatePullRequest,
     ) -> Result<PullRequest>;
 
-    /// Add a label to an issue or PR.
+    /// Request reviews on a PR. Reviewers GitHub rejects (e.g. without repo
+    /// access) are logged and skipped; the rest are still requested.
+    async fn request_reviewers(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+        reviewers: &[String],
+    ) -> Result<()>;
+
+    /// Add a label to an issue or PR. Adding one it already has is a no-op.
     async fn add_label(
         &self,
         installation_id: u64,
@@ -59,7 +111,8 @@ pub trait Platform: Send + Sync {
         label: &str,
     ) -> Result<()>;
 
-    /// Remove a label from an issue or PR.
+    /// Remove a label from an issue or PR. Removing one it doesn't have is
+    /// not an error.
     async fn remove_label(
         &self,
         installation_id: u64,
@@ -68,6 +121,14 @@ pub trait Platform: Send + Sync {
         label: &str,
     ) -> Result<()>;
 
+    /// Close an issue as completed.
+    async fn close_issue(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+    ) -> Result<()>;
+
     /// Fetch a pull request.
     async fn get_pull_request(
         &self,
@@ -76,6 +137,34 @@ pub trait Platform: Send + Sync {
         pr_number: u64,
     ) -> Result<PullRequest>;
 
+    /// Find the open pull request whose head is `head_branch` in this repo, if any.
+    async fn find_open_pull_request(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        head_branch: &str,
+    ) -> Result<Option<PullRequest>>;
+
+    /// Check whether a branch exists on the remote repository.
+    async fn branch_exists(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        branch: &str,
+    ) -> Result<bool>;
+
+    /// URL serving a gzipped tarball of the repository at `git_ref`, to be
+    /// fetched with an installation token.
+    fn tarball_url(&self, repo_full_name: &str, git_ref: &str) -> String;
+
+    /// Fetch the logs of every failed job in a GitHub Actions workflow run.
+    async fn get_failed_job_logs(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        run_id: u64,
+    ) -> Result<Vec<JobLog>>;
+
     /// Fetch reviews on a PR.
     async fn get_reviews(
         &self,
@@ -83,4 +172,25 @@ pub trait Platform: Send + Sync {
         repo_full_name: &str,
         pr_number: u64,
     ) -> Result<Vec<Review>>;
+
+    /// Reply in the thread of a PR review comment.
+    async fn reply_to_review_comment(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+        comment_id: u64,
+        body: &str,
+    ) -> Result<()>;
+
+    /// List the review comment threads on a PR.
+    async fn list_review_threads(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        pr_number: u64,
+    ) -> Result<Vec<ReviewThread>>;
+
+    /// Mark a review comment thread as resolved.
+    async fn resolve_review_thread(&self, installation_id: u64, thread_id: &str) -> Result<()>;
 }
@@ -6,6 +6,10 @@ pub struct Installation {
     pub id: u64,
 }
 
+/// The only installation when authenticating with a personal access token,
+/// which isn't tied to an App installation.
+pub const TOKEN_INSTALLATION_ID: u64 = 0;
+
 /// A repository accessible via an installation.
 #[derive(Debug, Clone)]
 pub struct InstallationRepo {
@@ -36,6 +40,7 @@ pub struct Issue {
     pub title: String,
     pub body: String,
     pub labels: Vec<String>,
+    pub assignees: Vec<String>,
     pub comments: Vec<Comment>,
 }
 
@@ -79,6 +84,25 @@ pub struct ReviewComment {
     pub path: Option<String>,
     pub line: Option<u64>,
     pub diff_hunk: Option<String>,
+    /// The comment that started the thread, if this is a reply.
+    pub in_reply_to: Option<u64>,
+}
+
+/// A review comment thread on a PR diff.
+#[derive(Debug, Clone)]
+pub struct ReviewThread {
+    /// ID used to resolve the thread: a GraphQL node ID on GitHub.
+    pub id: String,
+    /// REST ID of the comment that started the thread.
+    pub comment_id: u64,
+    pub is_resolved: bool,
+}
+
+/// Log output of a failed CI job.
+#[derive(Debug, Clone)]
+pub struct JobLog {
+    pub name: String,
+    pub log: String,
 }
 
 #[derive(Debug, Clone)]
@@ -87,4 +111,19 @@ pub struct CreatePullRequest {
     pub body: String,
     pub head_branch: String,
     pub base_branch: String,
+    /// Open as a draft PR.
+    pub draft: bool,
+}
+
+/// Emoji reaction on an issue, PR or comment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reaction {
+    /// 👀 — request seen and accepted.
+    Eyes,
+    /// 👍
+    PlusOne,
+    /// 😕
+    Confused,
+    /// 🚀
+    Rocket,
 }
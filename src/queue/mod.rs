@@ -1,21 +1,57 @@
+pub mod persist;
 pub mod sequential;
 pub mod startup;
 pub mod task;
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use tracing::Instrument;
+
+use crate::error::{AppError, Result};
+use crate::metrics::METRICS;
 use crate::server::AppState;
 use crate::workflow;
+use crate::workflow::types::WorkflowOutcome;
+use crate::workspace::manager::Workspace;
+use crate::workspace::WorkspaceManager;
 
 use task::Task;
 
+/// Span of the sliding window `max_tasks_per_hour` is counted over.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
 /// Simple task queue backed by a VecDeque per repo.
 pub struct TaskQueue {
     /// Pending tasks per repository (processed sequentially).
-    queues: std::collections::HashMap<String, VecDeque<Task>>,
+    queues: HashMap<String, VecDeque<QueuedTask>>,
+    /// Sequence number of the next task pushed onto a queue.
+    next_seq: u64,
+    /// Task currently running per repository (skipped by `take_next`).
+    running: HashMap<String, Task>,
     /// Notification channel for the processor.
     notify: Option<tokio::sync::mpsc::UnboundedSender<()>>,
+    /// New tasks accepted per repository in the last hour; 0 is unlimited.
+    max_tasks_per_hour: u32,
+    /// When each repository's tasks within the window were accepted, oldest first.
+    accepted: HashMap<String, VecDeque<Instant>>,
+    /// Retries waiting out their backoff, moved to `queues` once ready.
+    delayed: Vec<DelayedTask>,
+}
+
+/// A pending task and the order it was queued in across all repositories.
+struct QueuedTask {
+    seq: u64,
+    task: Task,
+}
+
+/// A task that may not run before `ready_at`.
+struct DelayedTask {
+    ready_at: Instant,
+    repo: String,
+    task: Task,
 }
 
 impl Default for TaskQueue {
@@ -27,8 +63,13 @@ impl Default for TaskQueue {
 impl TaskQueue {
     pub fn new() -> Self {
         Self {
-            queues: std::collections::HashMap::new(),
+            queues: HashMap::new(),
+            next_seq: 0,
+            running: HashMap::new(),
             notify: None,
+            max_tasks_per_hour: 0,
+            accepted: HashMap::new(),
+            delayed: Vec::new(),
         }
     }
 
@@ -36,149 +77,333 @@ impl TaskQueue {
         self.notify = Some(tx);
     }
 
-    pub fn enqueue(&mut self, repo: &str, task: Task) {
-        tracing::info!(repo = repo, task = %task.description(), "Enqueuing task");
-        self.queues
-            .entry(repo.to_string())
-            .or_default()
-            .push_back(task);
+    /// Limit the new tasks accepted per repository in any rolling hour
+    /// (`agent.max_tasks_per_repo_per_hour`); 0 removes the limit.
+    pub fn set_rate_limit(&mut self, max_tasks_per_hour: u32) {
+        self.max_tasks_per_hour = max_tasks_per_hour;
+    }
+
+    /// Enqueue a task, collapsing it into an already-pending task for the same
+    /// issue or PR instead of queueing a redundant run.
+    ///
+    /// A new task for a repository that has used up its hourly limit is
+    /// dropped and `false` returned; one collapsed into a pending task is
+    /// always accepted.
+    pub fn enqueue(&mut self, repo: &str, task: Task) -> bool {
+        let duplicate = self
+            .queues
+            .get(repo)
+            .is_some_and(|q| q.iter().any(|queued| queued.task.same_target(&task)));
+        if !duplicate && !self.take_rate_limit_slot(repo) {
+            tracing::warn!(
+                repo = repo,
+                task = %task.description(),
+                limit = self.max_tasks_per_hour,
+                "Repository is over its hourly task limit, dropping task"
+            );
+            return false;
+        }
+        self.push(repo, task);
+        true
+    }
+
+    /// Enqueue a task that was accepted before, i.e. a retry or one saved on
+    /// shutdown, without counting it against the hourly limit.
+    pub fn requeue(&mut self, repo: &str, task: Task) {
+        self.push(repo, task);
+    }
+
+    /// Hold a retry back until `ready_at`. Until then it is left out of
+    /// `take_next` but kept in `snapshot`, so a restart doesn't lose it. A
+    /// new task for the same target meanwhile takes it along right away.
+    pub fn defer(&mut self, repo: &str, task: Task, ready_at: Instant) {
+        tracing::info!(repo = repo, task = %task.description(), "Deferring task");
+        self.delayed.push(DelayedTask {
+            ready_at,
+            repo: repo.to_string(),
+            task,
+        });
+    }
+
+    /// Move deferred tasks whose time has come into their queues.
+    fn promote_ready(&mut self) {
+        let now = Instant::now();
+        let (ready, waiting) = std::mem::take(&mut self.delayed)
+            .into_iter()
+            .partition(|d| d.ready_at <= now);
+        self.delayed = waiting;
+        for DelayedTask { repo, task, .. } in ready {
+            self.push(&repo, task);
+        }
+    }
+
+    /// Wake the processor, e.g. once a deferred task is ready.
+    pub fn wake(&self) {
+        if let Some(ref tx) = self.notify {
+            let _ = tx.send(());
+        }
+    }
+
+    /// Count a new task against the repository's hourly limit, or return
+    /// `false` if the limit is already reached.
+    fn take_rate_limit_slot(&mut self, repo: &str) -> bool {
+        if self.max_tasks_per_hour == 0 {
+            return true;
+        }
+        let accepted = self.accepted.entry(repo.to_string()).or_default();
+        while accepted
+            .front()
+            .is_some_and(|at| at.elapsed() >= RATE_LIMIT_WINDOW)
+        {
+            accepted.pop_front();
+        }
+        if accepted.len() >= self.max_tasks_per_hour as usize {
+            return false;
+        }
+        accepted.push_back(Instant::now());
+        true
+    }
+
+    fn push(&mut self, repo: &str, mut task: Task) {
+        if let Some(index) = self
+            .delayed
+            .iter()
+            .position(|d| d.repo == repo && d.task.same_target(&task))
+        {
+            let mut retry = self.delayed.remove(index).task;
+            retry.merge(task);
+            task = retry;
+        }
+        let queue = self.queues.entry(repo.to_string()).or_default();
+
+        if let Some(pending) = queue.iter_mut().find(|q| q.task.same_target(&task)) {
+            tracing::info!(
+                repo = repo,
+                task = %task.description(),
+                "Collapsing duplicate task into pending one"
+            );
+            pending.task.merge(task);
+        } else {
+            tracing::info!(repo = repo, task = %task.description(), "Enqueuing task");
+            METRICS
+                .tasks_enqueued
+                .with_label_values(&[task.kind()])
+                .inc();
+            queue.push_back(QueuedTask {
+                seq: self.next_seq,
+                task,
+            });
+            self.next_seq += 1;
+        }
 
         if let Some(ref tx) = self.notify {
             let _ = tx.send(());
         }
     }
 
-    /// Remove all pending tasks for a specific issue from the queue.
-    pub fn cancel_issue(&mut self, repo_full_name: &str, issue_number: u64) {
+    /// Remove all pending tasks for a specific issue from the queue, returning
+    /// how many were removed. Review and CI fix tasks are keyed by PR number,
+    /// so a PR sharing the number is kept.
+    pub fn cancel_issue(&mut self, repo_full_name: &str, issue_number: u64) -> usize {
+        let matches = |repo: &str, task: &Task| {
+            repo == repo_full_name && task.issue_number() == Some(issue_number)
+        };
+        let before = self.delayed.len();
+        self.delayed.retain(|d| !matches(&d.repo, &d.task));
+        let mut removed = before - self.delayed.len();
         if let Some(queue) = self.queues.get_mut(repo_full_name) {
             let before = queue.len();
-            queue.retain(|task| {
-                !matches!(task, Task::ResolveIssue { issue_number: n, .. } if *n == issue_number)
-            });
-            let removed = before - queue.len();
-            if removed > 0 {
-                tracing::info!(
-                    repo = repo_full_name,
-                    issue = issue_number,
-                    removed = removed,
-                    "Cancelled queued tasks for closed issue"
-                );
-            }
+            queue.retain(|q| !matches(repo_full_name, &q.task));
+            removed += before - queue.len();
+        }
+        if removed > 0 {
+            tracing::info!(
+                repo = repo_full_name,
+                issue = issue_number,
+                removed = removed,
+                "Cancelled queued tasks for issue"
+            );
         }
+        removed
     }
 
-    /// Take the next task from any repo that has pending work.
+    /// Whether a task for `issue_number` is running, queued or waiting to be
+    /// retried. Review and CI fix tasks are keyed by PR number and don't count.
+    pub fn has_issue(&self, repo_full_name: &str, issue_number: u64) -> bool {
+        let matches = |task: &Task| task.issue_number() == Some(issue_number);
+        self.running.get(repo_full_name).is_some_and(matches)
+            || self
+                .queues
+                .get(repo_full_name)
+                .is_some_and(|queue| queue.iter().any(|q| matches(&q.task)))
+            || self
+                .delayed
+                .iter()
+                .any(|d| d.repo == repo_full_name && matches(&d.task))
+    }
+
+    /// Take the next task from any repo that has pending work and no task running.
+    ///
+    /// Higher-priority tasks are taken first; within a priority level tasks keep
+    /// FIFO order, across repos too. The repo is marked as running until [`TaskQueue::finish`] is
+    /// called, so tasks for the same repo never run concurrently.
     pub fn take_next(&mut self) -> Option<Task> {
-        // Round-robin: find first repo with a task
-        let repo = self
+        self.promote_ready();
+        // Oldest task of the highest priority in each idle repo
+        let (repo, index) = self
             .queues
             .iter()
-            .find(|(_, q)| !q.is_empty())
-            .map(|(k, _)| k.clone());
-
-        if let Some(repo) = repo {
-            let task = self.queues.get_mut(&repo).and_then(|q| q.pop_front());
-            // Clean up empty queues
-            if self.queues.get(&repo).is_some_and(|q| q.is_empty()) {
-                self.queues.remove(&repo);
-            }
-            task
-        } else {
-            None
+            .filter(|(k, _)| !self.running.contains_key(*k))
+            .filter_map(|(k, q)| {
+                let priority = q.iter().map(|queued| queued.task.priority()).max()?;
+                let index = q
+                    .iter()
+                    .position(|queued| queued.task.priority() == priority)?;
+                Some((k, index, priority, q[index].seq))
+            })
+            // Then the oldest of those, whichever repo it is in
+            .max_by_key(|(_, _, priority, seq)| (*priority, std::cmp::Reverse(*seq)))
+            .map(|(k, index, _, _)| (k.clone(), index))?;
+
+        let task = self
+            .queues
+            .get_mut(&repo)
+            .and_then(|q| q.remove(index))
+            .map(|q| q.task);
+        // Clean up empty queues
+        if self.queues.get(&repo).is_some_and(|q| q.is_empty()) {
+            self.queues.remove(&repo);
+        }
+        if let Some(ref task) = task {
+            self.running.insert(repo, task.clone());
+        }
+        task
+    }
+
+    /// Number of pending tasks per repository.
+    pub fn depths(&self) -> HashMap<String, usize> {
+        self.queues
+            .iter()
+            .map(|(repo, q)| (repo.clone(), q.len()))
+            .collect()
+    }
+
+    /// Repositories with a task currently running.
+    pub fn running_repos(&self) -> Vec<String> {
+        self.running.keys().cloned().collect()
+    }
+
+    /// Running, pending and deferred tasks, in the order they should be
+    /// resumed: each repo's running task first, then its queue, then retries
+    /// still waiting out their backoff.
+    pub fn snapshot(&self) -> Vec<Task> {
+        let mut repos: Vec<&String> = self
+            .running
+            .keys()
+            .chain(self.queues.keys())
+            .chain(self.delayed.iter().map(|d| &d.repo))
+            .collect();
+        repos.sort();
+        repos.dedup();
+
+        repos
+            .into_iter()
+            .flat_map(|repo| {
+                self.running
+                    .get(repo)
+                    .into_iter()
+                    .chain(self.queues.get(repo).into_iter().flatten().map(|q| &q.task))
+                    .chain(
+                        self.delayed
+                            .iter()
+                            .filter(move |d| &d.repo == repo)
+                            .map(|d| &d.task),
+                    )
+                    .cloned()
+            })
+            .collect()
+    }
+
+    /// Mark the running task for a repo as finished and wake the processor.
+    pub fn finish(&mut self, repo: &str) {
+        self.running.remove(repo);
+
+        if let Some(ref tx) = self.notify {
+            let _ = tx.send(());
         }
     }
 }
 
 /// Run the background queue processor.
+///
+/// Each task is spawned once a permit from `AppState::agent_permits` is available,
+/// so at most `agent.max_concurrent_runs` agents run at once. Tasks that can't get
+/// a permit stay in the queue until one is released.
 pub async fn run_queue_processor(state: Arc<AppState>) {
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<()>();
 
     {
         let mut queue = state.task_queue.write().await;
-        queue.set_notifier(tx);
+        queue.set_notifier(tx.clone());
     }
+    // Pick up tasks enqueued before the processor started (e.g. restored ones)
+    let _ = tx.send(());
 
-    tracing::info!("Queue processor started");
+    tracing::info!(
+        max_concurrent_runs = state.config.agent.max_concurrent_runs,
+        "Queue processor started"
+    );
 
     loop {
         // Wait for notification
         let _ = rx.recv().await;
 
-        // Process all available tasks
+        // Start as many tasks as permits allow
         loop {
+            let permit = match Arc::clone(&state.agent_permits).acquire_owned().await {
+                Ok(p) => p,
+                Err(_) => {
+                    tracing::error!("Agent semaphore closed, stopping queue processor");
+                    return;
+                }
+            };
+
             let task = {
                 let mut queue = state.task_queue.write().await;
                 queue.take_next()
             };
 
+            // Nothing runnable: the permit is released when dropped here
             let task = match task {
                 Some(t) => t,
                 None => break,
             };
 
-            tracing::info!(task = %task.description(), "Processing task");
+            let span = task_span(&task);
+            span.in_scope(|| {
+                tracing::info!(
+                    task = %task.description(),
+                    available_permits = state.agent_permits.available_permits(),
+                    "Processing task"
+                );
+            });
 
-            match &task {
-                Task::ResolveIssue {
-                    installation_id,
-                    repo_full_name,
-                    clone_url,
-                    default_branch,
-                    issue_number,
-                    issue_title,
-                    issue_body,
-                    mode,
-                } => {
-                    let result = workflow::issue::resolve_issue(
-                        workflow::issue::IssueContext {
-                            state: &state,
-                            installation_id: *installation_id,
-                            repo_full_name,
-                            clone_url,
-                            default_branch,
-                            issue_number: *issue_number,
-                            issue_title,
-                            issue_body,
-                            mode: *mode,
-                        },
-                    )
-                    .await;
+            let state = Arc::clone(&state);
+            tokio::spawn(
+                async move {
+                    let result = run_with_timeout(&state, &task).await;
+                    drop(permit);
 
-                    match result {
-                        Ok(outcome) => {
-                            tracing::info!(
-                                task = %task.description(),
-                                outcome = ?outcome,
-                                "Task completed"
-                            );
-                        }
-                        Err(e) => {
-                            tracing::error!(
-                                task = %task.description(),
-                                error = %e,
-                                "Task failed"
-                            );
-                        }
+                    tracing::debug!(
+                        task = %task.description(),
+                        available_permits = state.agent_permits.available_permits(),
+                        "Released agent permit"
+                    );
+
+                    {
+                        let mut queue = state.task_queue.write().await;
+                        queue.finish(task.repo_full_name());
                     }
-                }
-                Task::RespondToReview {
-                    installation_id,
-                    repo_full_name,
-                    clone_url,
-                    pr_number,
-                    pr_branch,
-                    review_body,
-                } => {
-                    let result = workflow::review::respond_to_review(
-                        &state,
-                        *installation_id,
-                        repo_full_name,
-                        clone_url,
-                        *pr_number,
-                        pr_branch,
-                        review_body,
-                    )
-                    .await;
 
                     match result {
                         Ok(outcome) => {
@@ -187,17 +412,846 @@ pub async fn run_queue_processor(state: Arc<AppState>) {
                                 outcome = ?outcome,
                                 "Task completed"
                             );
+                            METRICS
+                                .tasks_completed
+                                .with_label_values(&[task.kind(), outcome.label()])
+                                .inc();
+                            state.notifier.task_finished(&task, Ok(&outcome));
+                            state.record_outcome(&task, Ok(&outcome));
                         }
                         Err(e) => {
+                            METRICS.tasks_failed.with_label_values(&[task.kind()]).inc();
                             tracing::error!(
                                 task = %task.description(),
+                                attempt = task.attempt(),
                                 error = %e,
                                 "Task failed"
                             );
+                            // Only a failure that won't be retried is worth a notification
+                            if !schedule_retry(&state, task.clone(), &e) {
+                                state.notifier.task_finished(&task, Err(&e));
+                                state.record_outcome(&task, Err(&e));
+                            }
                         }
                     }
                 }
+                .instrument(span),
+            );
+        }
+    }
+}
+
+/// Span wrapping everything logged while a task runs, so interleaved logs of
+/// concurrent tasks can be told apart. `run_id` is unique per run, retries
+/// included.
+fn task_span(task: &Task) -> tracing::Span {
+    static NEXT_RUN: AtomicU64 = AtomicU64::new(0);
+    let started = chrono::Utc::now().timestamp_millis();
+    let run_id = format!("{started:x}-{}", NEXT_RUN.fetch_add(1, Ordering::Relaxed));
+
+    let span = tracing::info_span!(
+        crate::task_log::TASK_SPAN,
+        run_id = %run_id,
+        kind = task.kind(),
+        repo = %task.repo_full_name(),
+        issue = tracing::field::Empty,
+        pr = tracing::field::Empty,
+        attempt = task.attempt(),
+    );
+    match task.issue_number() {
+        Some(issue) => span.record("issue", issue),
+        None => span.record("pr", task.number()),
+    };
+    span
+}
+
+/// Run a task, stopping agent runs that exceed `agent.task_timeout_secs`.
+async fn run_with_timeout(state: &AppState, task: &Task) -> Result<WorkflowOutcome> {
+    let limit = state.config.agent.task_timeout_secs;
+    // Approvals only push, and validation has its own timeout
+    let runs_agent = matches!(
+        task,
+        Task::ResolveIssue { .. } | Task::RespondToReview { .. } | Task::FixCheckFailure { .. }
+    );
+    if limit == 0 || !runs_agent {
+        return process_task(state, task).await;
+    }
+
+    match tokio::time::timeout(Duration::from_secs(limit), process_task(state, task)).await {
+        Ok(result) => result,
+        Err(_) => Ok(handle_timeout(state, task, limit).await),
+    }
+}
+
+/// Report a run stopped by `agent.task_timeout_secs` and undo what the
+/// dropped workflow would have on its way out: the working label, the
+/// in-flight entry and the workspace.
+async fn handle_timeout(state: &AppState, task: &Task, limit_secs: u64) -> WorkflowOutcome {
+    let limit = if limit_secs.is_multiple_of(60) {
+        format!("{} minutes", limit_secs / 60)
+    } else {
+        format!("{limit_secs} seconds")
+    };
+    tracing::warn!(task = %task.description(), limit = %limit, "Task timed out");
+
+    let platform = &state.platform;
+    let github = &state.config.github;
+    let workspace_mgr = WorkspaceManager::new(
+        &state.config.workspace,
+        state.config.dry_run,
+        &state.clone_permits,
+    );
+    let (installation_id, repo_full_name, number, workspace) = match task {
+        Task::ResolveIssue {
+            installation_id,
+            repo_full_name,
+            issue_number,
+            issue_title,
+            ..
+        } => {
+            let _ = platform
+                .remove_label(
+                    *installation_id,
+                    repo_full_name,
+                    *issue_number,
+                    &format!("{}:working", github.trigger_label()),
+                )
+                .await;
+            let _ = platform
+                .add_label(
+                    *installation_id,
+                    repo_full_name,
+                    *issue_number,
+                    &github.failed_label(),
+                )
+                .await;
+            state
+                .unregister_in_flight(repo_full_name, *issue_number)
+                .await;
+            state
+                .clear_cancellation(repo_full_name, *issue_number)
+                .await;
+            let branch = match workspace_mgr
+                .load_issue_branch(repo_full_name, *issue_number)
+                .await
+            {
+                Some(branch) => branch,
+                None => github.issue_branch(*issue_number, issue_title),
+            };
+            let workspace = Workspace {
+                path: workspace_mgr.issue_workspace_path(repo_full_name, *issue_number),
+                branch,
+            };
+            (
+                *installation_id,
+                repo_full_name,
+                *issue_number,
+                Some(workspace),
+            )
+        }
+        Task::RespondToReview {
+            installation_id,
+            repo_full_name,
+            pr_number,
+            pr_branch,
+            ..
+        }
+        | Task::FixCheckFailure {
+            installation_id,
+            repo_full_name,
+            pr_number,
+            pr_branch,
+            ..
+        }
+        | Task::ValidatePullRequest {
+            installation_id,
+            repo_full_name,
+            pr_number,
+            pr_branch,
+            ..
+        } => {
+            let workspace = Workspace {
+                path: workspace_mgr.branch_workspace_path(repo_full_name, pr_branch),
+                branch: pr_branch.clone(),
+            };
+            (
+                *installation_id,
+                repo_full_name,
+                *pr_number,
+                Some(workspace),
+            )
+        }
+        Task::ApproveIssue {
+            installation_id,
+            repo_full_name,
+            issue_number,
+            ..
+        } => {
+            let workspace = workspace_mgr
+                .load_pending_approval(repo_full_name, *issue_number)
+                .await
+                .map(|(workspace, _)| workspace);
+            (*installation_id, repo_full_name, *issue_number, workspace)
+        }
+    };
+
+    let _ = platform
+        .post_comment(
+            installation_id,
+            repo_full_name,
+            number,
+            &format!("This run was stopped after reaching the {limit} time limit. Anything it hadn't pushed yet was discarded.\n\n---\n*Mycelium*"),
+        )
+        .await;
+
+    if let Some(workspace) = workspace {
+        let _ = workspace_mgr.release(&workspace, true).await;
+    }
+
+    WorkflowOutcome::Failed {
+        error: format!("Timed out after {limit}"),
+    }
+}
+
+/// Run a single task to completion.
+async fn process_task(state: &AppState, task: &Task) -> Result<WorkflowOutcome> {
+    match task {
+        Task::ResolveIssue {
+            installation_id,
+            repo_full_name,
+            clone_url,
+            default_branch,
+            issue_number,
+            issue_title,
+            issue_body,
+            mode,
+            model,
+            ..
+        } => {
+            workflow::issue::resolve_issue(workflow::issue::IssueContext {
+                state,
+                installation_id: *installation_id,
+                repo_full_name,
+                clone_url,
+                default_branch,
+                issue_number: *issue_number,
+                issue_title,
+                issue_body,
+                mode: *mode,
+                model: model.as_deref(),
+            })
+            .await
+        }
+        Task::RespondToReview {
+            installation_id,
+            repo_full_name,
+            clone_url,
+            pr_number,
+            pr_branch,
+            review_body,
+            ..
+        } => {
+            workflow::review::respond_to_review(
+                state,
+                *installation_id,
+                repo_full_name,
+                clone_url,
+                *pr_number,
+                pr_branch,
+                review_body,
+            )
+            .await
+        }
+        Task::FixCheckFailure {
+            installation_id,
+            repo_full_name,
+            clone_url,
+            pr_number,
+            pr_branch,
+            failure_report,
+            workflow_run_ids,
+            ..
+        } => {
+            workflow::checks::fix_check_failure(
+                state,
+                *installation_id,
+                repo_full_name,
+                clone_url,
+                *pr_number,
+                pr_branch,
+                failure_report,
+                workflow_run_ids,
+            )
+            .await
+        }
+        Task::ValidatePullRequest {
+            installation_id,
+            repo_full_name,
+            clone_url,
+            pr_number,
+            pr_branch,
+            head_sha,
+            ..
+        } => {
+            workflow::validate::validate_pull_request(
+                state,
+                *installation_id,
+                repo_full_name,
+                clone_url,
+                *pr_number,
+                pr_branch,
+                head_sha,
+            )
+            .await
+        }
+        Task::ApproveIssue {
+            installation_id,
+            repo_full_name,
+            issue_number,
+            ..
+        } => {
+            workflow::issue::approve_issue(state, *installation_id, repo_full_name, *issue_number)
+                .await
+        }
+    }
+}
+
+/// Enqueue a new task. If the repository is over
+/// `agent.max_tasks_per_repo_per_hour` the task is dropped, and with
+/// `agent.comment_when_rate_limited` its issue or PR is told so. Returns
+/// whether the task was accepted.
+pub async fn enqueue(state: &AppState, task: Task) -> bool {
+    let repo = task.repo_full_name().to_string();
+    let number = task.number();
+    let installation_id = task.installation_id();
+    if state.task_queue.write().await.enqueue(&repo, task) {
+        return true;
+    }
+
+    if state.config.agent.comment_when_rate_limited {
+        let platform = Arc::clone(&state.platform);
+        let body = format!(
+            "⏸️ This repository has reached its limit of {} Mycelium tasks per hour, so this request was dropped. Please try again later.\n\n---\n*Mycelium*",
+            state.config.agent.max_tasks_per_repo_per_hour
+        );
+        tokio::spawn(async move {
+            if let Err(e) = platform
+                .post_comment(installation_id, &repo, number, &body)
+                .await
+            {
+                tracing::warn!(
+                    repo = %repo,
+                    number,
+                    error = %e,
+                    "Failed to post rate limit comment"
+                );
+            }
+        });
+    }
+    false
+}
+
+/// Enqueue a review task once `agent.comment_debounce_secs` have passed since
+/// the first comment of a burst. Comments on the same PR arriving meanwhile
+/// are merged into it, so a reviewer leaving many comments gets one run.
+pub async fn enqueue_debounced(state: &Arc<AppState>, task: Task) {
+    let window = Duration::from_secs(state.config.agent.comment_debounce_secs);
+    let repo = task.repo_full_name().to_string();
+    if window.is_zero() {
+        enqueue(state, task).await;
+        return;
+    }
+
+    let key = format!("{repo}#{}", task.number());
+    {
+        let mut debounced = state.debounced_reviews.lock().await;
+        if let Some(waiting) = debounced.get_mut(&key) {
+            tracing::info!(task = %task.description(), "Merging comment into debounced task");
+            waiting.merge(task);
+            return;
+        }
+        tracing::info!(
+            task = %task.description(),
+            window_secs = window.as_secs(),
+            "Waiting for further comments before enqueuing"
+        );
+        debounced.insert(key.clone(), task);
+    }
+
+    let state = Arc::clone(state);
+    tokio::spawn(async move {
+        tokio::time::sleep(window).await;
+        let task = state.debounced_reviews.lock().await.remove(&key);
+        if let Some(task) = task {
+            enqueue(&state, task).await;
+        }
+    });
+}
+
+/// Re-enqueue a failed task after a capped exponential delay.
+///
+/// Only errors surfaced as `Err` are considered, and only transient ones.
+/// Agent outcomes such as clarification requests or turn limits come back as
+/// `WorkflowOutcome`s and have already been reported on the issue, so they are
+/// never retried. Returns whether a retry was scheduled.
+fn schedule_retry(state: &Arc<AppState>, mut task: Task, error: &AppError) -> bool {
+    let agent = &state.config.agent;
+
+    if !error.is_transient() {
+        return false;
+    }
+
+    if task.attempt() >= agent.task_max_retries {
+        tracing::warn!(
+            task = %task.description(),
+            attempts = task.attempt() + 1,
+            "Task failed too many times, giving up"
+        );
+        return false;
+    }
+
+    let delay = retry_delay(
+        task.attempt(),
+        Duration::from_secs(agent.task_retry_backoff_secs),
+        Duration::from_secs(agent.task_retry_max_backoff_secs),
+    );
+    task.increment_attempt();
+
+    tracing::info!(
+        task = %task.description(),
+        attempt = task.attempt(),
+        delay_secs = delay.as_secs(),
+        "Scheduling task retry"
+    );
+
+    // Kept in the queue rather than in the timer, so a shutdown during the
+    // backoff saves the retry
+    let repo = task.repo_full_name().to_string();
+    let issue_number = task.issue_number();
+    let state = Arc::clone(state);
+    tokio::spawn(async move {
+        state
+            .task_queue
+            .write()
+            .await
+            .defer(&repo, task, Instant::now() + delay);
+        tokio::time::sleep(delay).await;
+
+        // Closing or unlabeling the issue meanwhile dropped the retry from the
+        // queue; with no run left to stop, its cancellation must not linger
+        if let Some(issue_number) = issue_number {
+            if state.is_cancelled(&repo, issue_number).await
+                && !state.is_in_flight(&repo, issue_number).await
+            {
+                tracing::info!(
+                    repo = %repo,
+                    issue = issue_number,
+                    "Issue cancelled, dropped retry"
+                );
+                state.clear_cancellation(&repo, issue_number).await;
+                return;
+            }
+        }
+
+        state.task_queue.read().await.wake();
+    });
+    true
+}
+
+/// Delay before retry number `attempt + 1`: `initial * 2^attempt`, capped at `max`.
+fn retry_delay(attempt: u32, initial: Duration, max: Duration) -> Duration {
+    initial
+        .checked_mul(2u32.saturating_pow(attempt))
+        .map_or(max, |d| d.min(max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use task::{IssueMode, Priority};
+
+    fn issue_task(repo: &str, issue_number: u64) -> Task {
+        Task::ResolveIssue {
+            installation_id: 1,
+            repo_full_name: repo.to_string(),
+            clone_url: format!("https://github.com/{repo}.git"),
+            default_branch: "main".to_string(),
+            issue_number,
+            issue_title: format!("Issue {issue_number}"),
+            issue_body: String::new(),
+            mode: IssueMode::Implement,
+            priority: Priority::Normal,
+            model: None,
+            attempt: 0,
+        }
+    }
+
+    fn priority_task(repo: &str, issue_number: u64) -> Task {
+        let mut task = issue_task(repo, issue_number);
+        if let Task::ResolveIssue { priority, .. } = &mut task {
+            *priority = Priority::High;
+        }
+        task
+    }
+
+    fn issue_number(task: &Task) -> u64 {
+        match task {
+            Task::ResolveIssue { issue_number, .. } | Task::ApproveIssue { issue_number, .. } => {
+                *issue_number
             }
+            Task::RespondToReview { pr_number, .. }
+            | Task::FixCheckFailure { pr_number, .. }
+            | Task::ValidatePullRequest { pr_number, .. } => *pr_number,
         }
     }
+
+    #[test]
+    fn test_take_next_skips_repo_with_running_task() {
+        let mut queue = TaskQueue::new();
+        queue.enqueue("owner/repo", issue_task("owner/repo", 1));
+        queue.enqueue("owner/repo", issue_task("owner/repo", 2));
+
+        let first = queue.take_next().unwrap();
+        assert_eq!(issue_number(&first), 1);
+
+        // Same repo is busy until finish() is called
+        assert!(queue.take_next().is_none());
+
+        queue.finish("owner/repo");
+        let second = queue.take_next().unwrap();
+        assert_eq!(issue_number(&second), 2);
+    }
+
+    #[test]
+    fn test_take_next_runs_other_repos_concurrently() {
+        let mut queue = TaskQueue::new();
+        queue.enqueue("owner/a", issue_task("owner/a", 1));
+        queue.enqueue("owner/b", issue_task("owner/b", 2));
+
+        let first = queue.take_next().unwrap();
+        let second = queue.take_next().unwrap();
+        assert_ne!(first.repo_full_name(), second.repo_full_name());
+        assert!(queue.take_next().is_none());
+    }
+
+    #[test]
+    fn test_priority_task_enqueued_last_is_taken_first() {
+        let mut queue = TaskQueue::new();
+        queue.enqueue("owner/repo", issue_task("owner/repo", 1));
+        queue.enqueue("owner/repo", issue_task("owner/repo", 2));
+        queue.enqueue("owner/repo", priority_task("owner/repo", 3));
+
+        let order: Vec<u64> = (0..3)
+            .map(|_| {
+                let task = queue.take_next().unwrap();
+                queue.finish("owner/repo");
+                issue_number(&task)
+            })
+            .collect();
+        assert_eq!(order, vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn test_priority_task_preferred_across_repos() {
+        let mut queue = TaskQueue::new();
+        queue.enqueue("owner/a", issue_task("owner/a", 1));
+        queue.enqueue("owner/b", priority_task("owner/b", 2));
+
+        let first = queue.take_next().unwrap();
+        assert_eq!(issue_number(&first), 2);
+    }
+
+    #[test]
+    fn test_equal_priority_tasks_taken_in_order_across_repos() {
+        for repos in [["owner/a", "owner/b"], ["owner/b", "owner/a"]] {
+            let mut queue = TaskQueue::new();
+            for (number, repo) in (1..).zip(repos) {
+                queue.enqueue(repo, issue_task(repo, number));
+            }
+            queue.enqueue(repos[1], priority_task(repos[1], 3));
+            queue.enqueue(repos[0], priority_task(repos[0], 4));
+
+            let order: Vec<u64> = (0..4)
+                .map(|_| {
+                    let task = queue.take_next().unwrap();
+                    queue.finish(task.repo_full_name());
+                    issue_number(&task)
+                })
+                .collect();
+            assert_eq!(order, vec![3, 4, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn test_enqueue_drops_tasks_over_hourly_limit() {
+        let mut queue = TaskQueue::new();
+        queue.set_rate_limit(2);
+        assert!(queue.enqueue("owner/repo", issue_task("owner/repo", 1)));
+        assert!(queue.enqueue("owner/repo", issue_task("owner/repo", 2)));
+        assert!(!queue.enqueue("owner/repo", issue_task("owner/repo", 3)));
+
+        // Duplicates, requeued tasks and other repos are unaffected
+        assert!(queue.enqueue("owner/repo", issue_task("owner/repo", 1)));
+        queue.requeue("owner/repo", issue_task("owner/repo", 4));
+        assert!(queue.enqueue("owner/other", issue_task("owner/other", 1)));
+        assert_eq!(queue.depths()["owner/repo"], 3);
+        assert_eq!(queue.depths()["owner/other"], 1);
+
+        // Taking a task off the queue doesn't free up the hour's allowance
+        queue.take_next();
+        assert!(!queue.enqueue("owner/repo", issue_task("owner/repo", 5)));
+    }
+
+    #[test]
+    fn test_enqueue_collapses_duplicate_issue_task() {
+        let mut queue = TaskQueue::new();
+        queue.enqueue("owner/repo", issue_task("owner/repo", 1));
+        queue.enqueue("owner/repo", issue_task("owner/repo", 2));
+
+        let mut newer = issue_task("owner/repo", 1);
+        if let Task::ResolveIssue {
+            issue_body, mode, ..
+        } = &mut newer
+        {
+            *issue_body = "updated".to_string();
+            *mode = IssueMode::Research;
+        }
+        queue.enqueue("owner/repo", newer);
+
+        // Still two tasks, and the collapsed one keeps its original position
+        let first = queue.take_next().unwrap();
+        queue.finish("owner/repo");
+        let second = queue.take_next().unwrap();
+        queue.finish("owner/repo");
+        assert!(queue.take_next().is_none());
+
+        assert_eq!(issue_number(&first), 1);
+        assert_eq!(issue_number(&second), 2);
+        match first {
+            Task::ResolveIssue {
+                issue_body, mode, ..
+            } => {
+                assert_eq!(issue_body, "updated");
+                assert_eq!(mode, IssueMode::Research);
+            }
+            _ => panic!("expected ResolveIssue"),
+        }
+    }
+
+    #[test]
+    fn test_enqueue_coalesces_review_feedback() {
+        let review = |body: &str| Task::RespondToReview {
+            installation_id: 1,
+            repo_full_name: "owner/repo".to_string(),
+            clone_url: "https://github.com/owner/repo.git".to_string(),
+            pr_number: 7,
+            pr_branch: "mycelium/issue-1".to_string(),
+            review_body: body.to_string(),
+            attempt: 0,
+        };
+
+        let mut queue = TaskQueue::new();
+        queue.enqueue("owner/repo", review("first"));
+        queue.enqueue("owner/repo", review("second"));
+
+        match queue.take_next().unwrap() {
+            Task::RespondToReview { review_body, .. } => {
+                assert!(review_body.contains("first"));
+                assert!(review_body.contains("second"));
+            }
+            _ => panic!("expected RespondToReview"),
+        }
+        queue.finish("owner/repo");
+        assert!(queue.take_next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_debounced_review_comments_make_one_task() {
+        use crate::config::AppConfig;
+        use crate::platform::mock::MockPlatform;
+
+        let mut config = AppConfig::for_tests();
+        config.agent.comment_debounce_secs = 1;
+        let state = Arc::new(AppState::with_platform(config, Arc::new(MockPlatform::new())));
+        let review = |body: &str| Task::RespondToReview {
+            installation_id: 1,
+            repo_full_name: "owner/repo".to_string(),
+            clone_url: "https://github.com/owner/repo.git".to_string(),
+            pr_number: 7,
+            pr_branch: "mycelium/issue-1".to_string(),
+            review_body: body.to_string(),
+            attempt: 0,
+        };
+
+        enqueue_debounced(&state, review("first")).await;
+        enqueue_debounced(&state, review("second")).await;
+        assert!(state.task_queue.write().await.take_next().is_none());
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        let mut queue = state.task_queue.write().await;
+        match queue.take_next().unwrap() {
+            Task::RespondToReview { review_body, .. } => {
+                assert!(review_body.contains("first"));
+                assert!(review_body.contains("second"));
+            }
+            _ => panic!("expected RespondToReview"),
+        }
+        queue.finish("owner/repo");
+        assert!(queue.take_next().is_none());
+        assert!(state.debounced_reviews.lock().await.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_issue_keeps_pr_tasks_with_same_number() {
+        let mut queue = TaskQueue::new();
+        queue.enqueue("owner/repo", issue_task("owner/repo", 5));
+        queue.enqueue(
+            "owner/repo",
+            Task::ApproveIssue {
+                installation_id: 1,
+                repo_full_name: "owner/repo".to_string(),
+                issue_number: 5,
+                attempt: 0,
+            },
+        );
+        queue.enqueue(
+            "owner/repo",
+            Task::RespondToReview {
+                installation_id: 1,
+                repo_full_name: "owner/repo".to_string(),
+                clone_url: "https://github.com/owner/repo.git".to_string(),
+                pr_number: 5,
+                pr_branch: "mycelium/issue-2".to_string(),
+                review_body: "Please rename this".to_string(),
+                attempt: 0,
+            },
+        );
+        queue.enqueue(
+            "owner/repo",
+            Task::FixCheckFailure {
+                installation_id: 1,
+                repo_full_name: "owner/repo".to_string(),
+                clone_url: "https://github.com/owner/repo.git".to_string(),
+                pr_number: 5,
+                pr_branch: "mycelium/issue-2".to_string(),
+                failure_report: "test failed".to_string(),
+                workflow_run_ids: Vec::new(),
+                attempt: 0,
+            },
+        );
+        queue.enqueue("owner/repo", issue_task("owner/repo", 6));
+
+        queue.cancel_issue("owner/repo", 5);
+
+        let kinds: Vec<(&str, u64)> = queue.queues["owner/repo"]
+            .iter()
+            .map(|q| (q.task.kind(), q.task.number()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("respond_to_review", 5),
+                ("fix_check_failure", 5),
+                ("resolve_issue", 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deferred_retry_is_snapshotted_until_ready() {
+        let mut queue = TaskQueue::new();
+        let later = Instant::now() + Duration::from_secs(60);
+        queue.defer("owner/repo", issue_task("owner/repo", 1), later);
+        queue.defer("owner/repo", issue_task("owner/repo", 2), Instant::now());
+        queue.defer("owner/other", issue_task("owner/other", 3), later);
+
+        assert_eq!(queue.snapshot().len(), 3);
+        assert_eq!(queue.take_next().as_ref().map(issue_number), Some(2));
+        queue.finish("owner/repo");
+        assert!(queue.take_next().is_none());
+
+        // Cancelling the issue drops its retry; a new task for the other takes
+        // the retry along instead of waiting
+        assert_eq!(queue.cancel_issue("owner/repo", 1), 1);
+        queue.enqueue("owner/other", issue_task("owner/other", 3));
+        assert_eq!(queue.snapshot().len(), 1);
+        assert_eq!(queue.take_next().as_ref().map(issue_number), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_only_transient_errors_are_retried() {
+        use crate::config::AppConfig;
+        use crate::platform::mock::MockPlatform;
+
+        let state = Arc::new(AppState::with_platform(
+            AppConfig::for_tests(),
+            Arc::new(MockPlatform::new()),
+        ));
+        let rejected = [
+            AppError::GitHubApi("Failed to create pull request: Validation Failed".to_string()),
+            AppError::GitLabApi("404 Not Found: 404 Project Not Found".to_string()),
+            AppError::Git("Invalid branch name (starts with '-'): -x".to_string()),
+        ];
+        for error in &rejected {
+            assert!(!schedule_retry(&state, issue_task("owner/repo", 1), error));
+        }
+
+        let unavailable = AppError::Unavailable {
+            service: "GitHub API",
+            status: Some(502),
+            message: "Bad Gateway".to_string(),
+        };
+        let task = issue_task("owner/repo", 1);
+        assert!(schedule_retry(&state, task, &unavailable));
+        tokio::task::yield_now().await;
+        assert_eq!(state.task_queue.read().await.snapshot().len(), 1);
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_and_caps() {
+        let initial = Duration::from_secs(30);
+        let max = Duration::from_secs(600);
+        assert_eq!(retry_delay(0, initial, max), Duration::from_secs(30));
+        assert_eq!(retry_delay(1, initial, max), Duration::from_secs(60));
+        assert_eq!(retry_delay(4, initial, max), Duration::from_secs(480));
+        assert_eq!(retry_delay(5, initial, max), max);
+        assert_eq!(retry_delay(40, initial, max), max);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_issue_is_reported_and_cleaned_up() {
+        use crate::config::AppConfig;
+        use crate::platform::mock::MockPlatform;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::for_tests();
+        config.workspace.base_dir = tmp.path().to_path_buf();
+        config.workspace.keep_on_failure = false;
+        let platform = Arc::new(MockPlatform::new());
+        let state = AppState::with_platform(config, platform.clone());
+
+        let workspace_mgr = WorkspaceManager::new(
+            &state.config.workspace,
+            state.config.dry_run,
+            &state.clone_permits,
+        );
+        let workspace = workspace_mgr.issue_workspace_path("owner/repo", 4);
+        std::fs::create_dir_all(&workspace).unwrap();
+        state.register_in_flight(1, "owner/repo", 4).await;
+
+        let outcome = handle_timeout(&state, &issue_task("owner/repo", 4), 1800).await;
+
+        let WorkflowOutcome::Failed { error } = outcome else {
+            panic!("expected a failure, got {outcome:?}");
+        };
+        assert_eq!(error, "Timed out after 30 minutes");
+        let calls = platform.calls();
+        assert!(calls.contains(&"remove_label owner/repo#4 mycelium:working".to_string()));
+        assert!(calls.contains(&"add_label owner/repo#4 mycelium:failed".to_string()));
+        let comment =
+            "post_comment owner/repo#4 This run was stopped after reaching the 30 minutes";
+        assert!(calls.iter().any(|c| c.starts_with(comment)));
+        assert!(!workspace.exists());
+        assert!(!state.is_in_flight("owner/repo", 4).await);
+    }
 }
@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use crate::error::Result;
+use crate::queue::task::Task;
+
+/// Save tasks so the next process can resume them. An empty list removes any
+/// previously saved file.
+pub async fn save(path: &Path, tasks: &[Task]) -> Result<()> {
+    if tasks.is_empty() {
+        match tokio::fs::remove_file(path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => return Ok(()),
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    // Write then rename, so a crash mid-write never leaves a truncated file
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(tasks)?).await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// Load and remove saved tasks. A missing file means there is nothing to
+/// resume; an unreadable one is logged and dropped rather than retried forever.
+pub async fn take(path: &Path) -> Vec<Task> {
+    let contents = match tokio::fs::read(path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = %e, "Failed to read saved tasks");
+            return Vec::new();
+        }
+    };
+
+    let tasks = serde_json::from_slice(&contents).unwrap_or_else(|e| {
+        tracing::warn!(path = %path.display(), error = %e, "Discarding unreadable saved tasks");
+        Vec::new()
+    });
+
+    if let Err(e) = tokio::fs::remove_file(path).await {
+        tracing::warn!(path = %path.display(), error = %e, "Failed to remove saved tasks");
+    }
+
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::TaskQueue;
+
+    fn review_task(repo: &str, pr_number: u64) -> Task {
+        Task::RespondToReview {
+            installation_id: 1,
+            repo_full_name: repo.to_string(),
+            clone_url: format!("https://github.com/{repo}.git"),
+            pr_number,
+            pr_branch: format!("mycelium/issue-{pr_number}"),
+            review_body: "Please rename this".to_string(),
+            attempt: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_running_and_queued_tasks_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending-tasks.json");
+
+        let mut queue = TaskQueue::new();
+        queue.enqueue("owner/repo", review_task("owner/repo", 1));
+        queue.enqueue("owner/repo", review_task("owner/repo", 2));
+        let running = queue.take_next().unwrap();
+        assert_eq!(running.number(), 1);
+
+        save(&path, &queue.snapshot()).await.unwrap();
+
+        let restored = take(&path).await;
+        let numbers: Vec<u64> = restored.iter().map(Task::number).collect();
+        assert_eq!(numbers, vec![1, 2]);
+        assert!(matches!(restored[0], Task::RespondToReview { .. }));
+        // Tasks are resumed once, not on every restart
+        assert!(!path.exists());
+        assert!(take(&path).await.is_empty());
+    }
+}
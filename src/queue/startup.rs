@@ -1,131 +1,415 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::platform::Platform;
-use crate::queue::task::{IssueMode, Task};
+use futures::stream::{self, StreamExt};
+
+use crate::platform::types::{InstallationRepo, OpenIssue};
+use crate::queue::persist;
+use crate::queue::task::{model_from_labels, IssueMode, Priority, Task};
 use crate::server::AppState;
+use crate::workspace::WorkspaceManager;
+
+/// Re-enqueue tasks saved by the previous process on shutdown, including
+/// reviews and CI fixes that no scan would rediscover.
+pub async fn restore_saved_tasks(state: &AppState) {
+    let path = state.config.workspace.pending_tasks_file();
+    let tasks = persist::take(&path).await;
+    if tasks.is_empty() {
+        return;
+    }
 
-/// Scan for issues with trigger labels and enqueue them on startup.
+    tracing::info!(count = tasks.len(), "Resuming tasks saved on shutdown");
+    let mut queue = state.task_queue.write().await;
+    for task in tasks {
+        let repo = task.repo_full_name().to_string();
+        queue.requeue(&repo, task);
+    }
+}
+
+/// Scan for issues with trigger labels and enqueue them.
 ///
-/// This allows the service to resume work after a restart.
+/// Runs on startup so the service resumes work after a restart, and then
+/// every `github.rescan_interval_secs` to pick up issues whose webhook was
+/// missed. Issues being worked on, finished or awaiting approval are skipped,
+/// and queue dedup collapses issues that are already waiting. Repositories are
+/// queried `github.scan_concurrency` at a time.
 pub async fn scan_pending_issues(state: &Arc<AppState>) {
-    let trigger_label = &state.config.github.trigger_label;
-    let research_label = format!("{trigger_label}:research");
-    let working_label = format!("{trigger_label}:working");
-
     tracing::info!("Scanning for pending issues with trigger labels...");
 
     // List all installations
     let installations = match state.platform.list_installations().await {
         Ok(installations) => installations,
         Err(e) => {
-            tracing::error!(error = %e, "Failed to list installations on startup");
+            tracing::error!(error = %e, "Failed to list installations for scan");
             return;
         }
     };
 
     tracing::info!(count = installations.len(), "Found installations");
 
+    let mut repos = Vec::new();
     for installation in installations {
         // List repositories for this installation
-        let repos = match state.platform.list_installation_repos(installation.id).await {
-            Ok(repos) => repos,
+        match state.platform.list_installation_repos(installation.id).await {
+            Ok(installation_repos) => repos.extend(
+                installation_repos
+                    .into_iter()
+                    .map(|repo| (installation.id, repo)),
+            ),
             Err(e) => {
                 tracing::warn!(
                     installation_id = installation.id,
                     error = %e,
                     "Failed to list repos for installation"
                 );
+            }
+        }
+    }
+
+    let mut scans = stream::iter(repos)
+        .filter(|(_, repo)| {
+            let allowed = state.config.github.is_repo_allowed(&repo.full_name);
+            if !allowed {
+                tracing::debug!(
+                    repo = %repo.full_name,
+                    "Skipping repository not allowed by config"
+                );
+            }
+            std::future::ready(allowed)
+        })
+        .map(|(installation_id, repo)| scan_repo(state, installation_id, repo))
+        .buffer_unordered(state.config.github.scan_concurrency.max(1));
+
+    while let Some(tasks) = scans.next().await {
+        if tasks.is_empty() {
+            continue;
+        }
+        let mut queue = state.task_queue.write().await;
+        for task in tasks {
+            let repo = task.repo_full_name().to_string();
+            queue.enqueue(&repo, task);
+        }
+    }
+
+    tracing::info!("Scan complete");
+}
+
+/// Tasks for one repository's open issues carrying a trigger label (or alias)
+/// or the research label.
+async fn scan_repo(state: &AppState, installation_id: u64, repo: InstallationRepo) -> Vec<Task> {
+    let github = &state.config.github;
+    let research_label = format!("{}:research", github.trigger_label());
+
+    let mut tasks = Vec::new();
+    // An issue carrying several aliases is listed once per alias
+    let mut seen = HashSet::new();
+    let labels = github
+        .trigger_labels
+        .iter()
+        .map(|label| (label.as_str(), IssueMode::Implement))
+        .chain([(research_label.as_str(), IssueMode::Research)]);
+    for (label, mode) in labels {
+        let issues = match state
+            .platform
+            .list_open_issues_with_label(installation_id, &repo.full_name, label)
+            .await
+        {
+            Ok(issues) => issues,
+            Err(e) => {
+                tracing::warn!(
+                    repo = %repo.full_name,
+                    label = label,
+                    error = %e,
+                    "Failed to list labeled issues"
+                );
                 continue;
             }
         };
 
-        for repo in repos {
-            // Check for issues with the trigger label (implementation mode)
-            if let Ok(issues) = state
-                .platform
-                .list_open_issues_with_label(installation.id, &repo.full_name, trigger_label)
-                .await
-            {
-                for issue in issues {
-                    // Skip issues that are already being worked on
-                    if issue.labels.iter().any(|l| l == &working_label) {
-                        tracing::debug!(
-                            repo = %repo.full_name,
-                            issue = issue.number,
-                            "Skipping issue already being worked on"
-                        );
-                        continue;
-                    }
-
-                    // Skip if it also has the research label (handled below)
-                    if issue.labels.iter().any(|l| l == &research_label) {
-                        continue;
-                    }
-
-                    tracing::info!(
-                        repo = %repo.full_name,
-                        issue = issue.number,
-                        title = %issue.title,
-                        "Enqueuing pending issue (implement mode)"
-                    );
-
-                    let task = Task::ResolveIssue {
-                        installation_id: installation.id,
-                        repo_full_name: repo.full_name.clone(),
-                        clone_url: repo.clone_url.clone(),
-                        default_branch: repo.default_branch.clone(),
-                        issue_number: issue.number,
-                        issue_title: issue.title,
-                        issue_body: issue.body,
-                        mode: IssueMode::Implement,
-                    };
-
-                    let mut queue = state.task_queue.write().await;
-                    queue.enqueue(&repo.full_name, task);
-                }
+        for issue in issues {
+            // Issues with both labels are handled in research mode
+            if mode == IssueMode::Implement && issue.labels.iter().any(|l| l == &research_label) {
+                continue;
             }
-
-            // Check for issues with the research label
-            if let Ok(issues) = state
-                .platform
-                .list_open_issues_with_label(installation.id, &repo.full_name, &research_label)
-                .await
-            {
-                for issue in issues {
-                    // Skip issues that are already being worked on
-                    if issue.labels.iter().any(|l| l == &working_label) {
-                        tracing::debug!(
-                            repo = %repo.full_name,
-                            issue = issue.number,
-                            "Skipping issue already being worked on"
-                        );
-                        continue;
-                    }
-
-                    tracing::info!(
-                        repo = %repo.full_name,
-                        issue = issue.number,
-                        title = %issue.title,
-                        "Enqueuing pending issue (research mode)"
-                    );
-
-                    let task = Task::ResolveIssue {
-                        installation_id: installation.id,
-                        repo_full_name: repo.full_name.clone(),
-                        clone_url: repo.clone_url.clone(),
-                        default_branch: repo.default_branch.clone(),
-                        issue_number: issue.number,
-                        issue_title: issue.title,
-                        issue_body: issue.body,
-                        mode: IssueMode::Research,
-                    };
-
-                    let mut queue = state.task_queue.write().await;
-                    queue.enqueue(&repo.full_name, task);
-                }
+            if !seen.insert(issue.number) {
+                continue;
+            }
+            if let Some(task) = pending_task(state, installation_id, &repo, issue, mode) {
+                tasks.push(task);
             }
         }
     }
+    tasks
+}
+
+/// The task for a labeled issue, or `None` if it shouldn't be picked up.
+fn pending_task(
+    state: &AppState,
+    installation_id: u64,
+    repo: &InstallationRepo,
+    issue: OpenIssue,
+    mode: IssueMode,
+) -> Option<Task> {
+    let github = &state.config.github;
+    let trigger_label = github.trigger_label();
+    let has_label = |label: &str| issue.labels.iter().any(|l| l == label);
+
+    // Skip issues that are already being worked on
+    if has_label(&format!("{trigger_label}:working")) {
+        tracing::debug!(
+            repo = %repo.full_name,
+            issue = issue.number,
+            "Skipping issue already being worked on"
+        );
+        return None;
+    }
+
+    // Skip finished issues and those waiting for a human: changes to
+    // approve, or a question or no-change run to follow up on
+    if has_label(&format!("{trigger_label}:done"))
+        || has_label(&github.needs_approval_label())
+        || has_label(&github.needs_info_label())
+    {
+        tracing::debug!(
+            repo = %repo.full_name,
+            issue = issue.number,
+            "Skipping finished or waiting issue"
+        );
+        return None;
+    }
+
+    // Skip issues that failed before; re-labeling retries them
+    if github.scan_skip_failed && has_label(&github.failed_label()) {
+        tracing::debug!(
+            repo = %repo.full_name,
+            issue = issue.number,
+            "Skipping previously failed issue"
+        );
+        return None;
+    }
+
+    tracing::info!(
+        repo = %repo.full_name,
+        issue = issue.number,
+        title = %issue.title,
+        mode = ?mode,
+        "Enqueuing pending issue"
+    );
+
+    let priority = Priority::from_labels(
+        issue.labels.iter().map(String::as_str),
+        &github.priority_label(),
+    );
+    let model = model_from_labels(
+        issue.labels.iter().map(String::as_str),
+        trigger_label,
+        &state.config.claude.allowed_models,
+    );
+
+    Some(Task::ResolveIssue {
+        installation_id,
+        repo_full_name: repo.full_name.clone(),
+        clone_url: repo.clone_url.clone(),
+        default_branch: repo.default_branch.clone(),
+        issue_number: issue.number,
+        issue_title: issue.title,
+        issue_body: issue.body,
+        mode,
+        priority,
+        model,
+        attempt: 0,
+    })
+}
+
+/// Re-run `scan_pending_issues` every `github.rescan_interval_secs` so a
+/// labeled issue whose webhook delivery was missed still gets picked up.
+/// Returns immediately when the interval is 0.
+pub async fn rescan_periodically(state: Arc<AppState>) {
+    let secs = state.config.github.rescan_interval_secs;
+    if secs == 0 {
+        return;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(secs));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick fires immediately; the startup scan already covers it
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        scan_pending_issues(&state).await;
+    }
+}
+
+/// Delete workspace directories left behind by crashed or failed runs once
+/// they are older than `workspace.stale_workspace_ttl_secs`, skipping any
+/// belonging to an in-flight issue or a queued task.
+pub async fn sweep_stale_workspaces(state: &AppState) {
+    let config = &state.config;
+    let workspace_mgr =
+        WorkspaceManager::new(&config.workspace, config.dry_run, &state.clone_permits);
+
+    let mut active: HashSet<_> = state
+        .in_flight
+        .read()
+        .await
+        .values()
+        .map(|issue| workspace_mgr.issue_workspace_path(&issue.repo_full_name, issue.issue_number))
+        .collect();
+    active.extend(
+        state
+            .task_queue
+            .read()
+            .await
+            .snapshot()
+            .iter()
+            .map(|task| task_workspace_path(&workspace_mgr, task)),
+    );
+
+    let ttl = Duration::from_secs(config.workspace.stale_workspace_ttl_secs);
+    let stats = workspace_mgr.sweep_stale(ttl, &active).await;
+    if stats.removed > 0 {
+        tracing::info!(
+            removed = stats.removed,
+            reclaimed_bytes = stats.bytes,
+            "Removed stale workspaces"
+        );
+    }
+}
+
+/// Directory the workspace of `task` is checked out in.
+fn task_workspace_path(workspace_mgr: &WorkspaceManager, task: &Task) -> PathBuf {
+    match task {
+        Task::ResolveIssue {
+            repo_full_name,
+            issue_number,
+            ..
+        }
+        | Task::ApproveIssue {
+            repo_full_name,
+            issue_number,
+            ..
+        } => workspace_mgr.issue_workspace_path(repo_full_name, *issue_number),
+        Task::RespondToReview {
+            repo_full_name,
+            pr_branch,
+            ..
+        }
+        | Task::FixCheckFailure {
+            repo_full_name,
+            pr_branch,
+            ..
+        }
+        | Task::ValidatePullRequest {
+            repo_full_name,
+            pr_branch,
+            ..
+        } => workspace_mgr.branch_workspace_path(repo_full_name, pr_branch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::platform::mock::MockPlatform;
+    use crate::platform::types::Issue;
+
+    fn issue(number: u64, labels: &[&str]) -> Issue {
+        Issue {
+            number,
+            title: format!("Issue {number}"),
+            body: String::new(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            assignees: Vec::new(),
+            comments: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_matches_trigger_label_aliases() {
+        let platform = MockPlatform::new()
+            .with_issue("owner/repo", issue(1, &["mycelium", "ai-fix"]))
+            .with_issue("owner/repo", issue(2, &["ai-fix"]))
+            .with_issue("owner/repo", issue(3, &["ai-fix", "mycelium:research"]))
+            .with_issue("owner/repo", issue(4, &["ai-fix", "mycelium:done"]))
+            .with_issue("owner/repo", issue(5, &["bug"]))
+            .with_issue("owner/repo", issue(6, &["mycelium", "mycelium:needs-info"]));
+        let mut config = AppConfig::for_tests();
+        config.github.trigger_labels = vec!["mycelium".to_string(), "ai-fix".to_string()];
+        let state = AppState::with_platform(config, Arc::new(platform));
+        let repo = InstallationRepo {
+            full_name: "owner/repo".to_string(),
+            clone_url: "https://github.com/owner/repo.git".to_string(),
+            default_branch: "main".to_string(),
+        };
+
+        let tasks: Vec<(u64, IssueMode)> = scan_repo(&state, 1, repo)
+            .await
+            .into_iter()
+            .map(|task| match task {
+                Task::ResolveIssue {
+                    issue_number, mode, ..
+                } => (issue_number, mode),
+                other => panic!("unexpected task {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            tasks,
+            vec![
+                (1, IssueMode::Implement),
+                (2, IssueMode::Implement),
+                (3, IssueMode::Research),
+            ]
+        );
+    }
 
-    tracing::info!("Startup scan complete");
+    #[tokio::test]
+    async fn test_sweep_keeps_workspaces_of_restored_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::for_tests();
+        config.workspace.base_dir = dir.path().to_path_buf();
+        config.workspace.stale_workspace_ttl_secs = 0;
+        let state = AppState::with_platform(config, Arc::new(MockPlatform::new()));
+        let review = Task::RespondToReview {
+            installation_id: 1,
+            repo_full_name: "owner/repo".to_string(),
+            clone_url: "https://github.com/owner/repo.git".to_string(),
+            pr_number: 8,
+            pr_branch: "mycelium/issue-3-fix-typo".to_string(),
+            review_body: "Please rename this".to_string(),
+            attempt: 0,
+        };
+        let approval = Task::ApproveIssue {
+            installation_id: 1,
+            repo_full_name: "owner/repo".to_string(),
+            issue_number: 4,
+            attempt: 0,
+        };
+        persist::save(
+            &state.config.workspace.pending_tasks_file(),
+            &[review, approval],
+        )
+        .await
+        .unwrap();
+        for name in [
+            "owner__repo__mycelium__issue-3-fix-typo",
+            "owner__repo__mycelium__issue-4",
+            "owner__repo__mycelium__issue-5",
+        ] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+        }
+
+        restore_saved_tasks(&state).await;
+        sweep_stale_workspaces(&state).await;
+
+        assert!(dir
+            .path()
+            .join("owner__repo__mycelium__issue-3-fix-typo")
+            .exists());
+        assert!(dir.path().join("owner__repo__mycelium__issue-4").exists());
+        assert!(!dir.path().join("owner__repo__mycelium__issue-5").exists());
+    }
 }
@@ -1,5 +1,9 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
 /// Whether the agent should implement changes or just research.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IssueMode {
     /// Explore the codebase, make changes, and open a PR.
     Implement,
@@ -7,8 +11,54 @@ pub enum IssueMode {
     Research,
 }
 
+/// Scheduling priority of a task. Higher priorities are taken from the queue first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Priority {
+    #[default]
+    Normal,
+    /// Issue carries the priority label.
+    High,
+}
+
+impl Priority {
+    /// Determine the priority of an issue from its labels.
+    pub fn from_labels<'a>(
+        mut labels: impl Iterator<Item = &'a str>,
+        priority_label: &str,
+    ) -> Self {
+        if labels.any(|l| l == priority_label) {
+            Priority::High
+        } else {
+            Priority::Normal
+        }
+    }
+}
+
+/// Resolve a `<trigger_label>:model=<alias>` label to a model ID from
+/// `allowed_models`. Aliases not in the allowlist are ignored.
+pub fn model_from_labels<'a>(
+    labels: impl Iterator<Item = &'a str>,
+    trigger_label: &str,
+    allowed_models: &HashMap<String, String>,
+) -> Option<String> {
+    let prefix = format!("{trigger_label}:model=");
+    let alias = labels.filter_map(|l| l.strip_prefix(&prefix)).next()?;
+
+    match allowed_models.get(alias) {
+        Some(model) => Some(model.clone()),
+        None => {
+            tracing::warn!(
+                alias = alias,
+                "Ignoring model label not in claude.allowed_models"
+            );
+            None
+        }
+    }
+}
+
 /// Tasks that can be enqueued for processing.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Task {
     ResolveIssue {
         installation_id: u64,
@@ -19,6 +69,11 @@ pub enum Task {
         issue_title: String,
         issue_body: String,
         mode: IssueMode,
+        priority: Priority,
+        /// Model chosen by a model label, overriding the configured one.
+        model: Option<String>,
+        /// Number of previous failed attempts (0 on first run).
+        attempt: u32,
     },
     RespondToReview {
         installation_id: u64,
@@ -27,14 +82,245 @@ pub enum Task {
         pr_number: u64,
         pr_branch: String,
         review_body: String,
+        /// Number of previous failed attempts (0 on first run).
+        attempt: u32,
+    },
+    FixCheckFailure {
+        installation_id: u64,
+        repo_full_name: String,
+        clone_url: String,
+        pr_number: u64,
+        pr_branch: String,
+        /// Failing check names and their (truncated) logs.
+        failure_report: String,
+        /// GitHub Actions runs whose failed job logs are fetched and added to
+        /// the report when the task runs.
+        #[serde(default)]
+        workflow_run_ids: Vec<u64>,
+        /// Number of previous failed attempts (0 on first run).
+        attempt: u32,
+    },
+    /// Run `agent.test_command` on a PR's branch and post the result.
+    ValidatePullRequest {
+        installation_id: u64,
+        repo_full_name: String,
+        clone_url: String,
+        pr_number: u64,
+        pr_branch: String,
+        /// Commit the validation was requested for.
+        head_sha: String,
+        /// Number of previous failed attempts (0 on first run).
+        attempt: u32,
+    },
+    /// Push changes held for approval after a human approved them.
+    ApproveIssue {
+        installation_id: u64,
+        repo_full_name: String,
+        issue_number: u64,
+        /// Number of previous failed attempts (0 on first run).
+        attempt: u32,
     },
 }
 
 impl Task {
+    /// Short task kind, used as a metrics label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Task::ResolveIssue { .. } => "resolve_issue",
+            Task::RespondToReview { .. } => "respond_to_review",
+            Task::FixCheckFailure { .. } => "fix_check_failure",
+            Task::ValidatePullRequest { .. } => "validate_pull_request",
+            Task::ApproveIssue { .. } => "approve_issue",
+        }
+    }
+
+    /// The issue or PR number the task works on.
+    pub fn number(&self) -> u64 {
+        match self {
+            Task::ResolveIssue { issue_number, .. } | Task::ApproveIssue { issue_number, .. } => {
+                *issue_number
+            }
+            Task::RespondToReview { pr_number, .. }
+            | Task::FixCheckFailure { pr_number, .. }
+            | Task::ValidatePullRequest { pr_number, .. } => *pr_number,
+        }
+    }
+
+    /// The issue number for tasks working on an issue; `None` for PR tasks,
+    /// whose numbers are PR numbers even when an issue shares them.
+    pub fn issue_number(&self) -> Option<u64> {
+        match self {
+            Task::ResolveIssue { issue_number, .. } | Task::ApproveIssue { issue_number, .. } => {
+                Some(*issue_number)
+            }
+            Task::RespondToReview { .. }
+            | Task::FixCheckFailure { .. }
+            | Task::ValidatePullRequest { .. } => None,
+        }
+    }
+
+    pub fn installation_id(&self) -> u64 {
+        match self {
+            Task::ResolveIssue {
+                installation_id, ..
+            }
+            | Task::RespondToReview {
+                installation_id, ..
+            }
+            | Task::FixCheckFailure {
+                installation_id, ..
+            }
+            | Task::ValidatePullRequest {
+                installation_id, ..
+            }
+            | Task::ApproveIssue {
+                installation_id, ..
+            } => *installation_id,
+        }
+    }
+
     pub fn repo_full_name(&self) -> &str {
         match self {
             Task::ResolveIssue { repo_full_name, .. } => repo_full_name,
             Task::RespondToReview { repo_full_name, .. } => repo_full_name,
+            Task::FixCheckFailure { repo_full_name, .. } => repo_full_name,
+            Task::ValidatePullRequest { repo_full_name, .. } => repo_full_name,
+            Task::ApproveIssue { repo_full_name, .. } => repo_full_name,
+        }
+    }
+
+    /// Whether two tasks target the same issue or PR on the same repo.
+    pub fn same_target(&self, other: &Task) -> bool {
+        match (self, other) {
+            (
+                Task::ResolveIssue {
+                    repo_full_name: a_repo,
+                    issue_number: a,
+                    ..
+                },
+                Task::ResolveIssue {
+                    repo_full_name: b_repo,
+                    issue_number: b,
+                    ..
+                },
+            ) => a_repo == b_repo && a == b,
+            (
+                Task::RespondToReview {
+                    repo_full_name: a_repo,
+                    pr_number: a,
+                    ..
+                },
+                Task::RespondToReview {
+                    repo_full_name: b_repo,
+                    pr_number: b,
+                    ..
+                },
+            ) => a_repo == b_repo && a == b,
+            (
+                Task::FixCheckFailure {
+                    repo_full_name: a_repo,
+                    pr_number: a,
+                    ..
+                },
+                Task::FixCheckFailure {
+                    repo_full_name: b_repo,
+                    pr_number: b,
+                    ..
+                },
+            ) => a_repo == b_repo && a == b,
+            (
+                Task::ValidatePullRequest {
+                    repo_full_name: a_repo,
+                    pr_number: a,
+                    ..
+                },
+                Task::ValidatePullRequest {
+                    repo_full_name: b_repo,
+                    pr_number: b,
+                    ..
+                },
+            ) => a_repo == b_repo && a == b,
+            (
+                Task::ApproveIssue {
+                    repo_full_name: a_repo,
+                    issue_number: a,
+                    ..
+                },
+                Task::ApproveIssue {
+                    repo_full_name: b_repo,
+                    issue_number: b,
+                    ..
+                },
+            ) => a_repo == b_repo && a == b,
+            _ => false,
+        }
+    }
+
+    /// Fold a newer task for the same target into this pending one.
+    ///
+    /// Issue tasks take the newer title/body/mode outright, and validations the
+    /// newer commit. Review and CI fix
+    /// tasks keep the earlier feedback and append the newer one so nothing is
+    /// lost.
+    pub fn merge(&mut self, mut newer: Task) {
+        match (&*self, &mut newer) {
+            (
+                Task::RespondToReview {
+                    review_body: pending,
+                    ..
+                },
+                Task::RespondToReview { review_body, .. },
+            ) => {
+                *review_body = format!("{pending}\n\n---\n\n{review_body}");
+            }
+            (
+                Task::FixCheckFailure {
+                    failure_report: pending,
+                    workflow_run_ids: pending_runs,
+                    ..
+                },
+                Task::FixCheckFailure {
+                    failure_report,
+                    workflow_run_ids,
+                    ..
+                },
+            ) => {
+                *failure_report = format!("{pending}\n\n{failure_report}");
+                workflow_run_ids.splice(0..0, pending_runs.iter().copied());
+            }
+            _ => {}
+        }
+        *self = newer;
+    }
+
+    pub fn attempt(&self) -> u32 {
+        match self {
+            Task::ResolveIssue { attempt, .. } => *attempt,
+            Task::RespondToReview { attempt, .. } => *attempt,
+            Task::FixCheckFailure { attempt, .. } => *attempt,
+            Task::ValidatePullRequest { attempt, .. } => *attempt,
+            Task::ApproveIssue { attempt, .. } => *attempt,
+        }
+    }
+
+    /// Bump the attempt counter before re-enqueueing a failed task.
+    pub fn increment_attempt(&mut self) {
+        match self {
+            Task::ResolveIssue { attempt, .. } => *attempt += 1,
+            Task::RespondToReview { attempt, .. } => *attempt += 1,
+            Task::FixCheckFailure { attempt, .. } => *attempt += 1,
+            Task::ValidatePullRequest { attempt, .. } => *attempt += 1,
+            Task::ApproveIssue { attempt, .. } => *attempt += 1,
+        }
+    }
+
+    pub fn priority(&self) -> Priority {
+        match self {
+            Task::ResolveIssue { priority, .. } => *priority,
+            Task::RespondToReview { .. }
+            | Task::FixCheckFailure { .. }
+            | Task::ValidatePullRequest { .. }
+            | Task::ApproveIssue { .. } => Priority::Normal,
         }
     }
 
@@ -50,6 +336,49 @@ impl Task {
                 pr_number,
                 ..
             } => format!("Respond to review on PR #{pr_number} on {repo_full_name}"),
+            Task::FixCheckFailure {
+                repo_full_name,
+                pr_number,
+                ..
+            } => format!("Fix failing checks on PR #{pr_number} on {repo_full_name}"),
+            Task::ValidatePullRequest {
+                repo_full_name,
+                pr_number,
+                ..
+            } => format!("Validate PR #{pr_number} on {repo_full_name}"),
+            Task::ApproveIssue {
+                repo_full_name,
+                issue_number,
+                ..
+            } => format!("Push approved changes for issue #{issue_number} on {repo_full_name}"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_from_labels() {
+        let allowed = HashMap::from([("opus".to_string(), "claude-opus-4-20250514".to_string())]);
+
+        let labels = ["bug", "mycelium", "mycelium:model=opus"];
+        assert_eq!(
+            model_from_labels(labels.into_iter(), "mycelium", &allowed).as_deref(),
+            Some("claude-opus-4-20250514")
+        );
+
+        let labels = ["mycelium", "mycelium:model=gpt"];
+        assert_eq!(
+            model_from_labels(labels.into_iter(), "mycelium", &allowed),
+            None
+        );
+
+        let labels = ["mycelium"];
+        assert_eq!(
+            model_from_labels(labels.into_iter(), "mycelium", &allowed),
+            None
+        );
+    }
+}
@@ -1,25 +1,50 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use axum::{routing::post, Router};
-use tokio::sync::RwLock;
+use axum::extract::{DefaultBodyLimit, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::{routing::post, Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock, Semaphore};
 use tower_http::trace::TraceLayer;
 
-use crate::config::AppConfig;
+use crate::agent::claude::ClaudeClient;
+use crate::config::{AppConfig, PlatformKind};
+use crate::error::AppError;
+use crate::history::{History, HistoryEntry};
+use crate::metrics::METRICS;
+use crate::notify::Notifier;
 use crate::platform::github::GitHubPlatform;
-use crate::queue::TaskQueue;
+use crate::platform::gitlab::GitLabPlatform;
+use crate::platform::Platform;
+use crate::queue::task::{model_from_labels, IssueMode, Priority, Task};
+use crate::queue::{enqueue, TaskQueue};
+use crate::webhook::delivery::DeliveryCache;
+use crate::workflow::types::WorkflowOutcome;
+
+/// How long a `/ready` result is reused before GitHub is checked again.
+const READINESS_CACHE_TTL: Duration = Duration::from_secs(15);
 
 /// Reason why an issue was cancelled.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CancellationReason {
     /// The issue was closed.
     IssueClosed,
     /// The trigger label was removed (but issue is still open).
     LabelRemoved,
+    /// A `/mycelium stop` comment was posted.
+    StopCommand,
+    /// The issue title or body was edited; a fresh run is queued.
+    IssueEdited,
+    /// An operator called `POST /admin/cancel`.
+    AdminRequest,
 }
 
 /// Info about an in-flight issue (for cleanup on shutdown).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct InFlightIssue {
     pub installation_id: u64,
     pub repo_full_name: String,
@@ -28,12 +53,33 @@ pub struct InFlightIssue {
 
 pub struct AppState {
     pub config: AppConfig,
-    pub platform: GitHubPlatform,
+    pub platform: Arc<dyn Platform>,
     pub task_queue: RwLock<TaskQueue>,
     /// Map of cancelled issue keys ("owner/repo#123") to cancellation reason.
     pub cancelled: RwLock<HashMap<String, CancellationReason>>,
     /// Set of in-flight issues (those with :working label).
     pub in_flight: RwLock<HashMap<String, InFlightIssue>>,
+    /// Issues Mycelium closed itself, whose `closed` event must not cancel anything.
+    pub closed_by_mycelium: RwLock<HashSet<String>>,
+    /// Global limit on concurrently running agents (`agent.max_concurrent_runs`).
+    pub agent_permits: Arc<Semaphore>,
+    /// Global limit on concurrent clones (`workspace.max_concurrent_clones`).
+    pub clone_permits: Arc<Semaphore>,
+    /// Recently processed `X-GitHub-Delivery` IDs, to drop redeliveries.
+    pub deliveries: Mutex<DeliveryCache>,
+    /// Review tasks collecting comments for `agent.comment_debounce_secs`
+    /// before being enqueued, per PR key ("owner/repo#123").
+    pub debounced_reviews: Mutex<HashMap<String, Task>>,
+    /// CI fix runs started per PR key ("owner/repo#123").
+    pub check_fix_attempts: RwLock<HashMap<String, u32>>,
+    /// Sends task outcomes to `notifications.webhook_url`.
+    pub notifier: Notifier,
+    /// Task outcomes recorded for `GET /history` (`server.history_path`).
+    pub history: Option<Arc<History>>,
+    /// HTTP client for the Claude API, shared so runs reuse its connection pool.
+    pub claude_http: reqwest::Client,
+    /// Last `/ready` result and when it was checked.
+    pub readiness: Mutex<Option<(Instant, bool)>>,
 }
 
 fn issue_key(repo_full_name: &str, issue_number: u64) -> String {
@@ -42,16 +88,49 @@ fn issue_key(repo_full_name: &str, issue_number: u64) -> String {
 
 impl AppState {
     pub async fn new(config: AppConfig) -> crate::error::Result<Self> {
-        let platform = GitHubPlatform::new(&config.github).await?;
-        let task_queue = RwLock::new(TaskQueue::new());
-
+        let platform: Arc<dyn Platform> = match config.platform {
+            PlatformKind::GitHub => {
+                Arc::new(GitHubPlatform::new(&config.github, config.dry_run).await?)
+            }
+            PlatformKind::GitLab => Arc::new(GitLabPlatform::new(&config.gitlab, config.dry_run)?),
+        };
+        let history = match &config.server.history_path {
+            Some(path) => Some(Arc::new(History::open(path)?)),
+            None => None,
+        };
         Ok(Self {
+            history,
+            ..Self::with_platform(config, platform)
+        })
+    }
+
+    /// Build the state around any `Platform`, e.g. a mock in tests.
+    pub fn with_platform(config: AppConfig, platform: Arc<dyn Platform>) -> Self {
+        let mut task_queue = TaskQueue::new();
+        task_queue.set_rate_limit(config.agent.max_tasks_per_repo_per_hour);
+        let agent_permits = Arc::new(Semaphore::new(config.agent.max_concurrent_runs.max(1)));
+        let clone_permits = Arc::new(Semaphore::new(
+            config.workspace.max_concurrent_clones.max(1),
+        ));
+        let notifier = Notifier::new(&config);
+
+        Self {
             config,
             platform,
-            task_queue,
+            task_queue: RwLock::new(task_queue),
             cancelled: RwLock::new(HashMap::new()),
             in_flight: RwLock::new(HashMap::new()),
-        })
+            closed_by_mycelium: RwLock::new(HashSet::new()),
+            agent_permits,
+            clone_permits,
+            deliveries: Mutex::new(DeliveryCache::new()),
+            debounced_reviews: Mutex::new(HashMap::new()),
+            check_fix_attempts: RwLock::new(HashMap::new()),
+            notifier,
+            history: None,
+            claude_http: ClaudeClient::http_client(),
+            readiness: Mutex::new(None),
+        }
     }
 
     /// Mark an issue as cancelled so in-flight agents stop.
@@ -106,12 +185,107 @@ impl AppState {
         );
     }
 
+    /// Check if an issue currently has a run in progress.
+    pub async fn is_in_flight(&self, repo_full_name: &str, issue_number: u64) -> bool {
+        let key = issue_key(repo_full_name, issue_number);
+        self.in_flight.read().await.contains_key(&key)
+    }
+
     /// Unregister an issue from in-flight tracking.
     pub async fn unregister_in_flight(&self, repo_full_name: &str, issue_number: u64) {
         let key = issue_key(repo_full_name, issue_number);
         self.in_flight.write().await.remove(&key);
     }
 
+    /// Close an issue as completed, remembering that Mycelium closed it so the
+    /// resulting `closed` event isn't taken for a request to stop.
+    pub async fn close_issue(
+        &self,
+        installation_id: u64,
+        repo_full_name: &str,
+        issue_number: u64,
+    ) -> crate::error::Result<()> {
+        let key = issue_key(repo_full_name, issue_number);
+        self.closed_by_mycelium.write().await.insert(key.clone());
+        let result = self
+            .platform
+            .close_issue(installation_id, repo_full_name, issue_number)
+            .await;
+        if result.is_err() {
+            self.closed_by_mycelium.write().await.remove(&key);
+        }
+        result
+    }
+
+    /// Whether Mycelium closed the issue itself, forgetting it so a later
+    /// close by a human is handled normally.
+    pub async fn take_closed_by_mycelium(&self, repo_full_name: &str, issue_number: u64) -> bool {
+        let key = issue_key(repo_full_name, issue_number);
+        self.closed_by_mycelium.write().await.remove(&key)
+    }
+
+    /// Number of CI fix runs started for a PR since startup.
+    pub async fn check_fix_attempts(&self, repo_full_name: &str, pr_number: u64) -> u32 {
+        let key = issue_key(repo_full_name, pr_number);
+        self.check_fix_attempts
+            .read()
+            .await
+            .get(&key)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Count a CI fix run for a PR.
+    pub async fn record_check_fix_attempt(&self, repo_full_name: &str, pr_number: u64) {
+        let key = issue_key(repo_full_name, pr_number);
+        *self
+            .check_fix_attempts
+            .write()
+            .await
+            .entry(key)
+            .or_insert(0) += 1;
+    }
+
+    /// Whether GitHub accepts our credentials, rechecked at most every
+    /// `READINESS_CACHE_TTL`. Concurrent probes wait for one check.
+    pub async fn is_ready(&self) -> bool {
+        let mut readiness = self.readiness.lock().await;
+        if let Some((checked_at, ready)) = *readiness {
+            if checked_at.elapsed() < READINESS_CACHE_TTL {
+                return ready;
+            }
+        }
+
+        let ready = match self.platform.check_connection().await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!(error = %e, "Readiness check failed");
+                false
+            }
+        };
+        *readiness = Some((Instant::now(), ready));
+        ready
+    }
+
+    /// Record a finished task in the history store in the background.
+    /// Failures are logged and never affect the task.
+    pub fn record_outcome(&self, task: &Task, result: Result<&WorkflowOutcome, &AppError>) {
+        let Some(history) = self.history.clone() else {
+            return;
+        };
+        let entry = HistoryEntry::new(self.notifier.build(task, result), chrono::Utc::now());
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = history.record(&entry) {
+                tracing::warn!(
+                    repo = %entry.repo,
+                    number = entry.number,
+                    error = %e,
+                    "Failed to record outcome"
+                );
+            }
+        });
+    }
+
     /// Get all in-flight issues (for shutdown cleanup).
     pub async fn get_in_flight_issues(&self) -> Vec<InFlightIssue> {
         self.in_flight.read().await.values().cloned().collect()
@@ -119,13 +293,608 @@ impl AppState {
 }
 
 pub fn create_router(state: Arc<AppState>) -> Router {
-    Router::new()
-        .route("/webhooks/github", post(crate::webhook::handler::handle_webhook))
+    let (webhook_path, webhook_handler) = match state.config.platform {
+        PlatformKind::GitHub => (
+            "/webhooks/github",
+            post(crate::webhook::handler::handle_webhook),
+        ),
+        PlatformKind::GitLab => (
+            "/webhooks/gitlab",
+            post(crate::webhook::gitlab::handle_gitlab_webhook),
+        ),
+    };
+    let mut router = Router::new()
+        // Oversized bodies are rejected with 413 before the handler reads them
+        .route(
+            webhook_path,
+            webhook_handler.layer(DefaultBodyLimit::max(
+                state.config.server.max_webhook_body_bytes,
+            )),
+        )
         .route("/health", axum::routing::get(health_check))
-        .layer(TraceLayer::new_for_http())
-        .with_state(state)
+        .route("/ready", axum::routing::get(readiness_check))
+        .route("/status", axum::routing::get(status));
+
+    if state.history.is_some() {
+        router = router.route("/history", axum::routing::get(history));
+    }
+    if state.config.server.metrics_enabled {
+        router = router.route("/metrics", axum::routing::get(metrics));
+    }
+    if state.config.server.admin_token.is_some() {
+        router = router
+            .route("/admin/cancel", post(admin_cancel))
+            .route("/admin/enqueue", post(admin_enqueue));
+    }
+
+    router.layer(TraceLayer::new_for_http()).with_state(state)
 }
 
 async fn health_check() -> &'static str {
     "ok"
 }
+
+/// Unlike `/health`, answers 503 until GitHub can be reached with the
+/// configured credentials.
+async fn readiness_check(State(state): State<Arc<AppState>>) -> (StatusCode, &'static str) {
+    if state.is_ready().await {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    }
+}
+
+async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    // Gauges are derived from live state, so refresh them on each scrape
+    let depth: usize = state.task_queue.read().await.depths().values().sum();
+    METRICS.queue_depth.set(depth as i64);
+    METRICS
+        .in_flight
+        .set(state.in_flight.read().await.len() as i64);
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        METRICS.render(),
+    )
+}
+
+/// Snapshot of queue and agent state returned by `GET /status`.
+#[derive(Debug, Serialize)]
+pub struct StatusResponse {
+    /// Pending task count per repository.
+    pub queue_depths: HashMap<String, usize>,
+    /// Repositories with a task currently running.
+    pub running_repos: Vec<String>,
+    /// Issues currently carrying the :working label.
+    pub in_flight: Vec<InFlightIssue>,
+    /// Cancelled issue keys ("owner/repo#123") and why.
+    pub cancelled: HashMap<String, CancellationReason>,
+    /// Agent run permits currently free.
+    pub available_permits: usize,
+}
+
+async fn status(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<StatusResponse>, StatusCode> {
+    if let Some(expected) = state.config.server.status_token.as_deref() {
+        if !bearer_matches(&headers, expected) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    let (queue_depths, running_repos) = {
+        let queue = state.task_queue.read().await;
+        (queue.depths(), queue.running_repos())
+    };
+
+    Ok(Json(StatusResponse {
+        queue_depths,
+        running_repos,
+        in_flight: state.get_in_flight_issues().await,
+        cancelled: state.cancelled.read().await.clone(),
+        available_permits: state.agent_permits.available_permits(),
+    }))
+}
+
+/// Query of `GET /history`.
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// Only outcomes for this repository ("owner/repo").
+    pub repo: Option<String>,
+    /// Entries to return. Default: 50, at most 500.
+    #[serde(default = "default_history_limit")]
+    pub limit: usize,
+}
+
+fn default_history_limit() -> usize {
+    50
+}
+
+/// Recent task outcomes, newest first. Guarded by `server.status_token`
+/// like `/status`.
+async fn history(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<HistoryEntry>>, StatusCode> {
+    if let Some(expected) = state.config.server.status_token.as_deref() {
+        if !bearer_matches(&headers, expected) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    }
+    let Some(history) = state.history.clone() else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let entries =
+        tokio::task::spawn_blocking(move || history.recent(query.repo.as_deref(), query.limit))
+            .await
+            .map_err(|e| AppError::History(format!("History query task panicked: {e}")))
+            .and_then(|entries| entries);
+    match entries {
+        Ok(entries) => Ok(Json(entries)),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to read history");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Body of `POST /admin/cancel`.
+#[derive(Debug, Deserialize)]
+pub struct CancelRequest {
+    /// Repository as "owner/repo".
+    pub repo: String,
+    pub issue_number: u64,
+}
+
+/// What `POST /admin/cancel` stopped.
+#[derive(Debug, Serialize)]
+pub struct CancelResponse {
+    /// Queued tasks removed for the issue.
+    pub removed_queued: usize,
+    /// Whether a running agent was told to stop at its next turn.
+    pub stopped_running: bool,
+}
+
+/// Stop work on an issue without touching GitHub: queued tasks are dropped and
+/// a running agent is cancelled. 404 if there was nothing to stop.
+async fn admin_cancel(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<CancelRequest>,
+) -> Result<Json<CancelResponse>, StatusCode> {
+    if !admin_authorized(&state, &headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let removed_queued = state
+        .task_queue
+        .write()
+        .await
+        .cancel_issue(&request.repo, request.issue_number);
+    // Only a running agent ever clears the flag, so don't leave one behind
+    // to cancel a later run
+    let stopped_running = state
+        .is_in_flight(&request.repo, request.issue_number)
+        .await;
+    if stopped_running {
+        state
+            .cancel_issue(
+                &request.repo,
+                request.issue_number,
+                CancellationReason::AdminRequest,
+            )
+            .await;
+    }
+
+    if removed_queued == 0 && !stopped_running {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(CancelResponse {
+        removed_queued,
+        stopped_running,
+    }))
+}
+
+/// Body of `POST /admin/enqueue`: an issue to resolve or a PR whose review
+/// feedback should be addressed.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum EnqueueRequest {
+    Issue {
+        /// Repository as "owner/repo".
+        repo: String,
+        issue_number: u64,
+        /// `"Implement"` (default) or `"Research"`.
+        #[serde(default = "default_enqueue_mode")]
+        mode: IssueMode,
+    },
+    Review {
+        /// Repository as "owner/repo".
+        repo: String,
+        pr_number: u64,
+        /// Instructions for the run; the PR's review threads are fetched
+        /// either way.
+        #[serde(default)]
+        review_body: Option<String>,
+    },
+}
+
+fn default_enqueue_mode() -> IssueMode {
+    IssueMode::Implement
+}
+
+/// The task `POST /admin/enqueue` queued.
+#[derive(Debug, Serialize)]
+pub struct EnqueueResponse {
+    pub task: String,
+}
+
+/// Queue a task as if its webhook had arrived, e.g. to re-run a batch of
+/// issues after a prompt change. Issue and PR details are fetched from the
+/// platform. 404 if no installation can access the repository.
+async fn admin_enqueue(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<EnqueueRequest>,
+) -> Result<(StatusCode, Json<EnqueueResponse>), StatusCode> {
+    if !admin_authorized(&state, &headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let repo_name = match &request {
+        EnqueueRequest::Issue { repo, .. } | EnqueueRequest::Review { repo, .. } => repo,
+    };
+    let (installation_id, repo) =
+        match crate::workflow::oneshot::find_installation_repo(&state, repo_name).await {
+            Ok(found) => found,
+            Err(e) => {
+                tracing::warn!(repo = %repo_name, error = %e, "Cannot enqueue for repository");
+                return Err(StatusCode::NOT_FOUND);
+            }
+        };
+
+    let task = match build_enqueue_task(&state, installation_id, repo, request).await {
+        Ok(task) => task,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to fetch details for enqueued task");
+            return Err(StatusCode::BAD_GATEWAY);
+        }
+    };
+
+    // A leftover stop would otherwise cancel the new run as soon as it starts
+    if let Some(issue_number) = task.issue_number() {
+        state
+            .clear_cancellation(task.repo_full_name(), issue_number)
+            .await;
+    }
+    let description = task.description();
+    if !enqueue(&state, task).await {
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(EnqueueResponse { task: description }),
+    ))
+}
+
+async fn build_enqueue_task(
+    state: &AppState,
+    installation_id: u64,
+    repo: crate::platform::types::InstallationRepo,
+    request: EnqueueRequest,
+) -> crate::error::Result<Task> {
+    match request {
+        EnqueueRequest::Issue {
+            issue_number, mode, ..
+        } => {
+            let issue = state
+                .platform
+                .get_issue(installation_id, &repo.full_name, issue_number)
+                .await?;
+            let labels = || issue.labels.iter().map(String::as_str);
+            Ok(Task::ResolveIssue {
+                installation_id,
+                priority: Priority::from_labels(labels(), &state.config.github.priority_label()),
+                model: model_from_labels(
+                    labels(),
+                    state.config.github.trigger_label(),
+                    &state.config.claude.allowed_models,
+                ),
+                repo_full_name: repo.full_name,
+                clone_url: repo.clone_url,
+                default_branch: repo.default_branch,
+                issue_number,
+                issue_title: issue.title,
+                issue_body: issue.body,
+                mode,
+                attempt: 0,
+            })
+        }
+        EnqueueRequest::Review {
+            pr_number,
+            review_body,
+            ..
+        } => {
+            let pr = state
+                .platform
+                .get_pull_request(installation_id, &repo.full_name, pr_number)
+                .await?;
+            Ok(Task::RespondToReview {
+                installation_id,
+                repo_full_name: repo.full_name,
+                clone_url: repo.clone_url,
+                pr_number,
+                pr_branch: pr.head_branch,
+                review_body: review_body.unwrap_or_else(|| {
+                    "Please address the outstanding review feedback.".to_string()
+                }),
+                attempt: 0,
+            })
+        }
+    }
+}
+
+/// Whether the request carries the configured `server.admin_token`.
+fn admin_authorized(state: &AppState, headers: &HeaderMap) -> bool {
+    state
+        .config
+        .server
+        .admin_token
+        .as_deref()
+        .is_some_and(|expected| bearer_matches(headers, expected))
+}
+
+/// Whether the request carries `Authorization: Bearer <expected>`.
+fn bearer_matches(headers: &HeaderMap, expected: &str) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes()))
+}
+
+/// Compare two byte strings without short-circuiting on the first mismatch.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::mock::MockPlatform;
+    use crate::queue::task::{IssueMode, Priority};
+
+    #[tokio::test]
+    async fn test_readiness_checks_github_and_caches_result() {
+        let platform = Arc::new(MockPlatform::new());
+        let state = Arc::new(AppState::with_platform(
+            AppConfig::for_tests(),
+            Arc::clone(&platform) as Arc<dyn Platform>,
+        ));
+        let (status, _) = readiness_check(State(Arc::clone(&state))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = readiness_check(State(state)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(platform.calls(), ["check_connection"]);
+
+        let state = Arc::new(AppState::with_platform(
+            AppConfig::for_tests(),
+            Arc::new(MockPlatform::new().disconnected()),
+        ));
+        let (status, _) = readiness_check(State(state)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_admin_cancel_stops_queued_and_running_issues() {
+        let mut config = AppConfig::for_tests();
+        config.server.admin_token = Some("secret".to_string());
+        let state = Arc::new(AppState::with_platform(
+            config,
+            Arc::new(MockPlatform::new()),
+        ));
+        state.task_queue.write().await.enqueue(
+            "owner/repo",
+            Task::ResolveIssue {
+                installation_id: 1,
+                repo_full_name: "owner/repo".to_string(),
+                clone_url: "https://github.com/owner/repo.git".to_string(),
+                default_branch: "main".to_string(),
+                issue_number: 3,
+                issue_title: "Queued".to_string(),
+                issue_body: String::new(),
+                mode: IssueMode::Implement,
+                priority: Priority::Normal,
+                model: None,
+                attempt: 0,
+            },
+        );
+        state.register_in_flight(1, "owner/repo", 4).await;
+
+        let cancel = |token: &str, issue_number: u64| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::AUTHORIZATION,
+                format!("Bearer {token}").parse().unwrap(),
+            );
+            let request = CancelRequest {
+                repo: "owner/repo".to_string(),
+                issue_number,
+            };
+            admin_cancel(State(Arc::clone(&state)), headers, Json(request))
+        };
+
+        assert_eq!(
+            cancel("wrong", 4).await.unwrap_err(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert!(!state.is_cancelled("owner/repo", 4).await);
+
+        let Json(queued) = cancel("secret", 3).await.unwrap();
+        assert_eq!(queued.removed_queued, 1);
+        assert!(!queued.stopped_running);
+        assert!(!state.is_cancelled("owner/repo", 3).await);
+
+        let Json(running) = cancel("secret", 4).await.unwrap();
+        assert!(running.stopped_running);
+        assert_eq!(
+            state.get_cancellation_reason("owner/repo", 4).await,
+            Some(CancellationReason::AdminRequest)
+        );
+
+        assert_eq!(
+            cancel("secret", 5).await.unwrap_err(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_history_serves_recorded_outcomes() {
+        let mut config = AppConfig::for_tests();
+        config.server.status_token = Some("secret".to_string());
+        let store = Arc::new(History::in_memory().unwrap());
+        let state = Arc::new(AppState {
+            history: Some(Arc::clone(&store)),
+            ..AppState::with_platform(config, Arc::new(MockPlatform::new()))
+        });
+        let task = |repo: &str| Task::ApproveIssue {
+            installation_id: 1,
+            repo_full_name: repo.to_string(),
+            issue_number: 7,
+            attempt: 0,
+        };
+
+        state.record_outcome(&task("owner/other"), Ok(&WorkflowOutcome::NoChanges));
+        let failure = AppError::Agent("out of turns".to_string());
+        state.record_outcome(&task("owner/repo"), Err(&failure));
+        let created = WorkflowOutcome::PullRequestCreated { pr_number: 12 };
+        state.record_outcome(&task("owner/repo"), Ok(&created));
+        // Outcomes are written in the background
+        for _ in 0..50 {
+            if store.recent(None, 10).unwrap().len() == 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let get = |token: &str, repo: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::AUTHORIZATION,
+                format!("Bearer {token}").parse().unwrap(),
+            );
+            let query = HistoryQuery {
+                repo: repo.map(str::to_string),
+                limit: default_history_limit(),
+            };
+            history(State(Arc::clone(&state)), headers, Query(query))
+        };
+
+        assert_eq!(
+            get("wrong", None).await.unwrap_err(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(get("secret", None).await.unwrap().0.len(), 3);
+        let Json(entries) = get("secret", Some("owner/repo")).await.unwrap();
+        let outcomes: Vec<_> = entries.iter().map(|e| e.outcome.as_str()).collect();
+        assert_eq!(outcomes, ["pull_request_created", "error"]);
+        assert_eq!(
+            entries[0].pr_url.as_deref(),
+            Some("https://github.com/owner/repo/pull/12")
+        );
+        assert!(entries[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("out of turns"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_enqueue_builds_tasks_from_platform() {
+        use crate::platform::types::{InstallationRepo, Issue, PullRequest};
+
+        let mut config = AppConfig::for_tests();
+        config.server.admin_token = Some("secret".to_string());
+        let platform = MockPlatform::new()
+            .with_repo(InstallationRepo {
+                full_name: "owner/repo".to_string(),
+                clone_url: "https://github.com/owner/repo.git".to_string(),
+                default_branch: "trunk".to_string(),
+            })
+            .with_issue(
+                "owner/repo",
+                Issue {
+                    number: 7,
+                    title: "Fix it".to_string(),
+                    body: "Broken".to_string(),
+                    labels: vec!["mycelium:priority".to_string()],
+                    assignees: Vec::new(),
+                    comments: Vec::new(),
+                },
+            )
+            .with_pull_request(
+                "owner/repo",
+                PullRequest {
+                    number: 9,
+                    title: "Fix it".to_string(),
+                    body: String::new(),
+                    head_branch: "mycelium/issue-7".to_string(),
+                    base_branch: "trunk".to_string(),
+                },
+            );
+        let state = Arc::new(AppState::with_platform(config, Arc::new(platform)));
+
+        let enqueue = |body: serde_json::Value| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+            let request = serde_json::from_value(body).unwrap();
+            admin_enqueue(State(Arc::clone(&state)), headers, Json(request))
+        };
+
+        let (status, Json(response)) = enqueue(serde_json::json!({
+            "repo": "owner/repo",
+            "issue_number": 7,
+            "mode": "Research"
+        }))
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(response.task, "Resolve issue #7 on owner/repo");
+        let (_, Json(response)) = enqueue(serde_json::json!({
+            "repo": "owner/repo",
+            "pr_number": 9
+        }))
+        .await
+        .unwrap();
+        assert_eq!(response.task, "Respond to review on PR #9 on owner/repo");
+        assert_eq!(
+            enqueue(serde_json::json!({ "repo": "owner/other", "issue_number": 1 }))
+                .await
+                .unwrap_err(),
+            StatusCode::NOT_FOUND
+        );
+
+        let mut queue = state.task_queue.write().await;
+        let Some(Task::ResolveIssue {
+            default_branch,
+            mode,
+            priority,
+            ..
+        }) = queue.take_next()
+        else {
+            panic!("expected the issue task first");
+        };
+        assert_eq!(default_branch, "trunk");
+        assert_eq!(mode, IssueMode::Research);
+        assert_eq!(priority, Priority::High);
+        queue.finish("owner/repo");
+        let Some(Task::RespondToReview { pr_branch, .. }) = queue.take_next() else {
+            panic!("expected the review task");
+        };
+        assert_eq!(pr_branch, "mycelium/issue-7");
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use tokio::signal;
 
-use crate::platform::Platform;
+use crate::queue::persist;
 use crate::server::AppState;
 
 /// Wait for a shutdown signal (SIGINT or SIGTERM).
@@ -34,10 +34,32 @@ pub async fn wait_for_shutdown() {
     }
 }
 
-/// Perform graceful shutdown: remove :working labels from all in-flight issues.
+/// Perform graceful shutdown: save running and queued tasks so the next start
+/// resumes them, then remove :working labels from all in-flight issues.
 pub async fn graceful_shutdown(state: &Arc<AppState>) {
     tracing::info!("Starting graceful shutdown...");
 
+    // Interrupted runs restart from scratch, but reviews and CI fixes aren't
+    // rediscovered by the startup scan, so every task is saved, including
+    // retries waiting out their backoff and reviews still collecting comments
+    let mut tasks = state.task_queue.read().await.snapshot();
+    tasks.extend(state.debounced_reviews.lock().await.drain().map(|(_, task)| task));
+    let path = state.config.workspace.pending_tasks_file();
+    match persist::save(&path, &tasks).await {
+        Ok(()) if !tasks.is_empty() => tracing::info!(
+            count = tasks.len(),
+            path = %path.display(),
+            "Saved running and queued tasks for the next start"
+        ),
+        Ok(()) => {}
+        Err(e) => tracing::error!(
+            count = tasks.len(),
+            path = %path.display(),
+            error = %e,
+            "Failed to save running and queued tasks; they will be lost"
+        ),
+    }
+
     let in_flight_issues = state.get_in_flight_issues().await;
 
     if in_flight_issues.is_empty() {
@@ -50,7 +72,7 @@ pub async fn graceful_shutdown(state: &Arc<AppState>) {
         "Removing :working labels from in-flight issues"
     );
 
-    let working_label = format!("{}:working", state.config.github.trigger_label);
+    let working_label = format!("{}:working", state.config.github.trigger_label());
 
     for issue in in_flight_issues {
         tracing::info!(
@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+/// Name of the span the queue processor runs each task in.
+pub const TASK_SPAN: &str = "task";
+
+/// Log lines kept per running task; older ones are dropped.
+const MAX_TASK_LINES: usize = 500;
+
+/// Lines logged by one task, stored in the extensions of its span.
+#[derive(Clone, Default)]
+struct TaskLines(Arc<Mutex<VecDeque<String>>>);
+
+/// Tracing layer keeping the most recent log lines of each running task, so
+/// they can be attached to a failure report with [`recent_lines`].
+#[derive(Default)]
+pub struct TaskLogLayer;
+
+impl<S> Layer<S> for TaskLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != TASK_SPAN {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(TaskLines::default());
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(scope) = ctx.event_scope(event) else {
+            return;
+        };
+        let Some(lines) = scope
+            .from_root()
+            .find_map(|span| span.extensions().get::<TaskLines>().cloned())
+        else {
+            return;
+        };
+
+        let metadata = event.metadata();
+        let mut line = format!(
+            "{} {:>5} {}:",
+            chrono::Utc::now().format("%H:%M:%S"),
+            metadata.level(),
+            metadata.target()
+        );
+        event.record(&mut LineVisitor(&mut line));
+
+        let mut lines = lines.0.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == MAX_TASK_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+}
+
+/// Appends an event's message and fields to a log line.
+struct LineVisitor<'a>(&'a mut String);
+
+impl Visit for LineVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {value}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+/// The last `count` lines logged by the task whose span is current, oldest
+/// first. Empty outside a task or when `TaskLogLayer` isn't installed.
+pub fn recent_lines(count: usize) -> Vec<String> {
+    tracing::Span::current()
+        .with_subscriber(|(id, dispatch)| {
+            let registry = dispatch.downcast_ref::<Registry>()?;
+            let span = registry.span(id)?;
+            let lines = span
+                .scope()
+                .find_map(|span| span.extensions().get::<TaskLines>().cloned())?;
+            let lines = lines.0.lock().unwrap_or_else(|e| e.into_inner());
+            Some(lines.iter().skip(lines.len().saturating_sub(count)).cloned().collect())
+        })
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// A collapsed `<details>` block with the current task's last `count` log
+/// lines, for appending to a failure comment. Empty if `count` is 0 or
+/// nothing was captured.
+pub fn details_block(count: usize) -> String {
+    if count == 0 {
+        return String::new();
+    }
+    let lines = recent_lines(count);
+    if lines.is_empty() {
+        return String::new();
+    }
+    // A fence in a log line would end the code block early
+    format!(
+        "\n\n<details>\n<summary>Last {} log lines</summary>\n\n```\n{}\n```\n</details>",
+        lines.len(),
+        lines.join("\n").replace("```", "\\`\\`\\`")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn test_recent_lines_are_scoped_to_the_task_span() {
+        let subscriber = tracing_subscriber::registry().with(TaskLogLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("before any task");
+
+            let span = tracing::info_span!(TASK_SPAN, repo = "owner/repo");
+            let _guard = span.enter();
+            for i in 0..5 {
+                tracing::info!(turn = i, "Agent turn");
+            }
+            tracing::warn!(error = "boom", "Tool error");
+
+            let lines = recent_lines(2);
+            assert_eq!(lines.len(), 2);
+            assert!(lines[0].ends_with("INFO mycelium::task_log::tests: Agent turn turn=4"));
+            assert!(lines[1].ends_with("WARN mycelium::task_log::tests: Tool error error=\"boom\""));
+
+            let inner = tracing::info_span!("tool");
+            let _inner = inner.enter();
+            assert_eq!(recent_lines(100).len(), 6);
+        });
+        assert!(recent_lines(10).is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+/// Prefix that marks a comment line as a Mycelium command.
+pub const COMMAND_PREFIX: &str = "/mycelium";
+
+/// A `/mycelium <command>` directive found in a comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlashCommand {
+    /// Cancel queued and in-flight work on the issue.
+    Stop,
+    /// Enqueue the issue again from scratch (mode taken from its labels).
+    Retry,
+    /// Like `Retry`, but resume a run stopped at the turn limit if one was saved.
+    Continue,
+    /// Enqueue a research-only run regardless of labels.
+    Research,
+    /// `/mycelium` followed by something we don't understand (or nothing).
+    Unknown(String),
+}
+
+impl SlashCommand {
+    /// Find the first `/mycelium ...` line in a comment body.
+    pub fn parse(body: &str) -> Option<Self> {
+        body.lines().find_map(|line| {
+            let mut words = line.split_whitespace();
+            if words.next()? != COMMAND_PREFIX {
+                return None;
+            }
+            let command = words.next().unwrap_or_default().to_ascii_lowercase();
+            Some(match command.as_str() {
+                "stop" => SlashCommand::Stop,
+                "retry" => SlashCommand::Retry,
+                "continue" => SlashCommand::Continue,
+                "research" => SlashCommand::Research,
+                _ => SlashCommand::Unknown(command),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_known_commands() {
+        assert_eq!(
+            SlashCommand::parse("/mycelium stop"),
+            Some(SlashCommand::Stop)
+        );
+        assert_eq!(
+            SlashCommand::parse("Thanks!\n\n  /mycelium Retry please"),
+            Some(SlashCommand::Retry)
+        );
+        assert_eq!(
+            SlashCommand::parse("/mycelium research"),
+            Some(SlashCommand::Research)
+        );
+        assert_eq!(
+            SlashCommand::parse("/mycelium continue"),
+            Some(SlashCommand::Continue)
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_command() {
+        assert_eq!(
+            SlashCommand::parse("/mycelium deploy"),
+            Some(SlashCommand::Unknown("deploy".to_string()))
+        );
+        assert_eq!(
+            SlashCommand::parse("/mycelium"),
+            Some(SlashCommand::Unknown(String::new()))
+        );
+    }
+
+    #[test]
+    fn test_parse_ignores_non_commands() {
+        assert_eq!(SlashCommand::parse("Please stop the mycelium run"), None);
+        assert_eq!(SlashCommand::parse("see /mycelium stop in the docs"), None);
+        assert_eq!(SlashCommand::parse("/myceliumstop"), None);
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// How long a delivery ID is remembered. GitHub's automatic redeliveries
+/// happen well within this window.
+const DELIVERY_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Upper bound on remembered delivery IDs; the oldest are evicted first.
+const MAX_DELIVERIES: usize = 10_000;
+
+/// Recently seen `X-GitHub-Delivery` IDs, bounded in both age and count.
+pub struct DeliveryCache {
+    seen: HashMap<String, Instant>,
+    /// Insertion order, for evicting expired and excess entries.
+    order: VecDeque<String>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl Default for DeliveryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeliveryCache {
+    pub fn new() -> Self {
+        Self::with_limits(DELIVERY_TTL, MAX_DELIVERIES)
+    }
+
+    fn with_limits(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            ttl,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record a delivery ID. Returns false if it was already seen within the TTL.
+    pub fn insert(&mut self, delivery_id: &str) -> bool {
+        self.insert_at(delivery_id, Instant::now())
+    }
+
+    /// Forget a delivery ID, so a redelivery of it is accepted.
+    pub fn remove(&mut self, delivery_id: &str) {
+        if self.seen.remove(delivery_id).is_some() {
+            self.order.retain(|id| id != delivery_id);
+        }
+    }
+
+    fn insert_at(&mut self, delivery_id: &str, now: Instant) -> bool {
+        // Entries are in insertion order, so expired ones are at the front
+        while let Some(oldest) = self.order.front() {
+            let expired = self
+                .seen
+                .get(oldest)
+                .is_none_or(|seen_at| now.duration_since(*seen_at) >= self.ttl);
+            if !expired {
+                break;
+            }
+            if let Some(id) = self.order.pop_front() {
+                self.seen.remove(&id);
+            }
+        }
+
+        if self.seen.contains_key(delivery_id) {
+            return false;
+        }
+
+        while self.order.len() >= self.capacity {
+            if let Some(id) = self.order.pop_front() {
+                self.seen.remove(&id);
+            }
+        }
+
+        self.seen.insert(delivery_id.to_string(), now);
+        self.order.push_back(delivery_id.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeat_delivery_rejected() {
+        let mut cache = DeliveryCache::new();
+        assert!(cache.insert("abc"));
+        assert!(!cache.insert("abc"));
+        assert!(cache.insert("def"));
+    }
+
+    #[test]
+    fn test_delivery_forgotten_after_ttl() {
+        let mut cache = DeliveryCache::with_limits(Duration::from_secs(10), 100);
+        let start = Instant::now();
+        assert!(cache.insert_at("abc", start));
+        assert!(!cache.insert_at("abc", start + Duration::from_secs(5)));
+        assert!(cache.insert_at("abc", start + Duration::from_secs(10)));
+        assert_eq!(cache.seen.len(), 1);
+    }
+
+    #[test]
+    fn test_capacity_evicts_oldest() {
+        let mut cache = DeliveryCache::with_limits(Duration::from_secs(60), 2);
+        assert!(cache.insert("a"));
+        assert!(cache.insert("b"));
+        assert!(cache.insert("c"));
+        assert_eq!(cache.seen.len(), 2);
+        // "a" was evicted, so it is accepted again
+        assert!(cache.insert("a"));
+        assert!(!cache.insert("c"));
+    }
+
+    #[test]
+    fn test_removed_delivery_accepted_again() {
+        let mut cache = DeliveryCache::with_limits(Duration::from_secs(60), 2);
+        assert!(cache.insert("a"));
+        assert!(cache.insert("b"));
+        cache.remove("a");
+        assert!(cache.insert("a"));
+        assert!(cache.insert("c"));
+        // "b" is now the oldest and was evicted; "a" was kept
+        assert!(!cache.insert("a"));
+        assert_eq!(cache.order, ["a", "c"]);
+    }
+}
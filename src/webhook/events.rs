@@ -5,8 +5,11 @@ use serde::Deserialize;
 pub enum WebhookEvent {
     Issues(IssuesEvent),
     IssueComment(IssueCommentEvent),
+    PullRequest(PullRequestEvent),
     PullRequestReview(PullRequestReviewEvent),
     PullRequestReviewComment(PullRequestReviewCommentEvent),
+    CheckRun(CheckRunEvent),
+    WorkflowRun(WorkflowRunEvent),
     Ping,
     Unsupported(String),
 }
@@ -29,6 +32,14 @@ pub struct IssueCommentEvent {
     pub installation: Option<InstallationPayload>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PullRequestEvent {
+    pub action: String,
+    pub pull_request: PullRequestPayload,
+    pub repository: RepositoryPayload,
+    pub installation: Option<InstallationPayload>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PullRequestReviewEvent {
     pub action: String,
@@ -47,6 +58,22 @@ pub struct PullRequestReviewCommentEvent {
     pub installation: Option<InstallationPayload>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CheckRunEvent {
+    pub action: String,
+    pub check_run: CheckRunPayload,
+    pub repository: RepositoryPayload,
+    pub installation: Option<InstallationPayload>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkflowRunEvent {
+    pub action: String,
+    pub workflow_run: WorkflowRunPayload,
+    pub repository: RepositoryPayload,
+    pub installation: Option<InstallationPayload>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct IssuePayload {
     pub number: u64,
@@ -102,6 +129,68 @@ pub struct PullRequestRef {
     #[serde(rename = "ref")]
     pub ref_name: String,
     pub sha: String,
+    /// Repository the ref lives in; differs from the base repo for forks.
+    #[serde(default)]
+    pub repo: Option<RefRepoPayload>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefRepoPayload {
+    pub full_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckRunPayload {
+    pub id: u64,
+    pub name: String,
+    pub conclusion: Option<String>, // "success", "failure", "cancelled", ...
+    pub html_url: Option<String>,
+    pub output: CheckRunOutput,
+    pub app: Option<AppPayload>,
+    pub check_suite: CheckSuitePayload,
+    #[serde(default)]
+    pub pull_requests: Vec<PullRequestMinimal>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckRunOutput {
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckSuitePayload {
+    pub head_branch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppPayload {
+    pub slug: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkflowRunPayload {
+    pub id: u64,
+    pub name: Option<String>,
+    pub head_branch: Option<String>,
+    pub conclusion: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub pull_requests: Vec<PullRequestMinimal>,
+}
+
+/// PR reference attached to check and workflow runs (same-repo PRs only).
+#[derive(Debug, Deserialize)]
+pub struct PullRequestMinimal {
+    pub number: u64,
+    pub head: PullRequestMinimalRef,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequestMinimalRef {
+    #[serde(rename = "ref")]
+    pub ref_name: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -126,6 +215,20 @@ pub struct InstallationPayload {
 }
 
 impl WebhookEvent {
+    /// Repository the event belongs to, if any.
+    pub fn repository(&self) -> Option<&RepositoryPayload> {
+        match self {
+            WebhookEvent::Issues(e) => Some(&e.repository),
+            WebhookEvent::IssueComment(e) => Some(&e.repository),
+            WebhookEvent::PullRequest(e) => Some(&e.repository),
+            WebhookEvent::PullRequestReview(e) => Some(&e.repository),
+            WebhookEvent::PullRequestReviewComment(e) => Some(&e.repository),
+            WebhookEvent::CheckRun(e) => Some(&e.repository),
+            WebhookEvent::WorkflowRun(e) => Some(&e.repository),
+            WebhookEvent::Ping | WebhookEvent::Unsupported(_) => None,
+        }
+    }
+
     pub fn parse(event_type: &str, payload: &[u8]) -> Result<Self, serde_json::Error> {
         match event_type {
             "issues" => {
@@ -136,6 +239,10 @@ impl WebhookEvent {
                 let event: IssueCommentEvent = serde_json::from_slice(payload)?;
                 Ok(WebhookEvent::IssueComment(event))
             }
+            "pull_request" => {
+                let event: PullRequestEvent = serde_json::from_slice(payload)?;
+                Ok(WebhookEvent::PullRequest(event))
+            }
             "pull_request_review" => {
                 let event: PullRequestReviewEvent = serde_json::from_slice(payload)?;
                 Ok(WebhookEvent::PullRequestReview(event))
@@ -144,6 +251,14 @@ impl WebhookEvent {
                 let event: PullRequestReviewCommentEvent = serde_json::from_slice(payload)?;
                 Ok(WebhookEvent::PullRequestReviewComment(event))
             }
+            "check_run" => {
+                let event: CheckRunEvent = serde_json::from_slice(payload)?;
+                Ok(WebhookEvent::CheckRun(event))
+            }
+            "workflow_run" => {
+                let event: WorkflowRunEvent = serde_json::from_slice(payload)?;
+                Ok(WebhookEvent::WorkflowRun(event))
+            }
             "ping" => Ok(WebhookEvent::Ping),
             other => Ok(WebhookEvent::Unsupported(other.to_string())),
         }
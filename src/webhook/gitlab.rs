@@ -0,0 +1,393 @@
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use serde::Deserialize;
+
+use crate::platform::types::TOKEN_INSTALLATION_ID;
+use crate::server::AppState;
+use crate::webhook::events::{
+    InstallationPayload, IssuePayload, IssuesEvent, LabelPayload, RepositoryPayload, UserPayload,
+};
+use crate::webhook::handler::handle_issues_event;
+use crate::webhook::signature::verify_gitlab_token;
+
+/// Payload of a GitLab `Issue Hook` delivery.
+#[derive(Debug, Deserialize)]
+pub struct GitLabIssueEvent {
+    pub user: GitLabEventUser,
+    pub project: GitLabEventProject,
+    pub object_attributes: GitLabIssueAttributes,
+    /// Labels on the issue after the change.
+    #[serde(default)]
+    pub labels: Vec<GitLabEventLabel>,
+    #[serde(default)]
+    pub changes: GitLabIssueChanges,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabEventUser {
+    pub id: u64,
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabEventProject {
+    pub id: u64,
+    pub path_with_namespace: String,
+    pub git_http_url: String,
+    pub default_branch: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabIssueAttributes {
+    pub iid: u64,
+    pub title: String,
+    pub description: Option<String>,
+    /// "open", "close", "reopen" or "update".
+    pub action: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabEventLabel {
+    pub title: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GitLabIssueChanges {
+    pub labels: Option<GitLabLabelChange>,
+    pub title: Option<serde_json::Value>,
+    pub description: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabLabelChange {
+    #[serde(default)]
+    pub previous: Vec<GitLabEventLabel>,
+    #[serde(default)]
+    pub current: Vec<GitLabEventLabel>,
+}
+
+impl GitLabIssueEvent {
+    /// The GitHub `issues` events this delivery amounts to. One GitLab update
+    /// can close the issue, change several labels and edit the description
+    /// at once, so it maps to a closed event, or to an unlabeled event per
+    /// removed label, a labeled event per added one and an edited or
+    /// reopened event.
+    pub fn into_issues_events(self) -> Vec<IssuesEvent> {
+        let action = self.object_attributes.action.as_deref().unwrap_or("update");
+        let mut actions: Vec<(&str, Option<String>)> = Vec::new();
+
+        if action == "close" {
+            actions.push(("closed", None));
+        } else {
+            let (previous, current): (Vec<&str>, Vec<&str>) = match &self.changes.labels {
+                Some(change) => (
+                    change.previous.iter().map(|l| l.title.as_str()).collect(),
+                    change.current.iter().map(|l| l.title.as_str()).collect(),
+                ),
+                // A new issue may not report its labels as a change
+                None if action == "open" => (
+                    Vec::new(),
+                    self.labels.iter().map(|l| l.title.as_str()).collect(),
+                ),
+                None => (Vec::new(), Vec::new()),
+            };
+            for removed in previous.iter().filter(|l| !current.contains(l)) {
+                actions.push(("unlabeled", Some(removed.to_string())));
+            }
+            for added in current.iter().filter(|l| !previous.contains(l)) {
+                actions.push(("labeled", Some(added.to_string())));
+            }
+            if action == "update"
+                && (self.changes.title.is_some() || self.changes.description.is_some())
+            {
+                actions.push(("edited", None));
+            }
+            if action == "reopen" {
+                actions.push(("reopened", None));
+            }
+        }
+
+        actions
+            .into_iter()
+            .map(|(action, label)| IssuesEvent {
+                action: action.to_string(),
+                issue: IssuePayload {
+                    number: self.object_attributes.iid,
+                    title: self.object_attributes.title.clone(),
+                    body: self.object_attributes.description.clone(),
+                    labels: self
+                        .labels
+                        .iter()
+                        .map(|l| LabelPayload {
+                            name: l.title.clone(),
+                        })
+                        .collect(),
+                    user: UserPayload {
+                        login: self.user.username.clone(),
+                        id: self.user.id,
+                        user_type: "User".to_string(),
+                    },
+                    pull_request: None,
+                },
+                repository: RepositoryPayload {
+                    id: self.project.id,
+                    full_name: self.project.path_with_namespace.clone(),
+                    clone_url: self.project.git_http_url.clone(),
+                    default_branch: self.project.default_branch.clone(),
+                },
+                installation: Some(InstallationPayload {
+                    id: TOKEN_INSTALLATION_ID,
+                }),
+                label: label.map(|name| LabelPayload { name }),
+            })
+            .collect()
+    }
+}
+
+/// Handle a GitLab webhook delivery.
+///
+/// Issue events are translated into their GitHub equivalents and handled by
+/// the same code. Only issue events are supported: notes, merge request and
+/// pipeline events are acknowledged and ignored. As with GitHub, a request is
+/// authenticated before anything else is looked at.
+pub async fn handle_gitlab_webhook(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let token = header("x-gitlab-token").unwrap_or_default();
+    if let Err(e) = verify_gitlab_token(&state.config.gitlab.webhook_secret, token) {
+        tracing::warn!(error = %e, "GitLab webhook verification failed");
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let Some(event_type) = header("x-gitlab-event") else {
+        tracing::warn!("Missing X-Gitlab-Event header");
+        return StatusCode::BAD_REQUEST;
+    };
+
+    // Drop redeliveries of an event we've already handled
+    let delivery_id = header("x-gitlab-event-uuid");
+    if let Some(delivery_id) = delivery_id {
+        if !state.deliveries.lock().await.insert(delivery_id) {
+            tracing::info!(
+                delivery = %delivery_id,
+                event_type = %event_type,
+                "Ignoring duplicate webhook delivery"
+            );
+            return StatusCode::OK;
+        }
+    }
+
+    let status = handle_event(&state, event_type, &body).await;
+    // Forget a delivery that failed, so a manual redelivery is processed
+    if !status.is_success() {
+        if let Some(delivery_id) = delivery_id {
+            state.deliveries.lock().await.remove(delivery_id);
+        }
+    }
+    status
+}
+
+/// Parse a verified delivery and hand its issue events to the GitHub handlers.
+async fn handle_event(state: &Arc<AppState>, event_type: &str, body: &[u8]) -> StatusCode {
+    if event_type != "Issue Hook" {
+        tracing::debug!(event_type = %event_type, "Ignoring unsupported event");
+        return StatusCode::OK;
+    }
+
+    let event: GitLabIssueEvent = match serde_json::from_slice(body) {
+        Ok(event) => event,
+        Err(e) => {
+            tracing::error!(error = %e, event_type = %event_type, "Failed to parse webhook event");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    tracing::info!(event_type = %event_type, "Received webhook event");
+
+    let repo = &event.project.path_with_namespace;
+    if !state.config.github.is_repo_allowed(repo) {
+        tracing::debug!(
+            repo = %repo,
+            "Ignoring event for repository not allowed by config"
+        );
+        return StatusCode::OK;
+    }
+
+    let mut status = StatusCode::OK;
+    for issues_event in event.into_issues_events() {
+        let handled = handle_issues_event(state, issues_event).await;
+        if handled != StatusCode::OK {
+            status = handled;
+        }
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppConfig, PlatformKind};
+    use crate::platform::mock::MockPlatform;
+    use crate::queue::task::Task;
+
+    const SECRET: &str = "gitlab-secret";
+
+    fn issue_event(action: &str, labels: &[&str], changes: serde_json::Value) -> serde_json::Value {
+        let labels: Vec<_> = labels
+            .iter()
+            .map(|title| serde_json::json!({ "title": title }))
+            .collect();
+        serde_json::json!({
+            "object_kind": "issue",
+            "user": { "id": 5, "username": "alice" },
+            "project": {
+                "id": 10,
+                "path_with_namespace": "group/project",
+                "git_http_url": "https://gitlab.com/group/project.git",
+                "default_branch": "main"
+            },
+            "object_attributes": {
+                "iid": 7,
+                "title": "Crash on empty input",
+                "description": "Steps to reproduce",
+                "action": action
+            },
+            "labels": labels,
+            "changes": changes
+        })
+    }
+
+    fn actions(event: serde_json::Value) -> Vec<(String, Option<String>)> {
+        serde_json::from_value::<GitLabIssueEvent>(event)
+            .unwrap()
+            .into_issues_events()
+            .into_iter()
+            .map(|e| (e.action, e.label.map(|l| l.name)))
+            .collect()
+    }
+
+    #[test]
+    fn test_issue_hook_maps_to_issues_events() {
+        let label_change = |previous: &[&str], current: &[&str]| {
+            let titles = |labels: &[&str]| {
+                labels
+                    .iter()
+                    .map(|t| serde_json::json!({ "title": t }))
+                    .collect::<Vec<_>>()
+            };
+            serde_json::json!({
+                "labels": { "previous": titles(previous), "current": titles(current) }
+            })
+        };
+
+        assert_eq!(
+            actions(issue_event("open", &["mycelium"], serde_json::json!({}))),
+            [("labeled".to_string(), Some("mycelium".to_string()))]
+        );
+        assert_eq!(
+            actions(issue_event(
+                "update",
+                &["bug", "mycelium:research"],
+                label_change(&["bug", "mycelium"], &["bug", "mycelium:research"])
+            )),
+            [
+                ("unlabeled".to_string(), Some("mycelium".to_string())),
+                ("labeled".to_string(), Some("mycelium:research".to_string())),
+            ]
+        );
+        assert_eq!(
+            actions(issue_event(
+                "update",
+                &["mycelium"],
+                serde_json::json!({ "description": { "previous": "a", "current": "b" } })
+            )),
+            [("edited".to_string(), None)]
+        );
+        assert_eq!(
+            actions(issue_event("close", &["mycelium"], serde_json::json!({}))),
+            [("closed".to_string(), None)]
+        );
+        assert_eq!(
+            actions(issue_event("reopen", &["mycelium"], serde_json::json!({}))),
+            [("reopened".to_string(), None)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gitlab_webhook_enqueues_labeled_issue() {
+        let mut config = AppConfig::for_tests();
+        config.platform = PlatformKind::GitLab;
+        config.gitlab.webhook_secret = SECRET.to_string();
+        let state = Arc::new(AppState::with_platform(
+            config,
+            Arc::new(MockPlatform::new()),
+        ));
+        let body = Bytes::from(
+            serde_json::to_vec(&issue_event("open", &["mycelium"], serde_json::json!({}))).unwrap(),
+        );
+        let headers = |token: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-gitlab-token", token.parse().unwrap());
+            headers.insert("x-gitlab-event", "Issue Hook".parse().unwrap());
+            headers
+        };
+
+        let status =
+            handle_gitlab_webhook(State(Arc::clone(&state)), headers("wrong"), body.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let status = handle_gitlab_webhook(State(Arc::clone(&state)), headers(SECRET), body).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let task = state.task_queue.write().await.take_next().unwrap();
+        match task {
+            Task::ResolveIssue {
+                installation_id,
+                repo_full_name,
+                clone_url,
+                issue_number,
+                ..
+            } => {
+                assert_eq!(installation_id, TOKEN_INSTALLATION_ID);
+                assert_eq!(repo_full_name, "group/project");
+                assert_eq!(clone_url, "https://gitlab.com/group/project.git");
+                assert_eq!(issue_number, 7);
+            }
+            _ => panic!("expected ResolveIssue"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_gitlab_delivery_can_be_redelivered() {
+        let mut config = AppConfig::for_tests();
+        config.platform = PlatformKind::GitLab;
+        config.gitlab.webhook_secret = SECRET.to_string();
+        let state = Arc::new(AppState::with_platform(
+            config,
+            Arc::new(MockPlatform::new()),
+        ));
+        let status = |body: Bytes| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-gitlab-token", SECRET.parse().unwrap());
+            headers.insert("x-gitlab-event", "Issue Hook".parse().unwrap());
+            headers.insert("x-gitlab-event-uuid", "delivery-1".parse().unwrap());
+            handle_gitlab_webhook(State(Arc::clone(&state)), headers, body)
+        };
+        let body = Bytes::from(
+            serde_json::to_vec(&issue_event("open", &["mycelium"], serde_json::json!({}))).unwrap(),
+        );
+
+        assert_eq!(
+            status(Bytes::from_static(b"{}")).await,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(status(body.clone()).await, StatusCode::ACCEPTED);
+        assert_eq!(status(body).await, StatusCode::OK);
+    }
+}
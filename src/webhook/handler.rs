@@ -4,24 +4,44 @@ use axum::body::Bytes;
 use axum::extract::State;
 use axum::http::{HeaderMap, StatusCode};
 
-use crate::queue::task::{IssueMode, Task};
+use crate::config::GitHubAuth;
+use crate::platform::types::{Reaction, TOKEN_INSTALLATION_ID};
+use crate::queue::task::{model_from_labels, IssueMode, Priority, Task};
+use crate::queue::{enqueue, enqueue_debounced};
 use crate::server::{AppState, CancellationReason};
-use crate::webhook::events::WebhookEvent;
-use crate::webhook::signature::verify_signature;
+use crate::webhook::commands::SlashCommand;
+use crate::webhook::events::{
+    CheckRunEvent, InstallationPayload, IssueCommentEvent, PullRequestEvent, PullRequestMinimal,
+    RepositoryPayload, UserPayload, WebhookEvent, WorkflowRunEvent,
+};
+use crate::webhook::signature::{verify_signature, verify_signature_sha1};
+use crate::workflow::checks::tail_log;
+use crate::workspace::WorkspaceManager;
 
+/// Handle a GitHub webhook delivery.
+///
+/// Requests are authenticated before anything else is looked at, so an
+/// unauthenticated caller gets the same `401` whichever header is missing or
+/// wrong and can't probe the checks one at a time. Only a correctly signed
+/// request without `X-GitHub-Event` gets `400`.
 pub async fn handle_webhook(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     body: Bytes,
 ) -> StatusCode {
-    // Extract required headers
-    let signature = match headers.get("x-hub-signature-256").and_then(|v| v.to_str().ok()) {
-        Some(sig) => sig.to_string(),
-        None => {
-            tracing::warn!("Missing X-Hub-Signature-256 header");
-            return StatusCode::UNAUTHORIZED;
+    // A missing signature goes through the same verification as a wrong one
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let secrets = state.config.webhook_secrets();
+    let verified = match (header("x-hub-signature-256"), header("x-hub-signature")) {
+        (None, Some(sha1)) if state.config.github.allow_sha1_signatures => {
+            verify_signature_sha1(secrets, &body, sha1)
         }
+        (sha256, _) => verify_signature(secrets, &body, sha256.unwrap_or_default()),
     };
+    if let Err(e) = verified {
+        tracing::warn!(error = %e, "Webhook signature verification failed");
+        return StatusCode::UNAUTHORIZED;
+    }
 
     let event_type = match headers.get("x-github-event").and_then(|v| v.to_str().ok()) {
         Some(et) => et.to_string(),
@@ -31,14 +51,32 @@ pub async fn handle_webhook(
         }
     };
 
-    // Verify signature
-    if let Err(e) = verify_signature(state.config.webhook_secret(), &body, &signature) {
-        tracing::warn!(error = %e, "Webhook signature verification failed");
-        return StatusCode::UNAUTHORIZED;
+    // Drop redeliveries of an event we've already handled
+    let delivery_id = header("x-github-delivery");
+    if let Some(delivery_id) = delivery_id {
+        if !state.deliveries.lock().await.insert(delivery_id) {
+            tracing::info!(
+                delivery = %delivery_id,
+                event_type = %event_type,
+                "Ignoring duplicate webhook delivery"
+            );
+            return StatusCode::OK;
+        }
+    }
+
+    let status = handle_event(&state, &event_type, &body).await;
+    // Forget a delivery that failed, so a manual redelivery is processed
+    if !status.is_success() {
+        if let Some(delivery_id) = delivery_id {
+            state.deliveries.lock().await.remove(delivery_id);
+        }
     }
+    status
+}
 
-    // Parse event
-    let event = match WebhookEvent::parse(&event_type, &body) {
+/// Parse a verified delivery and dispatch it to its event handler.
+async fn handle_event(state: &Arc<AppState>, event_type: &str, body: &[u8]) -> StatusCode {
+    let event = match WebhookEvent::parse(event_type, body) {
         Ok(event) => event,
         Err(e) => {
             tracing::error!(error = %e, event_type = %event_type, "Failed to parse webhook event");
@@ -48,19 +86,30 @@ pub async fn handle_webhook(
 
     tracing::info!(event_type = %event_type, "Received webhook event");
 
-    match event {
-        WebhookEvent::Issues(issues_event) => {
-            handle_issues_event(&state, issues_event).await
+    if let Some(repo) = event.repository() {
+        if !state.config.github.is_repo_allowed(&repo.full_name) {
+            tracing::debug!(
+                repo = %repo.full_name,
+                "Ignoring event for repository not allowed by config"
+            );
+            return StatusCode::OK;
         }
+    }
+
+    match event {
+        WebhookEvent::Issues(issues_event) => handle_issues_event(state, issues_event).await,
         WebhookEvent::IssueComment(comment_event) => {
-            handle_issue_comment_event(&state, comment_event).await
+            handle_issue_comment_event(state, comment_event).await
         }
+        WebhookEvent::PullRequest(pr_event) => handle_pull_request_event(state, pr_event).await,
         WebhookEvent::PullRequestReview(review_event) => {
-            handle_pr_review_event(&state, review_event).await
+            handle_pr_review_event(state, review_event).await
         }
         WebhookEvent::PullRequestReviewComment(comment_event) => {
-            handle_pr_review_comment_event(&state, comment_event).await
+            handle_pr_review_comment_event(state, comment_event).await
         }
+        WebhookEvent::CheckRun(check_event) => handle_check_run_event(state, check_event).await,
+        WebhookEvent::WorkflowRun(run_event) => handle_workflow_run_event(state, run_event).await,
         WebhookEvent::Ping => {
             tracing::info!("Received ping event");
             StatusCode::OK
@@ -72,15 +121,64 @@ pub async fn handle_webhook(
     }
 }
 
-async fn handle_issues_event(
-    state: &AppState,
+/// Whether an edit should restart the issue's work with the new description:
+/// only when a run is in flight or a task is queued for it. Finished issues,
+/// and those waiting for a human to approve changes or answer a question,
+/// are left alone.
+async fn edit_restarts_work(state: &AppState, event: &crate::webhook::events::IssuesEvent) -> bool {
+    let github = &state.config.github;
+    let finished_or_waiting = [
+        format!("{}:done", github.trigger_label()),
+        github.needs_approval_label(),
+        github.needs_info_label(),
+    ];
+    if event
+        .issue
+        .labels
+        .iter()
+        .any(|l| finished_or_waiting.contains(&l.name))
+    {
+        return false;
+    }
+
+    let repo = &event.repository.full_name;
+    let tracked = state.is_in_flight(repo, event.issue.number).await
+        || state
+            .task_queue
+            .read()
+            .await
+            .has_issue(repo, event.issue.number);
+    if !tracked {
+        tracing::debug!(
+            repo = %repo,
+            issue = %event.issue.number,
+            "Issue edited with no run in flight or queued, ignoring"
+        );
+    }
+    tracked
+}
+
+pub(crate) async fn handle_issues_event(
+    state: &Arc<AppState>,
     event: crate::webhook::events::IssuesEvent,
 ) -> StatusCode {
-    let trigger_label = &state.config.github.trigger_label;
-    let research_label = format!("{trigger_label}:research");
+    let github = &state.config.github;
+    let research_label = format!("{}:research", github.trigger_label());
 
     // Handle issue closed — cancel any in-flight work
     if event.action == "closed" {
+        // `github.close_issue_on_done` closes issues once their run is over
+        if state
+            .take_closed_by_mycelium(&event.repository.full_name, event.issue.number)
+            .await
+        {
+            tracing::debug!(
+                repo = %event.repository.full_name,
+                issue = %event.issue.number,
+                "Issue closed by Mycelium, nothing to cancel"
+            );
+            return StatusCode::OK;
+        }
         tracing::info!(
             repo = %event.repository.full_name,
             issue = %event.issue.number,
@@ -101,7 +199,7 @@ async fn handle_issues_event(
     // Handle label removed — cancel in-flight work (but issue is still open)
     if event.action == "unlabeled" {
         if event.label.as_ref().is_some_and(|l| {
-            l.name == *trigger_label || l.name == research_label
+            github.is_trigger_label(&l.name) || l.name == research_label
         }) {
             tracing::info!(
                 repo = %event.repository.full_name,
@@ -121,23 +219,63 @@ async fn handle_issues_event(
         return StatusCode::OK;
     }
 
-    // We care about "labeled" action where a trigger label was added
-    if event.action != "labeled" {
-        return StatusCode::OK;
+    // A human approved changes held back by `github.require_approval`
+    if event.action == "labeled"
+        && event
+            .label
+            .as_ref()
+            .is_some_and(|l| l.name == state.config.github.approved_label())
+    {
+        let Some(installation_id) = installation_id(state, event.installation.as_ref()) else {
+            tracing::warn!("No installation ID in issues event");
+            return StatusCode::BAD_REQUEST;
+        };
+        tracing::info!(
+            repo = %event.repository.full_name,
+            issue = %event.issue.number,
+            "Changes approved, enqueuing push"
+        );
+        let task = Task::ApproveIssue {
+            installation_id,
+            repo_full_name: event.repository.full_name.clone(),
+            issue_number: event.issue.number,
+            attempt: 0,
+        };
+        if !enqueue(state, task).await {
+            return StatusCode::TOO_MANY_REQUESTS;
+        }
+        return StatusCode::ACCEPTED;
     }
 
-    let added_label = match event.label.as_ref() {
-        Some(l) => &l.name,
-        None => return StatusCode::OK,
-    };
+    let mode = match event.action.as_str() {
+        // A trigger label was added — determine mode from which one
+        "labeled" => {
+            let added_label = match event.label.as_ref() {
+                Some(l) => &l.name,
+                None => return StatusCode::OK,
+            };
 
-    // Determine mode from which label was added
-    let mode = if added_label == &research_label {
-        IssueMode::Research
-    } else if added_label == trigger_label {
-        IssueMode::Implement
-    } else {
-        return StatusCode::OK;
+            if added_label == &research_label {
+                IssueMode::Research
+            } else if github.is_trigger_label(added_label) {
+                IssueMode::Implement
+            } else {
+                return StatusCode::OK;
+            }
+        }
+        // A tracked issue was edited — rerun with the new title/body — or
+        // reopened after being closed, e.g. by mistake
+        "edited" if !edit_restarts_work(state, &event).await => return StatusCode::OK,
+        "edited" | "reopened" => {
+            if event.issue.labels.iter().any(|l| l.name == research_label) {
+                IssueMode::Research
+            } else if event.issue.labels.iter().any(|l| github.is_trigger_label(&l.name)) {
+                IssueMode::Implement
+            } else {
+                return StatusCode::OK;
+            }
+        }
+        _ => return StatusCode::OK,
     };
 
     // Don't process pull requests via the issues event
@@ -145,21 +283,72 @@ async fn handle_issues_event(
         return StatusCode::OK;
     }
 
-    let installation_id = match event.installation.as_ref() {
-        Some(inst) => inst.id,
+    let installation_id = match installation_id(state, event.installation.as_ref()) {
+        Some(id) => id,
         None => {
             tracing::warn!("No installation ID in issues event");
             return StatusCode::BAD_REQUEST;
         }
     };
 
-    tracing::info!(
-        repo = %event.repository.full_name,
-        issue = %event.issue.number,
-        mode = ?mode,
-        "Issue labeled with trigger label, enqueuing task"
+    let priority = Priority::from_labels(
+        event.issue.labels.iter().map(|l| l.name.as_str()),
+        &state.config.github.priority_label(),
+    );
+    let model = model_from_labels(
+        event.issue.labels.iter().map(|l| l.name.as_str()),
+        state.config.github.trigger_label(),
+        &state.config.claude.allowed_models,
     );
 
+    if event.action == "edited" {
+        tracing::info!(
+            repo = %event.repository.full_name,
+            issue = %event.issue.number,
+            mode = ?mode,
+            "Tracked issue edited, restarting with updated description"
+        );
+        // Stop a run working from the stale body; a queued task is replaced
+        // in place by the enqueue below.
+        if state
+            .is_in_flight(&event.repository.full_name, event.issue.number)
+            .await
+        {
+            state
+                .cancel_issue(
+                    &event.repository.full_name,
+                    event.issue.number,
+                    CancellationReason::IssueEdited,
+                )
+                .await;
+        }
+    } else if event.action == "reopened" {
+        tracing::info!(
+            repo = %event.repository.full_name,
+            issue = %event.issue.number,
+            mode = ?mode,
+            "Tracked issue reopened, enqueuing task"
+        );
+        // The close left a cancellation behind that would stop the new run
+        // as soon as it starts; one still winding down keeps it.
+        if !state
+            .is_in_flight(&event.repository.full_name, event.issue.number)
+            .await
+        {
+            state
+                .clear_cancellation(&event.repository.full_name, event.issue.number)
+                .await;
+        }
+    } else {
+        tracing::info!(
+            repo = %event.repository.full_name,
+            issue = %event.issue.number,
+            mode = ?mode,
+            priority = ?priority,
+            "Issue labeled with trigger label, enqueuing task"
+        );
+    }
+
     let task = Task::ResolveIssue {
         installation_id,
         repo_full_name: event.repository.full_name.clone(),
@@ -169,25 +358,75 @@ async fn handle_issues_event(
         issue_title: event.issue.title.clone(),
         issue_body: event.issue.body.clone().unwrap_or_default(),
         mode,
+        priority,
+        model,
+        attempt: 0,
     };
 
-    let mut queue = state.task_queue.write().await;
-    queue.enqueue(&event.repository.full_name, task);
+    if !enqueue(state, task).await {
+        return StatusCode::TOO_MANY_REQUESTS;
+    }
+
+    acknowledge(
+        state,
+        installation_id,
+        &event.repository.full_name,
+        event.issue.number,
+    );
 
     StatusCode::ACCEPTED
 }
 
-async fn handle_issue_comment_event(
-    state: &AppState,
-    event: crate::webhook::events::IssueCommentEvent,
-) -> StatusCode {
+/// The installation an event was delivered for. Repository webhooks used with
+/// a personal access token carry none, so those events belong to the token's
+/// single installation.
+fn installation_id(state: &AppState, installation: Option<&InstallationPayload>) -> Option<u64> {
+    match (installation, state.config.github.auth()) {
+        (Some(installation), _) => Some(installation.id),
+        (None, Some(GitHubAuth::Token(_))) => Some(TOKEN_INSTALLATION_ID),
+        (None, _) => None,
+    }
+}
+
+/// Whether a comment comes from a bot, including Mycelium itself. With a
+/// personal access token Mycelium comments as the token's user, so its own
+/// comments are recognised by the signature they end with.
+fn is_bot_comment(state: &AppState, user: &UserPayload, body: Option<&str>) -> bool {
+    user.user_type == "Bot"
+        || user.login.ends_with("[bot]")
+        || (matches!(state.config.github.auth(), Some(GitHubAuth::Token(_)))
+            && body.is_some_and(|body| body.trim_end().ends_with("Mycelium*")))
+}
+
+/// React with 👀 in the background so the user sees the request was accepted
+/// while it waits in the queue. Never delays the webhook response.
+fn acknowledge(state: &Arc<AppState>, installation_id: u64, repo_full_name: &str, number: u64) {
+    let state = Arc::clone(state);
+    let repo_full_name = repo_full_name.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = state
+            .platform
+            .add_reaction(installation_id, &repo_full_name, number, Reaction::Eyes)
+            .await
+        {
+            tracing::warn!(
+                repo = %repo_full_name,
+                issue = number,
+                error = %e,
+                "Failed to add acknowledgement reaction"
+            );
+        }
+    });
+}
+
+async fn handle_issue_comment_event(state: &Arc<AppState>, event: IssueCommentEvent) -> StatusCode {
     // Only handle new comments on issues (not PRs) that have a trigger label
     if event.action != "created" {
         return StatusCode::OK;
     }
 
     // Ignore comments from bots (including our own) to prevent feedback loops
-    if event.comment.user.user_type == "Bot" || event.comment.user.login.ends_with("[bot]") {
+    if is_bot_comment(state, &event.comment.user, event.comment.body.as_deref()) {
         tracing::debug!(
             user = %event.comment.user.login,
             "Ignoring comment from bot"
@@ -195,15 +434,20 @@ async fn handle_issue_comment_event(
         return StatusCode::OK;
     }
 
-    let installation_id = match event.installation.as_ref() {
-        Some(inst) => inst.id,
+    let installation_id = match installation_id(state, event.installation.as_ref()) {
+        Some(id) => id,
         None => return StatusCode::BAD_REQUEST,
     };
 
+    let comment_body = event.comment.body.clone().unwrap_or_default();
+
+    // `/mycelium <command>` comments are handled here and never treated as feedback
+    if let Some(command) = SlashCommand::parse(&comment_body) {
+        return handle_slash_command(state, installation_id, &event, command).await;
+    }
+
     // PR comment — route to review workflow if the PR branch is a mycelium branch
     if event.issue.pull_request.is_some() {
-        use crate::platform::Platform;
-
         let pr = match state
             .platform
             .get_pull_request(
@@ -221,7 +465,7 @@ async fn handle_issue_comment_event(
         };
 
         // Only respond to comments on PRs created by mycelium
-        if !pr.head_branch.starts_with("mycelium/") {
+        if !state.config.github.is_mycelium_branch(&pr.head_branch) {
             return StatusCode::OK;
         }
 
@@ -231,7 +475,6 @@ async fn handle_issue_comment_event(
             "New comment on mycelium PR, enqueuing review response task"
         );
 
-        let comment_body = event.comment.body.clone().unwrap_or_default();
         let task = Task::RespondToReview {
             installation_id,
             repo_full_name: event.repository.full_name.clone(),
@@ -239,30 +482,41 @@ async fn handle_issue_comment_event(
             pr_number: event.issue.number,
             pr_branch: pr.head_branch,
             review_body: comment_body,
+            attempt: 0,
         };
 
-        let mut queue = state.task_queue.write().await;
-        queue.enqueue(&event.repository.full_name, task);
+        enqueue_debounced(state, task).await;
 
         return StatusCode::ACCEPTED;
     }
 
-    let trigger_label = &state.config.github.trigger_label;
-    let research_label = format!("{trigger_label}:research");
+    let github = &state.config.github;
+    let research_label = format!("{}:research", github.trigger_label());
 
     // Determine mode from labels on the issue
     let mode = if event.issue.labels.iter().any(|l| l.name == research_label) {
         IssueMode::Research
-    } else if event.issue.labels.iter().any(|l| l.name == *trigger_label) {
+    } else if event.issue.labels.iter().any(|l| github.is_trigger_label(&l.name)) {
         IssueMode::Implement
     } else {
         return StatusCode::OK;
     };
 
+    let priority = Priority::from_labels(
+        event.issue.labels.iter().map(|l| l.name.as_str()),
+        &state.config.github.priority_label(),
+    );
+    let model = model_from_labels(
+        event.issue.labels.iter().map(|l| l.name.as_str()),
+        state.config.github.trigger_label(),
+        &state.config.claude.allowed_models,
+    );
+
     tracing::info!(
         repo = %event.repository.full_name,
         issue = %event.issue.number,
         mode = ?mode,
+        priority = ?priority,
         "New comment on tracked issue, enqueuing task"
     );
 
@@ -275,16 +529,219 @@ async fn handle_issue_comment_event(
         issue_title: event.issue.title.clone(),
         issue_body: event.issue.body.clone().unwrap_or_default(),
         mode,
+        priority,
+        model,
+        attempt: 0,
     };
 
-    let mut queue = state.task_queue.write().await;
-    queue.enqueue(&event.repository.full_name, task);
+    if !enqueue(state, task).await {
+        return StatusCode::TOO_MANY_REQUESTS;
+    }
 
     StatusCode::ACCEPTED
 }
 
+/// Act on a `/mycelium` command. On a PR the command applies to the issue the
+/// PR was opened for.
+async fn handle_slash_command(
+    state: &Arc<AppState>,
+    installation_id: u64,
+    event: &IssueCommentEvent,
+    command: SlashCommand,
+) -> StatusCode {
+    let repo_full_name = &event.repository.full_name;
+    let user = &event.comment.user.login;
+
+    if let SlashCommand::Unknown(name) = &command {
+        tracing::debug!(repo = %repo_full_name, command = %name, "Ignoring unknown slash command");
+        return StatusCode::OK;
+    }
+
+    match state
+        .platform
+        .has_write_access(installation_id, repo_full_name, user)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::info!(
+                repo = %repo_full_name,
+                user = %user,
+                command = ?command,
+                "Ignoring slash command from user without write access"
+            );
+            return StatusCode::OK;
+        }
+        Err(e) => {
+            // Failing closed: anyone can comment, but only collaborators may
+            // start or stop runs
+            tracing::warn!(
+                repo = %repo_full_name,
+                user = %user,
+                command = ?command,
+                error = %e,
+                "Could not check collaborator permission, ignoring slash command"
+            );
+            return StatusCode::OK;
+        }
+    }
+
+    // Resolve the issue the command targets
+    let (issue_number, issue_title, issue_body, labels) = if event.issue.pull_request.is_some() {
+        let pr = match state
+            .platform
+            .get_pull_request(installation_id, repo_full_name, event.issue.number)
+            .await
+        {
+            Ok(pr) => pr,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to fetch PR details for slash command");
+                return StatusCode::OK;
+            }
+        };
+        let Some(issue_number) = state
+            .config
+            .github
+            .issue_number_from_branch(&pr.head_branch)
+        else {
+            tracing::debug!(
+                repo = %repo_full_name,
+                pr = event.issue.number,
+                "Ignoring slash command on PR not opened for an issue"
+            );
+            return StatusCode::OK;
+        };
+        match state
+            .platform
+            .get_issue(installation_id, repo_full_name, issue_number)
+            .await
+        {
+            Ok(issue) => (issue.number, issue.title, issue.body, issue.labels),
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    issue = issue_number,
+                    "Failed to fetch issue for slash command"
+                );
+                return StatusCode::OK;
+            }
+        }
+    } else {
+        (
+            event.issue.number,
+            event.issue.title.clone(),
+            event.issue.body.clone().unwrap_or_default(),
+            event.issue.labels.iter().map(|l| l.name.clone()).collect(),
+        )
+    };
+
+    tracing::info!(
+        repo = %repo_full_name,
+        issue = issue_number,
+        user = %user,
+        command = ?command,
+        "Received slash command"
+    );
+
+    let mode = match command {
+        SlashCommand::Stop => {
+            let mut queue = state.task_queue.write().await;
+            queue.cancel_issue(repo_full_name, issue_number);
+            state
+                .cancel_issue(
+                    repo_full_name,
+                    issue_number,
+                    CancellationReason::StopCommand,
+                )
+                .await;
+            acknowledge_comment(state, installation_id, repo_full_name, event.comment.id);
+            return StatusCode::OK;
+        }
+        SlashCommand::Research => IssueMode::Research,
+        SlashCommand::Retry | SlashCommand::Continue => {
+            // Any saved turn-limited run is resumed unless asked to start over
+            if command == SlashCommand::Retry {
+                WorkspaceManager::new(
+                    &state.config.workspace,
+                    state.config.dry_run,
+                    &state.clone_permits,
+                )
+                .discard_saved_conversation(repo_full_name, issue_number)
+                .await;
+            }
+            let research_label = format!("{}:research", state.config.github.trigger_label());
+            if labels.contains(&research_label) {
+                IssueMode::Research
+            } else {
+                IssueMode::Implement
+            }
+        }
+        SlashCommand::Unknown(_) => return StatusCode::OK,
+    };
+
+    // A leftover stop would otherwise cancel the new run as soon as it starts
+    state.clear_cancellation(repo_full_name, issue_number).await;
+
+    let priority = Priority::from_labels(
+        labels.iter().map(String::as_str),
+        &state.config.github.priority_label(),
+    );
+    let model = model_from_labels(
+        labels.iter().map(String::as_str),
+        state.config.github.trigger_label(),
+        &state.config.claude.allowed_models,
+    );
+
+    let task = Task::ResolveIssue {
+        installation_id,
+        repo_full_name: repo_full_name.clone(),
+        clone_url: event.repository.clone_url.clone(),
+        default_branch: event.repository.default_branch.clone(),
+        issue_number,
+        issue_title,
+        issue_body,
+        mode,
+        priority,
+        model,
+        attempt: 0,
+    };
+
+    if !enqueue(state, task).await {
+        return StatusCode::TOO_MANY_REQUESTS;
+    }
+
+    acknowledge_comment(state, installation_id, repo_full_name, event.comment.id);
+
+    StatusCode::ACCEPTED
+}
+
+/// React with 👀 to a command comment in the background.
+fn acknowledge_comment(
+    state: &Arc<AppState>,
+    installation_id: u64,
+    repo_full_name: &str,
+    comment_id: u64,
+) {
+    let state = Arc::clone(state);
+    let repo_full_name = repo_full_name.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = state
+            .platform
+            .add_comment_reaction(installation_id, &repo_full_name, comment_id, Reaction::Eyes)
+            .await
+        {
+            tracing::warn!(
+                repo = %repo_full_name,
+                comment = comment_id,
+                error = %e,
+                "Failed to add acknowledgement reaction"
+            );
+        }
+    });
+}
+
 async fn handle_pr_review_event(
-    state: &AppState,
+    state: &Arc<AppState>,
     event: crate::webhook::events::PullRequestReviewEvent,
 ) -> StatusCode {
     // Only respond to reviews requesting changes
@@ -296,8 +753,8 @@ async fn handle_pr_review_event(
         return StatusCode::OK;
     }
 
-    let installation_id = match event.installation.as_ref() {
-        Some(inst) => inst.id,
+    let installation_id = match installation_id(state, event.installation.as_ref()) {
+        Some(id) => id,
         None => return StatusCode::BAD_REQUEST,
     };
 
@@ -314,16 +771,71 @@ async fn handle_pr_review_event(
         pr_number: event.pull_request.number,
         pr_branch: event.pull_request.head.ref_name.clone(),
         review_body: event.review.body.clone().unwrap_or_default(),
+        attempt: 0,
     };
 
-    let mut queue = state.task_queue.write().await;
-    queue.enqueue(&event.repository.full_name, task);
+    enqueue_debounced(state, task).await;
+
+    StatusCode::ACCEPTED
+}
+
+/// Validate Mycelium PRs with `agent.test_command` when they are opened or
+/// pushed to, by Mycelium or by a human.
+async fn handle_pull_request_event(state: &AppState, event: PullRequestEvent) -> StatusCode {
+    if !matches!(event.action.as_str(), "opened" | "synchronize") {
+        return StatusCode::OK;
+    }
+
+    if state.config.agent.test_command.is_none() {
+        return StatusCode::OK;
+    }
+
+    let pr = &event.pull_request;
+    if !state.config.github.is_mycelium_branch(&pr.head.ref_name) {
+        return StatusCode::OK;
+    }
+
+    // A fork's branch isn't in the repo we clone
+    if pr
+        .head
+        .repo
+        .as_ref()
+        .is_some_and(|repo| repo.full_name != event.repository.full_name)
+    {
+        return StatusCode::OK;
+    }
+
+    let installation_id = match installation_id(state, event.installation.as_ref()) {
+        Some(id) => id,
+        None => return StatusCode::BAD_REQUEST,
+    };
+
+    tracing::info!(
+        repo = %event.repository.full_name,
+        pr = pr.number,
+        sha = %pr.head.sha,
+        "Mycelium PR updated, enqueuing validation"
+    );
+
+    let task = Task::ValidatePullRequest {
+        installation_id,
+        repo_full_name: event.repository.full_name.clone(),
+        clone_url: event.repository.clone_url.clone(),
+        pr_number: pr.number,
+        pr_branch: pr.head.ref_name.clone(),
+        head_sha: pr.head.sha.clone(),
+        attempt: 0,
+    };
+
+    if !enqueue(state, task).await {
+        return StatusCode::TOO_MANY_REQUESTS;
+    }
 
     StatusCode::ACCEPTED
 }
 
 async fn handle_pr_review_comment_event(
-    state: &AppState,
+    state: &Arc<AppState>,
     event: crate::webhook::events::PullRequestReviewCommentEvent,
 ) -> StatusCode {
     // Only handle new line comments
@@ -332,17 +844,21 @@ async fn handle_pr_review_comment_event(
     }
 
     // Ignore bot comments
-    if event.comment.user.user_type == "Bot" || event.comment.user.login.ends_with("[bot]") {
+    if is_bot_comment(state, &event.comment.user, event.comment.body.as_deref()) {
         return StatusCode::OK;
     }
 
     // Only respond on mycelium branches
-    if !event.pull_request.head.ref_name.starts_with("mycelium/") {
+    if !state
+        .config
+        .github
+        .is_mycelium_branch(&event.pull_request.head.ref_name)
+    {
         return StatusCode::OK;
     }
 
-    let installation_id = match event.installation.as_ref() {
-        Some(inst) => inst.id,
+    let installation_id = match installation_id(state, event.installation.as_ref()) {
+        Some(id) => id,
         None => return StatusCode::BAD_REQUEST,
     };
 
@@ -377,10 +893,758 @@ async fn handle_pr_review_comment_event(
         pr_number: event.pull_request.number,
         pr_branch: event.pull_request.head.ref_name.clone(),
         review_body,
+        attempt: 0,
     };
 
-    let mut queue = state.task_queue.write().await;
-    queue.enqueue(&event.repository.full_name, task);
+    enqueue_debounced(state, task).await;
 
     StatusCode::ACCEPTED
 }
+
+async fn handle_workflow_run_event(state: &AppState, event: WorkflowRunEvent) -> StatusCode {
+    let run = &event.workflow_run;
+    if event.action != "completed" || run.conclusion.as_deref() != Some("failure") {
+        return StatusCode::OK;
+    }
+
+    // Only fix CI on mycelium branches
+    let Some(branch) = run
+        .head_branch
+        .as_deref()
+        .filter(|b| state.config.github.is_mycelium_branch(b))
+    else {
+        return StatusCode::OK;
+    };
+
+    let installation_id = match installation_id(state, event.installation.as_ref()) {
+        Some(id) => id,
+        None => return StatusCode::BAD_REQUEST,
+    };
+
+    let Some(pr_number) = find_check_pr(
+        state,
+        installation_id,
+        &event.repository,
+        branch,
+        &run.pull_requests,
+    )
+    .await
+    else {
+        return StatusCode::OK;
+    };
+
+    if check_fix_exhausted(state, &event.repository.full_name, pr_number).await {
+        return StatusCode::OK;
+    }
+
+    // The job logs are fetched when the task runs: downloading them here
+    // could outlast GitHub's delivery timeout, and the redelivery would be
+    // dropped as a duplicate
+    let name = run.name.as_deref().unwrap_or("workflow");
+    let report = format!("### Workflow `{name}` failed\n\nRun: {}\n", run.html_url);
+
+    enqueue_check_fix(
+        state,
+        installation_id,
+        &event.repository,
+        pr_number,
+        branch,
+        report,
+        vec![run.id],
+    )
+    .await
+}
+
+async fn handle_check_run_event(state: &AppState, event: CheckRunEvent) -> StatusCode {
+    let check = &event.check_run;
+    if event.action != "completed" || check.conclusion.as_deref() != Some("failure") {
+        return StatusCode::OK;
+    }
+
+    // GitHub Actions jobs are handled via workflow_run, which has the full logs
+    if check
+        .app
+        .as_ref()
+        .and_then(|a| a.slug.as_deref())
+        .is_some_and(|slug| slug == "github-actions")
+    {
+        return StatusCode::OK;
+    }
+
+    let Some(branch) = check
+        .check_suite
+        .head_branch
+        .as_deref()
+        .filter(|b| state.config.github.is_mycelium_branch(b))
+    else {
+        return StatusCode::OK;
+    };
+
+    let installation_id = match installation_id(state, event.installation.as_ref()) {
+        Some(id) => id,
+        None => return StatusCode::BAD_REQUEST,
+    };
+
+    let Some(pr_number) = find_check_pr(
+        state,
+        installation_id,
+        &event.repository,
+        branch,
+        &check.pull_requests,
+    )
+    .await
+    else {
+        return StatusCode::OK;
+    };
+
+    if check_fix_exhausted(state, &event.repository.full_name, pr_number).await {
+        return StatusCode::OK;
+    }
+
+    // Third-party checks have no downloadable logs; use the check's own output
+    let output = [
+        check.output.title.as_deref(),
+        check.output.summary.as_deref(),
+        check.output.text.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|s| !s.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n\n");
+
+    let mut report = format!("### Check `{}` failed\n", check.name);
+    if let Some(url) = &check.html_url {
+        report.push_str(&format!("\nDetails: {url}\n"));
+    }
+    if !output.is_empty() {
+        report.push_str(&format!("\n```\n{}\n```\n", tail_log(&output)));
+    }
+
+    enqueue_check_fix(
+        state,
+        installation_id,
+        &event.repository,
+        pr_number,
+        branch,
+        report,
+        Vec::new(),
+    )
+    .await
+}
+
+/// Find the PR a failed check ran for: from the event when GitHub includes it,
+/// otherwise the open PR for the branch.
+async fn find_check_pr(
+    state: &AppState,
+    installation_id: u64,
+    repository: &RepositoryPayload,
+    branch: &str,
+    pull_requests: &[PullRequestMinimal],
+) -> Option<u64> {
+    if let Some(pr) = pull_requests.iter().find(|pr| pr.head.ref_name == branch) {
+        return Some(pr.number);
+    }
+
+    match state
+        .platform
+        .find_open_pull_request(installation_id, &repository.full_name, branch)
+        .await
+    {
+        Ok(pr) => pr.map(|pr| pr.number),
+        Err(e) => {
+            tracing::warn!(branch = %branch, error = %e, "Failed to look up PR for failed check");
+            None
+        }
+    }
+}
+
+/// Whether the PR has used up its CI fix attempts (`agent.max_check_fix_attempts`).
+async fn check_fix_exhausted(state: &AppState, repo_full_name: &str, pr_number: u64) -> bool {
+    let max = state.config.agent.max_check_fix_attempts;
+    let attempts = state.check_fix_attempts(repo_full_name, pr_number).await;
+    if attempts >= max {
+        tracing::info!(
+            repo = %repo_full_name,
+            pr = pr_number,
+            attempts,
+            "CI fix attempts exhausted, leaving failing checks to a human"
+        );
+        return true;
+    }
+    false
+}
+
+async fn enqueue_check_fix(
+    state: &AppState,
+    installation_id: u64,
+    repository: &RepositoryPayload,
+    pr_number: u64,
+    branch: &str,
+    failure_report: String,
+    workflow_run_ids: Vec<u64>,
+) -> StatusCode {
+    tracing::info!(
+        repo = %repository.full_name,
+        pr = pr_number,
+        "Checks failed on mycelium PR, enqueuing fix task"
+    );
+
+    let task = Task::FixCheckFailure {
+        installation_id,
+        repo_full_name: repository.full_name.clone(),
+        clone_url: repository.clone_url.clone(),
+        pr_number,
+        pr_branch: branch.to_string(),
+        failure_report,
+        workflow_run_ids,
+        attempt: 0,
+    };
+
+    if !enqueue(state, task).await {
+        return StatusCode::TOO_MANY_REQUESTS;
+    }
+
+    StatusCode::ACCEPTED
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::platform::mock::MockPlatform;
+
+    const SECRET: &str = "test-secret";
+
+    fn test_state(platform: Arc<MockPlatform>) -> Arc<AppState> {
+        Arc::new(AppState::with_platform(AppConfig::for_tests(), platform))
+    }
+
+    fn signed_headers(event_type: &str, body: &[u8]) -> HeaderMap {
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(body);
+        let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-hub-signature-256", signature.parse().unwrap());
+        headers.insert("x-github-event", event_type.parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_missing_headers_status_codes() {
+        let state = test_state(Arc::new(MockPlatform::new()));
+        let body = br#"{"zen": "Keep it logically awesome."}"#;
+        let status = |headers: HeaderMap| {
+            handle_webhook(State(Arc::clone(&state)), headers, Bytes::from_static(body))
+        };
+
+        // Missing signature
+        let mut headers = signed_headers("ping", body);
+        headers.remove("x-hub-signature-256");
+        assert_eq!(status(headers).await, StatusCode::UNAUTHORIZED);
+
+        // Missing event with a wrong signature: rejected as unauthenticated,
+        // not reported as a missing event
+        let mut headers = signed_headers("ping", b"other body");
+        headers.remove("x-github-event");
+        assert_eq!(status(headers).await, StatusCode::UNAUTHORIZED);
+
+        // Both missing
+        assert_eq!(status(HeaderMap::new()).await, StatusCode::UNAUTHORIZED);
+
+        // Missing event on a correctly signed request
+        let mut headers = signed_headers("ping", body);
+        headers.remove("x-github-event");
+        assert_eq!(status(headers).await, StatusCode::BAD_REQUEST);
+
+        assert_eq!(status(signed_headers("ping", body)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_only_handled_deliveries_are_deduplicated() {
+        let state = test_state(Arc::new(MockPlatform::new()));
+        let status = |body: &'static str| {
+            let mut headers = signed_headers("issues", body.as_bytes());
+            headers.insert("x-github-delivery", "delivery-1".parse().unwrap());
+            handle_webhook(
+                State(Arc::clone(&state)),
+                headers,
+                Bytes::from_static(body.as_bytes()),
+            )
+        };
+        let labeled = r#"{
+            "action": "labeled",
+            "label": { "name": "mycelium" },
+            "issue": {
+                "number": 7,
+                "title": "Fix the parser",
+                "body": null,
+                "labels": [{ "name": "mycelium" }],
+                "user": { "login": "alice", "id": 1 }
+            },
+            "repository": {
+                "id": 1,
+                "full_name": "owner/repo",
+                "clone_url": "https://github.com/owner/repo.git",
+                "default_branch": "main"
+            },
+            "installation": { "id": 42 }
+        }"#;
+
+        // A failed delivery is processed again when redelivered
+        assert_eq!(status("{}").await, StatusCode::BAD_REQUEST);
+        assert_eq!(status("{}").await, StatusCode::BAD_REQUEST);
+        assert_eq!(status(labeled).await, StatusCode::ACCEPTED);
+        // A handled one is not
+        assert_eq!(status(labeled).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_sha1_signature_fallback() {
+        let body = br#"{"zen": "Keep it logically awesome."}"#;
+        let mut mac = Hmac::<sha1::Sha1>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(body);
+        let signature = format!("sha1={}", hex::encode(mac.finalize().into_bytes()));
+        let sha1_headers = || {
+            let mut headers = signed_headers("ping", body);
+            headers.remove("x-hub-signature-256");
+            headers.insert("x-hub-signature", signature.parse().unwrap());
+            headers
+        };
+
+        // SHA-256 only by default
+        let state = test_state(Arc::new(MockPlatform::new()));
+        let status = handle_webhook(State(state), sha1_headers(), Bytes::from_static(body)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let mut config = AppConfig::for_tests();
+        config.github.allow_sha1_signatures = true;
+        let state = Arc::new(AppState::with_platform(
+            config,
+            Arc::new(MockPlatform::new()),
+        ));
+        let status = |headers: HeaderMap| {
+            handle_webhook(State(Arc::clone(&state)), headers, Bytes::from_static(body))
+        };
+        assert_eq!(status(sha1_headers()).await, StatusCode::OK);
+        // A present SHA-256 header is always the one checked
+        let mut headers = signed_headers("ping", b"other body");
+        headers.insert("x-hub-signature", signature.parse().unwrap());
+        assert_eq!(status(headers).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(signed_headers("ping", body)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_labeled_issue_enqueues_resolve_task() {
+        let platform = Arc::new(MockPlatform::new());
+        let state = test_state(Arc::clone(&platform));
+        let body = serde_json::json!({
+            "action": "labeled",
+            "label": { "name": "mycelium" },
+            "issue": {
+                "number": 7,
+                "title": "Fix the parser",
+                "body": "It panics on empty input",
+                "labels": [{ "name": "mycelium" }],
+                "user": { "login": "alice", "id": 1 }
+            },
+            "repository": {
+                "id": 1,
+                "full_name": "owner/repo",
+                "clone_url": "https://github.com/owner/repo.git",
+                "default_branch": "main"
+            },
+            "installation": { "id": 42 }
+        })
+        .to_string();
+
+        let status = handle_webhook(
+            State(Arc::clone(&state)),
+            signed_headers("issues", body.as_bytes()),
+            Bytes::from(body),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let task = state.task_queue.write().await.take_next();
+        match task {
+            Some(Task::ResolveIssue {
+                installation_id,
+                repo_full_name,
+                issue_number,
+                mode,
+                ..
+            }) => {
+                assert_eq!(installation_id, 42);
+                assert_eq!(repo_full_name, "owner/repo");
+                assert_eq!(issue_number, 7);
+                assert_eq!(mode, IssueMode::Implement);
+            }
+            other => panic!("expected a ResolveIssue task, got {other:?}"),
+        }
+
+        // The 👀 acknowledgement is posted in the background
+        let ack = "add_reaction owner/repo#7 Eyes".to_string();
+        for _ in 0..50 {
+            if platform.calls().contains(&ack) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("no acknowledgement reaction, calls: {:?}", platform.calls());
+    }
+
+    #[tokio::test]
+    async fn test_closing_issue_itself_does_not_cancel() {
+        let platform = Arc::new(MockPlatform::new());
+        let state = test_state(Arc::clone(&platform));
+        let body = serde_json::json!({
+            "action": "closed",
+            "issue": {
+                "number": 7,
+                "title": "Fix the parser",
+                "body": null,
+                "labels": [{ "name": "mycelium:done" }],
+                "user": { "login": "alice", "id": 1 }
+            },
+            "repository": {
+                "id": 1,
+                "full_name": "owner/repo",
+                "clone_url": "https://github.com/owner/repo.git",
+                "default_branch": "main"
+            },
+            "installation": { "id": 42 }
+        })
+        .to_string();
+        let deliver = || {
+            handle_webhook(
+                State(Arc::clone(&state)),
+                signed_headers("issues", body.as_bytes()),
+                Bytes::from(body.clone()),
+            )
+        };
+
+        state.close_issue(42, "owner/repo", 7).await.unwrap();
+        assert!(platform.calls().contains(&"close_issue owner/repo#7".to_string()));
+        assert_eq!(deliver().await, StatusCode::OK);
+        assert!(!state.is_cancelled("owner/repo", 7).await);
+
+        // A later close, e.g. by a human after reopening, cancels as usual
+        assert_eq!(deliver().await, StatusCode::OK);
+        assert_eq!(
+            state.get_cancellation_reason("owner/repo", 7).await,
+            Some(CancellationReason::IssueClosed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reopened_issue_resumes_if_still_labeled() {
+        let state = test_state(Arc::new(MockPlatform::new()));
+        let deliver = |labels: &[&str]| {
+            let labels: Vec<_> = labels
+                .iter()
+                .map(|name| serde_json::json!({ "name": name }))
+                .collect();
+            let body = serde_json::json!({
+                "action": "reopened",
+                "issue": {
+                    "number": 7,
+                    "title": "Fix the parser",
+                    "body": null,
+                    "labels": labels,
+                    "user": { "login": "alice", "id": 1 }
+                },
+                "repository": {
+                    "id": 1,
+                    "full_name": "owner/repo",
+                    "clone_url": "https://github.com/owner/repo.git",
+                    "default_branch": "main"
+                },
+                "installation": { "id": 42 }
+            })
+            .to_string();
+            handle_webhook(
+                State(Arc::clone(&state)),
+                signed_headers("issues", body.as_bytes()),
+                Bytes::from(body),
+            )
+        };
+
+        assert_eq!(deliver(&["bug"]).await, StatusCode::OK);
+        assert!(state.task_queue.write().await.take_next().is_none());
+
+        // The close that came before must not stop the resumed run
+        state
+            .cancel_issue("owner/repo", 7, CancellationReason::IssueClosed)
+            .await;
+        assert_eq!(deliver(&["mycelium:research"]).await, StatusCode::ACCEPTED);
+        assert!(!state.is_cancelled("owner/repo", 7).await);
+        // A repeated delivery replaces the queued task rather than adding one
+        assert_eq!(deliver(&["mycelium:research"]).await, StatusCode::ACCEPTED);
+
+        let mut queue = state.task_queue.write().await;
+        match queue.take_next() {
+            Some(Task::ResolveIssue {
+                issue_number, mode, ..
+            }) => {
+                assert_eq!(issue_number, 7);
+                assert_eq!(mode, IssueMode::Research);
+            }
+            other => panic!("expected a ResolveIssue task, got {other:?}"),
+        }
+        assert!(queue.take_next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_edit_restarts_only_tracked_work() {
+        let state = test_state(Arc::new(MockPlatform::new()));
+        let deliver = |title: &str, labels: &[&str]| {
+            let labels: Vec<_> = labels
+                .iter()
+                .map(|name| serde_json::json!({ "name": name }))
+                .collect();
+            let body = serde_json::json!({
+                "action": "edited",
+                "issue": {
+                    "number": 7,
+                    "title": title,
+                    "body": null,
+                    "labels": labels,
+                    "user": { "login": "alice", "id": 1 }
+                },
+                "repository": {
+                    "id": 1,
+                    "full_name": "owner/repo",
+                    "clone_url": "https://github.com/owner/repo.git",
+                    "default_branch": "main"
+                },
+                "installation": { "id": 42 }
+            })
+            .to_string();
+            handle_webhook(
+                State(Arc::clone(&state)),
+                signed_headers("issues", body.as_bytes()),
+                Bytes::from(body),
+            )
+        };
+
+        // Nothing running or queued: the trigger label alone doesn't restart
+        assert_eq!(
+            deliver("Fix the parser", &["mycelium"]).await,
+            StatusCode::OK
+        );
+        assert!(state.task_queue.write().await.take_next().is_none());
+
+        // Finished issues and those awaiting approval are never restarted,
+        // even with a run still winding down
+        state.register_in_flight(42, "owner/repo", 7).await;
+        for waiting in ["mycelium:done", "mycelium:needs-approval"] {
+            let status = deliver("Fix the parser", &["mycelium", waiting]).await;
+            assert_eq!(status, StatusCode::OK);
+            assert!(!state.is_cancelled("owner/repo", 7).await);
+        }
+        assert!(state.task_queue.write().await.take_next().is_none());
+
+        // A run in flight is stopped and its task re-enqueued
+        let status = deliver("Fix the lexer", &["mycelium"]).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(
+            state.get_cancellation_reason("owner/repo", 7).await,
+            Some(CancellationReason::IssueEdited)
+        );
+        let task = state.task_queue.write().await.take_next();
+        match task {
+            Some(Task::ResolveIssue { issue_title, .. }) => {
+                assert_eq!(issue_title, "Fix the lexer")
+            }
+            other => panic!("expected a ResolveIssue task, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slash_command_ignored_when_permission_check_fails() {
+        let platform = Arc::new(MockPlatform::new().disconnected());
+        let state = test_state(Arc::clone(&platform));
+        let deliver = |command: &str| {
+            let body = serde_json::json!({
+                "action": "created",
+                "issue": {
+                    "number": 7,
+                    "title": "Fix the parser",
+                    "body": null,
+                    "labels": [{ "name": "mycelium" }],
+                    "user": { "login": "alice", "id": 1 }
+                },
+                "comment": {
+                    "id": 99,
+                    "body": command,
+                    "user": { "login": "mallory", "id": 2 }
+                },
+                "repository": {
+                    "id": 1,
+                    "full_name": "owner/repo",
+                    "clone_url": "https://github.com/owner/repo.git",
+                    "default_branch": "main"
+                },
+                "installation": { "id": 42 }
+            })
+            .to_string();
+            handle_webhook(
+                State(Arc::clone(&state)),
+                signed_headers("issue_comment", body.as_bytes()),
+                Bytes::from(body),
+            )
+        };
+
+        state.register_in_flight(42, "owner/repo", 7).await;
+        assert_eq!(deliver("/mycelium stop").await, StatusCode::OK);
+        assert!(!state.is_cancelled("owner/repo", 7).await);
+
+        state.unregister_in_flight("owner/repo", 7).await;
+        assert_eq!(deliver("/mycelium retry").await, StatusCode::OK);
+        assert!(state.task_queue.write().await.take_next().is_none());
+        assert!(platform
+            .calls()
+            .contains(&"has_write_access owner/repo mallory".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_token_auth_accepts_repository_webhooks() {
+        let mut config = AppConfig::for_tests();
+        let app = AppState::with_platform(config.clone(), Arc::new(MockPlatform::new()));
+        config.github.token = "pat".to_string();
+        let token = AppState::with_platform(config, Arc::new(MockPlatform::new()));
+
+        assert_eq!(installation_id(&app, None), None);
+        assert_eq!(installation_id(&token, None), Some(TOKEN_INSTALLATION_ID));
+        let installation = InstallationPayload { id: 42 };
+        assert_eq!(installation_id(&token, Some(&installation)), Some(42));
+
+        // Mycelium comments as the token's user, so only its signature tells them apart
+        let user = UserPayload {
+            login: "alice".to_string(),
+            id: 1,
+            user_type: "User".to_string(),
+        };
+        let own = Some("Addressed in the latest push.\n\n---\n*Mycelium*\n");
+        assert!(!is_bot_comment(&app, &user, own));
+        assert!(is_bot_comment(&token, &user, own));
+        assert!(!is_bot_comment(&token, &user, Some("Please handle tabs too")));
+    }
+
+    #[tokio::test]
+    async fn test_failed_workflow_run_defers_log_download() {
+        let platform = Arc::new(MockPlatform::new());
+        let state = test_state(Arc::clone(&platform));
+        let body = serde_json::json!({
+            "action": "completed",
+            "workflow_run": {
+                "id": 99,
+                "name": "CI",
+                "head_branch": "mycelium/issue-7",
+                "conclusion": "failure",
+                "html_url": "https://github.com/owner/repo/actions/runs/99",
+                "pull_requests": [{ "number": 12, "head": { "ref": "mycelium/issue-7" } }]
+            },
+            "repository": {
+                "id": 1,
+                "full_name": "owner/repo",
+                "clone_url": "https://github.com/owner/repo.git",
+                "default_branch": "main"
+            },
+            "installation": { "id": 42 }
+        })
+        .to_string();
+
+        let status = handle_webhook(
+            State(Arc::clone(&state)),
+            signed_headers("workflow_run", body.as_bytes()),
+            Bytes::from(body),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert!(platform.calls().is_empty(), "calls: {:?}", platform.calls());
+
+        let task = state.task_queue.write().await.take_next();
+        match task {
+            Some(Task::FixCheckFailure {
+                pr_number,
+                failure_report,
+                workflow_run_ids,
+                ..
+            }) => {
+                assert_eq!(pr_number, 12);
+                assert!(failure_report.contains("actions/runs/99"));
+                assert_eq!(workflow_run_ids, [99]);
+            }
+            other => panic!("expected a FixCheckFailure task, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_push_to_mycelium_pr_enqueues_validation() {
+        let mut config = AppConfig::for_tests();
+        config.agent.test_command = Some("cargo test".to_string());
+        let state = Arc::new(AppState::with_platform(config, Arc::new(MockPlatform::new())));
+        let event = |branch: &str, head_repo: &str| {
+            serde_json::json!({
+                "action": "synchronize",
+                "pull_request": {
+                    "number": 12,
+                    "title": "Fix #7",
+                    "body": null,
+                    "head": {
+                        "ref": branch,
+                        "sha": "0123456789abcdef",
+                        "repo": { "full_name": head_repo }
+                    },
+                    "base": { "ref": "main", "sha": "fedcba9876543210" },
+                    "user": { "login": "alice", "id": 1 }
+                },
+                "repository": {
+                    "id": 1,
+                    "full_name": "owner/repo",
+                    "clone_url": "https://github.com/owner/repo.git",
+                    "default_branch": "main"
+                },
+                "installation": { "id": 42 }
+            })
+            .to_string()
+        };
+        let deliver = |body: String| {
+            handle_webhook(
+                State(Arc::clone(&state)),
+                signed_headers("pull_request", body.as_bytes()),
+                Bytes::from(body),
+            )
+        };
+
+        // Other branches and fork PRs are left alone
+        assert_eq!(deliver(event("feature/x", "owner/repo")).await, StatusCode::OK);
+        assert_eq!(deliver(event("mycelium/issue-7", "fork/repo")).await, StatusCode::OK);
+        assert!(state.task_queue.write().await.take_next().is_none());
+
+        let status = deliver(event("mycelium/issue-7", "owner/repo")).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let task = state.task_queue.write().await.take_next();
+        match task {
+            Some(Task::ValidatePullRequest {
+                pr_number,
+                pr_branch,
+                head_sha,
+                ..
+            }) => {
+                assert_eq!(pr_number, 12);
+                assert_eq!(pr_branch, "mycelium/issue-7");
+                assert_eq!(head_sha, "0123456789abcdef");
+            }
+            other => panic!("expected a ValidatePullRequest task, got {other:?}"),
+        }
+    }
+}
@@ -1,3 +1,6 @@
+pub mod commands;
+pub mod delivery;
 pub mod events;
+pub mod gitlab;
 pub mod handler;
 pub mod signature;
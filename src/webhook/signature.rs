@@ -1,28 +1,80 @@
 use hmac::{Hmac, Mac};
+use sha1::Sha1;
 use sha2::Sha256;
 
 use crate::error::{AppError, Result};
+use crate::server::constant_time_eq;
 
 type HmacSha256 = Hmac<Sha256>;
+type HmacSha1 = Hmac<Sha1>;
 
-/// Verify the GitHub webhook HMAC-SHA256 signature.
+/// Verify the GitHub webhook HMAC-SHA256 signature against each of `secrets`,
+/// succeeding if any matches, so a secret can be rotated without downtime.
 ///
 /// GitHub sends the signature in the `X-Hub-Signature-256` header as `sha256=<hex>`.
-pub fn verify_signature(secret: &str, payload: &[u8], signature_header: &str) -> Result<()> {
+/// The MACs are computed before the header is parsed, so a missing or malformed
+/// header takes as long to reject as a wrong signature.
+pub fn verify_signature(secrets: &[String], payload: &[u8], signature_header: &str) -> Result<()> {
+    verify::<HmacSha256>(secrets, payload, signature_header, "sha256=")
+}
+
+/// Verify the legacy HMAC-SHA1 signature GitHub sends in the `X-Hub-Signature`
+/// header as `sha1=<hex>`.
+pub fn verify_signature_sha1(
+    secrets: &[String],
+    payload: &[u8],
+    signature_header: &str,
+) -> Result<()> {
+    verify::<HmacSha1>(secrets, payload, signature_header, "sha1=")
+}
+
+/// Verify the secret token GitLab sends in the `X-Gitlab-Token` header. GitLab
+/// doesn't sign payloads; the token is compared as is.
+pub fn verify_gitlab_token(secret: &str, token_header: &str) -> Result<()> {
+    if secret.is_empty() {
+        return Err(AppError::WebhookVerification(
+            "No GitLab webhook secret configured".to_string(),
+        ));
+    }
+    if !constant_time_eq(token_header.as_bytes(), secret.as_bytes()) {
+        return Err(AppError::WebhookVerification("Token mismatch".to_string()));
+    }
+    Ok(())
+}
+
+fn verify<M: Mac + hmac::digest::KeyInit>(
+    secrets: &[String],
+    payload: &[u8],
+    signature_header: &str,
+    prefix: &str,
+) -> Result<()> {
+    let macs = secrets
+        .iter()
+        .map(|secret| {
+            let mut mac = <M as Mac>::new_from_slice(secret.as_bytes())
+                .map_err(|e| AppError::WebhookVerification(format!("Invalid HMAC key: {e}")))?;
+            mac.update(payload);
+            Ok(mac)
+        })
+        .collect::<Result<Vec<M>>>()?;
+
     let signature_hex = signature_header
-        .strip_prefix("sha256=")
-        .ok_or_else(|| AppError::WebhookVerification("Missing sha256= prefix".to_string()))?;
+        .strip_prefix(prefix)
+        .ok_or_else(|| AppError::WebhookVerification(format!("Missing {prefix} prefix")))?;
 
     let signature_bytes = hex::decode(signature_hex)
         .map_err(|e| AppError::WebhookVerification(format!("Invalid hex in signature: {e}")))?;
 
-    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
-        .map_err(|e| AppError::WebhookVerification(format!("Invalid HMAC key: {e}")))?;
-
-    mac.update(payload);
-
-    mac.verify_slice(&signature_bytes)
-        .map_err(|_| AppError::WebhookVerification("Signature mismatch".to_string()))
+    // Check every secret, so the time taken doesn't tell which one matched
+    let matched = macs.into_iter().fold(false, |matched, mac| {
+        mac.verify_slice(&signature_bytes).is_ok() | matched
+    });
+    if !matched {
+        return Err(AppError::WebhookVerification(
+            "Signature mismatch".to_string(),
+        ));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -40,7 +92,7 @@ mod tests {
         let expected_hex = hex::encode(result.into_bytes());
 
         let header = format!("sha256={expected_hex}");
-        assert!(verify_signature(secret, payload, &header).is_ok());
+        assert!(verify_signature(&[secret.to_string()], payload, &header).is_ok());
     }
 
     #[test]
@@ -48,7 +100,7 @@ mod tests {
         let secret = "test-secret";
         let payload = b"hello world";
         let header = "sha256=0000000000000000000000000000000000000000000000000000000000000000";
-        assert!(verify_signature(secret, payload, header).is_err());
+        assert!(verify_signature(&[secret.to_string()], payload, header).is_err());
     }
 
     #[test]
@@ -56,6 +108,47 @@ mod tests {
         let secret = "test-secret";
         let payload = b"hello world";
         let header = "abcdef1234567890";
-        assert!(verify_signature(secret, payload, header).is_err());
+        assert!(verify_signature(&[secret.to_string()], payload, header).is_err());
+    }
+
+    #[test]
+    fn test_sha1_signature() {
+        let secret = "test-secret";
+        let payload = b"hello world";
+
+        let mut mac = HmacSha1::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(payload);
+        let expected_hex = hex::encode(mac.finalize().into_bytes());
+
+        let header = format!("sha1={expected_hex}");
+        assert!(verify_signature_sha1(&[secret.to_string()], payload, &header).is_ok());
+        // Neither algorithm accepts the other's header
+        assert!(verify_signature(&[secret.to_string()], payload, &header).is_err());
+        let header = format!("sha256={expected_hex}");
+        assert!(verify_signature_sha1(&[secret.to_string()], payload, &header).is_err());
+    }
+
+    #[test]
+    fn test_any_of_several_secrets_verifies() {
+        let payload = b"hello world";
+        let sign = |secret: &str| {
+            let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+            mac.update(payload);
+            format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+        };
+        let secrets = ["old-secret".to_string(), "new-secret".to_string()];
+
+        assert!(verify_signature(&secrets, payload, &sign("old-secret")).is_ok());
+        assert!(verify_signature(&secrets, payload, &sign("new-secret")).is_ok());
+        assert!(verify_signature(&secrets, payload, &sign("other-secret")).is_err());
+        assert!(verify_signature(&[], payload, &sign("old-secret")).is_err());
+    }
+
+    #[test]
+    fn test_gitlab_token() {
+        assert!(verify_gitlab_token("test-secret", "test-secret").is_ok());
+        assert!(verify_gitlab_token("test-secret", "test-secreT").is_err());
+        assert!(verify_gitlab_token("test-secret", "").is_err());
+        assert!(verify_gitlab_token("", "").is_err());
     }
 }
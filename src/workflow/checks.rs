@@ -0,0 +1,328 @@
+use crate::agent::engine::{AgentEngine, AgentOutcome};
+use crate::agent::prompt;
+use crate::error::Result;
+use crate::server::AppState;
+use crate::task_log;
+use crate::workflow::issue::{diff_too_large_comment, too_many_files_comment};
+use crate::workflow::types::WorkflowOutcome;
+use crate::workspace::{Finalized, WorkspaceManager};
+
+/// Lines kept from the end of each failed job's log.
+const MAX_LOG_LINES: usize = 200;
+
+/// Bytes kept from the end of each failed job's log.
+const MAX_LOG_BYTES: usize = 20_000;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn fix_check_failure(
+    state: &AppState,
+    installation_id: u64,
+    repo_full_name: &str,
+    clone_url: &str,
+    pr_number: u64,
+    pr_branch: &str,
+    failure_report: &str,
+    workflow_run_ids: &[u64],
+) -> Result<WorkflowOutcome> {
+    let platform = &state.platform;
+    let config = &state.config;
+
+    state
+        .record_check_fix_attempt(repo_full_name, pr_number)
+        .await;
+
+    let mut failure_report = failure_report.to_string();
+    for &run_id in workflow_run_ids {
+        append_job_logs(
+            state,
+            installation_id,
+            repo_full_name,
+            run_id,
+            &mut failure_report,
+        )
+        .await;
+    }
+    let failure_report = failure_report.as_str();
+
+    // Get access token for git operations
+    let token = platform.get_access_token(installation_id).await?;
+
+    // Set up workspace (checkout existing branch)
+    let workspace_mgr =
+        WorkspaceManager::new(&config.workspace, config.dry_run, &state.clone_permits);
+    let workspace = workspace_mgr
+        .setup_for_review(clone_url, &token, repo_full_name, pr_branch)
+        .await?;
+
+    // Settings committed in the repo override the global config for this run
+    let repo_config = WorkspaceManager::load_repo_config(&workspace).await;
+    let config = &config.with_repo_overrides(&repo_config);
+
+    let guidelines =
+        WorkspaceManager::load_guidelines(&workspace, &config.agent.guidelines_files).await;
+
+    // Run the agent
+    let engine = AgentEngine::from_config(config, &state.claude_http);
+
+    let system = prompt::system_prompt_for_check_failure(
+        repo_full_name,
+        pr_number,
+        failure_report,
+        guidelines.as_deref(),
+    );
+
+    let initial_message = format!(
+        "CI checks are failing on PR #{pr_number}. Read the failure output and fix the code so the checks pass."
+    );
+
+    // Like reviews, CI fixes have no cancellation mechanism
+    let outcome = engine
+        .run(
+            &system,
+            &workspace.path,
+            &initial_message,
+            || async { false },
+            |_| async {},
+        )
+        .await;
+
+    let result = match outcome {
+        AgentOutcome::Cancelled => {
+            let _ = workspace_mgr.cleanup(&workspace).await;
+            return Ok(WorkflowOutcome::Failed {
+                error: "Cancelled".to_string(),
+            });
+        }
+        AgentOutcome::Completed { summary } => {
+            let commit_msg = format!("fix: address failing checks on PR #{pr_number}\n\n{summary}");
+
+            let token = platform.get_access_token(installation_id).await?;
+            let finalized = workspace_mgr
+                .finalize(
+                    &workspace,
+                    &commit_msg,
+                    &config.git,
+                    config.agent.format_command.as_deref(),
+                    config.agent.max_changed_files,
+                    config.agent.max_diff_lines,
+                    &token,
+                    None,
+                    false,
+                )
+                .await?;
+
+            if let Finalized::TooManyFiles(files) = finalized {
+                let _ = platform
+                    .post_comment(
+                        installation_id,
+                        repo_full_name,
+                        pr_number,
+                        &too_many_files_comment(&files, config.agent.max_changed_files),
+                    )
+                    .await;
+
+                WorkflowOutcome::TooManyChangedFiles { files }
+            } else if let Finalized::TooLarge(lines) = finalized {
+                let _ = platform
+                    .post_comment(
+                        installation_id,
+                        repo_full_name,
+                        pr_number,
+                        &diff_too_large_comment(lines, config.agent.max_diff_lines, &summary),
+                    )
+                    .await;
+                if config.github.label_needs_split {
+                    let _ = platform
+                        .add_label(
+                            installation_id,
+                            repo_full_name,
+                            pr_number,
+                            &config.github.needs_split_label(),
+                        )
+                        .await;
+                }
+
+                WorkflowOutcome::DiffTooLarge {
+                    lines: lines.total(),
+                }
+            } else if finalized == Finalized::Pushed {
+                let _ = platform
+                    .post_comment(
+                        installation_id,
+                        repo_full_name,
+                        pr_number,
+                        &format!("I've pushed a fix for the failing checks.\n\n## Changes Made\n\n{summary}\n\n---\n*Mycelium*"),
+                    )
+                    .await;
+
+                WorkflowOutcome::ChecksFixed
+            } else {
+                let _ = platform
+                    .post_comment(
+                        installation_id,
+                        repo_full_name,
+                        pr_number,
+                        &format!("I looked into the failing checks but didn't find code changes to make.\n\n{summary}\n\n---\n*Mycelium*"),
+                    )
+                    .await;
+
+                WorkflowOutcome::NoChanges
+            }
+        }
+        AgentOutcome::ClarificationNeeded { question } => {
+            let _ = platform
+                .post_comment(
+                    installation_id,
+                    repo_full_name,
+                    pr_number,
+                    &format!("I need some help with the failing checks:\n\n{question}\n\n---\n*Mycelium*"),
+                )
+                .await;
+
+            WorkflowOutcome::ClarificationRequested
+        }
+        AgentOutcome::TurnLimitReached {
+            partial_summary, ..
+        } => {
+            let _ = platform
+                .post_comment(
+                    installation_id,
+                    repo_full_name,
+                    pr_number,
+                    &format!("I wasn't able to fix the failing checks within the allowed number of turns.\n\n{partial_summary}\n\n---\n*Mycelium*"),
+                )
+                .await;
+
+            WorkflowOutcome::Failed {
+                error: "Turn limit reached".to_string(),
+            }
+        }
+        AgentOutcome::RateLimited { message } => {
+            tracing::warn!(pr = pr_number, "Agent hit rate limit");
+            let _ = platform
+                .post_comment(
+                    installation_id,
+                    repo_full_name,
+                    pr_number,
+                    "I hit the Claude API rate limit and had to stop. Please try again later.\n\n---\n*Mycelium*",
+                )
+                .await;
+
+            WorkflowOutcome::Failed {
+                error: format!("Rate limited: {message}"),
+            }
+        }
+        AgentOutcome::Failed { error } => {
+            let _ = platform
+                .post_comment(
+                    installation_id,
+                    repo_full_name,
+                    pr_number,
+                    &format!(
+                        "I encountered an error:\n\n```\n{error}\n```{logs}\n\n---\n*Mycelium*",
+                        logs = task_log::details_block(config.agent.failure_log_lines)
+                    ),
+                )
+                .await;
+
+            WorkflowOutcome::Failed { error }
+        }
+    };
+
+    let failed = matches!(
+        result,
+        WorkflowOutcome::Failed { .. }
+            | WorkflowOutcome::TooManyChangedFiles { .. }
+            | WorkflowOutcome::DiffTooLarge { .. }
+    );
+    let _ = workspace_mgr.release(&workspace, failed).await;
+
+    Ok(result)
+}
+
+/// Add the tails of a workflow run's failed job logs to `report`.
+async fn append_job_logs(
+    state: &AppState,
+    installation_id: u64,
+    repo_full_name: &str,
+    run_id: u64,
+    report: &mut String,
+) {
+    let logs = match state
+        .platform
+        .get_failed_job_logs(installation_id, repo_full_name, run_id)
+        .await
+    {
+        Ok(logs) => logs,
+        Err(e) => {
+            tracing::warn!(run_id, error = %e, "Failed to fetch workflow job logs");
+            Vec::new()
+        }
+    };
+
+    if logs.is_empty() {
+        report.push_str(&format!(
+            "\nThe job logs of run {run_id} could not be fetched.\n"
+        ));
+    }
+    for job in logs {
+        report.push_str(&format!(
+            "\n#### Job `{}`\n\n```\n{}\n```\n",
+            job.name,
+            tail_log(&job.log)
+        ));
+    }
+}
+
+/// Keep the end of a CI log, where failures are usually reported, within
+/// `MAX_LOG_LINES` lines and `MAX_LOG_BYTES` bytes.
+pub fn tail_log(log: &str) -> String {
+    let lines: Vec<&str> = log.lines().collect();
+    let start = lines.len().saturating_sub(MAX_LOG_LINES);
+    let mut tail = lines[start..].join("\n");
+    let mut truncated = start > 0;
+
+    if tail.len() > MAX_LOG_BYTES {
+        let mut cut = tail.len() - MAX_LOG_BYTES;
+        while !tail.is_char_boundary(cut) {
+            cut += 1;
+        }
+        tail = tail[cut..].to_string();
+        truncated = true;
+    }
+
+    if truncated {
+        format!("[... earlier output truncated ...]\n{tail}")
+    } else {
+        tail
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_log_keeps_short_logs() {
+        assert_eq!(tail_log("a\nb\nc"), "a\nb\nc");
+    }
+
+    #[test]
+    fn test_tail_log_keeps_last_lines() {
+        let log = (0..500)
+            .map(|i| format!("line {i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let tail = tail_log(&log);
+        assert!(tail.starts_with("[... earlier output truncated ...]\nline 300\n"));
+        assert!(tail.ends_with("line 499"));
+    }
+
+    #[test]
+    fn test_tail_log_caps_bytes() {
+        let log = "é".repeat(MAX_LOG_BYTES);
+        let tail = tail_log(&log);
+        assert!(tail.len() <= MAX_LOG_BYTES + 40);
+        assert!(tail.starts_with("[... earlier output truncated ...]"));
+    }
+}
@@ -1,12 +1,18 @@
-use crate::agent::engine::{AgentEngine, AgentOutcome};
+use tokio::sync::Mutex;
+
+use crate::agent::claude::{ContentBlock, Message, MessageContent};
+use crate::agent::engine::{AgentEngine, AgentEvent, AgentOutcome, Progress};
 use crate::agent::prompt;
+use crate::config::AppConfig;
 use crate::error::Result;
-use crate::platform::types::CreatePullRequest;
-use crate::platform::Platform;
+use crate::platform::types::{Comment, CreatePullRequest, Issue, PullRequest};
 use crate::queue::task::IssueMode;
 use crate::server::{AppState, CancellationReason};
+use crate::task_log;
 use crate::workflow::types::WorkflowOutcome;
-use crate::workspace::WorkspaceManager;
+use crate::workspace::git::LineChanges;
+use crate::workspace::manager::{PendingApproval, SavedConversation, Workspace};
+use crate::workspace::{Finalized, WorkspaceManager};
 
 pub struct IssueContext<'a> {
     pub state: &'a AppState,
@@ -18,6 +24,8 @@ pub struct IssueContext<'a> {
     pub issue_title: &'a str,
     pub issue_body: &'a str,
     pub mode: IssueMode,
+    /// Model selected by a model label; wins over the global and repo config.
+    pub model: Option<&'a str>,
 }
 
 pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
@@ -31,18 +39,91 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
         issue_title,
         issue_body,
         mode,
+        model,
     } = ctx;
     let platform = &state.platform;
     let config = &state.config;
     let research_only = mode == IssueMode::Research;
 
+    let workspace_mgr =
+        WorkspaceManager::new(&config.workspace, config.dry_run, &state.clone_permits);
+
+    // Stay on the branch of earlier runs: with an `{issue_slug}` template a
+    // renamed issue would otherwise get a new branch, and a second PR
+    let branch = match workspace_mgr
+        .load_issue_branch(repo_full_name, issue_number)
+        .await
+    {
+        Some(branch) => branch,
+        None => {
+            let branch = config.github.issue_branch(issue_number, issue_title);
+            if !research_only {
+                if let Err(e) = workspace_mgr
+                    .save_issue_branch(repo_full_name, issue_number, &branch)
+                    .await
+                {
+                    tracing::warn!(error = %e, "Failed to record issue branch");
+                }
+            }
+            branch
+        }
+    };
+
+    // A re-triggered issue skips the run if its PR is still open, or with
+    // `skip_issues_with_open_pr` off continues on that PR's branch
+    let existing_pr = if research_only {
+        None
+    } else {
+        match platform
+            .find_open_pull_request(installation_id, repo_full_name, &branch)
+            .await
+        {
+            Ok(pr) => pr,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to look up existing PR, opening a new one");
+                None
+            }
+        }
+    };
+
+    if let Some(pr) = existing_pr
+        .as_ref()
+        .filter(|_| config.github.skip_issues_with_open_pr)
+    {
+        tracing::info!(issue = issue_number, pr = pr.number, "PR already open, skipping run");
+        let _ = platform
+            .post_comment(
+                installation_id,
+                repo_full_name,
+                issue_number,
+                &format!(
+                    "PR #{} is already open for this issue, so I haven't started another run. Leave review comments there, or close it and re-add the `{}` label to start over.\n\n---\n*Mycelium*",
+                    pr.number,
+                    config.github.trigger_label()
+                ),
+            )
+            .await;
+        // Mark it done so rescans don't pick it up again
+        let _ = platform
+            .add_label(
+                installation_id,
+                repo_full_name,
+                issue_number,
+                &format!("{}:done", config.github.trigger_label()),
+            )
+            .await;
+        return Ok(WorkflowOutcome::PullRequestAlreadyOpen {
+            pr_number: pr.number,
+        });
+    }
+
     // Add "working" label and register as in-flight
     let _ = platform
         .add_label(
             installation_id,
             repo_full_name,
             issue_number,
-            &format!("{}:working", config.github.trigger_label),
+            &format!("{}:working", config.github.trigger_label()),
         )
         .await;
     state
@@ -54,25 +135,143 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
         .get_issue(installation_id, repo_full_name, issue_number)
         .await?;
 
+    // This run is the follow-up an earlier question or no-change run waited for
+    let needs_info_label = config.github.needs_info_label();
+    if issue.labels.contains(&needs_info_label) {
+        let _ = platform
+            .remove_label(
+                installation_id,
+                repo_full_name,
+                issue_number,
+                &needs_info_label,
+            )
+            .await;
+    }
+
     // Format comments for the prompt
-    let comments_text = issue
-        .comments
-        .iter()
-        .map(|c| format!("**@{}:** {}", c.author, c.body))
-        .collect::<Vec<_>>()
-        .join("\n\n");
+    let comments_text = format_comments(&issue.comments, config.agent.max_issue_comments);
 
     // Get access token for git operations
     let token = platform.get_access_token(installation_id).await?;
 
-    // Set up workspace
-    let workspace_mgr = WorkspaceManager::new(&config.workspace);
-    let workspace = workspace_mgr
-        .setup_for_issue(clone_url, &token, repo_full_name, issue_number)
-        .await?;
+    // Research never pushes, so it reads a tarball of the base branch instead
+    // of cloning. Without a clone the repo's own config can't pick the base.
+    let research_base = if research_only {
+        Some(
+            resolve_base_branch(
+                state,
+                installation_id,
+                repo_full_name,
+                &issue.body,
+                config.github.base_branch.as_deref(),
+                default_branch,
+            )
+            .await,
+        )
+    } else {
+        None
+    };
+    // A run stopped at the turn limit continues in the workspace that still
+    // holds its changes
+    let saved = if research_only {
+        None
+    } else {
+        workspace_mgr
+            .take_saved_conversation(repo_full_name, issue_number)
+            .await
+    };
+    let (workspace, resumed) = match saved {
+        Some((workspace, saved)) => {
+            tracing::info!(
+                issue = issue_number,
+                "Resuming run stopped at the turn limit"
+            );
+            (workspace, Some(saved))
+        }
+        None => {
+            let workspace = match (&research_base, &existing_pr) {
+                (Some(base_branch), _) => {
+                    let tarball_url = platform.tarball_url(repo_full_name, base_branch);
+                    workspace_mgr
+                        .setup_for_research(
+                            &tarball_url,
+                            &token,
+                            repo_full_name,
+                            issue_number,
+                            &branch,
+                        )
+                        .await?
+                }
+                (None, Some(pr)) => {
+                    tracing::info!(
+                        issue = issue_number,
+                        pr = pr.number,
+                        "Reusing branch of existing PR"
+                    );
+                    workspace_mgr
+                        .setup_for_review(clone_url, &token, repo_full_name, &pr.head_branch)
+                        .await?
+                }
+                (None, None) => {
+                    workspace_mgr
+                        .setup_for_issue(clone_url, &token, repo_full_name, issue_number, &branch)
+                        .await?
+                }
+            };
+            (workspace, None)
+        }
+    };
+
+    // Settings committed in the repo override the global config for this run
+    let repo_config = WorkspaceManager::load_repo_config(&workspace).await;
+    let mut run_config = config.with_repo_overrides(&repo_config);
+    if let Some(model) = model {
+        tracing::info!(
+            issue = issue_number,
+            model = model,
+            "Using model from issue label"
+        );
+        run_config.claude.model = model.to_string();
+    }
+    let config = &run_config;
+
+    // Pick the branch the PR will target and branch off it
+    let base_branch = match (research_base, &resumed, &existing_pr) {
+        (Some(base_branch), _, _) => base_branch,
+        (None, Some(saved), _) => saved.base_branch.clone(),
+        (None, None, Some(pr)) => pr.base_branch.clone(),
+        (None, None, None) => {
+            let base_branch = resolve_base_branch(
+                state,
+                installation_id,
+                repo_full_name,
+                &issue.body,
+                config.github.base_branch.as_deref(),
+                default_branch,
+            )
+            .await;
+            workspace_mgr
+                .create_issue_branch(&workspace, &base_branch)
+                .await?;
+            base_branch
+        }
+    };
+
+    let guidelines =
+        WorkspaceManager::load_guidelines(&workspace, &config.agent.guidelines_files).await;
+    let exclude_dirs = &config.agent.search_exclude_dirs;
+    let file_tree = WorkspaceManager::file_tree(&workspace, exclude_dirs).await;
+    let languages = WorkspaceManager::primary_languages(&workspace, exclude_dirs).await;
 
     // Run the agent
-    let engine = AgentEngine::from_config(config);
+    let mut engine = AgentEngine::from_config(config, &state.claude_http);
+    // A resumed run has explored already and may have changed files
+    let post_plan = !research_only
+        && resumed.is_none()
+        && (config.agent.post_plan || issue.labels.contains(&config.github.plan_label()));
+    if post_plan {
+        engine.enable_plan();
+    }
 
     let system = prompt::system_prompt_for_issue(
         repo_full_name,
@@ -81,43 +280,133 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
         issue_body,
         &comments_text,
         research_only,
+        guidelines.as_deref(),
+        &file_tree,
+        &languages,
     );
 
     let initial_message = if research_only {
         format!(
             "Please research issue #{issue_number}: {issue_title}\n\nExplore the repository and report your findings. Do not modify any files."
         )
+    } else if let Some(pr) = &existing_pr {
+        format!(
+            "Please resolve issue #{issue_number}: {issue_title}\n\nPR #{} already exists for this issue and the workspace is checked out on its branch. Review what has been done so far, then make any further changes needed to resolve the issue.",
+            pr.number
+        )
     } else {
         format!(
             "Please resolve issue #{issue_number}: {issue_title}\n\nStart by exploring the repository structure to understand the codebase, then implement the necessary changes."
         )
     };
+    // A re-triggered issue starts from what the last run concluded; a resumed
+    // run carries on from its own conversation instead
+    let previous_summary = if resumed.is_none() {
+        workspace_mgr
+            .load_summary(repo_full_name, issue_number)
+            .await
+    } else {
+        None
+    };
+    let initial_message = match previous_summary {
+        Some(previous) => format!(
+            "{initial_message}\n\nPreviously you concluded:\n\n{previous}\n\nBuild on this, but check it against the current code and issue, which may have changed since."
+        ),
+        None => initial_message,
+    };
+    let initial_message = if post_plan {
+        format!(
+            "{initial_message}\n\nOnce you know your approach, and before changing any files, share it with the submit_plan tool."
+        )
+    } else {
+        initial_message
+    };
 
     let repo_name = repo_full_name.to_string();
-    let outcome = engine
-        .run(&system, &workspace.path, &initial_message, || {
-            let state_ref = &state;
-            let repo_ref = &repo_name;
-            async move { state_ref.is_cancelled(repo_ref, issue_number).await }
-        })
-        .await;
+    let progress_comment = Mutex::new(None);
+    let is_cancelled = || {
+        let state_ref = &state;
+        let repo_ref = &repo_name;
+        async move { state_ref.is_cancelled(repo_ref, issue_number).await }
+    };
+    let on_event = |event| {
+        let progress_comment = &progress_comment;
+        async move {
+            match event {
+                AgentEvent::Progress(progress) => {
+                    report_progress(
+                        state,
+                        installation_id,
+                        repo_full_name,
+                        issue_number,
+                        progress_comment,
+                        progress,
+                    )
+                    .await
+                }
+                AgentEvent::Plan(plan) => {
+                    post_plan_comment(state, installation_id, repo_full_name, issue_number, &plan)
+                        .await
+                }
+            }
+        }
+    };
+    let outcome = match resumed {
+        Some(saved) => {
+            let mut messages = saved.messages;
+            push_user_text(
+                &mut messages,
+                &format!(
+                    "You ran out of turns before finishing, and this is a new session with a fresh turn budget. Your changes so far are still in the workspace. Continue resolving issue #{issue_number} from where you left off. The issue and its comments in the system prompt are up to date and may include new guidance."
+                ),
+            );
+            engine
+                .resume(&system, &workspace.path, messages, is_cancelled, on_event)
+                .await
+        }
+        None => {
+            engine
+                .run(
+                    &system,
+                    &workspace.path,
+                    &initial_message,
+                    is_cancelled,
+                    on_event,
+                )
+                .await
+        }
+    };
+
+    // The outcome gets its own comment; mark the progress comment as stale
+    if let Some(comment_id) = progress_comment.into_inner() {
+        let _ = platform
+            .update_comment(
+                installation_id,
+                repo_full_name,
+                comment_id,
+                "🔧 Finished working on this issue.\n\n---\n*Mycelium*",
+            )
+            .await;
+    }
 
-    // Clear cancellation flag now that we're done
+    // Clear cancellation flag now that we're done, keeping the reason for feedback
+    let cancellation_reason = state
+        .get_cancellation_reason(repo_full_name, issue_number)
+        .await;
     state.clear_cancellation(repo_full_name, issue_number).await;
 
+    let mut conversation_saved = false;
     let result = match outcome {
         AgentOutcome::Cancelled => {
             // Check the cancellation reason to provide appropriate feedback
-            let reason = state
-                .get_cancellation_reason(repo_full_name, issue_number)
-                .await;
+            let reason = cancellation_reason;
 
             let _ = platform
                 .remove_label(
                     installation_id,
                     repo_full_name,
                     issue_number,
-                    &format!("{}:working", config.github.trigger_label),
+                    &format!("{}:working", config.github.trigger_label()),
                 )
                 .await;
 
@@ -137,6 +426,32 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
                         )
                         .await;
                 }
+                Some(CancellationReason::StopCommand) => {
+                    tracing::info!(issue = issue_number, "Task cancelled (stop command)");
+                    let _ = platform
+                        .post_comment(
+                            installation_id,
+                            repo_full_name,
+                            issue_number,
+                            "Task stopped by a `/mycelium stop` command. Comment `/mycelium retry` to restart.\n\n---\n*Mycelium*",
+                        )
+                        .await;
+                }
+                Some(CancellationReason::IssueEdited) => {
+                    tracing::info!(issue = issue_number, "Task cancelled (issue edited)");
+                    // No comment needed - a new run with the updated issue is queued
+                }
+                Some(CancellationReason::AdminRequest) => {
+                    tracing::info!(issue = issue_number, "Task cancelled (admin request)");
+                    let _ = platform
+                        .post_comment(
+                            installation_id,
+                            repo_full_name,
+                            issue_number,
+                            "Task stopped by an operator. Comment `/mycelium retry` to restart.\n\n---\n*Mycelium*",
+                        )
+                        .await;
+                }
                 None => {
                     tracing::info!(issue = issue_number, "Task cancelled (unknown reason)");
                 }
@@ -151,6 +466,9 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
             let error_msg = match reason {
                 Some(CancellationReason::IssueClosed) => "Cancelled (issue closed)",
                 Some(CancellationReason::LabelRemoved) => "Cancelled (label removed)",
+                Some(CancellationReason::StopCommand) => "Cancelled (stop command)",
+                Some(CancellationReason::IssueEdited) => "Cancelled (issue edited)",
+                Some(CancellationReason::AdminRequest) => "Cancelled (admin request)",
                 None => "Cancelled",
             };
             return Ok(WorkflowOutcome::Failed {
@@ -158,6 +476,12 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
             });
         }
         AgentOutcome::Completed { summary } => {
+            if let Err(e) = workspace_mgr
+                .save_summary(repo_full_name, issue_number, &summary)
+                .await
+            {
+                tracing::warn!(error = %e, "Failed to save run summary");
+            }
             if research_only {
                 // Research mode: post findings as a comment, no PR
                 let _ = platform
@@ -174,7 +498,7 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
                         installation_id,
                         repo_full_name,
                         issue_number,
-                        &format!("{}:working", config.github.trigger_label),
+                        &format!("{}:working", config.github.trigger_label()),
                     )
                     .await;
                 let _ = platform
@@ -182,7 +506,7 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
                         installation_id,
                         repo_full_name,
                         issue_number,
-                        &format!("{}:done", config.github.trigger_label),
+                        &format!("{}:done", config.github.trigger_label()),
                     )
                     .await;
 
@@ -193,46 +517,39 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
                     "fix: resolve #{issue_number} - {issue_title}\n\n{summary}"
                 );
 
-                let token = platform.get_access_token(installation_id).await?;
-                let has_changes = workspace_mgr.finalize(&workspace, &commit_msg, &token, true).await?;
+                let publish = PublishContext {
+                    state,
+                    config,
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    issue_title,
+                    issue: &issue,
+                    existing_pr: existing_pr.as_ref(),
+                    head_branch: &workspace.branch,
+                    base_branch: &base_branch,
+                    summary: &summary,
+                };
 
-                if has_changes {
-                    let pr = platform
-                        .create_pull_request(
-                            installation_id,
-                            repo_full_name,
-                            &CreatePullRequest {
-                                title: format!("Fix #{issue_number}: {issue_title}"),
-                                body: format!(
-                                    "Resolves #{issue_number}\n\n## Summary\n\n{summary}\n\n---\n*Automated by Mycelium*"
-                                ),
-                                head_branch: workspace.branch.clone(),
-                                base_branch: default_branch.to_string(),
-                            },
-                        )
+                let too_many =
+                    WorkspaceManager::too_many_changes(&workspace, config.agent.max_changed_files)
                         .await?;
-
-                    let _ = platform
-                        .remove_label(
-                            installation_id,
-                            repo_full_name,
-                            issue_number,
-                            &format!("{}:working", config.github.trigger_label),
-                        )
-                        .await;
-                    let _ = platform
-                        .add_label(
-                            installation_id,
-                            repo_full_name,
-                            issue_number,
-                            &format!("{}:done", config.github.trigger_label),
-                        )
-                        .await;
-
-                    WorkflowOutcome::PullRequestCreated {
-                        pr_number: pr.number,
-                    }
-                } else {
+                if let Some(files) = too_many {
+                    after_publish(&publish, Finalized::TooManyFiles(files)).await?
+                } else if let Some(lines) =
+                    WorkspaceManager::too_large_diff(&workspace, config.agent.max_diff_lines)
+                        .await?
+                {
+                    after_publish(&publish, Finalized::TooLarge(lines)).await?
+                } else if !workspace_mgr
+                    .commit_changes(
+                        &workspace,
+                        &commit_msg,
+                        &config.git,
+                        config.agent.format_command.as_deref(),
+                    )
+                    .await?
+                {
                     let _ = platform
                         .post_comment(
                             installation_id,
@@ -243,6 +560,22 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
                         .await;
 
                     WorkflowOutcome::NoChanges
+                } else if config.github.require_approval {
+                    request_approval(&publish, &workspace_mgr, &workspace).await?
+                } else {
+                    let token = platform.get_access_token(installation_id).await?;
+                    let force = must_force_push(
+                        state,
+                        installation_id,
+                        repo_full_name,
+                        &workspace.branch,
+                        existing_pr.as_ref(),
+                    )
+                    .await;
+                    let finalized = workspace_mgr
+                        .publish(&workspace, &config.git, &token, Some(&base_branch), force)
+                        .await?;
+                    after_publish(&publish, finalized).await?
                 }
             }
         }
@@ -261,19 +594,41 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
                     installation_id,
                     repo_full_name,
                     issue_number,
-                    &format!("{}:working", config.github.trigger_label),
+                    &format!("{}:working", config.github.trigger_label()),
                 )
                 .await;
 
             WorkflowOutcome::ClarificationRequested
         }
-        AgentOutcome::TurnLimitReached { partial_summary } => {
+        AgentOutcome::TurnLimitReached {
+            partial_summary,
+            messages,
+        } => {
+            // Keep the conversation and workspace so the run can be continued
+            if !research_only {
+                let saved = SavedConversation {
+                    base_branch: base_branch.clone(),
+                    messages,
+                };
+                match workspace_mgr.save_conversation(&workspace, &saved).await {
+                    Ok(()) => conversation_saved = true,
+                    Err(e) => tracing::warn!(error = %e, "Failed to save conversation"),
+                }
+            }
+            let continue_hint = if conversation_saved {
+                format!(
+                    "\n\nComment `/mycelium continue` or re-add the `{}` label to pick up where I left off with a fresh turn budget, or comment `/mycelium retry` to start over.",
+                    config.github.trigger_label()
+                )
+            } else {
+                String::new()
+            };
             let _ = platform
                 .post_comment(
                     installation_id,
                     repo_full_name,
                     issue_number,
-                    &format!("I wasn't able to fully resolve this issue within the allowed number of turns.\n\n{partial_summary}\n\n---\n*Mycelium*"),
+                    &format!("I wasn't able to fully resolve this issue within the allowed number of turns.\n\n{partial_summary}{continue_hint}\n\n---\n*Mycelium*"),
                 )
                 .await;
 
@@ -282,7 +637,15 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
                     installation_id,
                     repo_full_name,
                     issue_number,
-                    &format!("{}:working", config.github.trigger_label),
+                    &format!("{}:working", config.github.trigger_label()),
+                )
+                .await;
+            let _ = platform
+                .add_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &config.github.failed_label(),
                 )
                 .await;
 
@@ -306,7 +669,15 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
                     installation_id,
                     repo_full_name,
                     issue_number,
-                    &format!("{}:working", config.github.trigger_label),
+                    &format!("{}:working", config.github.trigger_label()),
+                )
+                .await;
+            let _ = platform
+                .add_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &config.github.failed_label(),
                 )
                 .await;
 
@@ -320,7 +691,10 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
                     installation_id,
                     repo_full_name,
                     issue_number,
-                    &format!("I encountered an error while trying to resolve this issue:\n\n```\n{error}\n```\n\n---\n*Mycelium*"),
+                    &format!(
+                        "I encountered an error while trying to resolve this issue:\n\n```\n{error}\n```{logs}\n\n---\n*Mycelium*",
+                        logs = task_log::details_block(config.agent.failure_log_lines)
+                    ),
                 )
                 .await;
 
@@ -329,7 +703,15 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
                     installation_id,
                     repo_full_name,
                     issue_number,
-                    &format!("{}:working", config.github.trigger_label),
+                    &format!("{}:working", config.github.trigger_label()),
+                )
+                .await;
+            let _ = platform
+                .add_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &config.github.failed_label(),
                 )
                 .await;
 
@@ -337,11 +719,895 @@ pub async fn resolve_issue(ctx: IssueContext<'_>) -> Result<WorkflowOutcome> {
         }
     };
 
-    // Unregister from in-flight tracking and cleanup workspace
+    // A successful run clears the failure marker from earlier attempts
+    let failed_label = config.github.failed_label();
+    let succeeded = matches!(
+        result,
+        WorkflowOutcome::PullRequestCreated { .. }
+            | WorkflowOutcome::PullRequestUpdated { .. }
+            | WorkflowOutcome::ResearchPosted
+            | WorkflowOutcome::NoChanges
+    );
+    if succeeded && issue.labels.contains(&failed_label) {
+        let _ = platform
+            .remove_label(installation_id, repo_full_name, issue_number, &failed_label)
+            .await;
+    }
+    // The issue stays open and labeled, so without a marker every rescan
+    // would ask the same question or find nothing to change again
+    if matches!(
+        result,
+        WorkflowOutcome::ClarificationRequested | WorkflowOutcome::NoChanges
+    ) {
+        let _ = platform
+            .add_label(
+                installation_id,
+                repo_full_name,
+                issue_number,
+                &needs_info_label,
+            )
+            .await;
+    }
+
+    // Unregister from in-flight tracking and cleanup workspace. A cancel that
+    // arrived after the agent finished has nothing left to stop, so drop it
+    // rather than let it cancel the next run.
     state
         .unregister_in_flight(repo_full_name, issue_number)
         .await;
-    let _ = workspace_mgr.cleanup(&workspace).await;
+    state.clear_cancellation(repo_full_name, issue_number).await;
+    let failed = matches!(
+        result,
+        WorkflowOutcome::Failed { .. }
+            | WorkflowOutcome::MergeConflict { .. }
+            | WorkflowOutcome::TooManyChangedFiles { .. }
+            | WorkflowOutcome::DiffTooLarge { .. }
+    );
+    // Changes awaiting approval stay in the workspace until approved, and a
+    // saved conversation until the run is continued
+    if !matches!(result, WorkflowOutcome::AwaitingApproval) && !conversation_saved {
+        let _ = workspace_mgr.release(&workspace, failed).await;
+    }
 
     Ok(result)
 }
+
+/// Add `text` to the conversation as a user message. A trailing user message,
+/// such as the tool results of the last turn, gets it as an extra block so
+/// that roles keep alternating.
+fn push_user_text(messages: &mut Vec<Message>, text: &str) {
+    let block = ContentBlock::Text {
+        text: text.to_string(),
+    };
+    match messages.last_mut() {
+        Some(Message { role, content }) if role == "user" => match content {
+            MessageContent::Blocks(blocks) => blocks.push(block),
+            MessageContent::Text(existing) => {
+                let existing = ContentBlock::Text {
+                    text: std::mem::take(existing),
+                };
+                *content = MessageContent::Blocks(vec![existing, block]);
+            }
+        },
+        _ => messages.push(Message {
+            role: "user".to_string(),
+            content: MessageContent::Text(text.to_string()),
+        }),
+    }
+}
+
+/// Everything needed to open or update the PR for an issue once its changes
+/// are committed.
+struct PublishContext<'a> {
+    state: &'a AppState,
+    config: &'a AppConfig,
+    installation_id: u64,
+    repo_full_name: &'a str,
+    issue_number: u64,
+    issue_title: &'a str,
+    issue: &'a Issue,
+    existing_pr: Option<&'a PullRequest>,
+    head_branch: &'a str,
+    base_branch: &'a str,
+    summary: &'a str,
+}
+
+/// Report the result of pushing an issue's changes: open or update the PR on
+/// success, or explain the conflict with the base branch.
+async fn after_publish(ctx: &PublishContext<'_>, finalized: Finalized) -> Result<WorkflowOutcome> {
+    let PublishContext {
+        state,
+        config,
+        installation_id,
+        repo_full_name,
+        issue_number,
+        issue_title,
+        issue,
+        existing_pr,
+        head_branch,
+        base_branch,
+        summary,
+    } = *ctx;
+    let platform = &state.platform;
+
+    match finalized {
+        Finalized::Pushed => {
+            let pr_outcome = match existing_pr {
+                Some(pr) => {
+                    let _ = platform
+                        .post_comment(
+                            installation_id,
+                            repo_full_name,
+                            pr.number,
+                            &format!("I've pushed further changes for #{issue_number}.\n\n## Summary\n\n{summary}\n\n---\n*Mycelium*"),
+                        )
+                        .await;
+
+                    WorkflowOutcome::PullRequestUpdated {
+                        pr_number: pr.number,
+                    }
+                }
+                None => {
+                    let draft = config.github.open_as_draft
+                        || issue.labels.contains(&config.github.draft_label());
+                    let pr = platform
+                        .create_pull_request(
+                            installation_id,
+                            repo_full_name,
+                            &CreatePullRequest {
+                                title: config.github.pr_title(issue_number, issue_title, summary),
+                                body: config.github.pr_body(issue_number, issue_title, summary),
+                                head_branch: head_branch.to_string(),
+                                base_branch: base_branch.to_string(),
+                                draft,
+                            },
+                        )
+                        .await?;
+
+                    // Drafts wait for a human to mark them ready before review
+                    let reviewers = if draft {
+                        Vec::new()
+                    } else {
+                        config.github.reviewers_for(&issue.assignees)
+                    };
+                    if let Err(e) = platform
+                        .request_reviewers(installation_id, repo_full_name, pr.number, &reviewers)
+                        .await
+                    {
+                        tracing::warn!(
+                            pr = pr.number,
+                            error = %e,
+                            "Failed to request reviewers"
+                        );
+                    }
+
+                    WorkflowOutcome::PullRequestCreated {
+                        pr_number: pr.number,
+                    }
+                }
+            };
+
+            let _ = platform
+                .remove_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &format!("{}:working", config.github.trigger_label()),
+                )
+                .await;
+            let _ = platform
+                .add_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &format!("{}:done", config.github.trigger_label()),
+                )
+                .await;
+
+            if config.github.close_issue_on_done
+                && matches!(pr_outcome, WorkflowOutcome::PullRequestCreated { .. })
+            {
+                if let Err(e) = state
+                    .close_issue(installation_id, repo_full_name, issue_number)
+                    .await
+                {
+                    tracing::warn!(issue = issue_number, error = %e, "Failed to close issue");
+                }
+            }
+
+            Ok(pr_outcome)
+        }
+        Finalized::NoChanges => Ok(WorkflowOutcome::NoChanges),
+        Finalized::Conflicts(paths) => {
+            let files = paths
+                .iter()
+                .map(|path| format!("- `{path}`"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let _ = platform
+                .post_comment(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &format!("My changes conflict with the latest `{base_branch}`, so I didn't push them. Conflicting files:\n\n{files}\n\nPlease resolve the conflicts or re-run once `{base_branch}` has settled.\n\n---\n*Mycelium*"),
+                )
+                .await;
+
+            let _ = platform
+                .remove_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &format!("{}:working", config.github.trigger_label()),
+                )
+                .await;
+            let _ = platform
+                .add_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &config.github.failed_label(),
+                )
+                .await;
+
+            Ok(WorkflowOutcome::MergeConflict { files: paths })
+        }
+        Finalized::TooManyFiles(files) => {
+            let _ = platform
+                .post_comment(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &too_many_files_comment(&files, config.agent.max_changed_files),
+                )
+                .await;
+
+            let _ = platform
+                .remove_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &format!("{}:working", config.github.trigger_label()),
+                )
+                .await;
+            let _ = platform
+                .add_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &config.github.failed_label(),
+                )
+                .await;
+
+            Ok(WorkflowOutcome::TooManyChangedFiles { files })
+        }
+        Finalized::TooLarge(lines) => {
+            let _ = platform
+                .post_comment(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &diff_too_large_comment(lines, config.agent.max_diff_lines, summary),
+                )
+                .await;
+
+            let _ = platform
+                .remove_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &format!("{}:working", config.github.trigger_label()),
+                )
+                .await;
+            let _ = platform
+                .add_label(
+                    installation_id,
+                    repo_full_name,
+                    issue_number,
+                    &config.github.failed_label(),
+                )
+                .await;
+            if config.github.label_needs_split {
+                let _ = platform
+                    .add_label(
+                        installation_id,
+                        repo_full_name,
+                        issue_number,
+                        &config.github.needs_split_label(),
+                    )
+                    .await;
+            }
+
+            Ok(WorkflowOutcome::DiffTooLarge {
+                lines: lines.total(),
+            })
+        }
+    }
+}
+
+/// Comment explaining that a run changed more than `limit` files, listing them.
+pub(crate) fn too_many_files_comment(files: &[String], limit: usize) -> String {
+    const MAX_LISTED_FILES: usize = 100;
+
+    let mut list = files
+        .iter()
+        .take(MAX_LISTED_FILES)
+        .map(|path| format!("- `{path}`"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if files.len() > MAX_LISTED_FILES {
+        list.push_str(&format!("\n- …and {} more", files.len() - MAX_LISTED_FILES));
+    }
+    format!("This run changed {} files, more than the limit of {limit}, so I didn't push anything. Changed files:\n\n{list}\n\nIf the changes are intended, raise `agent.max_changed_files` and re-run.\n\n---\n*Mycelium*", files.len())
+}
+
+/// Comment explaining that a run's diff is over `limit` lines, with the run's
+/// summary so the work isn't lost.
+pub(crate) fn diff_too_large_comment(lines: LineChanges, limit: usize, summary: &str) -> String {
+    format!("This run changed {} lines (+{} -{}), more than the limit of {limit}, so I didn't push anything: a diff that large is hard to review. Consider splitting the work into smaller issues.\n\n## Summary\n\n{summary}\n\nIf the change is intended, raise `agent.max_diff_lines` and re-run.\n\n---\n*Mycelium*", lines.total(), lines.added, lines.removed)
+}
+
+/// Hold committed changes until a human approves them: save the pending state
+/// in the workspace, post the diff and swap the working label for the
+/// needs-approval label. The workspace is kept for `approve_issue`.
+async fn request_approval(
+    ctx: &PublishContext<'_>,
+    workspace_mgr: &WorkspaceManager,
+    workspace: &Workspace,
+) -> Result<WorkflowOutcome> {
+    let platform = &ctx.state.platform;
+    let github = &ctx.config.github;
+
+    workspace_mgr
+        .save_pending_approval(
+            workspace,
+            &PendingApproval {
+                base_branch: ctx.base_branch.to_string(),
+                summary: ctx.summary.to_string(),
+            },
+        )
+        .await?;
+
+    let diff = WorkspaceManager::committed_diff(workspace).await?;
+    let diff = truncate_diff(&diff);
+    let _ = platform
+        .post_comment(
+            ctx.installation_id,
+            ctx.repo_full_name,
+            ctx.issue_number,
+            &format!(
+                "I've finished the changes below but haven't pushed them. Add the `{}` label to push them and open the PR.\n\n## Summary\n\n{}\n\n<details><summary>Diff</summary>\n\n```diff\n{diff}\n```\n\n</details>\n\n---\n*Mycelium*",
+                github.approved_label(),
+                ctx.summary,
+            ),
+        )
+        .await;
+
+    let _ = platform
+        .remove_label(
+            ctx.installation_id,
+            ctx.repo_full_name,
+            ctx.issue_number,
+            &format!("{}:working", github.trigger_label()),
+        )
+        .await;
+    let _ = platform
+        .add_label(
+            ctx.installation_id,
+            ctx.repo_full_name,
+            ctx.issue_number,
+            &github.needs_approval_label(),
+        )
+        .await;
+
+    Ok(WorkflowOutcome::AwaitingApproval)
+}
+
+/// Push changes held for approval and open or update the issue's PR.
+pub async fn approve_issue(
+    state: &AppState,
+    installation_id: u64,
+    repo_full_name: &str,
+    issue_number: u64,
+) -> Result<WorkflowOutcome> {
+    let platform = &state.platform;
+    let github = &state.config.github;
+    let workspace_mgr =
+        WorkspaceManager::new(&state.config.workspace, state.config.dry_run, &state.clone_permits);
+
+    let _ = platform
+        .remove_label(
+            installation_id,
+            repo_full_name,
+            issue_number,
+            &github.approved_label(),
+        )
+        .await;
+
+    let Some((workspace, pending)) = workspace_mgr
+        .load_pending_approval(repo_full_name, issue_number)
+        .await
+    else {
+        tracing::info!(
+            repo = repo_full_name,
+            issue = issue_number,
+            "Approval label added but no changes are awaiting approval"
+        );
+        let _ = platform
+            .post_comment(
+                installation_id,
+                repo_full_name,
+                issue_number,
+                "There are no changes waiting for approval on this issue. Re-add the trigger label to start a new run.\n\n---\n*Mycelium*",
+            )
+            .await;
+        return Ok(WorkflowOutcome::NoChanges);
+    };
+
+    let _ = platform
+        .remove_label(
+            installation_id,
+            repo_full_name,
+            issue_number,
+            &github.needs_approval_label(),
+        )
+        .await;
+
+    let issue = platform
+        .get_issue(installation_id, repo_full_name, issue_number)
+        .await?;
+    let existing_pr = match platform
+        .find_open_pull_request(installation_id, repo_full_name, &workspace.branch)
+        .await
+    {
+        Ok(pr) => pr,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to look up existing PR, opening a new one");
+            None
+        }
+    };
+
+    let repo_config = WorkspaceManager::load_repo_config(&workspace).await;
+    let config = &state.config.with_repo_overrides(&repo_config);
+
+    let token = platform.get_access_token(installation_id).await?;
+    let force = must_force_push(
+        state,
+        installation_id,
+        repo_full_name,
+        &workspace.branch,
+        existing_pr.as_ref(),
+    )
+    .await;
+    let finalized = workspace_mgr
+        .publish(
+            &workspace,
+            &config.git,
+            &token,
+            Some(&pending.base_branch),
+            force,
+        )
+        .await?;
+
+    let result = after_publish(
+        &PublishContext {
+            state,
+            config,
+            installation_id,
+            repo_full_name,
+            issue_number,
+            issue_title: &issue.title,
+            issue: &issue,
+            existing_pr: existing_pr.as_ref(),
+            head_branch: &workspace.branch,
+            base_branch: &pending.base_branch,
+            summary: &pending.summary,
+        },
+        finalized,
+    )
+    .await?;
+
+    let failed = matches!(result, WorkflowOutcome::MergeConflict { .. });
+    let _ = workspace_mgr.release(&workspace, failed).await;
+
+    Ok(result)
+}
+
+/// Cut a diff to fit comfortably in a GitHub comment (65536 characters).
+fn truncate_diff(diff: &str) -> String {
+    const MAX_DIFF_BYTES: usize = 50_000;
+
+    if diff.len() <= MAX_DIFF_BYTES {
+        return diff.trim_end().to_string();
+    }
+    let mut end = MAX_DIFF_BYTES;
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n... diff truncated ...", &diff[..end])
+}
+
+/// Post the progress comment on the first report and edit it on later ones,
+/// so a long run leaves a single comment behind. Failures are only logged.
+async fn report_progress(
+    state: &AppState,
+    installation_id: u64,
+    repo_full_name: &str,
+    issue_number: u64,
+    comment: &Mutex<Option<u64>>,
+    progress: Progress,
+) {
+    let body = format!(
+        "🔧 Working… (turn {}/{})\n\n---\n*Mycelium*",
+        progress.turn, progress.max_turns
+    );
+    let mut comment = comment.lock().await;
+
+    let result = match *comment {
+        Some(comment_id) => {
+            state
+                .platform
+                .update_comment(installation_id, repo_full_name, comment_id, &body)
+                .await
+        }
+        None => state
+            .platform
+            .post_comment(installation_id, repo_full_name, issue_number, &body)
+            .await
+            .map(|comment_id| *comment = Some(comment_id)),
+    };
+
+    if let Err(e) = result {
+        tracing::warn!(
+            issue = issue_number,
+            error = %e,
+            "Failed to report progress"
+        );
+    }
+}
+
+/// Post the plan the agent shared before implementing.
+async fn post_plan_comment(
+    state: &AppState,
+    installation_id: u64,
+    repo_full_name: &str,
+    issue_number: u64,
+    plan: &str,
+) {
+    let body = format!("📋 **Plan**\n\n{plan}\n\n---\n*Mycelium*");
+    if let Err(e) = state
+        .platform
+        .post_comment(installation_id, repo_full_name, issue_number, &body)
+        .await
+    {
+        tracing::warn!(
+            issue = issue_number,
+            error = %e,
+            "Failed to post plan"
+        );
+    }
+}
+
+/// Whether pushing the issue branch has to overwrite the remote one. Without
+/// an open PR the branch is recreated from the base, so a branch left on the
+/// remote by a prior attempt has diverged and is force-pushed. A brand-new
+/// branch, or an open PR's branch the run built on, takes a plain push.
+async fn must_force_push(
+    state: &AppState,
+    installation_id: u64,
+    repo_full_name: &str,
+    branch: &str,
+    existing_pr: Option<&PullRequest>,
+) -> bool {
+    if existing_pr.is_some() {
+        return false;
+    }
+    match state
+        .platform
+        .branch_exists(installation_id, repo_full_name, branch)
+        .await
+    {
+        Ok(exists) => exists,
+        Err(e) => {
+            // Forcing is harmless for a new branch and required for a stale one
+            tracing::warn!(
+                repo = repo_full_name,
+                branch = branch,
+                error = %e,
+                "Failed to check for an existing branch, force-pushing"
+            );
+            true
+        }
+    }
+}
+
+/// Determine the PR base branch: a `Base-Branch:` directive in the issue body
+/// wins over `github.base_branch` (from `.mycelium.toml`, then the global
+/// config), which wins over the repo default. A requested branch that doesn't
+/// exist on the remote falls back to the default.
+async fn resolve_base_branch(
+    state: &AppState,
+    installation_id: u64,
+    repo_full_name: &str,
+    issue_body: &str,
+    configured: Option<&str>,
+    default_branch: &str,
+) -> String {
+    let requested =
+        parse_base_branch_directive(issue_body).or_else(|| configured.map(str::to_string));
+
+    let requested = match requested {
+        Some(b) if b != default_branch => b,
+        _ => return default_branch.to_string(),
+    };
+
+    match state
+        .platform
+        .branch_exists(installation_id, repo_full_name, &requested)
+        .await
+    {
+        Ok(true) => requested,
+        Ok(false) => {
+            tracing::warn!(
+                repo = repo_full_name,
+                branch = %requested,
+                "Requested base branch does not exist, using default branch"
+            );
+            default_branch.to_string()
+        }
+        Err(e) => {
+            tracing::warn!(
+                repo = repo_full_name,
+                branch = %requested,
+                error = %e,
+                "Failed to check base branch, using default branch"
+            );
+            default_branch.to_string()
+        }
+    }
+}
+
+/// Format issue comments for the prompt, keeping at most `max` of them. The
+/// earliest comments (where requirements tend to be settled) and the latest
+/// are kept, with a note where the middle was omitted.
+fn format_comments(comments: &[Comment], max: usize) -> String {
+    let format = |c: &Comment| format!("**@{}:** {}", c.author, c.body);
+
+    if comments.len() <= max {
+        return comments.iter().map(format).collect::<Vec<_>>().join("\n\n");
+    }
+
+    let head = max.div_ceil(2);
+    let tail = max - head;
+    let omitted = comments.len() - max;
+    comments[..head]
+        .iter()
+        .map(format)
+        .chain(std::iter::once(format!(
+            "*[{omitted} comments omitted]*"
+        )))
+        .chain(comments[comments.len() - tail..].iter().map(format))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Extract the branch from a `Base-Branch: <name>` line (case-insensitive key).
+fn parse_base_branch_directive(body: &str) -> Option<String> {
+    body.lines().find_map(|line| {
+        let (key, value) = line.trim().split_once(':')?;
+        if !key.trim().eq_ignore_ascii_case("base-branch") {
+            return None;
+        }
+        let branch = value.split_whitespace().next()?.trim_matches('`');
+        (!branch.is_empty()).then(|| branch.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::platform::mock::MockPlatform;
+
+    #[test]
+    fn test_parse_base_branch_directive() {
+        let body = "Please fix the bug.\n\nBase-Branch: develop\n";
+        assert_eq!(
+            parse_base_branch_directive(body).as_deref(),
+            Some("develop")
+        );
+
+        let body = "base-branch: `release/1.2`";
+        assert_eq!(
+            parse_base_branch_directive(body).as_deref(),
+            Some("release/1.2")
+        );
+    }
+
+    #[test]
+    fn test_format_comments_omits_middle() {
+        let comments: Vec<Comment> = (1..=5)
+            .map(|i| Comment {
+                id: i,
+                author: "user".to_string(),
+                body: format!("comment {i}"),
+            })
+            .collect();
+
+        assert_eq!(format_comments(&comments, 5).matches("**@user:**").count(), 5);
+
+        let text = format_comments(&comments, 3);
+        assert_eq!(
+            text,
+            "**@user:** comment 1\n\n**@user:** comment 2\n\n*[2 comments omitted]*\n\n**@user:** comment 5"
+        );
+    }
+
+    #[test]
+    fn test_parse_base_branch_directive_absent() {
+        assert_eq!(parse_base_branch_directive("No directive here"), None);
+        assert_eq!(parse_base_branch_directive("Base-Branch:"), None);
+        assert_eq!(parse_base_branch_directive("Target branch: develop"), None);
+    }
+
+    #[test]
+    fn test_push_user_text_merges_into_trailing_user_message() {
+        let text = |t: &str| ContentBlock::Text {
+            text: t.to_string(),
+        };
+        let mut messages = vec![
+            Message {
+                role: "assistant".to_string(),
+                content: MessageContent::Blocks(vec![text("Reading files")]),
+            },
+            Message {
+                role: "user".to_string(),
+                content: MessageContent::Blocks(vec![ContentBlock::ToolResult {
+                    tool_use_id: "tool_1".to_string(),
+                    content: "fn main() {}".to_string(),
+                    is_error: None,
+                }]),
+            },
+        ];
+
+        push_user_text(&mut messages, "Continue");
+        assert_eq!(messages.len(), 2);
+        let MessageContent::Blocks(blocks) = &messages[1].content else {
+            panic!("expected blocks");
+        };
+        assert!(matches!(&blocks[1], ContentBlock::Text { text } if text == "Continue"));
+
+        let mut messages = vec![Message {
+            role: "assistant".to_string(),
+            content: MessageContent::Text("Done?".to_string()),
+        }];
+        push_user_text(&mut messages, "Continue");
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].role, "user");
+    }
+
+    #[tokio::test]
+    async fn test_issue_with_open_pr_is_skipped() {
+        let pr = PullRequest {
+            number: 12,
+            title: "Fix #7".to_string(),
+            body: String::new(),
+            head_branch: "fix/7-fix-the-bug".to_string(),
+            base_branch: "main".to_string(),
+        };
+        let platform = Arc::new(MockPlatform::new().with_pull_request("owner/repo", pr));
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::for_tests();
+        config.github.branch_template = "fix/{issue_number}-{issue_slug}".to_string();
+        config.workspace.base_dir = tmp.path().to_path_buf();
+        let state = AppState::with_platform(config, platform.clone());
+
+        let outcome = resolve_issue(IssueContext {
+            state: &state,
+            installation_id: 1,
+            repo_full_name: "owner/repo",
+            clone_url: "https://github.com/owner/repo.git",
+            default_branch: "main",
+            issue_number: 7,
+            issue_title: "Fix the bug",
+            issue_body: "",
+            mode: IssueMode::Implement,
+            model: None,
+        })
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            outcome,
+            WorkflowOutcome::PullRequestAlreadyOpen { pr_number: 12 }
+        ));
+        let calls = platform.calls();
+        assert!(calls
+            .iter()
+            .any(|c| c.starts_with("post_comment owner/repo#7 PR #12 is already open")));
+        assert!(!calls.iter().any(|c| c.starts_with("get_access_token")));
+    }
+
+    #[tokio::test]
+    async fn test_renamed_issue_keeps_its_branch() {
+        let pr = PullRequest {
+            number: 12,
+            title: "Fix #7".to_string(),
+            body: String::new(),
+            head_branch: "fix/7-fix-the-bug".to_string(),
+            base_branch: "main".to_string(),
+        };
+        let platform = Arc::new(MockPlatform::new().with_pull_request("owner/repo", pr));
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::for_tests();
+        config.github.branch_template = "fix/{issue_number}-{issue_slug}".to_string();
+        config.workspace.base_dir = tmp.path().to_path_buf();
+        let state = AppState::with_platform(config, platform.clone());
+        let run = |issue_title| {
+            resolve_issue(IssueContext {
+                state: &state,
+                installation_id: 1,
+                repo_full_name: "owner/repo",
+                clone_url: "https://github.com/owner/repo.git",
+                default_branch: "main",
+                issue_number: 7,
+                issue_title,
+                issue_body: "",
+                mode: IssueMode::Implement,
+                model: None,
+            })
+        };
+
+        // The re-run after a rename still finds the PR of the first run
+        for title in ["Fix the bug", "Fix the parser crash"] {
+            let outcome = run(title).await.unwrap();
+            assert!(
+                matches!(
+                    outcome,
+                    WorkflowOutcome::PullRequestAlreadyOpen { pr_number: 12 }
+                ),
+                "{title}: {outcome:?}"
+            );
+        }
+        let workspace_mgr = WorkspaceManager::new(
+            &state.config.workspace,
+            state.config.dry_run,
+            &state.clone_permits,
+        );
+        assert_eq!(
+            workspace_mgr
+                .load_issue_branch("owner/repo", 7)
+                .await
+                .as_deref(),
+            Some("fix/7-fix-the-bug")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rerun_force_pushes_over_earlier_branch() {
+        let branch = "mycelium/issue-7".to_string();
+        let pr = PullRequest {
+            number: 12,
+            title: "Fix #7".to_string(),
+            body: String::new(),
+            head_branch: branch.clone(),
+            base_branch: "main".to_string(),
+        };
+        let state_with = |platform: MockPlatform| {
+            AppState::with_platform(AppConfig::for_tests(), Arc::new(platform))
+        };
+
+        // First attempt: nothing on the remote yet
+        let state = state_with(MockPlatform::new());
+        assert!(!must_force_push(&state, 1, "owner/repo", &branch, None).await);
+
+        // Re-run after the PR was closed: the old branch is still there
+        let state = state_with(MockPlatform::new().with_branch("owner/repo", &branch));
+        assert!(must_force_push(&state, 1, "owner/repo", &branch, None).await);
+
+        // Re-run continuing an open PR builds on its branch
+        let state = state_with(MockPlatform::new().with_pull_request("owner/repo", pr.clone()));
+        assert!(!must_force_push(&state, 1, "owner/repo", &branch, Some(&pr)).await);
+    }
+}
@@ -1,3 +1,6 @@
+pub mod checks;
 pub mod issue;
+pub mod oneshot;
 pub mod review;
 pub mod types;
+pub mod validate;
@@ -0,0 +1,74 @@
+use crate::error::{AppError, Result};
+use crate::platform::types::InstallationRepo;
+use crate::queue::task::{model_from_labels, IssueMode};
+use crate::server::AppState;
+use crate::workflow::issue::{resolve_issue, IssueContext};
+use crate::workflow::types::WorkflowOutcome;
+
+/// Resolve a single issue directly, bypassing the webhook server and task queue.
+///
+/// Used by the `--once` CLI mode for iterating on prompts and tools locally.
+pub async fn resolve_single_issue(
+    state: &AppState,
+    repo_full_name: &str,
+    issue_number: u64,
+    mode: IssueMode,
+) -> Result<WorkflowOutcome> {
+    let (installation_id, repo) = find_installation_repo(state, repo_full_name).await?;
+
+    let issue = state
+        .platform
+        .get_issue(installation_id, &repo.full_name, issue_number)
+        .await?;
+
+    let model = model_from_labels(
+        issue.labels.iter().map(String::as_str),
+        state.config.github.trigger_label(),
+        &state.config.claude.allowed_models,
+    );
+
+    tracing::info!(
+        repo = %repo.full_name,
+        issue = issue_number,
+        title = %issue.title,
+        ?mode,
+        "Resolving issue (one-shot)"
+    );
+
+    resolve_issue(IssueContext {
+        state,
+        installation_id,
+        repo_full_name: &repo.full_name,
+        clone_url: &repo.clone_url,
+        default_branch: &repo.default_branch,
+        issue_number,
+        issue_title: &issue.title,
+        issue_body: &issue.body,
+        mode,
+        model: model.as_deref(),
+    })
+    .await
+}
+
+/// Find the app installation that has access to `repo_full_name`.
+pub(crate) async fn find_installation_repo(
+    state: &AppState,
+    repo_full_name: &str,
+) -> Result<(u64, InstallationRepo)> {
+    for installation in state.platform.list_installations().await? {
+        let repos = state
+            .platform
+            .list_installation_repos(installation.id)
+            .await?;
+        if let Some(repo) = repos
+            .into_iter()
+            .find(|r| r.full_name.eq_ignore_ascii_case(repo_full_name))
+        {
+            return Ok((installation.id, repo));
+        }
+    }
+
+    Err(AppError::GitHubApi(format!(
+        "No app installation has access to {repo_full_name}"
+    )))
+}
@@ -1,10 +1,20 @@
+use std::collections::HashSet;
+use std::path::Path;
+
 use crate::agent::engine::{AgentEngine, AgentOutcome};
 use crate::agent::prompt;
+use crate::agent::tools::{verified_path, DeniedPaths};
+use crate::config::AgentConfig;
 use crate::error::Result;
-use crate::platform::Platform;
+use crate::platform::types::{Review, ReviewComment};
 use crate::server::AppState;
+use crate::task_log;
+use crate::workflow::issue::{diff_too_large_comment, too_many_files_comment};
 use crate::workflow::types::WorkflowOutcome;
-use crate::workspace::WorkspaceManager;
+use crate::workspace::{Finalized, WorkspaceManager};
+
+/// Lines of the current file shown on each side of a commented line.
+const CONTEXT_LINES: usize = 10;
 
 pub async fn respond_to_review(
     state: &AppState,
@@ -23,39 +33,40 @@ pub async fn respond_to_review(
         .get_reviews(installation_id, repo_full_name, pr_number)
         .await?;
 
-    // Format review comments for the prompt
-    let review_comments_text = reviews
+    // Comments shown to the agent; their threads are resolved once changes are pushed
+    let addressed_comments: HashSet<u64> = reviews
         .iter()
-        .flat_map(|r| {
-            r.comments.iter().map(|c| {
-                let location = match (&c.path, c.line) {
-                    (Some(path), Some(line)) => format!(" ({path}:{line})"),
-                    (Some(path), None) => format!(" ({path})"),
-                    _ => String::new(),
-                };
-                format!("**@{}**{}: {}", c.author, location, c.body)
-            })
-        })
-        .collect::<Vec<_>>()
-        .join("\n\n");
+        .flat_map(|r| r.comments.iter().map(|c| c.id))
+        .collect();
 
     // Get access token for git operations
     let token = platform.get_access_token(installation_id).await?;
 
     // Set up workspace (checkout existing branch)
-    let workspace_mgr = WorkspaceManager::new(&config.workspace);
+    let workspace_mgr =
+        WorkspaceManager::new(&config.workspace, config.dry_run, &state.clone_permits);
     let workspace = workspace_mgr
         .setup_for_review(clone_url, &token, repo_full_name, pr_branch)
         .await?;
 
+    // Settings committed in the repo override the global config for this run
+    let repo_config = WorkspaceManager::load_repo_config(&workspace).await;
+    let config = &config.with_repo_overrides(&repo_config);
+
+    let guidelines =
+        WorkspaceManager::load_guidelines(&workspace, &config.agent.guidelines_files).await;
+
+    let review_comments_text = format_review_threads(&reviews, &workspace.path, &config.agent);
+
     // Run the agent
-    let engine = AgentEngine::from_config(config);
+    let engine = AgentEngine::from_config(config, &state.claude_http);
 
     let system = prompt::system_prompt_for_review(
         repo_full_name,
         pr_number,
         review_body,
         &review_comments_text,
+        guidelines.as_deref(),
     );
 
     let initial_message = format!(
@@ -64,7 +75,13 @@ pub async fn respond_to_review(
 
     // Reviews don't have a cancellation mechanism (PRs stay open), so pass a no-op check
     let outcome = engine
-        .run(&system, &workspace.path, &initial_message, || async { false })
+        .run(
+            &system,
+            &workspace.path,
+            &initial_message,
+            || async { false },
+            |_| async {},
+        )
         .await;
 
     let result = match outcome {
@@ -78,9 +95,55 @@ pub async fn respond_to_review(
             let commit_msg = format!("fix: address review feedback on PR #{pr_number}\n\n{summary}");
 
             let token = platform.get_access_token(installation_id).await?;
-            let has_changes = workspace_mgr.finalize(&workspace, &commit_msg, &token, false).await?;
+            let finalized = workspace_mgr
+                .finalize(
+                    &workspace,
+                    &commit_msg,
+                    &config.git,
+                    config.agent.format_command.as_deref(),
+                    config.agent.max_changed_files,
+                    config.agent.max_diff_lines,
+                    &token,
+                    None,
+                    false,
+                )
+                .await?;
 
-            if has_changes {
+            if let Finalized::TooManyFiles(files) = finalized {
+                let _ = platform
+                    .post_comment(
+                        installation_id,
+                        repo_full_name,
+                        pr_number,
+                        &too_many_files_comment(&files, config.agent.max_changed_files),
+                    )
+                    .await;
+
+                WorkflowOutcome::TooManyChangedFiles { files }
+            } else if let Finalized::TooLarge(lines) = finalized {
+                let _ = platform
+                    .post_comment(
+                        installation_id,
+                        repo_full_name,
+                        pr_number,
+                        &diff_too_large_comment(lines, config.agent.max_diff_lines, &summary),
+                    )
+                    .await;
+                if config.github.label_needs_split {
+                    let _ = platform
+                        .add_label(
+                            installation_id,
+                            repo_full_name,
+                            pr_number,
+                            &config.github.needs_split_label(),
+                        )
+                        .await;
+                }
+
+                WorkflowOutcome::DiffTooLarge {
+                    lines: lines.total(),
+                }
+            } else if finalized == Finalized::Pushed {
                 // Post a comment on the PR
                 let _ = platform
                     .post_comment(
@@ -91,6 +154,16 @@ pub async fn respond_to_review(
                     )
                     .await;
 
+                resolve_addressed_threads(
+                    state,
+                    installation_id,
+                    repo_full_name,
+                    pr_number,
+                    &addressed_comments,
+                    &summary,
+                )
+                .await;
+
                 WorkflowOutcome::ReviewAddressed
             } else {
                 let _ = platform
@@ -117,7 +190,9 @@ pub async fn respond_to_review(
 
             WorkflowOutcome::ClarificationRequested
         }
-        AgentOutcome::TurnLimitReached { partial_summary } => {
+        AgentOutcome::TurnLimitReached {
+            partial_summary, ..
+        } => {
             let _ = platform
                 .post_comment(
                     installation_id,
@@ -152,7 +227,10 @@ pub async fn respond_to_review(
                     installation_id,
                     repo_full_name,
                     pr_number,
-                    &format!("I encountered an error:\n\n```\n{error}\n```\n\n---\n*Mycelium*"),
+                    &format!(
+                        "I encountered an error:\n\n```\n{error}\n```{logs}\n\n---\n*Mycelium*",
+                        logs = task_log::details_block(config.agent.failure_log_lines)
+                    ),
                 )
                 .await;
 
@@ -160,7 +238,227 @@ pub async fn respond_to_review(
         }
     };
 
-    let _ = workspace_mgr.cleanup(&workspace).await;
+    let failed = matches!(
+        result,
+        WorkflowOutcome::Failed { .. }
+            | WorkflowOutcome::TooManyChangedFiles { .. }
+            | WorkflowOutcome::DiffTooLarge { .. }
+    );
+    let _ = workspace_mgr.release(&workspace, failed).await;
 
     Ok(result)
 }
+
+/// Format review comments as threads: each top-level comment with its diff
+/// hunk, its replies in order, and the commented lines as they read now in
+/// the workspace, so the agent sees the whole conversation.
+fn format_review_threads(reviews: &[Review], workspace_root: &Path, agent: &AgentConfig) -> String {
+    let mut comments: Vec<&ReviewComment> = reviews.iter().flat_map(|r| &r.comments).collect();
+    comments.sort_by_key(|c| c.id);
+    let ids: HashSet<u64> = comments.iter().map(|c| c.id).collect();
+    let denied = DeniedPaths::new(&agent.denied_paths);
+
+    comments
+        .iter()
+        // A reply whose thread start isn't listed is shown on its own
+        .filter(|c| c.in_reply_to.is_none_or(|parent| !ids.contains(&parent)))
+        .map(|root| {
+            let location = match (&root.path, root.line) {
+                (Some(path), Some(line)) => format!(" (`{path}` line {line})"),
+                (Some(path), None) => format!(" (`{path}`)"),
+                _ => String::new(),
+            };
+            let mut thread = format!("**@{}**{location}: {}", root.author, root.body);
+            if let Some(hunk) = &root.diff_hunk {
+                thread.push_str(&format!("\n\nDiff context:\n```\n{hunk}\n```"));
+            }
+            for reply in comments.iter().filter(|c| c.in_reply_to == Some(root.id)) {
+                thread.push_str(&format!("\n\n↳ **@{}** replied: {}", reply.author, reply.body));
+            }
+            if let (Some(path), Some(line)) = (&root.path, root.line) {
+                if let Some(excerpt) =
+                    file_excerpt(workspace_root, path, line, &denied, agent.max_file_size_bytes)
+                {
+                    thread.push_str(&format!("\n\n{excerpt}"));
+                }
+            }
+            thread
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n")
+}
+
+/// Up to `CONTEXT_LINES` lines either side of `line` in the workspace copy of
+/// `path`, numbered and with the commented line marked. `None` for files the
+/// agent couldn't read either, or a line past the end of the file.
+fn file_excerpt(
+    workspace_root: &Path,
+    path: &str,
+    line: u64,
+    denied: &DeniedPaths,
+    max_file_size: usize,
+) -> Option<String> {
+    let full_path = verified_path(workspace_root, path, denied).ok()?;
+    if std::fs::metadata(&full_path).ok()?.len() > max_file_size as u64 {
+        return None;
+    }
+    let content = std::fs::read_to_string(&full_path).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+
+    let target = usize::try_from(line).ok()?.checked_sub(1)?;
+    if target >= lines.len() {
+        return None;
+    }
+    let start = target.saturating_sub(CONTEXT_LINES);
+    let end = (target + CONTEXT_LINES + 1).min(lines.len());
+    let numbered = lines[start..end]
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+            let marker = if start + i == target { '>' } else { ' ' };
+            format!("{marker}{:>5} {text}", start + i + 1)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(format!(
+        "Current `{path}` (lines {}-{end}):\n```\n{numbered}\n```",
+        start + 1
+    ))
+}
+
+/// Reply on each unresolved review thread the agent was given and mark it
+/// resolved. Failures are logged; the pushed changes stand either way.
+async fn resolve_addressed_threads(
+    state: &AppState,
+    installation_id: u64,
+    repo_full_name: &str,
+    pr_number: u64,
+    addressed_comments: &HashSet<u64>,
+    summary: &str,
+) {
+    let platform = &state.platform;
+
+    let threads = match platform
+        .list_review_threads(installation_id, repo_full_name, pr_number)
+        .await
+    {
+        Ok(threads) => threads,
+        Err(e) => {
+            tracing::warn!(pr = pr_number, error = %e, "Failed to list review threads");
+            return;
+        }
+    };
+
+    let reply = format!("Addressed in the latest push.\n\n{summary}\n\n---\n*Mycelium*");
+
+    for thread in threads
+        .iter()
+        .filter(|t| !t.is_resolved && addressed_comments.contains(&t.comment_id))
+    {
+        if let Err(e) = platform
+            .reply_to_review_comment(
+                installation_id,
+                repo_full_name,
+                pr_number,
+                thread.comment_id,
+                &reply,
+            )
+            .await
+        {
+            tracing::warn!(
+                pr = pr_number,
+                comment_id = thread.comment_id,
+                error = %e,
+                "Failed to reply to review comment"
+            );
+        }
+
+        if let Err(e) = platform
+            .resolve_review_thread(installation_id, &thread.id)
+            .await
+        {
+            tracing::warn!(
+                pr = pr_number,
+                thread = %thread.id,
+                error = %e,
+                "Failed to resolve review thread"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::platform::types::ReviewState;
+
+    fn comment(id: u64, author: &str, body: &str, in_reply_to: Option<u64>) -> ReviewComment {
+        ReviewComment {
+            id,
+            author: author.to_string(),
+            body: body.to_string(),
+            path: Some("src/lib.rs".to_string()),
+            line: Some(15),
+            diff_hunk: Some("@@ -1,3 +1,3 @@".to_string()),
+            in_reply_to,
+        }
+    }
+
+    fn review(comments: Vec<ReviewComment>) -> Review {
+        Review {
+            id: comments[0].id,
+            author: comments[0].author.clone(),
+            body: String::new(),
+            state: ReviewState::Commented,
+            comments,
+        }
+    }
+
+    #[test]
+    fn test_review_threads_include_replies_and_file_context() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::create_dir(tmp.path().join("src")).unwrap();
+        let source: Vec<String> = (1..=40).map(|i| format!("line {i}")).collect();
+        std::fs::write(tmp.path().join("src/lib.rs"), source.join("\n")).unwrap();
+        std::fs::write(tmp.path().join(".env"), "SECRET=1").unwrap();
+        std::os::unix::fs::symlink("../.env", tmp.path().join("src/settings")).unwrap();
+
+        // Replies arrive as separate single-comment reviews
+        let mut secret = comment(4, "alice", "Remove this", None);
+        secret.path = Some(".env".to_string());
+        secret.line = Some(1);
+        let mut linked = comment(5, "alice", "Drop this file", None);
+        linked.path = Some("src/settings".to_string());
+        linked.line = Some(1);
+        let reviews = vec![
+            review(vec![
+                comment(1, "alice", "Rename this", None),
+                secret,
+                linked,
+            ]),
+            review(vec![comment(2, "mycelium", "Which name?", Some(1))]),
+            review(vec![comment(3, "alice", "`parse_line`", Some(1))]),
+        ];
+
+        let text = format_review_threads(&reviews, tmp.path(), &AppConfig::for_tests().agent);
+        let threads: Vec<&str> = text.split("\n\n---\n\n").collect();
+        assert_eq!(threads.len(), 3);
+
+        let thread = threads[0];
+        assert!(thread.starts_with("**@alice** (`src/lib.rs` line 15): Rename this"));
+        assert!(thread.contains("@@ -1,3 +1,3 @@"));
+        let replies = thread.find("↳ **@mycelium** replied: Which name?").unwrap();
+        assert!(replies < thread.find("↳ **@alice** replied: `parse_line`").unwrap());
+        assert!(thread.contains("Current `src/lib.rs` (lines 5-25):"));
+        assert!(thread.contains(">   15 line 15\n    16 line 16"));
+        assert!(!thread.contains("line 26"));
+
+        // Files the agent may not read are never quoted
+        assert!(threads[1].starts_with("**@alice** (`.env` line 1): Remove this"));
+        assert!(!threads[1].contains("SECRET"));
+        // Not even through a symlink
+        assert!(threads[2].starts_with("**@alice** (`src/settings` line 1): Drop this file"));
+        assert!(!threads[2].contains("SECRET"));
+    }
+}
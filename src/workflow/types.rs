@@ -3,14 +3,57 @@
 pub enum WorkflowOutcome {
     /// Successfully resolved: PR was created.
     PullRequestCreated { pr_number: u64 },
+    /// Re-run pushed new changes to an already-open PR for the issue.
+    PullRequestUpdated { pr_number: u64 },
+    /// The issue already has an open PR, so no run was started.
+    PullRequestAlreadyOpen { pr_number: u64 },
     /// Successfully pushed fixes in response to review.
     ReviewAddressed,
+    /// Pushed a fix for failing CI checks.
+    ChecksFixed,
+    /// `agent.test_command` passed on a PR; result posted.
+    ValidationPassed,
+    /// `agent.test_command` failed on a PR; output posted.
+    ValidationFailed,
     /// Research findings posted as a comment (no PR).
     ResearchPosted,
     /// Agent needs clarification; comment posted on issue.
     ClarificationRequested,
     /// No changes were needed or produced.
     NoChanges,
+    /// Changes are committed locally and wait for a human to approve them.
+    AwaitingApproval,
+    /// Changes conflict with the base branch; nothing was pushed.
+    MergeConflict { files: Vec<String> },
+    /// The run changed more files than `agent.max_changed_files` allows;
+    /// nothing was pushed.
+    TooManyChangedFiles { files: Vec<String> },
+    /// The run added and removed more lines than `agent.max_diff_lines`
+    /// allows; nothing was pushed.
+    DiffTooLarge { lines: usize },
     /// Workflow failed with an error.
     Failed { error: String },
 }
+
+impl WorkflowOutcome {
+    /// Short outcome name, used as a metrics label.
+    pub fn label(&self) -> &'static str {
+        match self {
+            WorkflowOutcome::PullRequestCreated { .. } => "pull_request_created",
+            WorkflowOutcome::PullRequestUpdated { .. } => "pull_request_updated",
+            WorkflowOutcome::PullRequestAlreadyOpen { .. } => "pull_request_already_open",
+            WorkflowOutcome::ReviewAddressed => "review_addressed",
+            WorkflowOutcome::ChecksFixed => "checks_fixed",
+            WorkflowOutcome::ValidationPassed => "validation_passed",
+            WorkflowOutcome::ValidationFailed => "validation_failed",
+            WorkflowOutcome::ResearchPosted => "research_posted",
+            WorkflowOutcome::ClarificationRequested => "clarification_requested",
+            WorkflowOutcome::NoChanges => "no_changes",
+            WorkflowOutcome::AwaitingApproval => "awaiting_approval",
+            WorkflowOutcome::MergeConflict { .. } => "merge_conflict",
+            WorkflowOutcome::TooManyChangedFiles { .. } => "too_many_changed_files",
+            WorkflowOutcome::DiffTooLarge { .. } => "diff_too_large",
+            WorkflowOutcome::Failed { .. } => "failed",
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::server::AppState;
+use crate::workflow::checks::tail_log;
+use crate::workflow::types::WorkflowOutcome;
+use crate::workspace::WorkspaceManager;
+
+/// Longest a test command may run before it counts as failed.
+const TEST_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Run `agent.test_command` on a checkout of a PR's branch and post whether it
+/// passed, with the end of its output on failure.
+pub async fn validate_pull_request(
+    state: &AppState,
+    installation_id: u64,
+    repo_full_name: &str,
+    clone_url: &str,
+    pr_number: u64,
+    pr_branch: &str,
+    head_sha: &str,
+) -> Result<WorkflowOutcome> {
+    let platform = &state.platform;
+    let config = &state.config;
+
+    // The command may have been unset since the task was queued
+    let Some(command) = config.agent.test_command.as_deref() else {
+        return Ok(WorkflowOutcome::NoChanges);
+    };
+
+    let token = platform.get_access_token(installation_id).await?;
+
+    let workspace_mgr =
+        WorkspaceManager::new(&config.workspace, config.dry_run, &state.clone_permits);
+    let workspace = workspace_mgr
+        .setup_for_review(clone_url, &token, repo_full_name, pr_branch)
+        .await?;
+
+    let (passed, output) = run_test_command(&workspace.path, command).await;
+    tracing::info!(
+        repo = repo_full_name,
+        pr = pr_number,
+        passed = passed,
+        "Validation finished"
+    );
+
+    let short_sha = &head_sha[..head_sha.len().min(7)];
+    let body = if passed {
+        format!("✅ `{command}` passed on {short_sha}.\n\n---\n*Mycelium*")
+    } else {
+        format!(
+            "❌ `{command}` failed on {short_sha}.\n\n<details><summary>Output</summary>\n\n```\n{}\n```\n\n</details>\n\n---\n*Mycelium*",
+            tail_log(&output)
+        )
+    };
+    let _ = platform
+        .post_comment(installation_id, repo_full_name, pr_number, &body)
+        .await;
+
+    let _ = workspace_mgr.release(&workspace, !passed).await;
+
+    Ok(if passed {
+        WorkflowOutcome::ValidationPassed
+    } else {
+        WorkflowOutcome::ValidationFailed
+    })
+}
+
+/// Run `command` in `dir`, returning whether it succeeded and its stdout
+/// followed by its stderr.
+async fn run_test_command(dir: &Path, command: &str) -> (bool, String) {
+    tracing::info!(command = command, "Running test command");
+
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+
+    match tokio::time::timeout(TEST_TIMEOUT, child).await {
+        Ok(Ok(output)) => {
+            let mut log = String::from_utf8_lossy(&output.stdout).into_owned();
+            log.push_str(&String::from_utf8_lossy(&output.stderr));
+            (output.status.success(), log)
+        }
+        Ok(Err(e)) => (false, format!("Failed to run test command: {e}")),
+        Err(_) => (
+            false,
+            format!("Timed out after {} minutes", TEST_TIMEOUT.as_secs() / 60),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_test_command_reports_status_and_output() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        let (passed, output) = run_test_command(tmp.path(), "echo ok").await;
+        assert!(passed);
+        assert_eq!(output, "ok\n");
+
+        let (passed, output) = run_test_command(tmp.path(), "echo out; echo err >&2; exit 1").await;
+        assert!(!passed);
+        assert_eq!(output, "out\nerr\n");
+    }
+}
@@ -1,15 +1,19 @@
+use std::io::Write;
 use std::path::Path;
+use std::process::{Command, Stdio};
 
 use git2::{
-    build::RepoBuilder, Cred, FetchOptions, IndexAddOption, PushOptions, RemoteCallbacks,
-    Repository, Signature,
+    build::{CheckoutBuilder, RepoBuilder},
+    Commit, Cred, DiffFormat, DiffOptions, FetchOptions, FetchPrune, IndexAddOption, PushOptions,
+    RemoteCallbacks, Repository, Signature, Status, StatusOptions, Tree,
 };
 
+use crate::config::{GitConfig, SigningFormat};
 use crate::error::{AppError, Result};
 
 /// Validate a branch name to prevent argument injection.
 /// Rejects names starting with `-` as defence in depth.
-fn validate_branch_name(name: &str) -> Result<()> {
+pub(crate) fn validate_branch_name(name: &str) -> Result<()> {
     if name.starts_with('-') {
         return Err(AppError::Git(format!(
             "Invalid branch name (starts with '-'): {name}"
@@ -41,16 +45,22 @@ fn make_push_options(token: &str) -> PushOptions<'_> {
     opts
 }
 
-/// Clone a repository into the target directory.
-///
-/// The remote URL stored in `.git/config` will be the **plain** URL
-/// (no credentials). Authentication is handled via credential callback only.
-pub async fn clone(url: &str, target: &Path, token: &str) -> Result<()> {
+/// Reject non-HTTPS remotes (token auth only works over HTTPS).
+fn validate_clone_url(url: &str) -> Result<()> {
     if !url.starts_with("https://") {
         return Err(AppError::Git(format!(
             "Expected HTTPS clone URL, got: {url}"
         )));
     }
+    Ok(())
+}
+
+/// Clone a repository into the target directory.
+///
+/// The remote URL stored in `.git/config` will be the **plain** URL
+/// (no credentials). Authentication is handled via credential callback only.
+pub async fn clone(url: &str, target: &Path, token: &str) -> Result<()> {
+    validate_clone_url(url)?;
 
     let url = url.to_string();
     let target = target.to_path_buf();
@@ -67,6 +77,71 @@ pub async fn clone(url: &str, target: &Path, token: &str) -> Result<()> {
     .map_err(|e| AppError::Git(format!("Clone task panicked: {e}")))?
 }
 
+/// Create or update a bare mirror of `url` at `mirror_dir`.
+///
+/// All remote branches are fetched into the mirror's `refs/heads/*` (pruning
+/// deleted ones) and `HEAD` follows the remote default branch, so a local clone
+/// of the mirror looks like a fresh clone of the remote.
+pub async fn update_mirror(url: &str, mirror_dir: &Path, token: &str) -> Result<()> {
+    validate_clone_url(url)?;
+
+    let url = url.to_string();
+    let mirror_dir = mirror_dir.to_path_buf();
+    let token = token.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        let repo = if mirror_dir.exists() {
+            Repository::open_bare(&mirror_dir)?
+        } else {
+            Repository::init_bare(&mirror_dir)?
+        };
+
+        // Create or repoint origin (the clone URL may change on repo rename)
+        if repo.find_remote("origin").is_ok() {
+            repo.remote_set_url("origin", &url)?;
+        } else {
+            repo.remote("origin", &url)?;
+        }
+
+        let mut remote = repo.find_remote("origin")?;
+        let mut fetch_opts = make_fetch_options(&token);
+        fetch_opts.prune(FetchPrune::On);
+        remote.fetch(&["+refs/heads/*:refs/heads/*"], Some(&mut fetch_opts), None)?;
+
+        // Remains available after the fetch disconnects
+        let default_branch = remote.default_branch()?;
+        if let Some(name) = default_branch.as_str() {
+            repo.set_head(name)?;
+        }
+
+        Ok(())
+    })
+    .await
+    .map_err(|e| AppError::Git(format!("Mirror update task panicked: {e}")))?
+}
+
+/// Clone a local mirror into `target`, then point `origin` at the real remote.
+///
+/// Remote-tracking refs come from the mirror, so no network access is needed.
+pub async fn clone_from_mirror(mirror_dir: &Path, target: &Path, origin_url: &str) -> Result<()> {
+    validate_clone_url(origin_url)?;
+
+    let mirror_url = mirror_dir
+        .to_str()
+        .ok_or_else(|| AppError::Git("Mirror path is not valid UTF-8".to_string()))?
+        .to_string();
+    let target = target.to_path_buf();
+    let origin_url = origin_url.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        let repo = RepoBuilder::new().clone(&mirror_url, &target)?;
+        repo.remote_set_url("origin", &origin_url)?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| AppError::Git(format!("Mirror clone task panicked: {e}")))?
+}
+
 /// Fetch the full history for a shallow clone (needed for some operations).
 pub async fn unshallow(dir: &Path, token: &str) -> Result<()> {
     let dir = dir.to_path_buf();
@@ -127,17 +202,19 @@ pub async fn fetch_and_checkout(dir: &Path, branch_name: &str, token: &str) -> R
     .map_err(|e| AppError::Git(format!("Fetch-and-checkout task panicked: {e}")))?
 }
 
-/// Create and checkout a new branch.
-pub async fn create_branch(dir: &Path, branch_name: &str) -> Result<()> {
+/// Create and checkout a new branch starting at `origin/<base_branch>`.
+pub async fn create_branch(dir: &Path, branch_name: &str, base_branch: &str) -> Result<()> {
     validate_branch_name(branch_name)?;
+    validate_branch_name(base_branch)?;
 
     let dir = dir.to_path_buf();
     let branch_name = branch_name.to_string();
+    let base_ref = format!("refs/remotes/origin/{base_branch}");
 
     tokio::task::spawn_blocking(move || {
         let repo = Repository::open(&dir)?;
-        let head = repo.head()?;
-        let commit = head.peel_to_commit()?;
+        let base = repo.find_reference(&base_ref)?;
+        let commit = base.peel_to_commit()?;
         repo.branch(&branch_name, &commit, false)?;
         let obj = repo.revparse_single(&format!("refs/heads/{branch_name}"))?;
         repo.checkout_tree(&obj, None)?;
@@ -166,6 +243,22 @@ pub async fn checkout(dir: &Path, branch_name: &str) -> Result<()> {
     .map_err(|e| AppError::Git(format!("Checkout task panicked: {e}")))?
 }
 
+/// Name of the branch checked out in `dir`. Errors if HEAD is detached.
+pub async fn current_branch(dir: &Path) -> Result<String> {
+    let dir = dir.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let repo = Repository::open(&dir)?;
+        let head = repo.find_reference("HEAD")?;
+        head.symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .map(str::to_string)
+            .ok_or_else(|| AppError::Git("HEAD is not on a branch".to_string()))
+    })
+    .await
+    .map_err(|e| AppError::Git(format!("Current-branch task panicked: {e}")))?
+}
+
 /// Stage all changes.
 pub async fn add_all(dir: &Path) -> Result<()> {
     let dir = dir.to_path_buf();
@@ -181,26 +274,173 @@ pub async fn add_all(dir: &Path) -> Result<()> {
     .map_err(|e| AppError::Git(format!("Add-all task panicked: {e}")))?
 }
 
-/// Commit with a message.
-pub async fn commit(dir: &Path, message: &str) -> Result<()> {
+/// Commit with a message, using the configured identity as author and
+/// committer and signing the commit if a signing key is configured.
+pub async fn commit(dir: &Path, message: &str, git: &GitConfig) -> Result<()> {
     let dir = dir.to_path_buf();
     let message = message.to_string();
+    let git = git.clone();
 
     tokio::task::spawn_blocking(move || {
         let repo = Repository::open(&dir)?;
-        let sig = Signature::now("Mycelium Bot", "mycelium[bot]@users.noreply.github.com")?;
         let mut index = repo.index()?;
         let tree_oid = index.write_tree()?;
         let tree = repo.find_tree(tree_oid)?;
         let head = repo.head()?;
         let parent = head.peel_to_commit()?;
-        repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &[&parent])?;
-        Ok(())
+        commit_to_head(&repo, &git, &message, &tree, &[&parent])
     })
     .await
     .map_err(|e| AppError::Git(format!("Commit task panicked: {e}")))?
 }
 
+/// Create a commit on top of HEAD, advancing the checked-out branch.
+fn commit_to_head(
+    repo: &Repository,
+    git: &GitConfig,
+    message: &str,
+    tree: &Tree<'_>,
+    parents: &[&Commit<'_>],
+) -> Result<()> {
+    let sig = Signature::now(&git.author_name, &git.author_email)?;
+
+    let Some(key) = git.signing_key.as_deref() else {
+        repo.commit(Some("HEAD"), &sig, &sig, message, tree, parents)?;
+        return Ok(());
+    };
+
+    let buffer = repo.commit_create_buffer(&sig, &sig, message, tree, parents)?;
+    let content = std::str::from_utf8(&buffer)
+        .map_err(|e| AppError::Git(format!("Commit buffer is not UTF-8: {e}")))?;
+    let signature = sign(content, key, git.signing_format)?;
+    let oid = repo.commit_signed(content, &signature, None)?;
+
+    let summary = message.lines().next().unwrap_or_default();
+    repo.head()?
+        .set_target(oid, &format!("commit: {summary}"))?;
+    Ok(())
+}
+
+/// Produce a detached signature over `payload`, as `git commit -S` would.
+fn sign(payload: &str, key: &str, format: SigningFormat) -> Result<String> {
+    let mut command = match format {
+        SigningFormat::Ssh => {
+            let mut command = Command::new("ssh-keygen");
+            command.args(["-Y", "sign", "-n", "git", "-f", key]);
+            command
+        }
+        SigningFormat::Gpg => {
+            let mut command = Command::new("gpg");
+            command.args(["--batch", "--status-fd=2", "-bsau", key]);
+            command
+        }
+    };
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::Git(format!("Failed to run commit signer: {e}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(payload.as_bytes())
+            .map_err(|e| AppError::Git(format!("Failed to write to commit signer: {e}")))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| AppError::Git(format!("Commit signer failed: {e}")))?;
+
+    if !output.status.success() {
+        return Err(AppError::Git(format!(
+            "Signing commit failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    String::from_utf8(output.stdout)
+        .map_err(|e| AppError::Git(format!("Commit signature is not UTF-8: {e}")))
+}
+
+/// Result of merging a base branch into the current branch.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MergeOutcome {
+    /// The branch already contains the base branch.
+    UpToDate,
+    /// A merge commit was created.
+    Merged,
+    /// The merge conflicts in these paths; the branch is left unchanged.
+    Conflicts(Vec<String>),
+}
+
+/// Fetch `branch_name` from origin into `refs/remotes/origin/<branch_name>`.
+pub async fn fetch_branch(dir: &Path, branch_name: &str, token: &str) -> Result<()> {
+    validate_branch_name(branch_name)?;
+
+    let dir = dir.to_path_buf();
+    let branch_name = branch_name.to_string();
+    let token = token.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        let repo = Repository::open(&dir)?;
+        let mut remote = repo.find_remote("origin")?;
+        let refspec = format!("+refs/heads/{branch_name}:refs/remotes/origin/{branch_name}");
+        let mut fetch_opts = make_fetch_options(&token);
+        remote.fetch(&[&refspec], Some(&mut fetch_opts), None)?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| AppError::Git(format!("Fetch task panicked: {e}")))?
+}
+
+/// Merge `origin/<base_branch>` into the checked-out branch, committing the
+/// merge with the configured identity. The merge is computed in memory, so
+/// on conflicts nothing is written and the conflicting paths are returned.
+pub async fn merge_base(dir: &Path, base_branch: &str, git: &GitConfig) -> Result<MergeOutcome> {
+    validate_branch_name(base_branch)?;
+
+    let dir = dir.to_path_buf();
+    let base_branch = base_branch.to_string();
+    let git = git.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let repo = Repository::open(&dir)?;
+        let base = repo
+            .find_reference(&format!("refs/remotes/origin/{base_branch}"))?
+            .peel_to_commit()?;
+        let head_ref = repo.head()?;
+        let head = head_ref.peel_to_commit()?;
+
+        if head.id() == base.id() || repo.graph_descendant_of(head.id(), base.id())? {
+            return Ok(MergeOutcome::UpToDate);
+        }
+
+        let mut index = repo.merge_commits(&head, &base, None)?;
+        if index.has_conflicts() {
+            let mut paths: Vec<String> = index
+                .conflicts()?
+                .filter_map(|conflict| conflict.ok())
+                .filter_map(|conflict| conflict.our.or(conflict.their).or(conflict.ancestor))
+                .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+                .collect();
+            paths.sort();
+            paths.dedup();
+            return Ok(MergeOutcome::Conflicts(paths));
+        }
+
+        let tree = repo.find_tree(index.write_tree_to(&repo)?)?;
+        let message = format!(
+            "Merge branch '{base_branch}' into {}",
+            head_ref.shorthand().unwrap_or("HEAD")
+        );
+        commit_to_head(&repo, &git, &message, &tree, &[&head, &base])?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        Ok(MergeOutcome::Merged)
+    })
+    .await
+    .map_err(|e| AppError::Git(format!("Merge task panicked: {e}")))?
+}
+
 /// Push the current branch to origin.
 pub async fn push(dir: &Path, branch_name: &str, token: &str) -> Result<()> {
     validate_branch_name(branch_name)?;
@@ -254,6 +494,160 @@ pub async fn has_changes(dir: &Path) -> Result<bool> {
     .map_err(|e| AppError::Git(format!("Has-changes task panicked: {e}")))?
 }
 
+/// How a file in the working tree differs from HEAD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl FileChange {
+    /// Single-letter code as shown by `git status --short`.
+    pub fn code(self) -> char {
+        match self {
+            FileChange::Added => 'A',
+            FileChange::Modified => 'M',
+            FileChange::Deleted => 'D',
+        }
+    }
+}
+
+/// Files changed relative to HEAD, staged or not, sorted by path. Ignored
+/// files are left out.
+pub async fn changed_files(dir: &Path) -> Result<Vec<(String, FileChange)>> {
+    let dir = dir.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let repo = Repository::open(&dir)?;
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        let statuses = repo.statuses(Some(&mut options))?;
+
+        let mut changes: Vec<(String, FileChange)> = statuses
+            .iter()
+            .filter_map(|entry| {
+                let status = entry.status();
+                let change = if status.intersects(Status::WT_NEW | Status::INDEX_NEW) {
+                    FileChange::Added
+                } else if status.intersects(Status::WT_DELETED | Status::INDEX_DELETED) {
+                    FileChange::Deleted
+                } else {
+                    FileChange::Modified
+                };
+                Some((entry.path()?.to_string(), change))
+            })
+            .collect();
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(changes)
+    })
+    .await
+    .map_err(|e| AppError::Git(format!("Changed-files task panicked: {e}")))?
+}
+
+/// Lines added and removed by a set of changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineChanges {
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl LineChanges {
+    pub fn total(self) -> usize {
+        self.added + self.removed
+    }
+}
+
+/// Lines added and removed relative to HEAD, staged or not, counting every
+/// line of an untracked file as added. Ignored files are left out.
+pub async fn changed_lines(dir: &Path) -> Result<LineChanges> {
+    let dir = dir.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let repo = Repository::open(&dir)?;
+        // An unborn HEAD has no tree: everything is new
+        let head_tree = match repo.head() {
+            Ok(head) => Some(head.peel_to_tree()?),
+            Err(_) => None,
+        };
+        let mut options = DiffOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        let diff = repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut options))?;
+        let stats = diff.stats()?;
+        Ok(LineChanges {
+            added: stats.insertions(),
+            removed: stats.deletions(),
+        })
+    })
+    .await
+    .map_err(|e| AppError::Git(format!("Diff stats task panicked: {e}")))?
+}
+
+/// Patch of the changes made by the HEAD commit, relative to its first parent.
+pub async fn head_commit_diff(dir: &Path) -> Result<String> {
+    let dir = dir.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let repo = Repository::open(&dir)?;
+        let head = repo.head()?.peel_to_commit()?;
+        let parent_tree = match head.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&head.tree()?), None)?;
+
+        let mut patch = Vec::new();
+        diff.print(DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin() as u8);
+            }
+            patch.extend_from_slice(line.content());
+            true
+        })?;
+        Ok(String::from_utf8_lossy(&patch).into_owned())
+    })
+    .await
+    .map_err(|e| AppError::Git(format!("Diff task panicked: {e}")))?
+}
+
+/// Contents of `path` (relative to the repository root) as of `rev`, which
+/// may be anything `git rev-parse` accepts. `None` if the file does not exist
+/// at that revision.
+pub async fn file_at_ref(dir: &Path, rev: &str, path: &Path) -> Result<Option<Vec<u8>>> {
+    let dir = dir.to_path_buf();
+    let rev = rev.to_string();
+    let path = path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || {
+        let repo = Repository::open(&dir)?;
+        let tree = repo
+            .revparse_single(&rev)
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|e| AppError::Git(format!("Unknown revision '{rev}': {}", e.message())))?;
+        let entry = match tree.get_path(&path) {
+            Ok(entry) => entry,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let object = entry.to_object(&repo)?;
+        match object.as_blob() {
+            Some(blob) => Ok(Some(blob.content().to_vec())),
+            None => Err(AppError::Git(format!(
+                "{} is not a file at {rev}",
+                path.display()
+            ))),
+        }
+    })
+    .await
+    .map_err(|e| AppError::Git(format!("Read-at-ref task panicked: {e}")))?
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -310,4 +704,166 @@ mod tests {
         let err = result.unwrap_err().to_string();
         assert!(err.contains("Expected HTTPS clone URL"));
     }
+
+    #[test]
+    fn test_clone_from_mirror_sets_origin_and_checks_out_head() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mirror_dir = tmp.path().join("mirror.git");
+        let target = tmp.path().join("work");
+
+        // Build a bare "mirror" with a single commit on main
+        let mirror = Repository::init_bare(&mirror_dir).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        let tree_oid = {
+            let blob = mirror.blob(b"hello").unwrap();
+            let mut builder = mirror.treebuilder(None).unwrap();
+            builder.insert("hello.txt", blob, 0o100644).unwrap();
+            builder.write().unwrap()
+        };
+        let tree = mirror.find_tree(tree_oid).unwrap();
+        mirror
+            .commit(Some("refs/heads/main"), &sig, &sig, "init", &tree, &[])
+            .unwrap();
+        mirror.set_head("refs/heads/main").unwrap();
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(clone_from_mirror(
+            &mirror_dir,
+            &target,
+            "https://github.com/owner/repo.git",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(target.join("hello.txt")).unwrap(),
+            "hello"
+        );
+        let repo = Repository::open(&target).unwrap();
+        let origin = repo.find_remote("origin").unwrap();
+        assert_eq!(origin.url(), Some("https://github.com/owner/repo.git"));
+        assert_eq!(repo.head().unwrap().shorthand(), Some("main"));
+    }
+
+    /// Commit `files` on top of `parent` to `ref_name`, returning the commit.
+    fn commit_files<'r>(
+        repo: &'r Repository,
+        ref_name: &str,
+        parent: Option<&git2::Commit<'r>>,
+        files: &[(&str, &str)],
+    ) -> git2::Commit<'r> {
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        let base_tree = parent.map(|p| p.tree().unwrap());
+        let mut builder = repo.treebuilder(base_tree.as_ref()).unwrap();
+        for (name, content) in files {
+            let blob = repo.blob(content.as_bytes()).unwrap();
+            builder.insert(name, blob, 0o100644).unwrap();
+        }
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let parents: Vec<&git2::Commit> = parent.into_iter().collect();
+        let oid = repo
+            .commit(Some(ref_name), &sig, &sig, "commit", &tree, &parents)
+            .unwrap();
+        repo.find_commit(oid).unwrap()
+    }
+
+    /// A repo on branch `work` forked from `origin/main`, where both sides
+    /// then changed `shared.txt` to the given contents.
+    fn diverged_repo(ours: &str, theirs: &str) -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = Repository::init(tmp.path()).unwrap();
+        let root = commit_files(&repo, "refs/heads/work", None, &[("shared.txt", "base")]);
+        commit_files(
+            &repo,
+            "refs/remotes/origin/main",
+            Some(&root),
+            &[("shared.txt", theirs), ("upstream.txt", "new")],
+        );
+        commit_files(&repo, "refs/heads/work", Some(&root), &[("shared.txt", ours)]);
+        repo.set_head("refs/heads/work").unwrap();
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+        tmp
+    }
+
+    #[tokio::test]
+    async fn test_merge_base_merges_upstream_changes() {
+        let tmp = diverged_repo("base", "base");
+
+        let outcome = merge_base(tmp.path(), "main", &GitConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(outcome, MergeOutcome::Merged);
+        assert_eq!(fs::read_to_string(tmp.path().join("upstream.txt")).unwrap(), "new");
+        let repo = Repository::open(tmp.path()).unwrap();
+        assert_eq!(repo.head().unwrap().peel_to_commit().unwrap().parent_count(), 2);
+        assert_eq!(
+            merge_base(tmp.path(), "main", &GitConfig::default())
+                .await
+                .unwrap(),
+            MergeOutcome::UpToDate
+        );
+    }
+
+    #[tokio::test]
+    async fn test_merge_base_reports_conflicts() {
+        let tmp = diverged_repo("ours", "theirs");
+        let repo = Repository::open(tmp.path()).unwrap();
+        let before = repo.head().unwrap().target();
+
+        let outcome = merge_base(tmp.path(), "main", &GitConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(outcome, MergeOutcome::Conflicts(vec!["shared.txt".to_string()]));
+        assert_eq!(repo.head().unwrap().target(), before);
+        assert_eq!(fs::read_to_string(tmp.path().join("shared.txt")).unwrap(), "ours");
+    }
+
+    #[tokio::test]
+    async fn test_commit_signs_with_ssh_key() {
+        let tmp = diverged_repo("ours", "theirs");
+        let key = tmp.path().join(".git").join("signing_key");
+        let status = Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+            .arg(&key)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        fs::write(tmp.path().join("signed.txt"), "signed").unwrap();
+        add_all(tmp.path()).await.unwrap();
+        let git = GitConfig {
+            signing_key: Some(key.to_string_lossy().into_owned()),
+            ..GitConfig::default()
+        };
+        commit(tmp.path(), "signed commit", &git).await.unwrap();
+
+        let repo = Repository::open(tmp.path()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.message(), Some("signed commit"));
+        let (signature, _) = repo.extract_signature(&head.id(), None).unwrap();
+        assert!(signature
+            .as_str()
+            .unwrap()
+            .starts_with("-----BEGIN SSH SIGNATURE-----"));
+    }
+
+    #[tokio::test]
+    async fn test_commit_unsigned_without_key() {
+        let tmp = diverged_repo("ours", "theirs");
+        fs::write(tmp.path().join("plain.txt"), "plain").unwrap();
+        add_all(tmp.path()).await.unwrap();
+
+        commit(tmp.path(), "plain commit", &GitConfig::default())
+            .await
+            .unwrap();
+
+        let repo = Repository::open(tmp.path()).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert!(repo.extract_signature(&head.id(), None).is_err());
+    }
 }
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::agent::tools::workspace_walker;
+
+/// Files looked at before the counts are considered representative.
+const MAX_SCANNED_FILES: usize = 20_000;
+
+/// Share of recognised source files a language needs to count as primary.
+const MIN_SHARE: f64 = 0.1;
+
+/// Most primary languages reported.
+const MAX_LANGUAGES: usize = 3;
+
+/// A programming language recognised by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Language {
+    pub name: &'static str,
+    /// Extensions without the dot, most common first.
+    pub extensions: &'static [&'static str],
+}
+
+impl Language {
+    const fn new(name: &'static str, extensions: &'static [&'static str]) -> Self {
+        Self { name, extensions }
+    }
+
+    /// `include` globs for `search_code`, e.g. "`*.ts`, `*.tsx`".
+    pub fn globs(&self) -> String {
+        self.extensions
+            .iter()
+            .map(|ext| format!("`*.{ext}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Languages worth telling apart for searches. Markup, config and data files
+/// are left out so they don't outnumber the code.
+const LANGUAGES: &[Language] = &[
+    Language::new("Rust", &["rs"]),
+    Language::new("Go", &["go"]),
+    Language::new("Python", &["py", "pyi"]),
+    Language::new("TypeScript", &["ts", "tsx", "mts", "cts"]),
+    Language::new("JavaScript", &["js", "jsx", "mjs", "cjs"]),
+    Language::new("Java", &["java"]),
+    Language::new("Kotlin", &["kt", "kts"]),
+    Language::new("Scala", &["scala"]),
+    Language::new("C#", &["cs"]),
+    Language::new("C", &["c", "h"]),
+    Language::new("C++", &["cpp", "cc", "cxx", "hpp", "hh", "hxx"]),
+    Language::new("Objective-C", &["m", "mm"]),
+    Language::new("Swift", &["swift"]),
+    Language::new("Ruby", &["rb"]),
+    Language::new("PHP", &["php"]),
+    Language::new("Elixir", &["ex", "exs"]),
+    Language::new("Erlang", &["erl", "hrl"]),
+    Language::new("Haskell", &["hs"]),
+    Language::new("OCaml", &["ml", "mli"]),
+    Language::new("Dart", &["dart"]),
+    Language::new("Lua", &["lua"]),
+    Language::new("Zig", &["zig"]),
+    Language::new("Shell", &["sh", "bash"]),
+    Language::new("Vue", &["vue"]),
+    Language::new("Svelte", &["svelte"]),
+];
+
+/// The languages most of the source files under `root` are written in, by
+/// file count, most common first. Ignored directories and those in
+/// `exclude_dirs` are left out, and only the first `MAX_SCANNED_FILES` files
+/// are counted.
+pub fn primary_languages(root: &Path, exclude_dirs: &[String]) -> Vec<Language> {
+    let by_extension: HashMap<&str, usize> = LANGUAGES
+        .iter()
+        .enumerate()
+        .flat_map(|(i, language)| language.extensions.iter().map(move |ext| (*ext, i)))
+        .collect();
+
+    let mut counts = vec![0usize; LANGUAGES.len()];
+    let files = workspace_walker(root, exclude_dirs)
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .take(MAX_SCANNED_FILES);
+    for entry in files {
+        let language = entry
+            .path()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| by_extension.get(ext.to_ascii_lowercase().as_str()));
+        if let Some(&i) = language {
+            counts[i] += 1;
+        }
+    }
+
+    let total: usize = counts.iter().sum();
+    let mut found: Vec<(usize, Language)> = counts
+        .into_iter()
+        .zip(LANGUAGES.iter().copied())
+        .filter(|&(count, _)| count > 0 && count as f64 >= total as f64 * MIN_SHARE)
+        .collect();
+    found.sort_by_key(|&(count, _)| std::cmp::Reverse(count));
+    found
+        .into_iter()
+        .take(MAX_LANGUAGES)
+        .map(|(_, language)| language)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primary_languages_by_file_count() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for dir in ["src", "web", "scripts", "node_modules/dep", "third_party"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for i in 0..8 {
+            std::fs::write(root.join(format!("src/m{i}.rs")), "").unwrap();
+        }
+        for file in ["web/app.ts", "web/App.TSX", "web/util.ts"] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        // Under the share threshold, excluded, or not code
+        std::fs::write(root.join("scripts/build.sh"), "").unwrap();
+        for i in 0..20 {
+            std::fs::write(root.join(format!("node_modules/dep/{i}.js")), "").unwrap();
+            std::fs::write(root.join(format!("third_party/{i}.c")), "").unwrap();
+        }
+        std::fs::write(root.join("README.md"), "").unwrap();
+
+        let exclude = ["third_party".to_string()];
+        let names: Vec<&str> = primary_languages(root, &exclude)
+            .iter()
+            .map(|l| l.name)
+            .collect();
+        assert_eq!(names, ["Rust", "TypeScript"]);
+        assert_eq!(primary_languages(root, &[])[0].name, "C");
+        assert!(primary_languages(&root.join("scripts/none"), &[]).is_empty());
+    }
+}
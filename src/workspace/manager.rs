@@ -1,12 +1,96 @@
-use std::path::{Path, PathBuf};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
 
-use crate::config::WorkspaceConfig;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::agent::claude::Message;
+use crate::agent::tools::workspace_walker;
+use crate::config::{GitConfig, RepoConfig, WorkspaceConfig};
 use crate::error::{AppError, Result};
-use crate::workspace::git;
+use crate::workspace::git::{self, LineChanges, MergeOutcome};
+use crate::workspace::languages::{self, Language};
+
+/// Upper bound on how long `format_command` may run before it is abandoned.
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Guidelines beyond this many bytes are cut off before reaching the prompt.
+const MAX_GUIDELINES_BYTES: usize = 16 * 1024;
+
+/// Entries listed in the file tree given to the agent; the rest are counted.
+const MAX_TREE_ENTRIES: usize = 200;
+
+/// Symlinks followed while resolving one path before giving up, as the kernel does.
+const MAX_SYMLINKS: usize = 40;
+
+/// File in a workspace's `.git` directory recording a commit awaiting approval.
+const PENDING_APPROVAL_FILE: &str = "mycelium-pending-approval.json";
+
+/// File in a workspace's `.git` directory holding a run stopped at the turn limit.
+const SAVED_CONVERSATION_FILE: &str = "mycelium-saved-conversation.json";
+
+/// Directory in `base_dir` holding the summary of each issue's last completed run.
+const SUMMARIES_DIR: &str = ".summaries";
+
+/// Directory in `base_dir` recording the branch each issue's runs work on.
+const BRANCHES_DIR: &str = ".branches";
 
 /// Manages workspace directories for agent operations.
 pub struct WorkspaceManager {
     base_dir: PathBuf,
+    cache_clones: bool,
+    keep_on_failure: bool,
+    /// Commit locally but skip pushing.
+    dry_run: bool,
+    /// Shared limit on clones in flight (`AppState::clone_permits`).
+    clone_permits: Arc<Semaphore>,
+}
+
+/// What a stale workspace sweep removed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SweepStats {
+    pub removed: usize,
+    pub bytes: u64,
+}
+
+/// Result of `WorkspaceManager::finalize`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Finalized {
+    /// There was nothing to commit.
+    NoChanges,
+    /// Changes were committed and pushed.
+    Pushed,
+    /// Merging the base branch conflicted in these paths; nothing was pushed.
+    Conflicts(Vec<String>),
+    /// The run changed these files, more than `agent.max_changed_files`;
+    /// nothing was committed or pushed.
+    TooManyFiles(Vec<String>),
+    /// The run's diff is larger than `agent.max_diff_lines`; nothing was
+    /// committed or pushed.
+    TooLarge(LineChanges),
+}
+
+/// A run whose changes are committed in its workspace but held back until a
+/// human approves them. Saved next to the commit so it survives restarts.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PendingApproval {
+    /// Branch the PR targets.
+    pub base_branch: String,
+    /// The agent's summary of the changes.
+    pub summary: String,
+}
+
+/// The conversation of an issue run that hit the turn limit, saved in its
+/// workspace (which keeps the uncommitted changes) so a later run can resume it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedConversation {
+    /// Branch the issue branch was created from.
+    pub base_branch: String,
+    pub messages: Vec<Message>,
 }
 
 /// A checked-out workspace ready for the agent to work in.
@@ -16,9 +100,13 @@ pub struct Workspace {
 }
 
 impl WorkspaceManager {
-    pub fn new(config: &WorkspaceConfig) -> Self {
+    pub fn new(config: &WorkspaceConfig, dry_run: bool, clone_permits: &Arc<Semaphore>) -> Self {
         Self {
             base_dir: config.base_dir.clone(),
+            cache_clones: config.cache_clones,
+            keep_on_failure: config.keep_on_failure,
+            dry_run,
+            clone_permits: Arc::clone(clone_permits),
         }
     }
 
@@ -37,31 +125,233 @@ impl WorkspaceManager {
         Ok(())
     }
 
-    /// Set up a workspace for a new issue: clone the repo and create a branch.
+    /// Wait for one of the `workspace.max_concurrent_clones` slots.
+    async fn acquire_clone_permit(&self, repo_full_name: &str) -> Result<OwnedSemaphorePermit> {
+        if let Ok(permit) = Arc::clone(&self.clone_permits).try_acquire_owned() {
+            return Ok(permit);
+        }
+        tracing::info!(repo = repo_full_name, "Waiting for a free clone slot");
+        Arc::clone(&self.clone_permits)
+            .acquire_owned()
+            .await
+            .map_err(|_| AppError::Internal("Clone semaphore closed".to_string()))
+    }
+
+    /// Clone `clone_url` into `target`, going through the per-repo mirror cache
+    /// when enabled. A broken cache is discarded and we fall back to a fresh clone.
+    /// Waits for a clone permit first, so only `workspace.max_concurrent_clones`
+    /// clones hit the disk and network at once.
+    async fn clone_repo(
+        &self,
+        clone_url: &str,
+        token: &str,
+        repo_full_name: &str,
+        target: &Path,
+    ) -> Result<()> {
+        let _permit = self.acquire_clone_permit(repo_full_name).await?;
+
+        if !self.cache_clones {
+            return git::clone(clone_url, target, token).await;
+        }
+
+        let mirror = self.mirror_path(repo_full_name);
+        if let Some(parent) = mirror.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| AppError::Workspace(format!("Failed to create cache dir: {e}")))?;
+        }
+
+        let cached = match git::update_mirror(clone_url, &mirror, token).await {
+            Ok(()) => git::clone_from_mirror(&mirror, target, clone_url).await,
+            Err(e) => Err(e),
+        };
+
+        match cached {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                tracing::warn!(
+                    repo = repo_full_name,
+                    error = %e,
+                    "Clone cache unusable, discarding it and cloning fresh"
+                );
+                if mirror.exists() {
+                    let _ = tokio::fs::remove_dir_all(&mirror).await;
+                }
+                Self::prepare_workspace_dir(target).await?;
+                git::clone(clone_url, target, token).await
+            }
+        }
+    }
+
+    /// Set up a workspace for a new issue: clone the repo, leaving the default
+    /// branch checked out. Call `create_issue_branch` once the base is known,
+    /// since it can depend on the repo's own `.mycelium.toml`.
     pub async fn setup_for_issue(
         &self,
         clone_url: &str,
         token: &str,
         repo_full_name: &str,
         issue_number: u64,
+        branch: &str,
     ) -> Result<Workspace> {
-        let branch = format!("mycelium/issue-{issue_number}");
-        let workspace_path = self.workspace_path(repo_full_name, &branch);
+        let workspace_path = self.issue_workspace_path(repo_full_name, issue_number);
 
         Self::prepare_workspace_dir(&workspace_path).await?;
 
         // Clone
-        git::clone(clone_url, &workspace_path, token).await?;
+        self.clone_repo(clone_url, token, repo_full_name, &workspace_path)
+            .await?;
 
-        // Create branch
-        git::create_branch(&workspace_path, &branch).await?;
+        Ok(Workspace {
+            path: workspace_path,
+            branch: branch.to_string(),
+        })
+    }
+
+    /// Set up a workspace for a research run from a tarball of the branch to
+    /// research, which is much faster than a clone. The workspace has no git
+    /// history, so nothing can be committed or pushed from it.
+    pub async fn setup_for_research(
+        &self,
+        tarball_url: &str,
+        token: &str,
+        repo_full_name: &str,
+        issue_number: u64,
+        branch: &str,
+    ) -> Result<Workspace> {
+        let workspace_path = self.issue_workspace_path(repo_full_name, issue_number);
+
+        Self::prepare_workspace_dir(&workspace_path).await?;
+        tokio::fs::create_dir(&workspace_path)
+            .await
+            .map_err(|e| AppError::Workspace(format!("Failed to create workspace dir: {e}")))?;
+
+        let _permit = self.acquire_clone_permit(repo_full_name).await?;
+        extract_tarball(tarball_url, token, &workspace_path).await?;
 
         Ok(Workspace {
             path: workspace_path,
-            branch,
+            branch: branch.to_string(),
         })
     }
 
+    /// Create the workspace's branch off `base_branch` and check it out.
+    pub async fn create_issue_branch(
+        &self,
+        workspace: &Workspace,
+        base_branch: &str,
+    ) -> Result<()> {
+        git::create_branch(&workspace.path, &workspace.branch, base_branch).await
+    }
+
+    /// Read the repo's `.mycelium.toml` from the workspace root. A missing
+    /// file yields no overrides; an unreadable or malformed one is logged and
+    /// ignored.
+    pub async fn load_repo_config(workspace: &Workspace) -> RepoConfig {
+        let path = workspace.path.join(RepoConfig::FILE_NAME);
+
+        // Only read a regular file; a symlink could point outside the repo
+        match tokio::fs::symlink_metadata(&path).await {
+            Ok(meta) if meta.is_file() => {}
+            Ok(_) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    "Ignoring repo config that is not a regular file"
+                );
+                return RepoConfig::default();
+            }
+            Err(_) => return RepoConfig::default(),
+        }
+
+        let contents = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => contents,
+            Err(e) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "Failed to read repo config, ignoring it"
+                );
+                return RepoConfig::default();
+            }
+        };
+
+        match RepoConfig::from_toml(&contents) {
+            Ok(config) => {
+                tracing::info!(
+                    path = %path.display(),
+                    overrides = ?config,
+                    "Loaded repo config"
+                );
+                config
+            }
+            Err(e) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "Malformed repo config, ignoring it"
+                );
+                RepoConfig::default()
+            }
+        }
+    }
+
+    /// Read the first of `file_names` present in the workspace root, for use
+    /// as project guidelines in the prompt. Truncated to `MAX_GUIDELINES_BYTES`.
+    pub async fn load_guidelines(workspace: &Workspace, file_names: &[String]) -> Option<String> {
+        for name in file_names {
+            if !workspace.path.join(name).is_file() {
+                continue;
+            }
+            // Reject names or symlinks that escape the workspace
+            let path = match Self::verify_path(&workspace.path, Path::new(name)) {
+                Ok(path) => path,
+                Err(e) => {
+                    tracing::warn!(file = %name, error = %e, "Ignoring guidelines file");
+                    continue;
+                }
+            };
+            let contents = match tokio::fs::read_to_string(&path).await {
+                Ok(contents) => contents,
+                Err(e) => {
+                    tracing::warn!(file = %name, error = %e, "Failed to read guidelines file");
+                    continue;
+                }
+            };
+            if contents.trim().is_empty() {
+                continue;
+            }
+
+            tracing::info!(file = %name, bytes = contents.len(), "Loaded project guidelines");
+            return Some(truncate_guidelines(contents));
+        }
+        None
+    }
+
+    /// The top two levels of the workspace, one entry per line with
+    /// directories marked by a trailing `/`, for orienting the agent before
+    /// it explores. Ignored directories and those in `exclude_dirs` are left
+    /// out, and the listing stops after `MAX_TREE_ENTRIES`.
+    pub async fn file_tree(workspace: &Workspace, exclude_dirs: &[String]) -> String {
+        let root = workspace.path.clone();
+        let exclude_dirs = exclude_dirs.to_vec();
+        tokio::task::spawn_blocking(move || file_tree(&root, &exclude_dirs))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// The workspace's primary programming languages, most common first. See
+    /// [`languages::primary_languages`].
+    pub async fn primary_languages(
+        workspace: &Workspace,
+        exclude_dirs: &[String],
+    ) -> Vec<Language> {
+        let root = workspace.path.clone();
+        let exclude_dirs = exclude_dirs.to_vec();
+        tokio::task::spawn_blocking(move || languages::primary_languages(&root, &exclude_dirs))
+            .await
+            .unwrap_or_default()
+    }
+
     /// Set up a workspace for responding to a PR review: clone and checkout existing branch.
     pub async fn setup_for_review(
         &self,
@@ -70,12 +360,13 @@ impl WorkspaceManager {
         repo_full_name: &str,
         branch: &str,
     ) -> Result<Workspace> {
-        let workspace_path = self.workspace_path(repo_full_name, branch);
+        let workspace_path = self.branch_workspace_path(repo_full_name, branch);
 
         Self::prepare_workspace_dir(&workspace_path).await?;
 
-        // Clone (shallow, default branch only)
-        git::clone(clone_url, &workspace_path, token).await?;
+        // Clone
+        self.clone_repo(clone_url, token, repo_full_name, &workspace_path)
+            .await?;
 
         // Fetch and checkout the specific branch
         git::fetch_and_checkout(&workspace_path, branch, token).await?;
@@ -86,17 +377,94 @@ impl WorkspaceManager {
         })
     }
 
-    /// Commit and push changes from the workspace.
-    ///
-    /// When `force` is true the push uses `+refs/…` so it overwrites the remote
-    /// branch even if histories have diverged (needed when re-processing an issue
-    /// whose branch already exists from a previous attempt).
+    /// Commit and push changes from the workspace; see `commit_changes` and
+    /// `publish`. Nothing is committed if more than `max_changed_files` files
+    /// or `max_diff_lines` lines changed (0 for no limit).
+    #[allow(clippy::too_many_arguments)]
     pub async fn finalize(
         &self,
         workspace: &Workspace,
         commit_message: &str,
+        author: &GitConfig,
+        format_command: Option<&str>,
+        max_changed_files: usize,
+        max_diff_lines: usize,
         token: &str,
+        base_branch: Option<&str>,
         force: bool,
+    ) -> Result<Finalized> {
+        if let Some(files) = Self::too_many_changes(workspace, max_changed_files).await? {
+            return Ok(Finalized::TooManyFiles(files));
+        }
+        if let Some(lines) = Self::too_large_diff(workspace, max_diff_lines).await? {
+            return Ok(Finalized::TooLarge(lines));
+        }
+        if !self
+            .commit_changes(workspace, commit_message, author, format_command)
+            .await?
+        {
+            return Ok(Finalized::NoChanges);
+        }
+        self.publish(workspace, author, token, base_branch, force)
+            .await
+    }
+
+    /// The files changed in the workspace, if there are more than `limit`
+    /// (0 for no limit).
+    pub async fn too_many_changes(
+        workspace: &Workspace,
+        limit: usize,
+    ) -> Result<Option<Vec<String>>> {
+        if limit == 0 {
+            return Ok(None);
+        }
+        let changed = git::changed_files(&workspace.path).await?;
+        if changed.len() <= limit {
+            return Ok(None);
+        }
+        tracing::warn!(
+            branch = %workspace.branch,
+            changed = changed.len(),
+            limit,
+            "Run changed too many files, not committing"
+        );
+        Ok(Some(changed.into_iter().map(|(path, _)| path).collect()))
+    }
+
+    /// The lines changed in the workspace, if more than `limit` were added
+    /// and removed in total (0 for no limit).
+    pub async fn too_large_diff(
+        workspace: &Workspace,
+        limit: usize,
+    ) -> Result<Option<LineChanges>> {
+        if limit == 0 {
+            return Ok(None);
+        }
+        let lines = git::changed_lines(&workspace.path).await?;
+        if lines.total() <= limit {
+            return Ok(None);
+        }
+        tracing::warn!(
+            branch = %workspace.branch,
+            added = lines.added,
+            removed = lines.removed,
+            limit,
+            "Run's diff is too large, not committing"
+        );
+        Ok(Some(lines))
+    }
+
+    /// Commit changes in the workspace without pushing. Returns false if there
+    /// was nothing to commit.
+    ///
+    /// When `format_command` is set it runs after staging, and anything it
+    /// rewrites is staged again so the commit contains formatted files.
+    pub async fn commit_changes(
+        &self,
+        workspace: &Workspace,
+        commit_message: &str,
+        author: &GitConfig,
+        format_command: Option<&str>,
     ) -> Result<bool> {
         if !git::has_changes(&workspace.path).await? {
             tracing::info!("No changes to commit");
@@ -104,14 +472,288 @@ impl WorkspaceManager {
         }
 
         git::add_all(&workspace.path).await?;
-        git::commit(&workspace.path, commit_message).await?;
-        if force {
+        if let Some(command) = format_command {
+            Self::run_format_command(&workspace.path, command).await;
+            git::add_all(&workspace.path).await?;
+        }
+        git::commit(&workspace.path, commit_message, author).await?;
+        Ok(true)
+    }
+
+    /// Push the workspace's committed changes.
+    ///
+    /// When `base_branch` is given, its latest commit is fetched and merged in
+    /// before pushing so the PR isn't born out of date. If that merge conflicts
+    /// nothing is pushed and the conflicting paths are returned.
+    ///
+    /// When `force` is true the push uses `+refs/…` so it overwrites the remote
+    /// branch even if histories have diverged (needed when re-processing an issue
+    /// whose branch already exists from a previous attempt).
+    pub async fn publish(
+        &self,
+        workspace: &Workspace,
+        author: &GitConfig,
+        token: &str,
+        base_branch: Option<&str>,
+        force: bool,
+    ) -> Result<Finalized> {
+        if let Some(base_branch) = base_branch {
+            git::fetch_branch(&workspace.path, base_branch, token).await?;
+            let merge = git::merge_base(&workspace.path, base_branch, author).await?;
+            match merge {
+                MergeOutcome::Conflicts(paths) => {
+                    tracing::warn!(
+                        branch = %workspace.branch,
+                        base = base_branch,
+                        conflicts = paths.len(),
+                        "Base branch conflicts with changes, not pushing"
+                    );
+                    return Ok(Finalized::Conflicts(paths));
+                }
+                MergeOutcome::Merged => {
+                    tracing::info!(base = base_branch, "Merged base branch before pushing");
+                }
+                MergeOutcome::UpToDate => {}
+            }
+        }
+
+        if self.dry_run {
+            tracing::info!(
+                branch = %workspace.branch,
+                force,
+                "[dry run] Would push branch"
+            );
+        } else if force {
             git::force_push(&workspace.path, &workspace.branch, token).await?;
         } else {
             git::push(&workspace.path, &workspace.branch, token).await?;
         }
 
-        Ok(true)
+        Ok(Finalized::Pushed)
+    }
+
+    /// Run the configured formatter in the workspace. Never fails the caller.
+    async fn run_format_command(dir: &Path, command: &str) {
+        tracing::info!(command = command, "Running format command");
+
+        let child = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output();
+
+        match tokio::time::timeout(FORMAT_TIMEOUT, child).await {
+            Ok(Ok(output)) if output.status.success() => {
+                tracing::debug!(command = command, "Format command succeeded");
+            }
+            Ok(Ok(output)) => {
+                tracing::warn!(
+                    command = command,
+                    status = %output.status,
+                    stderr = %String::from_utf8_lossy(&output.stderr),
+                    "Format command failed, committing unformatted changes"
+                );
+            }
+            Ok(Err(e)) => {
+                tracing::warn!(command = command, error = %e, "Failed to run format command");
+            }
+            Err(_) => {
+                tracing::warn!(
+                    command = command,
+                    timeout_secs = FORMAT_TIMEOUT.as_secs(),
+                    "Format command timed out"
+                );
+            }
+        }
+    }
+
+    /// Patch of the workspace's last commit.
+    pub async fn committed_diff(workspace: &Workspace) -> Result<String> {
+        git::head_commit_diff(&workspace.path).await
+    }
+
+    /// Record that the workspace's last commit awaits approval.
+    pub async fn save_pending_approval(
+        &self,
+        workspace: &Workspace,
+        pending: &PendingApproval,
+    ) -> Result<()> {
+        let contents = serde_json::to_vec(pending)
+            .map_err(|e| AppError::Workspace(format!("Failed to encode pending approval: {e}")))?;
+        tokio::fs::write(Self::pending_approval_path(&workspace.path), contents)
+            .await
+            .map_err(|e| AppError::Workspace(format!("Failed to save pending approval: {e}")))
+    }
+
+    /// The workspace of an issue whose changes await approval, if any.
+    pub async fn load_pending_approval(
+        &self,
+        repo_full_name: &str,
+        issue_number: u64,
+    ) -> Option<(Workspace, PendingApproval)> {
+        let path = self.issue_workspace_path(repo_full_name, issue_number);
+        let contents = tokio::fs::read(Self::pending_approval_path(&path))
+            .await
+            .ok()?;
+        let branch = Self::checked_out_branch(&path).await?;
+        match serde_json::from_slice(&contents) {
+            Ok(pending) => Some((Workspace { path, branch }, pending)),
+            Err(e) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "Ignoring unreadable pending approval"
+                );
+                None
+            }
+        }
+    }
+
+    /// The branch a kept workspace is on, which can't be derived from the
+    /// issue number alone.
+    async fn checked_out_branch(path: &Path) -> Option<String> {
+        match git::current_branch(path).await {
+            Ok(branch) => Some(branch),
+            Err(e) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "Ignoring kept workspace without a branch"
+                );
+                None
+            }
+        }
+    }
+
+    fn pending_approval_path(workspace_path: &Path) -> PathBuf {
+        workspace_path.join(".git").join(PENDING_APPROVAL_FILE)
+    }
+
+    /// Save the conversation of a run that hit the turn limit. The workspace
+    /// must then be kept for [`Self::take_saved_conversation`] to find.
+    pub async fn save_conversation(
+        &self,
+        workspace: &Workspace,
+        saved: &SavedConversation,
+    ) -> Result<()> {
+        let contents = serde_json::to_vec(saved)
+            .map_err(|e| AppError::Workspace(format!("Failed to encode conversation: {e}")))?;
+        tokio::fs::write(Self::saved_conversation_path(&workspace.path), contents)
+            .await
+            .map_err(|e| AppError::Workspace(format!("Failed to save conversation: {e}")))
+    }
+
+    /// The workspace and saved conversation of an issue's turn-limited run,
+    /// if any. The saved file is removed, so it is resumed at most once.
+    pub async fn take_saved_conversation(
+        &self,
+        repo_full_name: &str,
+        issue_number: u64,
+    ) -> Option<(Workspace, SavedConversation)> {
+        let path = self.issue_workspace_path(repo_full_name, issue_number);
+        let file = Self::saved_conversation_path(&path);
+        let contents = tokio::fs::read(&file).await.ok()?;
+        let _ = tokio::fs::remove_file(&file).await;
+        let branch = Self::checked_out_branch(&path).await?;
+        match serde_json::from_slice(&contents) {
+            Ok(saved) => Some((Workspace { path, branch }, saved)),
+            Err(e) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "Ignoring unreadable saved conversation"
+                );
+                None
+            }
+        }
+    }
+
+    /// Forget an issue's saved conversation so its next run starts over.
+    pub async fn discard_saved_conversation(&self, repo_full_name: &str, issue_number: u64) {
+        let path = self.issue_workspace_path(repo_full_name, issue_number);
+        let _ = tokio::fs::remove_file(Self::saved_conversation_path(&path)).await;
+    }
+
+    fn saved_conversation_path(workspace_path: &Path) -> PathBuf {
+        workspace_path.join(".git").join(SAVED_CONVERSATION_FILE)
+    }
+
+    /// Remember the summary of an issue's completed run, so the next run on
+    /// the issue can start from it. Kept outside the workspace, which is
+    /// deleted once the run is over.
+    pub async fn save_summary(
+        &self,
+        repo_full_name: &str,
+        issue_number: u64,
+        summary: &str,
+    ) -> Result<()> {
+        let path = self.summary_path(repo_full_name, issue_number);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| AppError::Workspace(format!("Failed to create summaries dir: {e}")))?;
+        }
+        tokio::fs::write(&path, summary)
+            .await
+            .map_err(|e| AppError::Workspace(format!("Failed to save summary: {e}")))
+    }
+
+    /// The summary of the last completed run on an issue, if any.
+    pub async fn load_summary(&self, repo_full_name: &str, issue_number: u64) -> Option<String> {
+        tokio::fs::read_to_string(self.summary_path(repo_full_name, issue_number))
+            .await
+            .ok()
+            .filter(|summary| !summary.trim().is_empty())
+    }
+
+    fn summary_path(&self, repo_full_name: &str, issue_number: u64) -> PathBuf {
+        let safe_name = repo_full_name.replace('/', "__");
+        self.base_dir
+            .join(SUMMARIES_DIR)
+            .join(format!("{safe_name}__issue-{issue_number}.md"))
+    }
+
+    /// Remember the branch an issue's runs work on, so later runs keep using
+    /// it even if the issue's title, and with it `{issue_slug}`, changes.
+    pub async fn save_issue_branch(
+        &self,
+        repo_full_name: &str,
+        issue_number: u64,
+        branch: &str,
+    ) -> Result<()> {
+        let path = self.issue_branch_path(repo_full_name, issue_number);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| AppError::Workspace(format!("Failed to create branches dir: {e}")))?;
+        }
+        tokio::fs::write(&path, branch)
+            .await
+            .map_err(|e| AppError::Workspace(format!("Failed to save issue branch: {e}")))
+    }
+
+    /// The branch recorded by [`Self::save_issue_branch`], if any.
+    pub async fn load_issue_branch(
+        &self,
+        repo_full_name: &str,
+        issue_number: u64,
+    ) -> Option<String> {
+        tokio::fs::read_to_string(self.issue_branch_path(repo_full_name, issue_number))
+            .await
+            .ok()
+            .map(|branch| branch.trim().to_string())
+            .filter(|branch| !branch.is_empty())
+    }
+
+    fn issue_branch_path(&self, repo_full_name: &str, issue_number: u64) -> PathBuf {
+        let safe_name = repo_full_name.replace('/', "__");
+        self.base_dir
+            .join(BRANCHES_DIR)
+            .join(format!("{safe_name}__issue-{issue_number}"))
     }
 
     /// Clean up a workspace directory.
@@ -124,54 +766,622 @@ impl WorkspaceManager {
         Ok(())
     }
 
-    fn workspace_path(&self, repo_full_name: &str, branch: &str) -> PathBuf {
-        let safe_name = repo_full_name.replace('/', "__");
-        let safe_branch = branch.replace('/', "__");
-        self.base_dir.join(format!("{safe_name}__{safe_branch}"))
+    /// Clean up after a run, unless it failed and `keep_on_failure` is set, in
+    /// which case the workspace is left for inspection.
+    pub async fn release(&self, workspace: &Workspace, failed: bool) -> Result<()> {
+        if failed && self.keep_on_failure {
+            tracing::info!(
+                path = %workspace.path.display(),
+                "Keeping workspace of failed run"
+            );
+            return Ok(());
+        }
+        self.cleanup(workspace).await
     }
 
-    /// Verify a path is within the workspace (path traversal protection).
-    pub fn verify_path(workspace_root: &Path, requested_path: &Path) -> Result<PathBuf> {
-        let full_path = workspace_root.join(requested_path);
+    /// Delete workspaces in `base_dir` not modified for `max_age`, such as
+    /// those kept by `keep_on_failure` or left by a crash. Paths in `active`,
+    /// workspaces awaiting approval or holding a saved conversation, and the
+    /// clone cache are left alone.
+    pub async fn sweep_stale(&self, max_age: Duration, active: &HashSet<PathBuf>) -> SweepStats {
+        let mut stats = SweepStats::default();
+        let Ok(mut entries) = tokio::fs::read_dir(&self.base_dir).await else {
+            return stats;
+        };
 
-        // Canonicalize to resolve .. and symlinks
-        // If the file doesn't exist yet (create_file), canonicalize the parent
-        let canonical = if full_path.exists() {
-            full_path.canonicalize()
-        } else {
-            // For new files, verify the parent directory exists and is within workspace
-            let parent = full_path
-                .parent()
-                .ok_or_else(|| AppError::Workspace("Invalid file path".to_string()))?;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            // Workspace directories are named `<owner>__<repo>__<branch>`
+            let name = entry.file_name();
+            if !name.to_string_lossy().contains("__")
+                || active.contains(&entry.path())
+                || Self::pending_approval_path(&entry.path()).exists()
+                || Self::saved_conversation_path(&entry.path()).exists()
+            {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.elapsed().ok());
+            if !metadata.is_dir() || age.is_none_or(|age| age < max_age) {
+                continue;
+            }
 
-            if !parent.exists() {
-                std::fs::create_dir_all(parent)
-                    .map_err(|e| AppError::Workspace(format!("Failed to create directory: {e}")))?;
+            let path = entry.path();
+            let size = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || dir_size(&path)
+            })
+            .await
+            .unwrap_or(0);
+
+            match tokio::fs::remove_dir_all(&path).await {
+                Ok(()) => {
+                    stats.removed += 1;
+                    stats.bytes += size;
+                }
+                Err(e) => tracing::warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "Failed to remove stale workspace"
+                ),
             }
+        }
+        stats
+    }
 
-            let canonical_parent = parent
-                .canonicalize()
-                .map_err(|e| AppError::Workspace(format!("Failed to resolve path: {e}")))?;
+    /// Directory a workspace for `issue_number` is checked out in. It's named
+    /// after the issue rather than its branch, whose name may depend on the
+    /// issue's title, and matches the default branch's workspace so existing
+    /// workspaces are still found.
+    pub fn issue_workspace_path(&self, repo_full_name: &str, issue_number: u64) -> PathBuf {
+        self.branch_workspace_path(repo_full_name, &format!("mycelium/issue-{issue_number}"))
+    }
 
-            let file_name = full_path
-                .file_name()
-                .ok_or_else(|| AppError::Workspace("Invalid file name".to_string()))?;
+    /// Directory a workspace for `branch` is checked out in.
+    pub fn branch_workspace_path(&self, repo_full_name: &str, branch: &str) -> PathBuf {
+        let safe_name = repo_full_name.replace('/', "__");
+        let safe_branch = branch.replace('/', "__");
+        self.base_dir.join(format!("{safe_name}__{safe_branch}"))
+    }
 
-            Ok(canonical_parent.join(file_name))
-        }
-        .map_err(|e| AppError::Workspace(format!("Failed to resolve path: {e}")))?;
+    fn mirror_path(&self, repo_full_name: &str) -> PathBuf {
+        let safe_name = repo_full_name.replace('/', "__");
+        self.base_dir
+            .join(".cache")
+            .join(format!("{safe_name}.git"))
+    }
 
-        let canonical_root = workspace_root
+    /// Resolve a path inside the workspace (path traversal protection).
+    ///
+    /// Symlinks are followed, including dangling ones, and the path is
+    /// rejected if it resolves outside the workspace. Components that don't
+    /// exist yet are kept as-is, and nothing is created.
+    pub fn verify_path(workspace_root: &Path, requested_path: &Path) -> Result<PathBuf> {
+        let root = workspace_root
             .canonicalize()
             .map_err(|e| AppError::Workspace(format!("Failed to resolve workspace root: {e}")))?;
 
-        if !canonical.starts_with(&canonical_root) {
+        let resolved = resolve_symlinks(&root, requested_path)?;
+        if !resolved.starts_with(&root) {
             return Err(AppError::Workspace(format!(
-                "Path traversal detected: {} is outside workspace",
+                "Path traversal detected: {} resolves outside workspace",
                 requested_path.display()
             )));
         }
 
-        Ok(canonical)
+        Ok(resolved)
+    }
+
+    /// Like [`Self::verify_path`], then create the missing parent directories
+    /// so a new file can be written at the returned path.
+    pub fn verify_path_for_create(workspace_root: &Path, requested_path: &Path) -> Result<PathBuf> {
+        let path = Self::verify_path(workspace_root, requested_path)?;
+        let parent = path
+            .parent()
+            .ok_or_else(|| AppError::Workspace("Invalid file path".to_string()))?;
+        if !parent.exists() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| AppError::Workspace(format!("Failed to create directory: {e}")))?;
+        }
+        Ok(path)
+    }
+}
+
+/// Walk `requested` from `root` one component at a time, following symlinks
+/// (dangling ones too) the way the kernel would when opening the path.
+fn resolve_symlinks(root: &Path, requested: &Path) -> Result<PathBuf> {
+    let mut resolved = root.to_path_buf();
+    let mut rest = requested.to_path_buf();
+    let mut links_followed = 0;
+
+    loop {
+        let mut components = rest.components();
+        let Some(component) = components.next() else {
+            return Ok(resolved);
+        };
+        let remaining = components.as_path().to_path_buf();
+
+        match component {
+            Component::Prefix(_) | Component::RootDir => {
+                resolved.push(component.as_os_str());
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                let next = resolved.join(name);
+                let is_symlink = std::fs::symlink_metadata(&next)
+                    .is_ok_and(|meta| meta.file_type().is_symlink());
+                if is_symlink {
+                    links_followed += 1;
+                    if links_followed > MAX_SYMLINKS {
+                        return Err(AppError::Workspace(format!(
+                            "Too many levels of symbolic links in {}",
+                            requested.display()
+                        )));
+                    }
+                    let target = std::fs::read_link(&next).map_err(|e| {
+                        AppError::Workspace(format!("Failed to read symlink: {e}"))
+                    })?;
+                    // An absolute target starts again from `/`; a relative
+                    // one from the link's directory, which `resolved` still is
+                    rest = target.join(remaining);
+                    continue;
+                }
+                resolved = next;
+            }
+        }
+        rest = remaining;
+    }
+}
+
+fn file_tree(root: &Path, exclude_dirs: &[String]) -> String {
+    let mut lines = Vec::new();
+    let mut omitted = 0;
+    let walker = workspace_walker(root, exclude_dirs)
+        .max_depth(Some(2))
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    for entry in walker.flatten() {
+        let depth = entry.depth();
+        if depth == 0 {
+            continue;
+        }
+        if lines.len() == MAX_TREE_ENTRIES {
+            omitted += 1;
+            continue;
+        }
+        let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+        lines.push(format!(
+            "{}{}{}",
+            "  ".repeat(depth - 1),
+            entry.file_name().to_string_lossy(),
+            if is_dir { "/" } else { "" }
+        ));
+    }
+    if omitted > 0 {
+        lines.push(format!("[... {omitted} more entries ...]"));
+    }
+    lines.join("\n")
+}
+
+/// Cut `contents` to `MAX_GUIDELINES_BYTES` on a char boundary, noting the cut.
+fn truncate_guidelines(mut contents: String) -> String {
+    if contents.len() <= MAX_GUIDELINES_BYTES {
+        return contents;
+    }
+    let mut end = MAX_GUIDELINES_BYTES;
+    while !contents.is_char_boundary(end) {
+        end -= 1;
+    }
+    contents.truncate(end);
+    contents.push_str("\n\n[... truncated ...]");
+    contents
+}
+
+/// Download a gzipped tarball and stream it into `tar`, dropping the
+/// top-level directory GitHub wraps the archive in.
+async fn extract_tarball(url: &str, token: &str, dest: &Path) -> Result<()> {
+    let download_failed = |e: reqwest::Error| {
+        AppError::Workspace(format!("Failed to download repository tarball: {e}"))
+    };
+    let mut response = reqwest::Client::new()
+        .get(url)
+        .bearer_auth(token)
+        .header(reqwest::header::USER_AGENT, "mycelium")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(download_failed)?;
+
+    let mut tar = tokio::process::Command::new("tar")
+        .args(["-xzf", "-", "--strip-components=1", "-C"])
+        .arg(dest)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| AppError::Workspace(format!("Failed to run tar: {e}")))?;
+
+    if let Some(mut stdin) = tar.stdin.take() {
+        while let Some(chunk) = response.chunk().await.map_err(download_failed)? {
+            // tar exited early; its exit status explains why
+            if stdin.write_all(&chunk).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    let output = tar.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(AppError::Workspace(format!(
+            "Failed to extract repository tarball: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Total size of the files under `path`, without following symlinks.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_guidelines() {
+        assert_eq!(truncate_guidelines("short".to_string()), "short");
+
+        let long = "é".repeat(MAX_GUIDELINES_BYTES);
+        let truncated = truncate_guidelines(long);
+        assert!(truncated.ends_with("[... truncated ...]"));
+        assert!(truncated.len() <= MAX_GUIDELINES_BYTES + 32);
+    }
+
+    #[test]
+    fn test_file_tree_lists_two_levels() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for dir in ["src/agent/tools", "docs", "target/debug", "build"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in ["Cargo.toml", "src/main.rs", "src/agent/mod.rs", "build/out.o"] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+        std::fs::write(root.join(".gitignore"), "build/\n").unwrap();
+
+        assert_eq!(
+            file_tree(root, &[]),
+            ".gitignore\nCargo.toml\ndocs/\nsrc/\n  agent/\n  main.rs"
+        );
+        assert_eq!(
+            file_tree(root, &["docs".to_string()]),
+            ".gitignore\nCargo.toml\nsrc/\n  agent/\n  main.rs"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_path_follows_symlinks_without_creating_dirs() {
+        use std::os::unix::fs::symlink;
+
+        let outside = tempfile::tempdir().unwrap();
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir(root.join("src")).unwrap();
+        symlink(outside.path().join("missing"), root.join("escape")).unwrap();
+        symlink("src/new.rs", root.join("inside")).unwrap();
+
+        // Resolving a new path for reading leaves the tree alone
+        let path = WorkspaceManager::verify_path(root, Path::new("a/b/c.txt")).unwrap();
+        assert!(path.ends_with("a/b/c.txt"));
+        assert!(!root.join("a").exists());
+
+        // Dangling symlinks are judged by where they point
+        let err = WorkspaceManager::verify_path(root, Path::new("escape")).unwrap_err();
+        assert!(err.to_string().contains("outside workspace"), "{err}");
+        let path = WorkspaceManager::verify_path(root, Path::new("inside")).unwrap();
+        assert!(path.ends_with("src/new.rs"));
+        assert!(WorkspaceManager::verify_path(root, Path::new("src/../../x")).is_err());
+
+        let path = WorkspaceManager::verify_path_for_create(root, Path::new("a/b/c.txt")).unwrap();
+        assert!(path.parent().unwrap().is_dir());
+    }
+
+    #[tokio::test]
+    async fn test_sweep_stale_skips_cache_and_active() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            ".cache",
+            "owner__repo__mycelium__issue-1",
+            "owner__repo__mycelium__issue-2",
+            "owner__repo__mycelium__issue-3/.git",
+            "unrelated",
+        ] {
+            std::fs::create_dir_all(dir.path().join(name)).unwrap();
+        }
+        std::fs::write(dir.path().join("owner__repo__mycelium__issue-1/file"), "12345").unwrap();
+        std::fs::write(
+            dir.path()
+                .join("owner__repo__mycelium__issue-3/.git")
+                .join(SAVED_CONVERSATION_FILE),
+            "[]",
+        )
+        .unwrap();
+        let config = WorkspaceConfig {
+            base_dir: dir.path().to_path_buf(),
+            cache_clones: true,
+            keep_on_failure: true,
+            stale_workspace_ttl_secs: 0,
+            pending_tasks_file: None,
+            max_concurrent_clones: 1,
+        };
+        let manager = WorkspaceManager::new(&config, false, &Arc::new(Semaphore::new(1)));
+        let active = HashSet::from([manager.issue_workspace_path("owner/repo", 2)]);
+
+        let stats = manager.sweep_stale(Duration::from_secs(3600), &active).await;
+        assert_eq!(stats, SweepStats::default());
+
+        let stats = manager.sweep_stale(Duration::ZERO, &active).await;
+        assert_eq!(stats, SweepStats { removed: 1, bytes: 5 });
+        assert!(dir.path().join(".cache").exists());
+        assert!(dir.path().join("unrelated").exists());
+        assert!(dir.path().join("owner__repo__mycelium__issue-2").exists());
+        assert!(dir.path().join("owner__repo__mycelium__issue-3").exists());
+        assert!(!dir.path().join("owner__repo__mycelium__issue-1").exists());
+    }
+
+    #[tokio::test]
+    async fn test_pending_approval_round_trip_survives_sweep() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = WorkspaceConfig {
+            base_dir: dir.path().to_path_buf(),
+            cache_clones: false,
+            keep_on_failure: false,
+            stale_workspace_ttl_secs: 0,
+            pending_tasks_file: None,
+            max_concurrent_clones: 1,
+        };
+        let manager = WorkspaceManager::new(&config, false, &Arc::new(Semaphore::new(1)));
+        let workspace = Workspace {
+            path: manager.issue_workspace_path("owner/repo", 3),
+            branch: "fix/3-crash".to_string(),
+        };
+        let repo = git2::Repository::init(&workspace.path).unwrap();
+        repo.set_head("refs/heads/fix/3-crash").unwrap();
+        assert!(manager.load_pending_approval("owner/repo", 3).await.is_none());
+
+        let pending = PendingApproval {
+            base_branch: "main".to_string(),
+            summary: "Fixed it".to_string(),
+        };
+        manager
+            .save_pending_approval(&workspace, &pending)
+            .await
+            .unwrap();
+        manager.sweep_stale(Duration::ZERO, &HashSet::new()).await;
+
+        let (loaded, loaded_pending) = manager
+            .load_pending_approval("owner/repo", 3)
+            .await
+            .unwrap();
+        assert_eq!(loaded.path, workspace.path);
+        assert_eq!(loaded.branch, "fix/3-crash");
+        assert_eq!(loaded_pending, pending);
+    }
+
+    #[tokio::test]
+    async fn test_saved_conversation_is_taken_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = WorkspaceConfig {
+            base_dir: dir.path().to_path_buf(),
+            cache_clones: false,
+            keep_on_failure: false,
+            stale_workspace_ttl_secs: 0,
+            pending_tasks_file: None,
+            max_concurrent_clones: 1,
+        };
+        let manager = WorkspaceManager::new(&config, false, &Arc::new(Semaphore::new(1)));
+        let workspace = Workspace {
+            path: manager.issue_workspace_path("owner/repo", 5),
+            branch: "fix/5-typo".to_string(),
+        };
+        let repo = git2::Repository::init(&workspace.path).unwrap();
+        repo.set_head("refs/heads/fix/5-typo").unwrap();
+
+        let saved = SavedConversation {
+            base_branch: "develop".to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: crate::agent::claude::MessageContent::Text("Fix it".to_string()),
+            }],
+        };
+        manager.save_conversation(&workspace, &saved).await.unwrap();
+
+        let (loaded, loaded_saved) = manager
+            .take_saved_conversation("owner/repo", 5)
+            .await
+            .unwrap();
+        assert_eq!(loaded.path, workspace.path);
+        assert_eq!(loaded.branch, "fix/5-typo");
+        assert_eq!(loaded_saved.base_branch, "develop");
+        assert_eq!(loaded_saved.messages.len(), 1);
+        assert!(manager
+            .take_saved_conversation("owner/repo", 5)
+            .await
+            .is_none());
+
+        manager.save_conversation(&workspace, &saved).await.unwrap();
+        manager.discard_saved_conversation("owner/repo", 5).await;
+        assert!(manager
+            .take_saved_conversation("owner/repo", 5)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_setup_for_research_extracts_tarball() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("owner-repo-abc123");
+        std::fs::create_dir_all(source.join("src")).unwrap();
+        std::fs::write(source.join("src/lib.rs"), "pub fn hello() {}").unwrap();
+        let archive = dir.path().join("repo.tar.gz");
+        let status = std::process::Command::new("tar")
+            .args(["-czf"])
+            .arg(&archive)
+            .arg("-C")
+            .arg(dir.path())
+            .arg("owner-repo-abc123")
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let tarball = std::fs::read(&archive).unwrap();
+        let app = axum::Router::new().route(
+            "/repos/owner/repo/tarball/main",
+            axum::routing::get(move |headers: axum::http::HeaderMap| async move {
+                assert_eq!(headers["authorization"], "Bearer token");
+                tarball
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/repos/owner/repo/tarball/main",
+            listener.local_addr().unwrap()
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config = WorkspaceConfig {
+            base_dir: dir.path().join("workspaces"),
+            cache_clones: true,
+            keep_on_failure: false,
+            stale_workspace_ttl_secs: 0,
+            pending_tasks_file: None,
+            max_concurrent_clones: 1,
+        };
+        let manager = WorkspaceManager::new(&config, false, &Arc::new(Semaphore::new(1)));
+        let workspace = manager
+            .setup_for_research(&url, "token", "owner/repo", 4, "mycelium/issue-4")
+            .await
+            .unwrap();
+
+        let contents = std::fs::read_to_string(workspace.path.join("src/lib.rs")).unwrap();
+        assert_eq!(contents, "pub fn hello() {}");
+        assert!(!workspace.path.join(".git").exists());
+    }
+
+    #[tokio::test]
+    async fn test_too_many_changes_lists_files_over_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(dir.path()).unwrap();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            std::fs::write(dir.path().join(name), "fn main() {}").unwrap();
+        }
+        let workspace = Workspace {
+            path: dir.path().to_path_buf(),
+            branch: "mycelium/issue-1".to_string(),
+        };
+
+        let too_many = WorkspaceManager::too_many_changes(&workspace, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            too_many,
+            Some(vec![
+                "a.rs".to_string(),
+                "b.rs".to_string(),
+                "c.rs".to_string()
+            ])
+        );
+        for limit in [0, 3] {
+            assert!(WorkspaceManager::too_many_changes(&workspace, limit)
+                .await
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_too_large_diff_counts_added_and_removed_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "a\nb\nc\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("lib.rs")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let author = git2::Signature::now("test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &author, &author, "init", &tree, &[])
+            .unwrap();
+
+        // One line replaced, plus a new untracked file of two lines
+        std::fs::write(dir.path().join("lib.rs"), "a\nB\nc\n").unwrap();
+        std::fs::write(dir.path().join("new.rs"), "x\ny\n").unwrap();
+        let workspace = Workspace {
+            path: dir.path().to_path_buf(),
+            branch: "mycelium/issue-1".to_string(),
+        };
+
+        let too_large = WorkspaceManager::too_large_diff(&workspace, 3)
+            .await
+            .unwrap();
+        assert_eq!(
+            too_large,
+            Some(LineChanges {
+                added: 3,
+                removed: 1
+            })
+        );
+        for limit in [0, 4] {
+            assert!(WorkspaceManager::too_large_diff(&workspace, limit)
+                .await
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_summary_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = WorkspaceConfig {
+            base_dir: dir.path().to_path_buf(),
+            cache_clones: false,
+            keep_on_failure: false,
+            stale_workspace_ttl_secs: 0,
+            pending_tasks_file: None,
+            max_concurrent_clones: 1,
+        };
+        let manager = WorkspaceManager::new(&config, false, &Arc::new(Semaphore::new(1)));
+        assert!(manager.load_summary("owner/repo", 4).await.is_none());
+
+        manager
+            .save_summary("owner/repo", 4, "The parser drops trailing commas")
+            .await
+            .unwrap();
+        manager.sweep_stale(Duration::ZERO, &HashSet::new()).await;
+        assert_eq!(
+            manager.load_summary("owner/repo", 4).await.as_deref(),
+            Some("The parser drops trailing commas")
+        );
+        assert!(manager.load_summary("owner/repo", 5).await.is_none());
     }
 }
@@ -1,4 +1,5 @@
 pub mod git;
+pub mod languages;
 pub mod manager;
 
-pub use manager::WorkspaceManager;
+pub use manager::{Finalized, WorkspaceManager};